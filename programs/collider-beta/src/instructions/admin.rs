@@ -9,25 +9,22 @@
 //! Contact: dev@antitoken.pro
 
 // instructions/admin.rs
+use crate::state::{AdminParameter, QueuedTreasury, QueuedUpdate};
 use crate::utils::*;
 use crate::Admin;
-use crate::SetPredictionTokenAuthority;
+use crate::CollectFees;
 use crate::Update;
 use anchor_lang::prelude::*;
-use anchor_spl::token;
-use anchor_spl::token::spl_token::instruction::AuthorityType;
-use anchor_spl::token::SetAuthority;
 
 pub fn initialise_admin(ctx: Context<Admin>) -> Result<()> {
-    let now: i64 = 1736899200; // CRITICAL: Remove line in production!
-
-    // CRITICAL: Add line in production!let now: i64 = Clock::get()?.unix_timestamp;
+    let now = current_time()?;
 
     let config = &mut ctx.accounts.admin;
     require!(!config.initialised, PredictError::AlreadyInitialised);
 
+    config.version = ADMIN_VERSION;
     config.initialised = true;
-    config.creation_fee = 100_000_000;
+    config.creation_fee = CREATION_FEE;
     config.max_title_length = MAX_TITLE_LENGTH;
     config.max_description_length = MAX_DESCRIPTION_LENGTH;
     config.truth_basis = TRUTH_BASIS;
@@ -36,6 +33,14 @@ pub fn initialise_admin(ctx: Context<Admin>) -> Result<()> {
     config.antitoken_multisig = ANTITOKEN_MULTISIG;
     config.anti_mint_address = ANTI_MINT_ADDRESS;
     config.pro_mint_address = PRO_MINT_ADDRESS;
+    config.challenge_period = CHALLENGE_PERIOD;
+    config.dispute_bond = DISPUTE_BOND;
+    config.arbiter = ANTITOKEN_MULTISIG;
+    config.protocol_fee_bps = PROTOCOL_FEE_BPS;
+    config.treasury = ANTITOKEN_MULTISIG;
+    config.pending_multisig = None;
+    config.queue = Vec::new();
+    config.pending_treasury = None;
 
     emit!(AdminEvent {
         action: "initialise_admin".to_string(),
@@ -46,22 +51,20 @@ pub fn initialise_admin(ctx: Context<Admin>) -> Result<()> {
     Ok(())
 }
 
-pub fn update_creation_fee(ctx: Context<Update>, new_fee: u64) -> Result<()> {
-    let now: i64 = 1736899200; // CRITICAL: Remove line in production!
-
-    // CRITICAL: Add line in production!let now: i64 = Clock::get()?.unix_timestamp;
+pub fn update_max_title_length(ctx: Context<Update>, new_length: u64) -> Result<()> {
+    let now = current_time()?;
 
     require!(
         ctx.accounts.authority.key() == ctx.accounts.admin.antitoken_multisig,
         ErrorCode::Unauthorised
     );
-    ctx.accounts.admin.creation_fee = new_fee;
+    ctx.accounts.admin.max_title_length = new_length;
 
     emit!(AdminEvent {
-        action: "update_creation_fee".to_string(),
+        action: "update_max_title_length".to_string(),
         args: vec![KeyValue {
-            key: "new_fee".to_string(),
-            value: new_fee.to_string(),
+            key: "new_length".to_string(),
+            value: new_length.to_string(),
         }],
         timestamp: now,
     });
@@ -69,19 +72,17 @@ pub fn update_creation_fee(ctx: Context<Update>, new_fee: u64) -> Result<()> {
     Ok(())
 }
 
-pub fn update_max_title_length(ctx: Context<Update>, new_length: u64) -> Result<()> {
-    let now: i64 = 1736899200; // CRITICAL: Remove line in production!
-
-    // CRITICAL: Add line in production!let now: i64 = Clock::get()?.unix_timestamp;
+pub fn update_max_description_length(ctx: Context<Update>, new_length: u64) -> Result<()> {
+    let now = current_time()?;
 
     require!(
         ctx.accounts.authority.key() == ctx.accounts.admin.antitoken_multisig,
         ErrorCode::Unauthorised
     );
-    ctx.accounts.admin.max_title_length = new_length;
+    ctx.accounts.admin.max_description_length = new_length;
 
     emit!(AdminEvent {
-        action: "update_max_title_length".to_string(),
+        action: "update_max_description_length".to_string(),
         args: vec![KeyValue {
             key: "new_length".to_string(),
             value: new_length.to_string(),
@@ -92,22 +93,20 @@ pub fn update_max_title_length(ctx: Context<Update>, new_length: u64) -> Result<
     Ok(())
 }
 
-pub fn update_max_description_length(ctx: Context<Update>, new_length: u64) -> Result<()> {
-    let now: i64 = 1736899200; // CRITICAL: Remove line in production!
-
-    // CRITICAL: Add line in production!let now: i64 = Clock::get()?.unix_timestamp;
+pub fn update_anti_mint(ctx: Context<Update>, new_mint: Pubkey) -> Result<()> {
+    let now = current_time()?;
 
     require!(
         ctx.accounts.authority.key() == ctx.accounts.admin.antitoken_multisig,
         ErrorCode::Unauthorised
     );
-    ctx.accounts.admin.max_description_length = new_length;
+    ctx.accounts.admin.anti_mint_address = new_mint;
 
     emit!(AdminEvent {
-        action: "update_max_description_length".to_string(),
+        action: "update_anti_mint".to_string(),
         args: vec![KeyValue {
-            key: "new_length".to_string(),
-            value: new_length.to_string(),
+            key: "new_mint".to_string(),
+            value: new_mint.to_string()
         }],
         timestamp: now,
     });
@@ -115,22 +114,20 @@ pub fn update_max_description_length(ctx: Context<Update>, new_length: u64) -> R
     Ok(())
 }
 
-pub fn update_truth_basis(ctx: Context<Update>, new_basis: u64) -> Result<()> {
-    let now: i64 = 1736899200; // CRITICAL: Remove line in production!
-
-    // CRITICAL: Add line in production!let now: i64 = Clock::get()?.unix_timestamp;
+pub fn update_pro_mint(ctx: Context<Update>, new_mint: Pubkey) -> Result<()> {
+    let now = current_time()?;
 
     require!(
         ctx.accounts.authority.key() == ctx.accounts.admin.antitoken_multisig,
         ErrorCode::Unauthorised
     );
-    ctx.accounts.admin.truth_basis = new_basis;
+    ctx.accounts.admin.pro_mint_address = new_mint;
 
     emit!(AdminEvent {
-        action: "update_truth_basis".to_string(),
+        action: "update_pro_mint".to_string(),
         args: vec![KeyValue {
-            key: "new_basis".to_string(),
-            value: new_basis.to_string()
+            key: "new_mint".to_string(),
+            value: new_mint.to_string()
         }],
         timestamp: now,
     });
@@ -138,22 +135,24 @@ pub fn update_truth_basis(ctx: Context<Update>, new_basis: u64) -> Result<()> {
     Ok(())
 }
 
-pub fn update_float_basis(ctx: Context<Update>, new_basis: u64) -> Result<()> {
-    let now: i64 = 1736899200; // CRITICAL: Remove line in production!
-
-    // CRITICAL: Add line in production!let now: i64 = Clock::get()?.unix_timestamp;
+pub fn propose_multisig(ctx: Context<Update>, new_multisig: Pubkey) -> Result<()> {
+    let now = current_time()?;
 
     require!(
         ctx.accounts.authority.key() == ctx.accounts.admin.antitoken_multisig,
         ErrorCode::Unauthorised
     );
-    ctx.accounts.admin.float_basis = new_basis;
+    require!(
+        new_multisig != Pubkey::default(),
+        PredictError::InvalidParameter
+    );
+    ctx.accounts.admin.pending_multisig = Some(new_multisig);
 
     emit!(AdminEvent {
-        action: "update_float_basis".to_string(),
+        action: "propose_multisig".to_string(),
         args: vec![KeyValue {
-            key: "new_basis".to_string(),
-            value: new_basis.to_string(),
+            key: "new_multisig".to_string(),
+            value: new_multisig.to_string(),
         }],
         timestamp: now,
     });
@@ -161,22 +160,27 @@ pub fn update_float_basis(ctx: Context<Update>, new_basis: u64) -> Result<()> {
     Ok(())
 }
 
-pub fn update_min_deposit_amount(ctx: Context<Update>, new_min_amount: u64) -> Result<()> {
-    let now: i64 = 1736899200; // CRITICAL: Remove line in production!
-
-    // CRITICAL: Add line in production!let now: i64 = Clock::get()?.unix_timestamp;
+pub fn accept_multisig(ctx: Context<Update>) -> Result<()> {
+    let now = current_time()?;
 
+    // Only the proposed multisig can complete the handover
+    let pending_multisig = ctx
+        .accounts
+        .admin
+        .pending_multisig
+        .ok_or(error!(PredictError::NoPendingMultisig))?;
     require!(
-        ctx.accounts.authority.key() == ctx.accounts.admin.antitoken_multisig,
+        ctx.accounts.authority.key() == pending_multisig,
         ErrorCode::Unauthorised
     );
-    ctx.accounts.admin.min_deposit_amount = new_min_amount;
+    ctx.accounts.admin.antitoken_multisig = pending_multisig;
+    ctx.accounts.admin.pending_multisig = None;
 
     emit!(AdminEvent {
-        action: "update_min_deposit_amount".to_string(),
+        action: "accept_multisig".to_string(),
         args: vec![KeyValue {
-            key: "new_min_amount".to_string(),
-            value: new_min_amount.to_string()
+            key: "new_multisig".to_string(),
+            value: pending_multisig.to_string(),
         }],
         timestamp: now,
     });
@@ -184,22 +188,25 @@ pub fn update_min_deposit_amount(ctx: Context<Update>, new_min_amount: u64) -> R
     Ok(())
 }
 
-pub fn update_anti_mint(ctx: Context<Update>, new_mint: Pubkey) -> Result<()> {
-    let now: i64 = 1736899200; // CRITICAL: Remove line in production!
-
-    // CRITICAL: Add line in production!let now: i64 = Clock::get()?.unix_timestamp;
+pub fn cancel_multisig_proposal(ctx: Context<Update>) -> Result<()> {
+    let now = current_time()?;
 
     require!(
         ctx.accounts.authority.key() == ctx.accounts.admin.antitoken_multisig,
         ErrorCode::Unauthorised
     );
-    ctx.accounts.admin.anti_mint_address = new_mint;
+    let pending_multisig = ctx
+        .accounts
+        .admin
+        .pending_multisig
+        .take()
+        .ok_or(error!(PredictError::NoPendingMultisig))?;
 
     emit!(AdminEvent {
-        action: "update_anti_mint".to_string(),
+        action: "cancel_multisig_proposal".to_string(),
         args: vec![KeyValue {
-            key: "new_mint".to_string(),
-            value: new_mint.to_string()
+            key: "pending_multisig".to_string(),
+            value: pending_multisig.to_string(),
         }],
         timestamp: now,
     });
@@ -207,326 +214,356 @@ pub fn update_anti_mint(ctx: Context<Update>, new_mint: Pubkey) -> Result<()> {
     Ok(())
 }
 
-pub fn update_pro_mint(ctx: Context<Update>, new_mint: Pubkey) -> Result<()> {
-    let now: i64 = 1736899200; // CRITICAL: Remove line in production!
-
-    // CRITICAL: Add line in production!let now: i64 = Clock::get()?.unix_timestamp;
+pub fn queue_update(ctx: Context<Update>, parameter: AdminParameter, value: u64) -> Result<()> {
+    let now = current_time()?;
 
     require!(
         ctx.accounts.authority.key() == ctx.accounts.admin.antitoken_multisig,
         ErrorCode::Unauthorised
     );
-    ctx.accounts.admin.pro_mint_address = new_mint;
+
+    parameter.validate(value)?;
+
+    // One change per parameter may be pending at a time
+    let queue = &mut ctx.accounts.admin.queue;
+    require!(
+        queue.iter().all(|update| update.parameter != parameter),
+        PredictError::UpdateAlreadyQueued
+    );
+    let eta = now
+        .checked_add(TIMELOCK_DELAY)
+        .ok_or(error!(PredictError::MathError))?;
+    queue.push(QueuedUpdate {
+        parameter,
+        value,
+        eta,
+    });
 
     emit!(AdminEvent {
-        action: "update_pro_mint".to_string(),
-        args: vec![KeyValue {
-            key: "new_mint".to_string(),
-            value: new_mint.to_string()
-        }],
+        action: "queue_update".to_string(),
+        args: vec![
+            KeyValue {
+                key: "parameter".to_string(),
+                value: parameter.name().to_string(),
+            },
+            KeyValue {
+                key: "value".to_string(),
+                value: value.to_string(),
+            },
+            KeyValue {
+                key: "eta".to_string(),
+                value: eta.to_string(),
+            },
+        ],
         timestamp: now,
     });
 
     Ok(())
 }
 
-pub fn update_multisig(ctx: Context<Update>, new_multisig: Pubkey) -> Result<()> {
-    let now: i64 = 1736899200; // CRITICAL: Remove line in production!
-
-    // CRITICAL: Add line in production!let now: i64 = Clock::get()?.unix_timestamp;
+pub fn execute_update(ctx: Context<Update>, parameter: AdminParameter) -> Result<()> {
+    let now = current_time()?;
 
     require!(
         ctx.accounts.authority.key() == ctx.accounts.admin.antitoken_multisig,
         ErrorCode::Unauthorised
     );
-    ctx.accounts.admin.antitoken_multisig = new_multisig;
+
+    // Apply the queued change once its timelock has elapsed
+    let admin = &mut ctx.accounts.admin;
+    let position = admin
+        .queue
+        .iter()
+        .position(|update| update.parameter == parameter)
+        .ok_or(error!(PredictError::UpdateNotQueued))?;
+    require!(
+        now >= admin.queue[position].eta,
+        PredictError::TimelockActive
+    );
+    parameter.validate(admin.queue[position].value)?;
+    let update = admin.queue.remove(position);
+    match update.parameter {
+        AdminParameter::CreationFee => admin.creation_fee = update.value,
+        AdminParameter::MinDepositAmount => admin.min_deposit_amount = update.value,
+        AdminParameter::FloatBasis => admin.float_basis = update.value,
+        AdminParameter::TruthBasis => admin.truth_basis = update.value,
+        AdminParameter::ProtocolFeeBps => admin.protocol_fee_bps = update.value as u16,
+        AdminParameter::DisputeBond => admin.dispute_bond = update.value,
+        AdminParameter::ChallengePeriod => admin.challenge_period = update.value as i64,
+    }
 
     emit!(AdminEvent {
-        action: "update_multisig".to_string(),
-        args: vec![KeyValue {
-            key: "new_multisig".to_string(),
-            value: new_multisig.to_string()
-        }],
+        action: "execute_update".to_string(),
+        args: vec![
+            KeyValue {
+                key: "parameter".to_string(),
+                value: parameter.name().to_string(),
+            },
+            KeyValue {
+                key: "value".to_string(),
+                value: update.value.to_string(),
+            },
+        ],
         timestamp: now,
     });
 
     Ok(())
 }
 
-pub fn set_token_authority(ctx: Context<SetPredictionTokenAuthority>, index: u64) -> Result<()> {
-    let now: i64 = 1736899200; // CRITICAL: Remove line in production!
-
-    // CRITICAL: Add line in production!let now: i64 = Clock::get()?.unix_timestamp;
+pub fn cancel_update(ctx: Context<Update>, parameter: AdminParameter) -> Result<()> {
+    let now = current_time()?;
 
-    // Verify only ANTITOKEN_MULTISIG can execute this
     require!(
-        ctx.accounts.authority.key() == ANTITOKEN_MULTISIG,
+        ctx.accounts.authority.key() == ctx.accounts.admin.antitoken_multisig,
         ErrorCode::Unauthorised
     );
 
-    // Transfer authority of $ANTI token account to state PDA
-    token::set_authority(
-        CpiContext::new(
-            ctx.accounts.token_program.to_account_info(),
-            SetAuthority {
-                current_authority: ctx.accounts.authority.to_account_info(),
-                account_or_mint: ctx.accounts.prediction_anti_token.to_account_info(),
+    let queue = &mut ctx.accounts.admin.queue;
+    let position = queue
+        .iter()
+        .position(|update| update.parameter == parameter)
+        .ok_or(error!(PredictError::UpdateNotQueued))?;
+    let update = queue.remove(position);
+
+    emit!(AdminEvent {
+        action: "cancel_update".to_string(),
+        args: vec![
+            KeyValue {
+                key: "parameter".to_string(),
+                value: parameter.name().to_string(),
             },
-        ),
-        AuthorityType::AccountOwner,
-        Some(ctx.accounts.state.key()),
-    )?;
-
-    // Transfer authority of $PRO token account to state PDA
-    token::set_authority(
-        CpiContext::new(
-            ctx.accounts.token_program.to_account_info(),
-            SetAuthority {
-                current_authority: ctx.accounts.authority.to_account_info(),
-                account_or_mint: ctx.accounts.prediction_pro_token.to_account_info(),
+            KeyValue {
+                key: "value".to_string(),
+                value: update.value.to_string(),
             },
-        ),
-        AuthorityType::AccountOwner,
-        Some(ctx.accounts.state.key()),
-    )?;
+        ],
+        timestamp: now,
+    });
+
+    Ok(())
+}
+
+pub fn queue_treasury(ctx: Context<Update>, new_treasury: Pubkey) -> Result<()> {
+    let now = current_time()?;
+
+    require!(
+        ctx.accounts.authority.key() == ctx.accounts.admin.antitoken_multisig,
+        ErrorCode::Unauthorised
+    );
+    require!(
+        new_treasury != Pubkey::default(),
+        PredictError::InvalidParameter
+    );
+
+    // Fees follow the treasury, so a new one waits out the same timelock
+    let admin = &mut ctx.accounts.admin;
+    require!(
+        admin.pending_treasury.is_none(),
+        PredictError::UpdateAlreadyQueued
+    );
+    let eta = now
+        .checked_add(TIMELOCK_DELAY)
+        .ok_or(error!(PredictError::MathError))?;
+    admin.pending_treasury = Some(QueuedTreasury {
+        treasury: new_treasury,
+        eta,
+    });
 
     emit!(AdminEvent {
-        action: "set_token_authority".to_string(),
-        args: vec![KeyValue {
-            key: "index".to_string(),
-            value: index.to_string()
-        }],
+        action: "queue_treasury".to_string(),
+        args: vec![
+            KeyValue {
+                key: "new_treasury".to_string(),
+                value: new_treasury.to_string(),
+            },
+            KeyValue {
+                key: "eta".to_string(),
+                value: eta.to_string(),
+            },
+        ],
         timestamp: now,
     });
 
     Ok(())
 }
 
-/// Error codes
-#[error_code]
-pub enum ErrorCode {
-    #[msg("Unauthorised")]
-    Unauthorised,
-}
+pub fn execute_treasury(ctx: Context<Update>) -> Result<()> {
+    let now = current_time()?;
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use crate::{state::AdminAccount, utils::PROGRAM_ID, AdminBumps};
-    use crate::{
-        Deposit, Equalisation, PredictionAccount, SetPredictionTokenAuthorityBumps, StateAccount,
-        UpdateBumps,
-    };
-    use anchor_lang::{system_program, Discriminator};
-    use anchor_spl::token::{
-        spl_token, spl_token::state::Account as SplTokenAccount, Mint, TokenAccount,
-    };
-    use solana_program::program_pack::Pack;
-    use solana_sdk::program_option::COption;
-    use solana_sdk::signature::{Keypair, Signer as _};
-    use std::str::FromStr;
+    require!(
+        ctx.accounts.authority.key() == ctx.accounts.admin.antitoken_multisig,
+        ErrorCode::Unauthorised
+    );
 
-    struct TestAccountData {
-        key: Pubkey,
-        lamports: u64,
-        data: Vec<u8>,
-        owner: Pubkey,
-        executable: bool,
-        rent_epoch: u64,
-    }
+    let admin = &mut ctx.accounts.admin;
+    let pending = admin
+        .pending_treasury
+        .clone()
+        .ok_or(error!(PredictError::UpdateNotQueued))?;
+    require!(now >= pending.eta, PredictError::TimelockActive);
+    admin.treasury = pending.treasury;
+    admin.pending_treasury = None;
 
-    impl TestAccountData {
-        fn new_account_with_key_and_owner<T: AccountSerialize + AccountDeserialize + Clone>(
-            key: Pubkey,
-            owner: Pubkey,
-        ) -> Self {
-            Self {
-                key,
-                lamports: 1_000_000,
-                data: vec![0; 8 + PredictionAccount::LEN],
-                owner,
-                executable: true,
-                rent_epoch: 0,
-            }
-        }
-        fn new_owned_admin<T: AccountSerialize + AccountDeserialize + Clone>(
-            key: Pubkey,
-            owner: Pubkey,
-        ) -> Self {
-            Self {
-                key,
-                lamports: 1_000_000,
-                data: vec![0; 8 + AdminAccount::LEN],
-                owner,
-                executable: true,
-                rent_epoch: 0,
-            }
-        }
+    emit!(AdminEvent {
+        action: "execute_treasury".to_string(),
+        args: vec![KeyValue {
+            key: "new_treasury".to_string(),
+            value: pending.treasury.to_string(),
+        }],
+        timestamp: now,
+    });
 
-        fn new_token_program() -> Self {
-            Self {
-                key: spl_token::ID,
-                lamports: 1_000_000,
-                data: vec![],
-                owner: Pubkey::default(),
-                executable: true,
-                rent_epoch: 0,
-            }
-        }
+    Ok(())
+}
 
-        fn new_system_account() -> Self {
-            Self {
-                key: system_program::ID,
-                lamports: 1_000_000,
-                data: vec![],
-                owner: system_program::ID,
-                executable: true,
-                rent_epoch: 0,
-            }
-        }
+pub fn cancel_treasury(ctx: Context<Update>) -> Result<()> {
+    let now = current_time()?;
 
-        fn new_authority_account(pubkey: Pubkey) -> Self {
-            Self {
-                key: pubkey,
-                lamports: 1_000_000,
-                data: vec![],
-                owner: system_program::ID,
-                executable: true,
-                rent_epoch: 0,
-            }
-        }
+    require!(
+        ctx.accounts.authority.key() == ctx.accounts.admin.antitoken_multisig,
+        ErrorCode::Unauthorised
+    );
+    let pending = ctx
+        .accounts
+        .admin
+        .pending_treasury
+        .take()
+        .ok_or(error!(PredictError::UpdateNotQueued))?;
 
-        fn new_mint(mint: Pubkey) -> Self {
-            Self {
-                key: mint,
-                lamports: 1_000_000,
-                data: vec![0; Mint::LEN],
-                owner: spl_token::ID,
-                executable: false,
-                rent_epoch: 0,
-            }
-        }
+    emit!(AdminEvent {
+        action: "cancel_treasury".to_string(),
+        args: vec![KeyValue {
+            key: "new_treasury".to_string(),
+            value: pending.treasury.to_string(),
+        }],
+        timestamp: now,
+    });
 
-        fn new_token(key: Pubkey) -> Self {
-            Self {
-                key,
-                lamports: 1_000_000,
-                data: vec![0; 165],
-                owner: spl_token::ID,
-                executable: false,
-                rent_epoch: 0,
-            }
-        }
+    Ok(())
+}
 
-        fn to_account_info<'a>(&'a mut self, is_signer: bool) -> AccountInfo<'a> {
-            AccountInfo::new(
-                &self.key,
-                is_signer,
-                true,
-                &mut self.lamports,
-                &mut self.data,
-                &self.owner,
-                self.executable,
-                self.rent_epoch,
-            )
-        }
+pub fn update_arbiter(ctx: Context<Update>, new_arbiter: Pubkey) -> Result<()> {
+    let now = current_time()?;
 
-        fn init_token_account(&mut self, owner: Pubkey, mint: Pubkey) -> Result<()> {
-            self.data = vec![0; TokenAccount::LEN]; // Ensure correct buffer size
-            let data = self.data.as_mut_slice();
-
-            let close_authority: COption<Pubkey> = COption::None;
-
-            // Initialise a new SPL Token Account manually
-            let token_account = SplTokenAccount {
-                mint,
-                owner,
-                amount: 0,
-                delegate: None.into(),
-                state: spl_token::state::AccountState::Initialized,
-                is_native: None.into(),
-                delegated_amount: 0,
-                close_authority,
-            };
+    require!(
+        ctx.accounts.authority.key() == ctx.accounts.admin.antitoken_multisig,
+        ErrorCode::Unauthorised
+    );
+    ctx.accounts.admin.arbiter = new_arbiter;
 
-            token_account.pack_into_slice(data);
-            self.owner = spl_token::ID;
+    emit!(AdminEvent {
+        action: "update_arbiter".to_string(),
+        args: vec![KeyValue {
+            key: "new_arbiter".to_string(),
+            value: new_arbiter.to_string(),
+        }],
+        timestamp: now,
+    });
 
-            Ok(())
-        }
+    Ok(())
+}
 
-        fn init_state_data(&mut self, state: &StateAccount) -> Result<()> {
-            self.data = vec![0; 8 + StateAccount::LEN];
-            let data = self.data.as_mut_slice();
+pub fn collect_fees(ctx: Context<CollectFees>) -> Result<()> {
+    let now = current_time()?;
 
-            let disc = StateAccount::discriminator();
-            data[..8].copy_from_slice(&disc);
+    require!(
+        ctx.accounts.authority.key() == ctx.accounts.admin.antitoken_multisig,
+        ErrorCode::Unauthorised
+    );
+    require!(
+        ctx.accounts.treasury.key() == ctx.accounts.admin.treasury,
+        PredictError::InvalidTokenAccount
+    );
 
-            let account_data = state.try_to_vec()?;
-            data[8..8 + account_data.len()].copy_from_slice(&account_data);
+    // Creation fees are everything the state account holds above rent exemption
+    let state_info = ctx.accounts.state.to_account_info();
+    let rent_exempt = ctx.accounts.rent.minimum_balance(state_info.data_len());
+    let amount = state_info.lamports().saturating_sub(rent_exempt);
+
+    let state_lamports = state_info
+        .lamports()
+        .checked_sub(amount)
+        .ok_or(error!(PredictError::MathError))?;
+    let treasury_lamports = ctx
+        .accounts
+        .treasury
+        .lamports()
+        .checked_add(amount)
+        .ok_or(error!(PredictError::MathError))?;
+    **state_info.try_borrow_mut_lamports()? = state_lamports;
+    **ctx.accounts.treasury.try_borrow_mut_lamports()? = treasury_lamports;
 
-            Ok(())
-        }
+    emit!(AdminEvent {
+        action: "collect_fees".to_string(),
+        args: vec![
+            KeyValue {
+                key: "amount".to_string(),
+                value: amount.to_string(),
+            },
+            KeyValue {
+                key: "treasury".to_string(),
+                value: ctx.accounts.treasury.key().to_string(),
+            },
+        ],
+        timestamp: now,
+    });
 
-        fn init_admin_data(&mut self, admin: &AdminAccount) -> Result<()> {
-            self.data = vec![0; 8 + AdminAccount::LEN];
-            let data = self.data.as_mut_slice();
+    Ok(())
+}
 
-            let disc = AdminAccount::discriminator();
-            data[..8].copy_from_slice(&disc);
+/// Error codes
+#[error_code]
+pub enum ErrorCode {
+    #[msg("Unauthorised")]
+    Unauthorised,
+}
 
-            let account_data = admin.try_to_vec()?;
-            data[8..8 + account_data.len()].copy_from_slice(&account_data);
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::*;
+    use crate::{state::AdminAccount, utils::PROGRAM_ID, AdminBumps};
+    use crate::{state::StateAccount, CollectFeesBumps, UpdateBumps};
+    use anchor_lang::Discriminator;
+    use solana_sdk::signature::{Keypair, Signer as _};
+    use std::str::FromStr;
 
-            Ok(())
+    fn new_owned_admin(key: Pubkey, owner: Pubkey) -> TestAccountData {
+        TestAccountData {
+            key,
+            lamports: 1_000_000,
+            data: vec![0; 8 + AdminAccount::LEN],
+            owner,
+            executable: true,
+            rent_epoch: 0,
         }
+    }
 
-        // Reusable method to create an equalised test prediction
-        fn create_equalised_test_prediction(authority: Pubkey, index: u64) -> PredictionAccount {
-            PredictionAccount {
-                index: index,
-                title: "Test Prediction".to_string(),
-                description: "Test Description".to_string(),
-                start_time: "2025-01-01T00:00:00Z".to_string(),
-                end_time: "2025-01-02T00:00:00Z".to_string(), // Already ended
-                etc: None,
-                anti: 70000,
-                pro: 30000,
-                deposits: vec![Deposit {
-                    address: authority,
-                    anti: 70000,
-                    pro: 30000,
-                    mean: 40000,
-                    stddev: 100000,
-                    withdrawn: false,
-                }],
-                equalised: true,
-                equalisation: Some(Equalisation {
-                    truth: vec![60000, 40000],
-                    anti: vec![70000],
-                    pro: vec![30000],
-                    timestamp: 0,
-                }),
-            }
+    fn new_state_account(key: Pubkey, owner: Pubkey, lamports: u64) -> TestAccountData {
+        let mut data = vec![0; 8 + StateAccount::LEN];
+        data[..8].copy_from_slice(&StateAccount::discriminator());
+        TestAccountData {
+            key,
+            lamports,
+            data,
+            owner,
+            executable: false,
+            rent_epoch: 0,
         }
+    }
 
-        // Initialise admin config
-        const ADMIN_DATA: AdminAccount = AdminAccount {
+    // Admin config awaiting initialisation
+    fn uninitialised_admin() -> AdminAccount {
+        AdminAccount {
             initialised: false,
             creation_fee: 100_000_000,
-            max_title_length: MAX_TITLE_LENGTH,
-            max_description_length: MAX_DESCRIPTION_LENGTH,
-            truth_basis: TRUTH_BASIS,
-            float_basis: FLOAT_BASIS,
-            min_deposit_amount: MIN_DEPOSIT_AMOUNT,
-            antitoken_multisig: ANTITOKEN_MULTISIG,
-            anti_mint_address: ANTI_MINT_ADDRESS,
-            pro_mint_address: PRO_MINT_ADDRESS,
-        };
+            ..test_admin()
+        }
     }
 
     #[test]
-    fn test_admin_initialisation<'info>() {
+    fn test_admin_initialisation() {
+        set_clock_override(Some(1736899200));
         let program_id = Pubkey::from_str(&PROGRAM_ID.to_string()).unwrap();
         let manager = Keypair::new();
 
@@ -534,11 +571,11 @@ mod tests {
         let (admin_pda, admin_bump) = Pubkey::find_program_address(&[b"admin"], &program_id);
 
         // Test initialisation
-        let mut admin = TestAccountData::new_owned_admin::<AdminAccount>(admin_pda, program_id);
+        let mut admin = new_owned_admin(admin_pda, program_id);
         let mut authority = TestAccountData::new_authority_account(manager.pubkey());
         let mut system = TestAccountData::new_system_account();
 
-        admin.init_admin_data(&TestAccountData::ADMIN_DATA).unwrap();
+        admin.init_admin_data(&uninitialised_admin()).unwrap();
 
         let admin_info = admin.to_account_info(false);
         let authority_info = authority.to_account_info(true);
@@ -558,10 +595,8 @@ mod tests {
         let result = initialise_admin(Context::new(&program_id, &mut accounts, &[], bumps));
 
         // If the test fails, print the error
-        if result.is_err() {
-            println!("Error: {:?}", result.unwrap_err());
-        } else {
-            assert!(result.is_ok());
+        if let Err(error) = &result {
+            println!("Error: {:?}", error);
         }
 
         // Verify all config after initialisation
@@ -635,16 +670,17 @@ mod tests {
     // Additional test for double initialisation prevention
     #[test]
     fn test_double_initialisation_prevented() {
+        set_clock_override(Some(1736899200));
         let program_id = Pubkey::from_str(&PROGRAM_ID.to_string()).unwrap();
         let manager = Keypair::new();
 
         // Create test accounts
         let (admin_pda, admin_bump) = Pubkey::find_program_address(&[b"admin"], &program_id);
-        let mut admin = TestAccountData::new_owned_admin::<AdminAccount>(admin_pda, program_id);
+        let mut admin = new_owned_admin(admin_pda, program_id);
         let mut authority = TestAccountData::new_authority_account(manager.pubkey());
         let mut system = TestAccountData::new_system_account();
 
-        admin.init_admin_data(&TestAccountData::ADMIN_DATA).unwrap();
+        admin.init_admin_data(&uninitialised_admin()).unwrap();
 
         // First initialisation
         let admin_info = admin.to_account_info(false);
@@ -684,16 +720,17 @@ mod tests {
     // Test unauthorised updates
     #[test]
     fn test_unauthorised_updates() {
+        set_clock_override(Some(1736899200));
         let program_id = Pubkey::from_str(&PROGRAM_ID.to_string()).unwrap();
         let unauthorised_user = Keypair::new();
 
         // Create test accounts
         let (admin_pda, admin_bump) = Pubkey::find_program_address(&[b"admin"], &program_id);
 
-        let mut admin = TestAccountData::new_owned_admin::<AdminAccount>(admin_pda, program_id);
+        let mut admin = new_owned_admin(admin_pda, program_id);
         let mut manager = TestAccountData::new_authority_account(unauthorised_user.pubkey());
 
-        admin.init_admin_data(&TestAccountData::ADMIN_DATA).unwrap();
+        admin.init_admin_data(&uninitialised_admin()).unwrap();
 
         let admin_info = admin.to_account_info(false);
         let authority_info = manager.to_account_info(true);
@@ -707,8 +744,9 @@ mod tests {
         let bumps: UpdateBumps = UpdateBumps { admin: admin_bump };
 
         // Test unauthorised fee update
-        let result = update_creation_fee(
+        let result = queue_update(
             Context::new(&program_id, &mut accounts, &[], bumps),
+            AdminParameter::CreationFee,
             200_000_000,
         );
         assert!(result.is_err(), "Unauthorised update should fail");
@@ -727,18 +765,18 @@ mod tests {
     // Test successful updates
     #[test]
     fn test_successful_updates() {
+        set_clock_override(Some(1736899200));
         let program_id = Pubkey::from_str(&PROGRAM_ID.to_string()).unwrap();
 
         // Create test accounts with multisig authority
         let (admin_pda, admin_bump) = Pubkey::find_program_address(&[b"admin"], &program_id);
-        let mut admin_account =
-            TestAccountData::new_owned_admin::<AdminAccount>(admin_pda, program_id);
+        let mut admin_account = new_owned_admin(admin_pda, program_id);
 
         admin_account
-            .init_admin_data(&TestAccountData::ADMIN_DATA)
+            .init_admin_data(&uninitialised_admin())
             .unwrap(); // Now using correct variable names
 
-        // Test fee update
+        // Test title length update
         {
             let bumps = UpdateBumps { admin: admin_bump };
 
@@ -751,155 +789,607 @@ mod tests {
                 authority: Signer::try_from(&authority_info).unwrap(),
             };
 
-            let new_fee = 200_000_000;
-            let result = update_creation_fee(
+            let new_length = 128;
+            let result = update_max_title_length(
                 Context::new(&program_id, &mut accounts, &[], bumps),
-                new_fee,
+                new_length,
             );
 
-            assert!(result.is_ok(), "Authorised fee update should succeed");
+            assert!(result.is_ok(), "Authorised length update should succeed");
 
             assert_eq!(
-                accounts.admin.creation_fee, new_fee,
-                "Fee should be updated"
+                accounts.admin.max_title_length, new_length,
+                "Title length should be updated"
             );
             assert_ne!(
-                CREATION_FEE, accounts.admin.creation_fee,
-                "Fee should have changed"
+                MAX_TITLE_LENGTH, accounts.admin.max_title_length,
+                "Title length should have changed"
             );
         }
     }
 
+    // Test queued parameter updates behind the timelock
     #[test]
-    fn test_set_token_authority() {
+    fn test_timelocked_updates() {
         let program_id = Pubkey::from_str(&PROGRAM_ID.to_string()).unwrap();
-        let manager: Pubkey = Pubkey::new_unique();
-        let mut token_program = TestAccountData::new_token_program();
-
-        let index: u64 = 0;
+        let (admin_pda, admin_bump) = Pubkey::find_program_address(&[b"admin"], &program_id);
+        let mut admin_account = new_owned_admin(admin_pda, program_id);
+        admin_account
+            .init_admin_data(&uninitialised_admin())
+            .unwrap();
 
-        // Create prediction with user's deposit and equalisation results
-        let prediction_data =
-            TestAccountData::create_equalised_test_prediction(manager.key(), index);
+        let admin_info = admin_account.to_account_info(false);
+        let mut authority_binding = TestAccountData::new_authority_account(ANTITOKEN_MULTISIG);
+        let authority_info = authority_binding.to_account_info(true);
+        let mut accounts = Update {
+            admin: Account::try_from(&admin_info).unwrap(),
+            authority: Signer::try_from(&authority_info).unwrap(),
+        };
 
-        // Create mints
-        let anti_mint = TestAccountData::new_mint(ANTI_MINT_ADDRESS);
-        let pro_mint = TestAccountData::new_mint(PRO_MINT_ADDRESS);
+        let now = 1736899200;
+        set_clock_override(Some(now));
+        let new_fee = 200_000_000;
+        queue_update(
+            Context::new(
+                &program_id,
+                &mut accounts,
+                &[],
+                UpdateBumps { admin: admin_bump },
+            ),
+            AdminParameter::CreationFee,
+            new_fee,
+        )
+        .unwrap();
+        assert_eq!(
+            accounts.admin.queue,
+            vec![QueuedUpdate {
+                parameter: AdminParameter::CreationFee,
+                value: new_fee,
+                eta: now + TIMELOCK_DELAY,
+            }]
+        );
+        assert_eq!(accounts.admin.creation_fee, CREATION_FEE);
+
+        // A parameter can only be queued once
+        let result = queue_update(
+            Context::new(
+                &program_id,
+                &mut accounts,
+                &[],
+                UpdateBumps { admin: admin_bump },
+            ),
+            AdminParameter::CreationFee,
+            new_fee,
+        );
+        assert_eq!(
+            result.unwrap_err(),
+            PredictError::UpdateAlreadyQueued.into()
+        );
 
-        // Initialise state account
-        let authority = TestAccountData::new_authority_account(manager);
-        let mut state = TestAccountData::new_account_with_key_and_owner::<StateAccount>(
-            authority.key,
-            program_id,
+        // Execution waits for the delay to elapse
+        set_clock_override(Some(now + TIMELOCK_DELAY - 1));
+        let result = execute_update(
+            Context::new(
+                &program_id,
+                &mut accounts,
+                &[],
+                UpdateBumps { admin: admin_bump },
+            ),
+            AdminParameter::CreationFee,
         );
-        state
-            .init_state_data(&StateAccount {
-                index: 0,
-                authority: manager,
-            })
+        assert_eq!(result.unwrap_err(), PredictError::TimelockActive.into());
+        assert_eq!(accounts.admin.creation_fee, CREATION_FEE);
+
+        set_clock_override(Some(now + TIMELOCK_DELAY));
+        execute_update(
+            Context::new(
+                &program_id,
+                &mut accounts,
+                &[],
+                UpdateBumps { admin: admin_bump },
+            ),
+            AdminParameter::CreationFee,
+        )
+        .unwrap();
+        assert_eq!(accounts.admin.creation_fee, new_fee);
+        assert!(accounts.admin.queue.is_empty());
+
+        // Cancelled updates are never applied
+        queue_update(
+            Context::new(
+                &program_id,
+                &mut accounts,
+                &[],
+                UpdateBumps { admin: admin_bump },
+            ),
+            AdminParameter::TruthBasis,
+            TRUTH_BASIS * 2,
+        )
+        .unwrap();
+        cancel_update(
+            Context::new(
+                &program_id,
+                &mut accounts,
+                &[],
+                UpdateBumps { admin: admin_bump },
+            ),
+            AdminParameter::TruthBasis,
+        )
+        .unwrap();
+        assert!(accounts.admin.queue.is_empty());
+        let result = execute_update(
+            Context::new(
+                &program_id,
+                &mut accounts,
+                &[],
+                UpdateBumps { admin: admin_bump },
+            ),
+            AdminParameter::TruthBasis,
+        );
+        assert_eq!(result.unwrap_err(), PredictError::UpdateNotQueued.into());
+        assert_eq!(accounts.admin.truth_basis, TRUTH_BASIS);
+
+        // Values outside a parameter's bounds cannot be queued
+        for (parameter, value, error) in [
+            (
+                AdminParameter::FloatBasis,
+                0,
+                PredictError::InvalidParameter,
+            ),
+            (
+                AdminParameter::TruthBasis,
+                0,
+                PredictError::InvalidParameter,
+            ),
+            (
+                AdminParameter::ProtocolFeeBps,
+                MAX_PROTOCOL_FEE_BPS as u64 + 1,
+                PredictError::InvalidFee,
+            ),
+            (
+                AdminParameter::DisputeBond,
+                MIN_DISPUTE_BOND - 1,
+                PredictError::InvalidParameter,
+            ),
+            (
+                AdminParameter::ChallengePeriod,
+                0,
+                PredictError::InvalidParameter,
+            ),
+            (
+                AdminParameter::ChallengePeriod,
+                i64::MAX as u64 + 1,
+                PredictError::InvalidParameter,
+            ),
+        ] {
+            let result = queue_update(
+                Context::new(
+                    &program_id,
+                    &mut accounts,
+                    &[],
+                    UpdateBumps { admin: admin_bump },
+                ),
+                parameter,
+                value,
+            );
+            assert_eq!(result.unwrap_err(), error.into());
+        }
+        assert!(accounts.admin.queue.is_empty());
+
+        // Nor applied, should one reach the queue
+        accounts.admin.queue.push(QueuedUpdate {
+            parameter: AdminParameter::FloatBasis,
+            value: 0,
+            eta: now,
+        });
+        let result = execute_update(
+            Context::new(
+                &program_id,
+                &mut accounts,
+                &[],
+                UpdateBumps { admin: admin_bump },
+            ),
+            AdminParameter::FloatBasis,
+        );
+        assert_eq!(result.unwrap_err(), PredictError::InvalidParameter.into());
+        assert_eq!(accounts.admin.float_basis, FLOAT_BASIS);
+        accounts.admin.queue.clear();
+
+        // Dispute and fee parameters go through the timelock too
+        set_clock_override(Some(now));
+        let updates = [
+            (AdminParameter::ProtocolFeeBps, MAX_PROTOCOL_FEE_BPS as u64),
+            (AdminParameter::DisputeBond, DISPUTE_BOND * 2),
+            (AdminParameter::ChallengePeriod, CHALLENGE_PERIOD as u64 * 2),
+        ];
+        for (parameter, value) in updates {
+            queue_update(
+                Context::new(
+                    &program_id,
+                    &mut accounts,
+                    &[],
+                    UpdateBumps { admin: admin_bump },
+                ),
+                parameter,
+                value,
+            )
             .unwrap();
+        }
+        set_clock_override(Some(now + TIMELOCK_DELAY));
+        for (parameter, _) in updates {
+            execute_update(
+                Context::new(
+                    &program_id,
+                    &mut accounts,
+                    &[],
+                    UpdateBumps { admin: admin_bump },
+                ),
+                parameter,
+            )
+            .unwrap();
+        }
+        assert_eq!(accounts.admin.protocol_fee_bps, MAX_PROTOCOL_FEE_BPS);
+        assert_eq!(accounts.admin.dispute_bond, DISPUTE_BOND * 2);
+        assert_eq!(accounts.admin.challenge_period, CHALLENGE_PERIOD * 2);
+        assert!(accounts.admin.queue.is_empty());
+    }
 
-        // Create test accounts
-        let (admin_pda, _admin_bump) = Pubkey::find_program_address(&[b"admin"], &program_id);
-        let (_state_pda, state_bump) = Pubkey::find_program_address(&[b"state"], &program_id);
-        let (anti_token_pda, anti_token_bump) = Pubkey::find_program_address(
-            &[b"anti_token", index.to_le_bytes().as_ref()],
+    // Test two-step multisig handover
+    #[test]
+    fn test_multisig_handover() {
+        set_clock_override(Some(1736899200));
+        let program_id = Pubkey::from_str(&PROGRAM_ID.to_string()).unwrap();
+        let new_multisig = Keypair::new().pubkey();
+        let (admin_pda, admin_bump) = Pubkey::find_program_address(&[b"admin"], &program_id);
+        let mut admin_account = new_owned_admin(admin_pda, program_id);
+        admin_account
+            .init_admin_data(&uninitialised_admin())
+            .unwrap();
+
+        let admin_info = admin_account.to_account_info(false);
+        let mut current_binding = TestAccountData::new_authority_account(ANTITOKEN_MULTISIG);
+        let current_info = current_binding.to_account_info(true);
+        let mut new_binding = TestAccountData::new_authority_account(new_multisig);
+        let new_info = new_binding.to_account_info(true);
+        let mut accounts = Update {
+            admin: Account::try_from(&admin_info).unwrap(),
+            authority: Signer::try_from(&current_info).unwrap(),
+        };
+
+        // Nothing to accept before a proposal
+        accounts.authority = Signer::try_from(&new_info).unwrap();
+        let result = accept_multisig(Context::new(
             &program_id,
+            &mut accounts,
+            &[],
+            UpdateBumps { admin: admin_bump },
+        ));
+        assert_eq!(result.unwrap_err(), PredictError::NoPendingMultisig.into());
+
+        // Only the current multisig can propose
+        let result = propose_multisig(
+            Context::new(
+                &program_id,
+                &mut accounts,
+                &[],
+                UpdateBumps { admin: admin_bump },
+            ),
+            new_multisig,
         );
-        let (pro_token_pda, pro_token_bump) = Pubkey::find_program_address(
-            &[b"pro_token", index.to_le_bytes().as_ref()],
-            &program_id,
+        assert_eq!(result.unwrap_err(), ErrorCode::Unauthorised.into());
+
+        accounts.authority = Signer::try_from(&current_info).unwrap();
+
+        // Nor hand over to the default key, which no one can sign for
+        let result = propose_multisig(
+            Context::new(
+                &program_id,
+                &mut accounts,
+                &[],
+                UpdateBumps { admin: admin_bump },
+            ),
+            Pubkey::default(),
         );
+        assert_eq!(result.unwrap_err(), PredictError::InvalidParameter.into());
+        assert_eq!(accounts.admin.pending_multisig, None);
+
+        propose_multisig(
+            Context::new(
+                &program_id,
+                &mut accounts,
+                &[],
+                UpdateBumps { admin: admin_bump },
+            ),
+            new_multisig,
+        )
+        .unwrap();
+        assert_eq!(accounts.admin.pending_multisig, Some(new_multisig));
+        assert_eq!(accounts.admin.antitoken_multisig, ANTITOKEN_MULTISIG);
+
+        // A proposal withdrawn by the current multisig can no longer be accepted
+        cancel_multisig_proposal(Context::new(
+            &program_id,
+            &mut accounts,
+            &[],
+            UpdateBumps { admin: admin_bump },
+        ))
+        .unwrap();
+        assert!(accounts.admin.pending_multisig.is_none());
+        let result = cancel_multisig_proposal(Context::new(
+            &program_id,
+            &mut accounts,
+            &[],
+            UpdateBumps { admin: admin_bump },
+        ));
+        assert_eq!(result.unwrap_err(), PredictError::NoPendingMultisig.into());
+        accounts.authority = Signer::try_from(&new_info).unwrap();
+        let result = accept_multisig(Context::new(
+            &program_id,
+            &mut accounts,
+            &[],
+            UpdateBumps { admin: admin_bump },
+        ));
+        assert_eq!(result.unwrap_err(), PredictError::NoPendingMultisig.into());
+
+        // Only the current multisig can withdraw a proposal
+        accounts.authority = Signer::try_from(&current_info).unwrap();
+        propose_multisig(
+            Context::new(
+                &program_id,
+                &mut accounts,
+                &[],
+                UpdateBumps { admin: admin_bump },
+            ),
+            new_multisig,
+        )
+        .unwrap();
+        accounts.authority = Signer::try_from(&new_info).unwrap();
+        let result = cancel_multisig_proposal(Context::new(
+            &program_id,
+            &mut accounts,
+            &[],
+            UpdateBumps { admin: admin_bump },
+        ));
+        assert_eq!(result.unwrap_err(), ErrorCode::Unauthorised.into());
+        assert_eq!(accounts.admin.pending_multisig, Some(new_multisig));
+        accounts.authority = Signer::try_from(&current_info).unwrap();
+
+        // Only the proposed multisig can accept
+        let result = accept_multisig(Context::new(
+            &program_id,
+            &mut accounts,
+            &[],
+            UpdateBumps { admin: admin_bump },
+        ));
+        assert_eq!(result.unwrap_err(), ErrorCode::Unauthorised.into());
+
+        accounts.authority = Signer::try_from(&new_info).unwrap();
+        accept_multisig(Context::new(
+            &program_id,
+            &mut accounts,
+            &[],
+            UpdateBumps { admin: admin_bump },
+        ))
+        .unwrap();
+        assert_eq!(accounts.admin.antitoken_multisig, new_multisig);
+        assert!(accounts.admin.pending_multisig.is_none());
+    }
 
-        let mut admin = TestAccountData::new_owned_admin::<AdminAccount>(admin_pda, program_id);
-        admin.init_admin_data(&TestAccountData::ADMIN_DATA).unwrap();
-
-        // Create test prediction account
-        let mut prediction = TestAccountData::new_account_with_key_and_owner::<PredictionAccount>(
-            Pubkey::new_unique(),
-            program_id,
-        );
-        let mut prediction_anti_token = TestAccountData::new_token(anti_token_pda);
-        let mut prediction_pro_token = TestAccountData::new_token(pro_token_pda);
+    #[test]
+    fn test_collect_fees() {
+        set_clock_override(Some(1736899200));
+        let program_id = Pubkey::from_str(&PROGRAM_ID.to_string()).unwrap();
+        let (admin_pda, admin_bump) = Pubkey::find_program_address(&[b"admin"], &program_id);
+        let (state_pda, state_bump) = Pubkey::find_program_address(&[b"state"], &program_id);
 
-        prediction_anti_token
-            .init_token_account(ANTITOKEN_MULTISIG, anti_mint.key)
-            .unwrap();
-        prediction_pro_token
-            .init_token_account(ANTITOKEN_MULTISIG, pro_mint.key)
+        // State account holding two creation fees on top of its rent-exempt minimum
+        let rent_exempt = Rent::default().minimum_balance(8 + StateAccount::LEN);
+        let fees = 2 * CREATION_FEE;
+        let mut admin_account = new_owned_admin(admin_pda, program_id);
+        admin_account
+            .init_admin_data(&uninitialised_admin())
             .unwrap();
+        let mut state = new_state_account(state_pda, program_id, rent_exempt + fees);
+        let mut treasury = TestAccountData::new_authority_account(ANTITOKEN_MULTISIG);
+        let mut multisig = TestAccountData::new_authority_account(ANTITOKEN_MULTISIG);
+        let mut stranger = TestAccountData::new_authority_account(Pubkey::new_unique());
+        let mut rent = TestAccountData::new_rent_account();
 
-        // Write discriminator and serialise prediction data
-        prediction.data[..8].copy_from_slice(&PredictionAccount::discriminator());
-        let serialised_prediction = prediction_data.try_to_vec().unwrap();
-        prediction.data[8..8 + serialised_prediction.len()].copy_from_slice(&serialised_prediction);
-
-        // Get account infos
+        let admin_info = admin_account.to_account_info(false);
         let state_info = state.to_account_info(false);
-        let prediction_info = prediction.to_account_info(false);
-        let mut authority_binding = TestAccountData::new_authority_account(ANTITOKEN_MULTISIG);
-        let authority_info = authority_binding.to_account_info(true);
-        let token_data = {
-            let data_slice = &prediction_anti_token.data[..];
-            SplTokenAccount::unpack(data_slice).expect("Failed to unpack token account")
-        };
-        let prediction_anti_token_info = prediction_anti_token.to_account_info(false);
-        let prediction_pro_token_info = prediction_pro_token.to_account_info(false);
-        let token_program_info = token_program.to_account_info(false);
+        let treasury_info = treasury.to_account_info(false);
+        let multisig_info = multisig.to_account_info(true);
+        let stranger_info = stranger.to_account_info(true);
+        let rent_info = rent.to_account_info(false);
 
-        // Accounts for transaction
-        let mut accounts = SetPredictionTokenAuthority {
+        let mut accounts = CollectFees {
+            admin: Account::try_from(&admin_info).unwrap(),
             state: Account::try_from(&state_info).unwrap(),
-            prediction: Account::try_from(&prediction_info).unwrap(),
-            authority: Signer::try_from(&authority_info).unwrap(),
-            prediction_anti_token: Account::try_from(&prediction_anti_token_info).unwrap(),
-            prediction_pro_token: Account::try_from(&prediction_pro_token_info).unwrap(),
-            token_program: Program::try_from(&token_program_info).unwrap(),
+            authority: Signer::try_from(&stranger_info).unwrap(),
+            treasury: treasury_info.clone(),
+            rent: Sysvar::<Rent>::from_account_info(&rent_info).unwrap(),
         };
 
-        let bumps = SetPredictionTokenAuthorityBumps {
+        // Only the multisig may collect
+        let bumps = CollectFeesBumps {
+            admin: admin_bump,
             state: state_bump,
-            prediction_anti_token: anti_token_bump,
-            prediction_pro_token: pro_token_bump,
         };
+        let result = collect_fees(Context::new(&program_id, &mut accounts, &[], bumps));
+        assert_eq!(result.unwrap_err(), Error::from(ErrorCode::Unauthorised));
+        assert_eq!(state_info.lamports(), rent_exempt + fees);
+
+        // Fees above the rent-exempt minimum move to the treasury
+        accounts.authority = Signer::try_from(&multisig_info).unwrap();
+        let bumps = CollectFeesBumps {
+            admin: admin_bump,
+            state: state_bump,
+        };
+        collect_fees(Context::new(&program_id, &mut accounts, &[], bumps)).unwrap();
+        assert_eq!(state_info.lamports(), rent_exempt);
+        assert_eq!(treasury_info.lamports(), 1_000_000 + fees);
 
+        // Collecting again leaves the state account rent exempt
+        let bumps = CollectFeesBumps {
+            admin: admin_bump,
+            state: state_bump,
+        };
+        collect_fees(Context::new(&program_id, &mut accounts, &[], bumps)).unwrap();
+        assert_eq!(state_info.lamports(), rent_exempt);
+        assert_eq!(treasury_info.lamports(), 1_000_000 + fees);
+
+        // Fees only ever go to the configured treasury
+        **state_info.try_borrow_mut_lamports().unwrap() += fees;
+        accounts.treasury = stranger_info.clone();
+        let bumps = CollectFeesBumps {
+            admin: admin_bump,
+            state: state_bump,
+        };
+        let result = collect_fees(Context::new(&program_id, &mut accounts, &[], bumps));
         assert_eq!(
-            token_data.owner, ANTITOKEN_MULTISIG,
-            "Token owner should be ANTITOKEN_MULTISIG"
+            result.unwrap_err(),
+            Error::from(PredictError::InvalidTokenAccount)
         );
+        assert_eq!(state_info.lamports(), rent_exempt + fees);
 
-        // Test with correct authority (ANTITOKEN_MULTISIG)
-        let _ = set_token_authority(Context::new(&program_id, &mut accounts, &[], bumps), index);
+        // Until the stranger becomes the treasury
+        accounts.admin.treasury = stranger_info.key();
+        let stranger_lamports = stranger_info.lamports();
+        let bumps = CollectFeesBumps {
+            admin: admin_bump,
+            state: state_bump,
+        };
+        collect_fees(Context::new(&program_id, &mut accounts, &[], bumps)).unwrap();
+        assert_eq!(stranger_info.lamports(), stranger_lamports + fees);
+        assert_eq!(treasury_info.lamports(), 1_000_000 + fees);
+    }
 
-        // Test unauthorised call
-        let mut unauthorised_keypair = TestAccountData::new_authority_account(Pubkey::new_unique());
-        let unauthorised_keypair_info = unauthorised_keypair.to_account_info(true);
-        accounts.authority = Signer::try_from(&unauthorised_keypair_info).unwrap();
+    // Test timelocked treasury changes
+    #[test]
+    fn test_timelocked_treasury() {
+        let program_id = Pubkey::from_str(&PROGRAM_ID.to_string()).unwrap();
+        let new_treasury = Pubkey::new_unique();
+        let (admin_pda, admin_bump) = Pubkey::find_program_address(&[b"admin"], &program_id);
+        let mut admin_account = new_owned_admin(admin_pda, program_id);
+        admin_account
+            .init_admin_data(&uninitialised_admin())
+            .unwrap();
 
-        let bumps = SetPredictionTokenAuthorityBumps {
-            state: state_bump,
-            prediction_anti_token: anti_token_bump,
-            prediction_pro_token: pro_token_bump,
+        let admin_info = admin_account.to_account_info(false);
+        let mut multisig = TestAccountData::new_authority_account(ANTITOKEN_MULTISIG);
+        let multisig_info = multisig.to_account_info(true);
+        let mut stranger = TestAccountData::new_authority_account(Pubkey::new_unique());
+        let stranger_info = stranger.to_account_info(true);
+        let mut accounts = Update {
+            admin: Account::try_from(&admin_info).unwrap(),
+            authority: Signer::try_from(&stranger_info).unwrap(),
         };
 
-        let result_unauthorised =
-            set_token_authority(Context::new(&program_id, &mut accounts, &[], bumps), index);
-        assert!(
-            result_unauthorised.is_err(),
-            "Unauthorised call should fail"
+        let now = 1736899200;
+        set_clock_override(Some(now));
+
+        // Only the multisig can queue a treasury
+        let result = queue_treasury(
+            Context::new(
+                &program_id,
+                &mut accounts,
+                &[],
+                UpdateBumps { admin: admin_bump },
+            ),
+            new_treasury,
+        );
+        assert_eq!(result.unwrap_err(), ErrorCode::Unauthorised.into());
+
+        accounts.authority = Signer::try_from(&multisig_info).unwrap();
+        let result = queue_treasury(
+            Context::new(
+                &program_id,
+                &mut accounts,
+                &[],
+                UpdateBumps { admin: admin_bump },
+            ),
+            Pubkey::default(),
+        );
+        assert_eq!(result.unwrap_err(), PredictError::InvalidParameter.into());
+
+        queue_treasury(
+            Context::new(
+                &program_id,
+                &mut accounts,
+                &[],
+                UpdateBumps { admin: admin_bump },
+            ),
+            new_treasury,
+        )
+        .unwrap();
+        assert_eq!(
+            accounts.admin.pending_treasury,
+            Some(QueuedTreasury {
+                treasury: new_treasury,
+                eta: now + TIMELOCK_DELAY,
+            })
+        );
+        let result = queue_treasury(
+            Context::new(
+                &program_id,
+                &mut accounts,
+                &[],
+                UpdateBumps { admin: admin_bump },
+            ),
+            new_treasury,
+        );
+        assert_eq!(
+            result.unwrap_err(),
+            PredictError::UpdateAlreadyQueued.into()
         );
 
-        if let Err(error) = result_unauthorised {
-            match error {
-                anchor_lang::error::Error::AnchorError(e) => {
-                    let error_code: u32 = ErrorCode::Unauthorised.into();
-                    assert_eq!(
-                        e.error_code_number, error_code,
-                        "Should return Unauthorised error"
-                    );
-                }
-                _ => panic!("Expected Unauthorised error"),
-            }
-        }
+        // Execution waits for the delay to elapse
+        set_clock_override(Some(now + TIMELOCK_DELAY - 1));
+        let result = execute_treasury(Context::new(
+            &program_id,
+            &mut accounts,
+            &[],
+            UpdateBumps { admin: admin_bump },
+        ));
+        assert_eq!(result.unwrap_err(), PredictError::TimelockActive.into());
+        assert_eq!(accounts.admin.treasury, ANTITOKEN_MULTISIG);
+
+        set_clock_override(Some(now + TIMELOCK_DELAY));
+        execute_treasury(Context::new(
+            &program_id,
+            &mut accounts,
+            &[],
+            UpdateBumps { admin: admin_bump },
+        ))
+        .unwrap();
+        assert_eq!(accounts.admin.treasury, new_treasury);
+        assert!(accounts.admin.pending_treasury.is_none());
+
+        // Cancelled changes are never applied
+        queue_treasury(
+            Context::new(
+                &program_id,
+                &mut accounts,
+                &[],
+                UpdateBumps { admin: admin_bump },
+            ),
+            ANTITOKEN_MULTISIG,
+        )
+        .unwrap();
+        cancel_treasury(Context::new(
+            &program_id,
+            &mut accounts,
+            &[],
+            UpdateBumps { admin: admin_bump },
+        ))
+        .unwrap();
+        let result = execute_treasury(Context::new(
+            &program_id,
+            &mut accounts,
+            &[],
+            UpdateBumps { admin: admin_bump },
+        ));
+        assert_eq!(result.unwrap_err(), PredictError::UpdateNotQueued.into());
+        assert_eq!(accounts.admin.treasury, new_treasury);
     }
 }
@@ -8,119 +8,165 @@
 //! Contact: dev@antitoken.pro
 
 // instructions/bulk_withdraw.rs
+use crate::state::*;
 use crate::utils::*;
 use crate::BulkWithdrawTokens;
 use anchor_lang::prelude::*;
-use anchor_spl::token::TokenAccount;
-use anchor_spl::token::{self, Transfer};
-use borsh::BorshSerialize;
+use anchor_spl::token_interface::{self, TokenAccount, TransferChecked};
 
 pub fn bulk_withdraw<'a, 'b, 'c: 'info, 'info>(
     ctx: Context<'a, 'b, 'c, 'info, BulkWithdrawTokens<'info>>,
     index: u64,
+    offset: u64,
+    batch: u64,
 ) -> Result<()> {
-    let authority_key = ctx.accounts.authority.key();
+    // Get current time
+    let now = current_time()?;
+
+    // Withdrawals need a settled prediction, or one that was voided
+    let prediction = &ctx.accounts.prediction;
+    if !prediction.voided {
+        require!(prediction.equalised, PredictError::NotEqualised);
+
+        // Verify the challenge period has closed without a pending dispute
+        require!(
+            prediction.is_settled(now),
+            PredictError::ChallengePeriodActive
+        );
+    }
+
+    // Pool accounts are held by the prediction's PDA authority, which signs payouts
+    let index_bytes = index.to_le_bytes();
+    let pool_bump = [ctx.bumps.pool_authority];
+    let pool_seeds: &[&[u8]] = &[b"authority", &index_bytes, &pool_bump];
+    let signer_seeds: &[&[&[u8]]] = &[pool_seeds];
+
+    // Records are whatever the caller supplies, in no fixed order, so the cursor is
+    // not a position among them: it counts records settled in bulk, and a batch
+    // must quote it so that a replayed batch is rejected
     require!(
-        authority_key == ANTITOKEN_MULTISIG,
-        PredictError::Unauthorised
+        offset == prediction.bulk.cursor,
+        PredictError::InvalidWithdrawOffset
     );
     require!(
-        &ctx.accounts.prediction.equalised,
-        PredictError::NotEqualised
+        prediction.withdrawn < prediction.deposits,
+        PredictError::AlreadyWithdrawn
+    );
+    require!(
+        batch > 0 && batch <= MAX_WITHDRAW_BATCH,
+        PredictError::InvalidBatchSize
     );
 
-    let equalisation = ctx
-        .accounts
-        .prediction
-        .equalisation
-        .clone()
-        .ok_or(error!(PredictError::NotEqualised))?;
-
-    let prediction_info = ctx.accounts.prediction.to_account_info();
-    let mut prediction_data = prediction_info.try_borrow_mut_data()?;
-
-    let prediction = &mut ctx.accounts.prediction;
     let remaining_accounts: &[AccountInfo<'info>] = ctx.remaining_accounts;
-    let mut deposits = prediction.deposits.clone();
-    let num_deposits = deposits.len();
 
+    // Expect (deposit record, $ANTI account, $PRO account, depositor) per deposit
+    // in the batch
     require!(
-        remaining_accounts.len() == num_deposits * 2,
+        remaining_accounts.len() as u64 == batch * 4,
         PredictError::InvalidTokenAccount
     );
 
     let mut total_anti_withdrawn: u64 = 0;
     let mut total_pro_withdrawn: u64 = 0;
+    let mut records_withdrawn: u64 = 0;
+
+    for accounts in remaining_accounts.chunks(4) {
+        let deposit_record: Account<'info, DepositRecord> = Account::try_from(&accounts[0])?;
+        require!(
+            deposit_record.index == index,
+            PredictError::InvalidDepositRecord
+        );
+        // Records are closed to their depositor, who paid their rent
+        let depositor = &accounts[3];
+        require!(
+            depositor.key() == deposit_record.deposit.address,
+            PredictError::InvalidRecipient
+        );
 
-    for (deposit_index, deposit) in deposits.iter_mut().enumerate() {
-        if deposit.withdrawn {
+        // Records settled before they could be closed pay nothing but are closed too
+        if deposit_record.deposit.withdrawn {
+            deposit_record.close(depositor.clone())?;
             continue;
         }
 
-        let anti_return = equalisation.anti[deposit_index];
-        let pro_return = equalisation.pro[deposit_index];
-
-        let user_anti_token: Account<'info, TokenAccount> =
-            Account::try_from(&remaining_accounts[deposit_index * 2])?;
-        let user_pro_token: Account<'info, TokenAccount> =
-            Account::try_from(&remaining_accounts[deposit_index * 2 + 1])?;
+        let (anti_return, pro_return) = withdrawal(&ctx.accounts.prediction, &deposit_record, now)?;
+
+        // Payouts only go to the depositor's accounts for the prediction's mints
+        let user_anti_token: InterfaceAccount<'info, TokenAccount> =
+            InterfaceAccount::try_from(&accounts[1])?;
+        let user_pro_token: InterfaceAccount<'info, TokenAccount> =
+            InterfaceAccount::try_from(&accounts[2])?;
+        require!(
+            user_anti_token.owner == deposit_record.deposit.address
+                && user_anti_token.mint == ctx.accounts.prediction.anti_mint,
+            PredictError::InvalidRecipient
+        );
+        require!(
+            user_pro_token.owner == deposit_record.deposit.address
+                && user_pro_token.mint == ctx.accounts.prediction.pro_mint,
+            PredictError::InvalidRecipient
+        );
 
         if anti_return > 0 {
-            token::transfer(
+            token_interface::transfer_checked(
                 CpiContext::new_with_signer(
                     ctx.accounts.token_program.to_account_info(),
-                    Transfer {
+                    TransferChecked {
                         from: ctx.accounts.prediction_anti_token.to_account_info(),
+                        mint: ctx.accounts.anti_mint.to_account_info(),
                         to: user_anti_token.to_account_info(),
-                        authority: ctx.accounts.authority.to_account_info(),
+                        authority: ctx.accounts.pool_authority.to_account_info(),
                     },
-                    &[],
+                    signer_seeds,
                 ),
                 anti_return,
+                ctx.accounts.anti_mint.decimals,
             )?;
             total_anti_withdrawn += anti_return;
         }
 
         if pro_return > 0 {
-            token::transfer(
+            token_interface::transfer_checked(
                 CpiContext::new_with_signer(
                     ctx.accounts.token_program.to_account_info(),
-                    Transfer {
+                    TransferChecked {
                         from: ctx.accounts.prediction_pro_token.to_account_info(),
+                        mint: ctx.accounts.pro_mint.to_account_info(),
                         to: user_pro_token.to_account_info(),
-                        authority: ctx.accounts.authority.to_account_info(),
+                        authority: ctx.accounts.pool_authority.to_account_info(),
                     },
-                    &[],
+                    signer_seeds,
                 ),
                 pro_return,
+                ctx.accounts.pro_mint.decimals,
             )?;
             total_pro_withdrawn += pro_return;
         }
 
-        deposit.withdrawn = true;
+        deposit_record.close(depositor.clone())?;
+        records_withdrawn += 1;
     }
 
-    // Verify total withdrawals match equalisation sums
-    require!(
-        total_anti_withdrawn == equalisation.anti.iter().copied().sum::<u64>(),
-        PredictError::InvalidEqualisation
-    );
-    require!(
-        total_pro_withdrawn == equalisation.pro.iter().copied().sum::<u64>(),
-        PredictError::InvalidEqualisation
-    );
+    // Count only newly settled records, so records already withdrawn never
+    // advance the cursor; the batch settling the last record must pay out the
+    // allocations exactly
+    let prediction = &mut ctx.accounts.prediction;
+    prediction.record_withdrawal(records_withdrawn, total_anti_withdrawn, total_pro_withdrawn)?;
+    prediction.bulk.cursor = prediction
+        .bulk
+        .cursor
+        .checked_add(records_withdrawn)
+        .ok_or(error!(PredictError::MathError))?;
 
-    // Serialise updated prediction state and store it in account data
-    prediction.deposits = deposits;
-    let serialised_prediction = prediction.try_to_vec()?;
-    prediction_data[8..8 + serialised_prediction.len()].copy_from_slice(&serialised_prediction);
+    // Serialise updated prediction state
+    write_account(&prediction.to_account_info(), &**prediction)?;
 
     emit!(WithdrawEvent {
         index,
         address: ctx.accounts.authority.key(),
         anti: total_anti_withdrawn,
         pro: total_pro_withdrawn,
-        timestamp: Clock::get()?.unix_timestamp,
+        timestamp: now,
     });
 
     Ok(())
@@ -129,158 +175,56 @@ pub fn bulk_withdraw<'a, 'b, 'c: 'info, 'info>(
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::utils::PROGRAM_ID;
+    use crate::test_utils::*;
     use crate::BulkWithdrawTokensBumps;
-    use crate::Deposit;
     use crate::Equalisation;
     use crate::PredictionAccount;
-    use crate::StateAccount;
-    use anchor_lang::system_program;
     use anchor_lang::Discriminator;
-    use anchor_spl::token::{spl_token, Token};
-    use anchor_spl::token::{spl_token::state::Account as SplTokenAccount, TokenAccount};
-    use solana_sdk::program_option::COption;
-    use solana_sdk::program_pack::Pack;
-    use std::str::FromStr;
-
-    // Fixed test IDs - these should be consistent across tests
-    fn program_id() -> Pubkey {
-        Pubkey::from_str(&PROGRAM_ID.to_string()).unwrap()
-    }
-
-    struct TestAccountData {
-        key: Pubkey,
-        lamports: u64,
-        data: Vec<u8>,
-        owner: Pubkey,
-        executable: bool,
-        rent_epoch: u64,
-    }
-
-    impl TestAccountData {
-        fn new_account_with_key_and_owner<T: AccountSerialize + AccountDeserialize + Clone>(
-            key: Pubkey,
-            owner: Pubkey,
-        ) -> Self {
-            Self {
-                key,
-                lamports: 1_000_000,
-                data: vec![0; 8 + PredictionAccount::LEN],
-                owner,
-                executable: true,
-                rent_epoch: 0,
-            }
-        }
-
-        fn to_account_info<'a>(&'a mut self, is_signer: bool) -> AccountInfo<'a> {
-            AccountInfo::new(
-                &self.key,
-                is_signer,
-                true,
-                &mut self.lamports,
-                &mut self.data,
-                &self.owner,
-                self.executable,
-                self.rent_epoch,
-            )
-        }
-
-        fn new_token() -> Self {
-            Self {
-                key: Pubkey::new_unique(),
-                lamports: 1_000_000,
-                data: vec![0; 165],
-                owner: spl_token::ID,
-                executable: false,
-                rent_epoch: 0,
-            }
-        }
-
-        fn new_authority_account(pubkey: Pubkey) -> Self {
-            Self {
-                key: pubkey,
-                lamports: 1_000_000,
-                data: vec![],
-                owner: system_program::ID,
-                executable: true,
-                rent_epoch: 0,
-            }
-        }
-
-        fn init_state_data(&mut self, state: &StateAccount) -> Result<()> {
-            self.data = vec![0; 8 + StateAccount::LEN];
-            let data = self.data.as_mut_slice();
-
-            let disc = StateAccount::discriminator();
-            data[..8].copy_from_slice(&disc);
-
-            let account_data = state.try_to_vec()?;
-            data[8..8 + account_data.len()].copy_from_slice(&account_data);
-
-            Ok(())
+    use anchor_spl::token::spl_token;
+    use anchor_spl::token_interface::{Mint, TokenInterface};
+
+    // Reusable method to create an equalised test prediction
+    fn create_equalised_test_prediction() -> PredictionAccount {
+        PredictionAccount {
+            anti: 10000,
+            pro: 8000,
+            deposits: 2,
+            equalised: true,
+            equalisation: Some(Equalisation {
+                anti: vec![10000],
+                pro: vec![8000],
+                anti_staked: vec![10000],
+                pro_staked: vec![8000],
+                binned: 2,
+                truth: vec![6000, 4000],
+                timestamp: 1736899200,
+                round: 0,
+            }),
+            ..test_prediction()
         }
+    }
 
-        fn init_token_account(&mut self, owner: Pubkey, mint: Pubkey) -> Result<()> {
-            self.data = vec![0; TokenAccount::LEN]; // Ensure correct buffer size
-            let data = self.data.as_mut_slice();
-
-            let close_authority: COption<Pubkey> = COption::None;
-
-            // Initialise a new SPL Token Account manually
-            let token_account = SplTokenAccount {
-                mint,
-                owner,
-                amount: 0,
-                delegate: None.into(),
-                state: spl_token::state::AccountState::Initialized,
-                is_native: None.into(),
-                delegated_amount: 0,
-                close_authority,
-            };
-
-            token_account.pack_into_slice(data);
+    #[test]
+    fn test_withdrawals_conserve_allocations() {
+        // Payouts never exceed the allocations
+        let mut prediction = create_equalised_test_prediction();
+        assert_eq!(
+            prediction.record_withdrawal(1, 10001, 0).unwrap_err(),
+            PredictError::InvalidEqualisation.into()
+        );
 
-            Ok(())
-        }
+        // Settling the last record short of the allocations is rejected
+        prediction.record_withdrawal(1, 6000, 5000).unwrap();
+        assert_eq!(
+            prediction.record_withdrawal(1, 3999, 3000).unwrap_err(),
+            PredictError::InvalidEqualisation.into()
+        );
+        assert_eq!((prediction.withdrawn, prediction.bulk.anti), (1, 6000));
 
-        // Reusable method to create an equalised test prediction
-        fn create_equalised_test_prediction(authority: Pubkey) -> PredictionAccount {
-            PredictionAccount {
-                index: 0,
-                title: "Test Prediction".to_string(),
-                description: "Test Description".to_string(),
-                start_time: "2025-01-01T00:00:00Z".to_string(),
-                end_time: "2025-01-02T00:00:00Z".to_string(),
-                etc: None,
-                anti: 10000,
-                pro: 8000,
-                deposits: vec![
-                    Deposit {
-                        address: authority,
-                        anti: 6000,
-                        pro: 5000,
-                        mean: 1000,
-                        stddev: 11000,
-                        withdrawn: false,
-                    },
-                    Deposit {
-                        address: Pubkey::new_unique(),
-                        anti: 4000,
-                        pro: 3000,
-                        mean: 1000,
-                        stddev: 7000,
-                        withdrawn: false,
-                    },
-                ],
-                equalised: true,
-                equalisation: Some(Equalisation {
-                    anti: vec![6000, 4000],
-                    pro: vec![5000, 3000],
-                    truth: vec![6000, 4000],
-                    timestamp: 1736899200,
-                }),
-            }
-        }
+        // And accepted once the payouts match them exactly
+        prediction.record_withdrawal(1, 4000, 3000).unwrap();
+        assert_eq!(prediction.withdrawn, 2);
+        assert_eq!((prediction.bulk.anti, prediction.bulk.pro), (10000, 8000));
     }
 
     #[test]
@@ -288,41 +232,60 @@ mod tests {
         let program_id = program_id();
 
         // Create test accounts
-        let mut prediction = TestAccountData::new_account_with_key_and_owner::<PredictionAccount>(
-            Pubkey::new_unique(),
-            program_id,
-        );
+        let mut prediction =
+            TestAccountData::new_account_with_key_and_owner(Pubkey::new_unique(), program_id);
         let mut authority = TestAccountData::new_authority_account(Pubkey::new_unique());
+        let (pool_authority_pda, pool_authority_bump) =
+            Pubkey::find_program_address(&[b"authority", 0u64.to_le_bytes().as_ref()], &program_id);
+        let mut pool_authority = TestAccountData::new_authority_account(pool_authority_pda);
+
+        // Admin config naming the withdrawing authority as multisig
+        let (admin_pda, admin_bump) = Pubkey::find_program_address(&[b"admin"], &program_id);
+        let mut admin = TestAccountData::new_account_with_key_and_owner(admin_pda, program_id);
+        admin
+            .init_admin_data(&AdminAccount {
+                antitoken_multisig: authority.key,
+                ..test_admin()
+            })
+            .unwrap();
 
         // Initialise token accounts
-        let mint_key = Pubkey::new_unique();
         let authority_key = Pubkey::new_unique();
+        let other_key = Pubkey::new_unique();
 
-        let mut user_anti = TestAccountData::new_token();
-        let mut user_pro = TestAccountData::new_token();
-        let mut prediction_anti = TestAccountData::new_token();
-        let mut prediction_pro = TestAccountData::new_token();
+        let mut anti_mint = TestAccountData::new_mint(ANTI_MINT_ADDRESS);
+        let mut pro_mint = TestAccountData::new_mint(PRO_MINT_ADDRESS);
+        let mut user_anti = TestAccountData::new_token(Pubkey::new_unique());
+        let mut user_pro = TestAccountData::new_token(Pubkey::new_unique());
+        let mut other_anti = TestAccountData::new_token(Pubkey::new_unique());
+        let mut other_pro = TestAccountData::new_token(Pubkey::new_unique());
+        let mut prediction_anti = TestAccountData::new_token(Pubkey::new_unique());
+        let mut prediction_pro = TestAccountData::new_token(Pubkey::new_unique());
 
         user_anti
-            .init_token_account(authority_key, mint_key)
+            .init_token_account(authority_key, ANTI_MINT_ADDRESS)
             .unwrap();
         user_pro
-            .init_token_account(authority_key, mint_key)
+            .init_token_account(authority_key, PRO_MINT_ADDRESS)
+            .unwrap();
+        other_anti
+            .init_token_account(other_key, ANTI_MINT_ADDRESS)
+            .unwrap();
+        other_pro
+            .init_token_account(other_key, PRO_MINT_ADDRESS)
             .unwrap();
         prediction_anti
-            .init_token_account(Pubkey::new_unique(), mint_key)
+            .init_token_account(pool_authority_pda, ANTI_MINT_ADDRESS)
             .unwrap();
         prediction_pro
-            .init_token_account(Pubkey::new_unique(), mint_key)
+            .init_token_account(pool_authority_pda, PRO_MINT_ADDRESS)
             .unwrap();
 
-        let mut token_program = TestAccountData::new_account_with_key_and_owner::<StateAccount>(
-            spl_token::ID,
-            spl_token::ID,
-        );
+        let mut token_program =
+            TestAccountData::new_account_with_key_and_owner(spl_token::ID, spl_token::ID);
 
         // Create prediction with deposits and results
-        let prediction_data = TestAccountData::create_equalised_test_prediction(authority.key);
+        let prediction_data = create_equalised_test_prediction();
 
         // Write discriminator and serialise prediction data
         prediction.data[..8].copy_from_slice(&PredictionAccount::discriminator());
@@ -330,57 +293,87 @@ mod tests {
         prediction.data[8..8 + serialised_prediction.len()].copy_from_slice(&serialised_prediction);
 
         // Get account infos
+        let admin_info = admin.to_account_info(false);
         let prediction_info = prediction.to_account_info(false);
         let authority_info = authority.to_account_info(true);
+        let pool_authority_info = pool_authority.to_account_info(false);
         let user_anti_info = user_anti.to_account_info(false);
         let user_pro_info = user_pro.to_account_info(false);
+        let other_anti_info = other_anti.to_account_info(false);
+        let other_pro_info = other_pro.to_account_info(false);
         let prediction_anti_info = prediction_anti.to_account_info(false);
         let prediction_pro_info = prediction_pro.to_account_info(false);
+        let anti_mint_info = anti_mint.to_account_info(false);
+        let pro_mint_info = pro_mint.to_account_info(false);
         let token_program_info = token_program.to_account_info(false);
 
-        // Initialise state account
-        let root: Pubkey = Pubkey::new_unique();
-        let mut state =
-            TestAccountData::new_account_with_key_and_owner::<StateAccount>(root, program_id);
-        state
-            .init_state_data(&StateAccount {
-                index: 0,
-                authority: root,
-            })
-            .unwrap();
-
         let (_prediction_pda, prediction_bump) = Pubkey::find_program_address(
             &[b"prediction", 0u64.to_le_bytes().as_ref()],
             &program_id,
         );
 
         let (_anti_token_pda, anti_token_bump) = Pubkey::find_program_address(
-            &[b"anti_token", state.data[8..16].try_into().unwrap()],
+            &[b"anti_token", 0u64.to_le_bytes().as_ref()],
             &program_id,
         );
 
-        let (_pro_token_pda, pro_token_bump) = Pubkey::find_program_address(
-            &[b"pro_token", state.data[8..16].try_into().unwrap()],
-            &program_id,
+        let (_pro_token_pda, pro_token_bump) =
+            Pubkey::find_program_address(&[b"pro_token", 0u64.to_le_bytes().as_ref()], &program_id);
+
+        // Create deposit records
+        let mut first_record = ReallocAccountData::new_deposit_record(
+            Pubkey::new_unique(),
+            program_id,
+            &test_deposit_record(authority_key, 0, 6000, 5000),
+        );
+        let mut second_deposit_record = test_deposit_record(other_key, 0, 4000, 3000);
+        second_deposit_record.anti_offset = 6000; // Binned after the first record
+        second_deposit_record.pro_offset = 5000;
+        let mut second_record = ReallocAccountData::new_deposit_record(
+            Pubkey::new_unique(),
+            program_id,
+            &second_deposit_record,
         );
+        let first_record_info = first_record.to_account_info();
+        let second_record_info = second_record.to_account_info();
+        let mut depositor = TestAccountData::new_authority_account(authority_key);
+        let mut other = TestAccountData::new_authority_account(other_key);
+        let depositor_info = depositor.to_account_info(false);
+        let other_info = other.to_account_info(false);
 
         // Use `remaining_accounts` dynamically
-        let remaining_accounts = vec![user_anti_info.clone(), user_pro_info.clone()];
+        let remaining_accounts = vec![
+            first_record_info.clone(),
+            user_anti_info.clone(),
+            user_pro_info.clone(),
+            depositor_info.clone(),
+            second_record_info.clone(),
+            other_anti_info.clone(),
+            other_pro_info.clone(),
+            other_info.clone(),
+        ];
 
         let mut accounts = BulkWithdrawTokens {
+            admin: Account::try_from(&admin_info).unwrap(),
             prediction: Account::try_from(&prediction_info).unwrap(),
             authority: Signer::try_from(&authority_info).unwrap(),
-            prediction_anti_token: Account::try_from(&prediction_anti_info).unwrap(),
-            prediction_pro_token: Account::try_from(&prediction_pro_info).unwrap(),
-            token_program: Program::<Token>::try_from(&token_program_info).unwrap(),
+            pool_authority: pool_authority_info,
+            prediction_anti_token: InterfaceAccount::try_from(&prediction_anti_info).unwrap(),
+            prediction_pro_token: InterfaceAccount::try_from(&prediction_pro_info).unwrap(),
+            anti_mint: InterfaceAccount::<Mint>::try_from(&anti_mint_info).unwrap(),
+            pro_mint: InterfaceAccount::<Mint>::try_from(&pro_mint_info).unwrap(),
+            token_program: Interface::<TokenInterface>::try_from(&token_program_info).unwrap(),
         };
 
         let bumps = BulkWithdrawTokensBumps {
+            admin: admin_bump,
             prediction: prediction_bump,
+            pool_authority: pool_authority_bump,
             prediction_anti_token: anti_token_bump,
             prediction_pro_token: pro_token_bump,
         };
 
+        set_clock_override(Some(1736899200 + CHALLENGE_PERIOD));
         let _ = bulk_withdraw(
             Context::new(
                 &program_id,
@@ -389,6 +382,527 @@ mod tests {
                 bumps,
             ),
             0,
+            0,
+            2,
+        );
+
+        // Both deposit records are settled by the admin-configured multisig and closed
+        for record_info in [&first_record_info, &second_record_info] {
+            assert!(record_info.data_is_empty() && record_info.lamports() == 0);
+        }
+
+        // Prediction counts both records as withdrawn
+        let prediction_account: PredictionAccount = PredictionAccount::try_deserialize(
+            &mut prediction_info.try_borrow_data().unwrap().as_ref(),
+        )
+        .unwrap();
+        assert_eq!(prediction_account.withdrawn, 2);
+    }
+
+    #[test]
+    fn test_withdrawal_of_multiple_deposits_by_one_user() {
+        let program_id = program_id();
+
+        // Create test accounts
+        let mut prediction =
+            TestAccountData::new_account_with_key_and_owner(Pubkey::new_unique(), program_id);
+        let mut authority = TestAccountData::new_authority_account(Pubkey::new_unique());
+        let (pool_authority_pda, pool_authority_bump) =
+            Pubkey::find_program_address(&[b"authority", 0u64.to_le_bytes().as_ref()], &program_id);
+        let mut pool_authority = TestAccountData::new_authority_account(pool_authority_pda);
+
+        // Admin config naming the withdrawing authority as multisig
+        let (admin_pda, admin_bump) = Pubkey::find_program_address(&[b"admin"], &program_id);
+        let mut admin = TestAccountData::new_account_with_key_and_owner(admin_pda, program_id);
+        admin
+            .init_admin_data(&AdminAccount {
+                antitoken_multisig: authority.key,
+                ..test_admin()
+            })
+            .unwrap();
+
+        // One user holding both deposits of the prediction
+        let user_key = Pubkey::new_unique();
+
+        let mut anti_mint = TestAccountData::new_mint(ANTI_MINT_ADDRESS);
+        let mut pro_mint = TestAccountData::new_mint(PRO_MINT_ADDRESS);
+        let mut user_anti = TestAccountData::new_token(Pubkey::new_unique());
+        let mut user_pro = TestAccountData::new_token(Pubkey::new_unique());
+        let mut prediction_anti = TestAccountData::new_token(Pubkey::new_unique());
+        let mut prediction_pro = TestAccountData::new_token(Pubkey::new_unique());
+
+        user_anti
+            .init_token_account(user_key, ANTI_MINT_ADDRESS)
+            .unwrap();
+        user_pro
+            .init_token_account(user_key, PRO_MINT_ADDRESS)
+            .unwrap();
+        prediction_anti
+            .init_token_account(pool_authority_pda, ANTI_MINT_ADDRESS)
+            .unwrap();
+        prediction_pro
+            .init_token_account(pool_authority_pda, PRO_MINT_ADDRESS)
+            .unwrap();
+
+        let mut token_program =
+            TestAccountData::new_account_with_key_and_owner(spl_token::ID, spl_token::ID);
+
+        // Create prediction with deposits and results
+        let prediction_data = create_equalised_test_prediction();
+        prediction.data[..8].copy_from_slice(&PredictionAccount::discriminator());
+        let serialised_prediction = prediction_data.try_to_vec().unwrap();
+        prediction.data[8..8 + serialised_prediction.len()].copy_from_slice(&serialised_prediction);
+
+        // Create the user's deposit records
+        let mut first_record = ReallocAccountData::new_deposit_record(
+            Pubkey::new_unique(),
+            program_id,
+            &test_deposit_record(user_key, 0, 6000, 5000),
+        );
+        let mut second_deposit_record = test_deposit_record(user_key, 1, 4000, 3000);
+        second_deposit_record.anti_offset = 6000; // Binned after the first record
+        second_deposit_record.pro_offset = 5000;
+        let mut second_record = ReallocAccountData::new_deposit_record(
+            Pubkey::new_unique(),
+            program_id,
+            &second_deposit_record,
+        );
+
+        // Get account infos
+        let admin_info = admin.to_account_info(false);
+        let prediction_info = prediction.to_account_info(false);
+        let authority_info = authority.to_account_info(true);
+        let pool_authority_info = pool_authority.to_account_info(false);
+        let user_anti_info = user_anti.to_account_info(false);
+        let user_pro_info = user_pro.to_account_info(false);
+        let prediction_anti_info = prediction_anti.to_account_info(false);
+        let prediction_pro_info = prediction_pro.to_account_info(false);
+        let anti_mint_info = anti_mint.to_account_info(false);
+        let pro_mint_info = pro_mint.to_account_info(false);
+        let token_program_info = token_program.to_account_info(false);
+        let first_record_info = first_record.to_account_info();
+        let second_record_info = second_record.to_account_info();
+
+        let (_prediction_pda, prediction_bump) = Pubkey::find_program_address(
+            &[b"prediction", 0u64.to_le_bytes().as_ref()],
+            &program_id,
         );
+        let (_anti_token_pda, anti_token_bump) = Pubkey::find_program_address(
+            &[b"anti_token", 0u64.to_le_bytes().as_ref()],
+            &program_id,
+        );
+        let (_pro_token_pda, pro_token_bump) =
+            Pubkey::find_program_address(&[b"pro_token", 0u64.to_le_bytes().as_ref()], &program_id);
+
+        // Both records pay into the same user accounts and close to the same user
+        let mut depositor = TestAccountData::new_authority_account(user_key);
+        let depositor_info = depositor.to_account_info(false);
+        let remaining_accounts = vec![
+            first_record_info.clone(),
+            user_anti_info.clone(),
+            user_pro_info.clone(),
+            depositor_info.clone(),
+            second_record_info.clone(),
+            user_anti_info.clone(),
+            user_pro_info.clone(),
+            depositor_info.clone(),
+        ];
+
+        let mut accounts = BulkWithdrawTokens {
+            admin: Account::try_from(&admin_info).unwrap(),
+            prediction: Account::try_from(&prediction_info).unwrap(),
+            authority: Signer::try_from(&authority_info).unwrap(),
+            pool_authority: pool_authority_info,
+            prediction_anti_token: InterfaceAccount::try_from(&prediction_anti_info).unwrap(),
+            prediction_pro_token: InterfaceAccount::try_from(&prediction_pro_info).unwrap(),
+            anti_mint: InterfaceAccount::<Mint>::try_from(&anti_mint_info).unwrap(),
+            pro_mint: InterfaceAccount::<Mint>::try_from(&pro_mint_info).unwrap(),
+            token_program: Interface::<TokenInterface>::try_from(&token_program_info).unwrap(),
+        };
+
+        let bumps = BulkWithdrawTokensBumps {
+            admin: admin_bump,
+            prediction: prediction_bump,
+            pool_authority: pool_authority_bump,
+            prediction_anti_token: anti_token_bump,
+            prediction_pro_token: pro_token_bump,
+        };
+
+        set_clock_override(Some(1736899200 + CHALLENGE_PERIOD));
+        let result = bulk_withdraw(
+            Context::new(&program_id, &mut accounts, &remaining_accounts, bumps),
+            0,
+            0,
+            2,
+        );
+        assert!(result.is_ok());
+
+        // Every deposit of the user is settled and its record closed
+        for record_info in [&first_record_info, &second_record_info] {
+            assert!(record_info.data_is_empty() && record_info.lamports() == 0);
+        }
+
+        // Prediction counts both records as withdrawn
+        let prediction_account: PredictionAccount = PredictionAccount::try_deserialize(
+            &mut prediction_info.try_borrow_data().unwrap().as_ref(),
+        )
+        .unwrap();
+        assert_eq!(prediction_account.withdrawn, 2);
+    }
+
+    #[test]
+    fn test_paginated_withdrawal() {
+        let program_id = program_id();
+
+        // Create test accounts
+        let mut prediction =
+            TestAccountData::new_account_with_key_and_owner(Pubkey::new_unique(), program_id);
+        let mut authority = TestAccountData::new_authority_account(Pubkey::new_unique());
+        let (pool_authority_pda, pool_authority_bump) =
+            Pubkey::find_program_address(&[b"authority", 0u64.to_le_bytes().as_ref()], &program_id);
+        let mut pool_authority = TestAccountData::new_authority_account(pool_authority_pda);
+
+        // Admin config naming the withdrawing authority as multisig
+        let (admin_pda, admin_bump) = Pubkey::find_program_address(&[b"admin"], &program_id);
+        let mut admin = TestAccountData::new_account_with_key_and_owner(admin_pda, program_id);
+        admin
+            .init_admin_data(&AdminAccount {
+                antitoken_multisig: authority.key,
+                ..test_admin()
+            })
+            .unwrap();
+
+        // Two depositors, settled one batch at a time
+        let first_user = Pubkey::new_unique();
+        let second_user = Pubkey::new_unique();
+
+        let mut anti_mint = TestAccountData::new_mint(ANTI_MINT_ADDRESS);
+        let mut pro_mint = TestAccountData::new_mint(PRO_MINT_ADDRESS);
+        let mut first_anti = TestAccountData::new_token(Pubkey::new_unique());
+        let mut first_pro = TestAccountData::new_token(Pubkey::new_unique());
+        let mut second_anti = TestAccountData::new_token(Pubkey::new_unique());
+        let mut second_pro = TestAccountData::new_token(Pubkey::new_unique());
+        let mut foreign_anti = TestAccountData::new_token(Pubkey::new_unique());
+        let mut prediction_anti = TestAccountData::new_token(Pubkey::new_unique());
+        let mut prediction_pro = TestAccountData::new_token(Pubkey::new_unique());
+
+        first_anti
+            .init_token_account(first_user, ANTI_MINT_ADDRESS)
+            .unwrap();
+        first_pro
+            .init_token_account(first_user, PRO_MINT_ADDRESS)
+            .unwrap();
+        second_anti
+            .init_token_account(second_user, ANTI_MINT_ADDRESS)
+            .unwrap();
+        second_pro
+            .init_token_account(second_user, PRO_MINT_ADDRESS)
+            .unwrap();
+        foreign_anti
+            .init_token_account(first_user, Pubkey::new_unique())
+            .unwrap();
+        prediction_anti
+            .init_token_account(pool_authority_pda, ANTI_MINT_ADDRESS)
+            .unwrap();
+        prediction_pro
+            .init_token_account(pool_authority_pda, PRO_MINT_ADDRESS)
+            .unwrap();
+
+        let mut token_program =
+            TestAccountData::new_account_with_key_and_owner(spl_token::ID, spl_token::ID);
+
+        // Create prediction with deposits and results
+        let prediction_data = create_equalised_test_prediction();
+        prediction.data[..8].copy_from_slice(&PredictionAccount::discriminator());
+        let serialised_prediction = prediction_data.try_to_vec().unwrap();
+        prediction.data[8..8 + serialised_prediction.len()].copy_from_slice(&serialised_prediction);
+
+        // Create the deposit records
+        let mut first_record = ReallocAccountData::new_deposit_record(
+            Pubkey::new_unique(),
+            program_id,
+            &test_deposit_record(first_user, 0, 6000, 5000),
+        );
+        let mut second_deposit_record = test_deposit_record(second_user, 0, 4000, 3000);
+        second_deposit_record.anti_offset = 6000; // Binned after the first record
+        second_deposit_record.pro_offset = 5000;
+        let mut second_record = ReallocAccountData::new_deposit_record(
+            Pubkey::new_unique(),
+            program_id,
+            &second_deposit_record,
+        );
+
+        // Get account infos
+        let admin_info = admin.to_account_info(false);
+        let prediction_info = prediction.to_account_info(false);
+        let authority_info = authority.to_account_info(true);
+        let pool_authority_info = pool_authority.to_account_info(false);
+        let first_anti_info = first_anti.to_account_info(false);
+        let first_pro_info = first_pro.to_account_info(false);
+        let second_anti_info = second_anti.to_account_info(false);
+        let second_pro_info = second_pro.to_account_info(false);
+        let foreign_anti_info = foreign_anti.to_account_info(false);
+        let prediction_anti_info = prediction_anti.to_account_info(false);
+        let prediction_pro_info = prediction_pro.to_account_info(false);
+        let anti_mint_info = anti_mint.to_account_info(false);
+        let pro_mint_info = pro_mint.to_account_info(false);
+        let token_program_info = token_program.to_account_info(false);
+        let first_record_info = first_record.to_account_info();
+        let second_record_info = second_record.to_account_info();
+
+        let (_prediction_pda, prediction_bump) = Pubkey::find_program_address(
+            &[b"prediction", 0u64.to_le_bytes().as_ref()],
+            &program_id,
+        );
+        let (_anti_token_pda, anti_token_bump) = Pubkey::find_program_address(
+            &[b"anti_token", 0u64.to_le_bytes().as_ref()],
+            &program_id,
+        );
+        let (_pro_token_pda, pro_token_bump) =
+            Pubkey::find_program_address(&[b"pro_token", 0u64.to_le_bytes().as_ref()], &program_id);
+
+        // One record per batch, paid into its depositor's accounts
+        let mut first_depositor = TestAccountData::new_authority_account(first_user);
+        let mut second_depositor = TestAccountData::new_authority_account(second_user);
+        let first_depositor_info = first_depositor.to_account_info(false);
+        let second_depositor_info = second_depositor.to_account_info(false);
+        let first_batch = vec![
+            first_record_info.clone(),
+            first_anti_info.clone(),
+            first_pro_info.clone(),
+            first_depositor_info.clone(),
+        ];
+        let second_batch = vec![
+            second_record_info.clone(),
+            second_anti_info.clone(),
+            second_pro_info.clone(),
+            second_depositor_info.clone(),
+        ];
+        let misrouted_batch = vec![
+            second_record_info.clone(),
+            first_anti_info.clone(),
+            first_pro_info.clone(),
+            second_depositor_info.clone(),
+        ];
+        let swapped_batch = vec![
+            first_record_info.clone(),
+            first_pro_info.clone(),
+            first_anti_info.clone(),
+            first_depositor_info.clone(),
+        ];
+        let foreign_mint_batch = vec![
+            first_record_info.clone(),
+            foreign_anti_info.clone(),
+            first_pro_info.clone(),
+            first_depositor_info.clone(),
+        ];
+        let foreign_depositor_batch = vec![
+            first_record_info.clone(),
+            first_anti_info.clone(),
+            first_pro_info.clone(),
+            second_depositor_info.clone(),
+        ];
+
+        let mut accounts = BulkWithdrawTokens {
+            admin: Account::try_from(&admin_info).unwrap(),
+            prediction: Account::try_from(&prediction_info).unwrap(),
+            authority: Signer::try_from(&authority_info).unwrap(),
+            pool_authority: pool_authority_info,
+            prediction_anti_token: InterfaceAccount::try_from(&prediction_anti_info).unwrap(),
+            prediction_pro_token: InterfaceAccount::try_from(&prediction_pro_info).unwrap(),
+            anti_mint: InterfaceAccount::<Mint>::try_from(&anti_mint_info).unwrap(),
+            pro_mint: InterfaceAccount::<Mint>::try_from(&pro_mint_info).unwrap(),
+            token_program: Interface::<TokenInterface>::try_from(&token_program_info).unwrap(),
+        };
+
+        let bumps = || BulkWithdrawTokensBumps {
+            admin: admin_bump,
+            prediction: prediction_bump,
+            pool_authority: pool_authority_bump,
+            prediction_anti_token: anti_token_bump,
+            prediction_pro_token: pro_token_bump,
+        };
+
+        set_clock_override(Some(1736899200 + CHALLENGE_PERIOD));
+
+        // Batches must start at the stored cursor and stay within the batch limit
+        for (remaining_accounts, offset, batch, error) in [
+            (&first_batch, 1, 1, PredictError::InvalidWithdrawOffset),
+            (&first_batch, 0, 0, PredictError::InvalidBatchSize),
+            (
+                &first_batch,
+                0,
+                MAX_WITHDRAW_BATCH + 1,
+                PredictError::InvalidBatchSize,
+            ),
+            (&first_batch, 0, 2, PredictError::InvalidTokenAccount),
+            // Recipients must be the depositor's accounts for the prediction's mints
+            (&misrouted_batch, 0, 1, PredictError::InvalidRecipient),
+            (&swapped_batch, 0, 1, PredictError::InvalidRecipient),
+            (&foreign_mint_batch, 0, 1, PredictError::InvalidRecipient),
+            // Records close only to their depositor
+            (
+                &foreign_depositor_batch,
+                0,
+                1,
+                PredictError::InvalidRecipient,
+            ),
+        ] {
+            let result = bulk_withdraw(
+                Context::new(&program_id, &mut accounts, remaining_accounts, bumps()),
+                0,
+                offset,
+                batch,
+            );
+            assert_eq!(result.unwrap_err(), error.into());
+        }
+
+        // First batch advances the cursor by the record it settles
+        let result = bulk_withdraw(
+            Context::new(&program_id, &mut accounts, &first_batch, bumps()),
+            0,
+            0,
+            1,
+        );
+        assert!(result.is_ok());
+        assert_eq!(accounts.prediction.bulk.cursor, 1);
+        assert_eq!(accounts.prediction.withdrawn, 1);
+
+        // Replaying a batch is rejected
+        let result = bulk_withdraw(
+            Context::new(&program_id, &mut accounts, &first_batch, bumps()),
+            0,
+            0,
+            1,
+        );
+        assert_eq!(
+            result.unwrap_err(),
+            PredictError::InvalidWithdrawOffset.into()
+        );
+
+        // Settled records are closed to their depositor and cannot be resubmitted
+        assert!(first_record_info.data_is_empty());
+        assert_eq!(first_depositor_info.lamports(), 2_000_000);
+        let result = bulk_withdraw(
+            Context::new(&program_id, &mut accounts, &first_batch, bumps()),
+            0,
+            1,
+            1,
+        );
+        assert_eq!(
+            result.unwrap_err(),
+            anchor_lang::error::ErrorCode::AccountNotInitialized.into()
+        );
+        assert_eq!(accounts.prediction.bulk.cursor, 1);
+        assert_eq!(accounts.prediction.bulk.anti, 6000);
+        assert_eq!(accounts.prediction.bulk.pro, 5000);
+
+        // Final batch settles the rest with payouts matching the allocations
+        let result = bulk_withdraw(
+            Context::new(&program_id, &mut accounts, &second_batch, bumps()),
+            0,
+            1,
+            1,
+        );
+        assert!(result.is_ok());
+
+        for record_info in [&first_record_info, &second_record_info] {
+            assert!(record_info.data_is_empty() && record_info.lamports() == 0);
+        }
+
+        let prediction_account: PredictionAccount = PredictionAccount::try_deserialize(
+            &mut prediction_info.try_borrow_data().unwrap().as_ref(),
+        )
+        .unwrap();
+        assert_eq!(prediction_account.withdrawn, 2);
+        assert_eq!(prediction_account.bulk.cursor, 2);
+        assert_eq!(prediction_account.bulk.anti, 10000);
+        assert_eq!(prediction_account.bulk.pro, 8000);
+
+        // Nothing is left to settle once every record is withdrawn
+        let result = bulk_withdraw(
+            Context::new(&program_id, &mut accounts, &second_batch, bumps()),
+            0,
+            2,
+            1,
+        );
+        assert_eq!(result.unwrap_err(), PredictError::AlreadyWithdrawn.into());
+    }
+
+    #[test]
+    fn test_bulk_withdraw_constraints() {
+        let program_id = program_id();
+        let seeds = |prefix: &[u8]| {
+            Pubkey::find_program_address(&[prefix, 0u64.to_le_bytes().as_ref()], &program_id).0
+        };
+        let (admin_pda, _) = Pubkey::find_program_address(&[b"admin"], &program_id);
+        let pool_authority_pda = seeds(b"authority");
+        let mut admin = TestAccountData::new_account_with_key_and_owner(admin_pda, program_id);
+        admin.init_data(&test_admin(), AdminAccount::LEN);
+        let mut prediction =
+            TestAccountData::new_account_with_key_and_owner(seeds(b"prediction"), program_id);
+        prediction.init_data(&create_equalised_test_prediction(), PredictionAccount::LEN);
+        let pool_at = |prefix: &[u8], owner: Pubkey, mint: Pubkey| {
+            let mut account = TestAccountData::new_token(seeds(prefix));
+            account.init_token_account(owner, mint).unwrap();
+            account
+        };
+        let mut authority = TestAccountData::new_authority_account(ANTITOKEN_MULTISIG);
+        let mut stranger = TestAccountData::new_authority_account(Pubkey::new_unique());
+        let mut pool_authority = TestAccountData::new_authority_account(pool_authority_pda);
+        let mut prediction_anti = pool_at(b"anti_token", pool_authority_pda, ANTI_MINT_ADDRESS);
+        let mut prediction_pro = pool_at(b"pro_token", pool_authority_pda, PRO_MINT_ADDRESS);
+        let mut stray_anti = pool_at(b"anti_token", ANTITOKEN_MULTISIG, ANTI_MINT_ADDRESS);
+        let mut mismatched_anti = pool_at(b"anti_token", pool_authority_pda, PRO_MINT_ADDRESS);
+        let mut anti_mint = TestAccountData::new_mint(ANTI_MINT_ADDRESS);
+        let mut pro_mint = TestAccountData::new_mint(PRO_MINT_ADDRESS);
+        let mut token_program = TestAccountData::new_token_program();
+
+        let authority_info = authority.to_account_info(true);
+        let accounts = vec![
+            admin.to_account_info(false),
+            prediction.to_account_info(false),
+            authority_info.clone(),
+            pool_authority.to_account_info(false),
+            prediction_anti.to_account_info(false),
+            prediction_pro.to_account_info(false),
+            anti_mint.to_account_info(false),
+            pro_mint.to_account_info(false),
+            token_program.to_account_info(false),
+        ];
+        let stranger_info = stranger.to_account_info(true);
+        let stray_anti_info = stray_anti.to_account_info(false);
+        let mismatched_anti_info = mismatched_anti.to_account_info(false);
+
+        // Only the multisig settles in bulk, from pools held by the prediction's
+        // authority in the prediction's mints
+        let cases: Vec<(Vec<AccountInfo>, Option<Error>)> = [
+            (2, &authority_info, None),
+            (2, &stranger_info, Some(PredictError::Unauthorised.into())),
+            (
+                4,
+                &stray_anti_info,
+                Some(PredictError::InvalidTokenAccount.into()),
+            ),
+            (
+                4,
+                &mismatched_anti_info,
+                Some(PredictError::InvalidTokenAccount.into()),
+            ),
+        ]
+        .into_iter()
+        .map(|(slot, account, expected)| {
+            let mut swapped = accounts.clone();
+            swapped[slot] = account.clone();
+            (swapped, expected)
+        })
+        .collect();
+        for (swapped, expected) in &cases {
+            let result = try_accounts::<BulkWithdrawTokens, BulkWithdrawTokensBumps>(
+                swapped,
+                &0u64.to_le_bytes(),
+            );
+            assert_eq!(result.err().as_ref(), expected.as_ref());
+        }
     }
 }
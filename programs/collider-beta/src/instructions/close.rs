@@ -0,0 +1,635 @@
+//! Program Author: sshmatrix, for Antitoken
+//! Program Description: Collider's instruction set
+//! Version: 1.0.0-beta
+//! License: MIT
+//! Created: 20 Jan 2025
+//! Last Modified: 20 Jan 2025
+//! Repository: https://github.com/antitokens/solana-collider
+//! Contact: dev@antitoken.pro
+
+// instructions/close.rs
+use crate::state::*;
+use crate::utils::*;
+use crate::{ClosePrediction, CloseUnclaimedRecords};
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{self, CloseAccount, TransferChecked};
+
+pub fn close_prediction(ctx: Context<ClosePrediction>, index: u64) -> Result<()> {
+    // Get current time
+    let now = current_time()?;
+
+    // Verify prediction has settled, unless voided
+    let prediction = &ctx.accounts.prediction;
+    if !prediction.voided {
+        require!(prediction.equalised, PredictError::NotEqualised);
+        require!(
+            prediction.is_settled(now),
+            PredictError::ChallengePeriodActive
+        );
+    }
+
+    // Every deposit record must be withdrawn, or closed unclaimed past the grace period
+    require!(
+        prediction.withdrawn == prediction.deposits,
+        PredictError::WithdrawalsPending
+    );
+
+    // Leftovers owed to records closed unclaimed are reported apart from dust
+    let ((anti_unclaimed, pro_unclaimed), (anti_dust, pro_dust)) = prediction.split_leftovers(
+        ctx.accounts.prediction_anti_token.amount,
+        ctx.accounts.prediction_pro_token.amount,
+    );
+    let anti_left = anti_unclaimed + anti_dust;
+    let pro_left = pro_unclaimed + pro_dust;
+
+    // Pool accounts are held by the prediction's PDA authority, which signs
+    let index_bytes = index.to_le_bytes();
+    let pool_bump = [ctx.bumps.pool_authority];
+    let pool_seeds: &[&[u8]] = &[b"authority", &index_bytes, &pool_bump];
+    let signer_seeds: &[&[&[u8]]] = &[pool_seeds];
+
+    // Transfer leftover ANTI tokens, then close the pool account
+    if anti_left > 0 {
+        token_interface::transfer_checked(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                TransferChecked {
+                    from: ctx.accounts.prediction_anti_token.to_account_info(),
+                    mint: ctx.accounts.anti_mint.to_account_info(),
+                    to: ctx.accounts.treasury_anti_token.to_account_info(),
+                    authority: ctx.accounts.pool_authority.to_account_info(),
+                },
+                signer_seeds,
+            ),
+            anti_left,
+            ctx.accounts.anti_mint.decimals,
+        )?;
+    }
+    token_interface::close_account(CpiContext::new_with_signer(
+        ctx.accounts.token_program.to_account_info(),
+        CloseAccount {
+            account: ctx.accounts.prediction_anti_token.to_account_info(),
+            destination: ctx.accounts.creator.to_account_info(),
+            authority: ctx.accounts.pool_authority.to_account_info(),
+        },
+        signer_seeds,
+    ))?;
+
+    // Transfer leftover PRO tokens, then close the pool account
+    if pro_left > 0 {
+        token_interface::transfer_checked(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                TransferChecked {
+                    from: ctx.accounts.prediction_pro_token.to_account_info(),
+                    mint: ctx.accounts.pro_mint.to_account_info(),
+                    to: ctx.accounts.treasury_pro_token.to_account_info(),
+                    authority: ctx.accounts.pool_authority.to_account_info(),
+                },
+                signer_seeds,
+            ),
+            pro_left,
+            ctx.accounts.pro_mint.decimals,
+        )?;
+    }
+    token_interface::close_account(CpiContext::new_with_signer(
+        ctx.accounts.token_program.to_account_info(),
+        CloseAccount {
+            account: ctx.accounts.prediction_pro_token.to_account_info(),
+            destination: ctx.accounts.creator.to_account_info(),
+            authority: ctx.accounts.pool_authority.to_account_info(),
+        },
+        signer_seeds,
+    ))?;
+
+    // Archive the prediction; its account is closed to the creator on exit
+    let state = &mut ctx.accounts.state;
+    state.archived = state
+        .archived
+        .checked_add(1)
+        .ok_or(error!(PredictError::MathError))?;
+
+    // Serialise updated state
+    write_account(&state.to_account_info(), &**state)?;
+
+    emit!(CloseEvent {
+        index,
+        address: ctx.accounts.creator.key(),
+        anti: anti_dust,
+        pro: pro_dust,
+        unclaimed_anti: anti_unclaimed,
+        unclaimed_pro: pro_unclaimed,
+        timestamp: now,
+    });
+
+    Ok(())
+}
+
+pub fn close_unclaimed_records<'a, 'b, 'c: 'info, 'info>(
+    ctx: Context<'a, 'b, 'c, 'info, CloseUnclaimedRecords<'info>>,
+    index: u64,
+) -> Result<()> {
+    // Get current time
+    let now = current_time()?;
+
+    // Verify prediction has settled, unless voided
+    let prediction = &ctx.accounts.prediction;
+    if !prediction.voided {
+        require!(prediction.equalised, PredictError::NotEqualised);
+        require!(
+            prediction.is_settled(now),
+            PredictError::ChallengePeriodActive
+        );
+    }
+    require!(
+        prediction.withdrawn < prediction.deposits,
+        PredictError::AlreadyWithdrawn
+    );
+
+    // Depositors keep their claim until the grace period ends
+    require!(
+        now >= prediction.grace_end()?,
+        PredictError::GracePeriodActive
+    );
+
+    // Expect (deposit record, depositor) per record to close
+    let remaining_accounts: &[AccountInfo<'info>] = ctx.remaining_accounts;
+    require!(
+        !remaining_accounts.is_empty() && remaining_accounts.len() % 2 == 0,
+        PredictError::InvalidDepositRecord
+    );
+
+    let mut anti_unclaimed: u64 = 0;
+    let mut pro_unclaimed: u64 = 0;
+    let mut records_closed: u64 = 0;
+
+    for accounts in remaining_accounts.chunks(2) {
+        let deposit_record: Account<'info, DepositRecord> = Account::try_from(&accounts[0])?;
+        require!(
+            deposit_record.index == index,
+            PredictError::InvalidDepositRecord
+        );
+
+        // Rent goes back to the depositor, who paid it
+        let depositor = &accounts[1];
+        require!(
+            depositor.key() == deposit_record.deposit.address,
+            PredictError::InvalidRecipient
+        );
+
+        // Records already settled owe nothing but are closed too
+        if !deposit_record.deposit.withdrawn {
+            let (anti_owed, pro_owed) = withdrawal(prediction, &deposit_record, now)?;
+            anti_unclaimed = anti_unclaimed
+                .checked_add(anti_owed)
+                .ok_or(error!(PredictError::MathError))?;
+            pro_unclaimed = pro_unclaimed
+                .checked_add(pro_owed)
+                .ok_or(error!(PredictError::MathError))?;
+            records_closed += 1;
+        }
+        deposit_record.close(depositor.clone())?;
+    }
+
+    // What the closed records were owed stays in the pools, counted as unclaimed
+    let prediction = &mut ctx.accounts.prediction;
+    prediction.record_unclaimed(records_closed, anti_unclaimed, pro_unclaimed)?;
+
+    // Serialise updated prediction state
+    write_account(&prediction.to_account_info(), &**prediction)?;
+
+    emit!(UnclaimedEvent {
+        index,
+        records: records_closed,
+        anti: anti_unclaimed,
+        pro: pro_unclaimed,
+        timestamp: now,
+    });
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::*;
+    use crate::{ClosePredictionBumps, CloseUnclaimedRecordsBumps};
+    use anchor_spl::token::spl_token;
+    use anchor_spl::token_interface::{Mint, TokenInterface};
+
+    // Reusable method to create a settled test prediction
+    fn create_settled_test_prediction(creator: Pubkey, withdrawn: u64) -> PredictionAccount {
+        PredictionAccount {
+            creator,
+            anti: 10000,
+            pro: 8000,
+            deposits: 2,
+            withdrawn,
+            equalised: true,
+            equalisation: Some(Equalisation::new(vec![6000, 4000], 1736899200)),
+            challenge_end: 1736899200 + CHALLENGE_PERIOD,
+            ..test_prediction()
+        }
+    }
+
+    #[test]
+    fn test_close_prediction() {
+        let program_id = program_id();
+        let now: i64 = 1736899200 + CHALLENGE_PERIOD;
+        let abandoned = now + CLOSE_GRACE_PERIOD;
+        let multisig = Pubkey::new_unique();
+        let creator = Pubkey::new_unique();
+
+        // Create test accounts
+        let (admin_pda, admin_bump) = Pubkey::find_program_address(&[b"admin"], &program_id);
+        let (state_pda, state_bump) = Pubkey::find_program_address(&[b"state"], &program_id);
+        let (prediction_pda, prediction_bump) = Pubkey::find_program_address(
+            &[b"prediction", 0u64.to_le_bytes().as_ref()],
+            &program_id,
+        );
+        let (pool_authority_pda, pool_authority_bump) =
+            Pubkey::find_program_address(&[b"authority", 0u64.to_le_bytes().as_ref()], &program_id);
+        let (_, anti_token_bump) = Pubkey::find_program_address(
+            &[b"anti_token", 0u64.to_le_bytes().as_ref()],
+            &program_id,
+        );
+        let (_, pro_token_bump) =
+            Pubkey::find_program_address(&[b"pro_token", 0u64.to_le_bytes().as_ref()], &program_id);
+
+        let mut admin = TestAccountData::new_account_with_key_and_owner(admin_pda, program_id);
+        admin.init_data(
+            &AdminAccount {
+                antitoken_multisig: multisig,
+                ..test_admin()
+            },
+            AdminAccount::LEN,
+        );
+        let mut state = TestAccountData::new_account_with_key_and_owner(state_pda, program_id);
+        state.init_data(
+            &StateAccount {
+                version: STATE_VERSION,
+                index: 1,
+                authority: multisig,
+                archived: 0,
+            },
+            StateAccount::LEN,
+        );
+        let mut prediction =
+            TestAccountData::new_account_with_key_and_owner(prediction_pda, program_id);
+        let mut authority = TestAccountData::new_authority_account(multisig);
+        let mut pool_authority = TestAccountData::new_authority_account(pool_authority_pda);
+        let mut prediction_anti =
+            TestAccountData::new_token_account(pool_authority_pda, ANTI_MINT_ADDRESS, 3);
+        let mut prediction_pro =
+            TestAccountData::new_token_account(pool_authority_pda, PRO_MINT_ADDRESS, 1);
+        let mut treasury_anti = TestAccountData::new_token_account(multisig, ANTI_MINT_ADDRESS, 0);
+        let mut treasury_pro = TestAccountData::new_token_account(multisig, PRO_MINT_ADDRESS, 0);
+        let mut anti_mint = TestAccountData::new_mint(ANTI_MINT_ADDRESS);
+        let mut pro_mint = TestAccountData::new_mint(PRO_MINT_ADDRESS);
+        let mut token_program =
+            TestAccountData::new_account_with_key_and_owner(spl_token::ID, spl_token::ID);
+
+        // Close only once every record is withdrawn or closed unclaimed, even past the
+        // grace period
+        let mut archived = 0;
+        for (withdrawn, voided, at, expected) in [
+            (1, false, now, Err(PredictError::WithdrawalsPending)),
+            (2, false, now - 1, Err(PredictError::ChallengePeriodActive)),
+            (2, false, now, Ok(())),
+            (1, false, abandoned, Err(PredictError::WithdrawalsPending)),
+            (2, true, now - 1, Ok(())),
+        ] {
+            prediction.init_data(
+                &PredictionAccount {
+                    version: PREDICTION_VERSION,
+                    voided,
+                    ..create_settled_test_prediction(creator, withdrawn)
+                },
+                PredictionAccount::LEN,
+            );
+            let mut creator_account = TestAccountData::new_authority_account(creator);
+
+            let admin_info = admin.to_account_info(false);
+            let state_info = state.to_account_info(false);
+            let prediction_info = prediction.to_account_info(false);
+            let authority_info = authority.to_account_info(true);
+            let creator_info = creator_account.to_account_info(false);
+            let pool_authority_info = pool_authority.to_account_info(false);
+            let prediction_anti_info = prediction_anti.to_account_info(false);
+            let prediction_pro_info = prediction_pro.to_account_info(false);
+            let treasury_anti_info = treasury_anti.to_account_info(false);
+            let treasury_pro_info = treasury_pro.to_account_info(false);
+            let anti_mint_info = anti_mint.to_account_info(false);
+            let pro_mint_info = pro_mint.to_account_info(false);
+            let token_program_info = token_program.to_account_info(false);
+
+            let mut accounts = ClosePrediction {
+                admin: Account::try_from(&admin_info).unwrap(),
+                state: Account::try_from(&state_info).unwrap(),
+                prediction: Account::try_from(&prediction_info).unwrap(),
+                authority: Signer::try_from(&authority_info).unwrap(),
+                creator: creator_info,
+                pool_authority: pool_authority_info,
+                prediction_anti_token: InterfaceAccount::try_from(&prediction_anti_info).unwrap(),
+                prediction_pro_token: InterfaceAccount::try_from(&prediction_pro_info).unwrap(),
+                treasury_anti_token: InterfaceAccount::try_from(&treasury_anti_info).unwrap(),
+                treasury_pro_token: InterfaceAccount::try_from(&treasury_pro_info).unwrap(),
+                anti_mint: InterfaceAccount::<Mint>::try_from(&anti_mint_info).unwrap(),
+                pro_mint: InterfaceAccount::<Mint>::try_from(&pro_mint_info).unwrap(),
+                token_program: Interface::<TokenInterface>::try_from(&token_program_info).unwrap(),
+            };
+            let bumps = ClosePredictionBumps {
+                admin: admin_bump,
+                state: state_bump,
+                prediction: prediction_bump,
+                pool_authority: pool_authority_bump,
+                prediction_anti_token: anti_token_bump,
+                prediction_pro_token: pro_token_bump,
+            };
+
+            set_clock_override(Some(at));
+            let result = close_prediction(Context::new(&program_id, &mut accounts, &[], bumps), 0);
+            match expected {
+                Ok(()) => {
+                    assert!(result.is_ok());
+                    archived += 1;
+                }
+                Err(error) => assert_eq!(result.unwrap_err(), error.into()),
+            }
+
+            // Each closure is counted in the state archive
+            let data = state_info.try_borrow_data().unwrap();
+            let updated_state = StateAccount::try_deserialize(&mut &data[..]).unwrap();
+            assert_eq!(updated_state.archived, archived);
+        }
+    }
+
+    #[test]
+    fn test_close_unclaimed_records() {
+        let program_id = program_id();
+        let multisig = Pubkey::new_unique();
+        let first_key = Pubkey::new_unique();
+        let second_key = Pubkey::new_unique();
+
+        let (admin_pda, admin_bump) = Pubkey::find_program_address(&[b"admin"], &program_id);
+        let (prediction_pda, prediction_bump) = Pubkey::find_program_address(
+            &[b"prediction", 0u64.to_le_bytes().as_ref()],
+            &program_id,
+        );
+
+        let mut admin = TestAccountData::new_account_with_key_and_owner(admin_pda, program_id);
+        admin.init_data(
+            &AdminAccount {
+                antitoken_multisig: multisig,
+                ..test_admin()
+            },
+            AdminAccount::LEN,
+        );
+
+        // Two records, owed (6000, 5000) and (4000, 3000) of the allocations
+        let prediction_data = PredictionAccount {
+            version: PREDICTION_VERSION,
+            deposits: 2,
+            equalisation: Some(Equalisation {
+                anti: vec![10000],
+                pro: vec![8000],
+                anti_staked: vec![10000],
+                pro_staked: vec![8000],
+                binned: 2,
+                truth: vec![6000, 4000],
+                timestamp: 1736899200,
+                round: 0,
+            }),
+            ..create_settled_test_prediction(Pubkey::new_unique(), 0)
+        };
+        let grace_end = prediction_data.grace_end().unwrap();
+        let mut prediction =
+            TestAccountData::new_account_with_key_and_owner(prediction_pda, program_id);
+        prediction.init_data(&prediction_data, PredictionAccount::LEN);
+        let mut authority = TestAccountData::new_authority_account(multisig);
+
+        let mut first_record = ReallocAccountData::new_deposit_record(
+            Pubkey::new_unique(),
+            program_id,
+            &test_deposit_record(first_key, 0, 6000, 5000),
+        );
+        let mut second_deposit_record = test_deposit_record(second_key, 0, 4000, 3000);
+        second_deposit_record.anti_offset = 6000; // Binned after the first record
+        second_deposit_record.pro_offset = 5000;
+        let mut second_record = ReallocAccountData::new_deposit_record(
+            Pubkey::new_unique(),
+            program_id,
+            &second_deposit_record,
+        );
+        let mut first_depositor = TestAccountData::new_authority_account(first_key);
+        let mut second_depositor = TestAccountData::new_authority_account(second_key);
+
+        let admin_info = admin.to_account_info(false);
+        let prediction_info = prediction.to_account_info(false);
+        let authority_info = authority.to_account_info(true);
+        let first_record_info = first_record.to_account_info();
+        let second_record_info = second_record.to_account_info();
+        let first_depositor_info = first_depositor.to_account_info(false);
+        let second_depositor_info = second_depositor.to_account_info(false);
+
+        let mut accounts = CloseUnclaimedRecords {
+            admin: Account::try_from(&admin_info).unwrap(),
+            prediction: Account::try_from(&prediction_info).unwrap(),
+            authority: Signer::try_from(&authority_info).unwrap(),
+        };
+        let bumps = || CloseUnclaimedRecordsBumps {
+            admin: admin_bump,
+            prediction: prediction_bump,
+        };
+        // Only the multisig may close records on their depositors' behalf
+        let mut stranger = TestAccountData::new_authority_account(Pubkey::new_unique());
+        let signers = [
+            vec![
+                admin_info.clone(),
+                prediction_info.clone(),
+                authority_info.clone(),
+            ],
+            vec![
+                admin_info.clone(),
+                prediction_info.clone(),
+                stranger.to_account_info(true),
+            ],
+        ];
+        assert!(
+            try_accounts::<CloseUnclaimedRecords, CloseUnclaimedRecordsBumps>(
+                &signers[0],
+                &0u64.to_le_bytes()
+            )
+            .is_ok()
+        );
+        let result = try_accounts::<CloseUnclaimedRecords, CloseUnclaimedRecordsBumps>(
+            &signers[1],
+            &0u64.to_le_bytes(),
+        );
+        assert_eq!(result.err(), Some(PredictError::Unauthorised.into()));
+
+        let first = [first_record_info.clone(), first_depositor_info.clone()];
+        let second = [second_record_info.clone(), second_depositor_info.clone()];
+        let misdirected = [first_record_info.clone(), second_depositor_info.clone()];
+
+        // Depositors keep their claim until the grace period ends
+        set_clock_override(Some(grace_end - 1));
+        let result =
+            close_unclaimed_records(Context::new(&program_id, &mut accounts, &first, bumps()), 0);
+        assert_eq!(result.unwrap_err(), PredictError::GracePeriodActive.into());
+
+        // Rent is only refunded to the depositor of the record
+        set_clock_override(Some(grace_end));
+        let result = close_unclaimed_records(
+            Context::new(&program_id, &mut accounts, &misdirected, bumps()),
+            0,
+        );
+        assert_eq!(result.unwrap_err(), PredictError::InvalidRecipient.into());
+
+        // Each record is closed to its depositor, and what it was owed counted unclaimed
+        let rent = first_record_info.lamports();
+        close_unclaimed_records(Context::new(&program_id, &mut accounts, &first, bumps()), 0)
+            .unwrap();
+        assert!(first_record_info.data_is_empty() && first_record_info.lamports() == 0);
+        assert_eq!(first_depositor_info.lamports(), 1_000_000 + rent);
+        close_unclaimed_records(
+            Context::new(&program_id, &mut accounts, &second, bumps()),
+            0,
+        )
+        .unwrap();
+        assert!(second_record_info.data_is_empty());
+
+        let updated = PredictionAccount::try_deserialize(
+            &mut prediction_info.try_borrow_data().unwrap().as_ref(),
+        )
+        .unwrap();
+        assert_eq!(updated.withdrawn, 2);
+        assert_eq!(
+            (updated.bulk.unclaimed_anti, updated.bulk.unclaimed_pro),
+            (10000, 8000)
+        );
+
+        // Pool leftovers split into the unclaimed amounts and dust
+        assert_eq!(
+            updated.split_leftovers(10003, 8001),
+            ((10000, 8000), (3, 1))
+        );
+    }
+
+    #[test]
+    fn test_close_prediction_constraints() {
+        let program_id = program_id();
+        let multisig = Pubkey::new_unique();
+        let creator = Pubkey::new_unique();
+        let pda = |seed: &[u8]| {
+            Pubkey::find_program_address(&[seed, 0u64.to_le_bytes().as_ref()], &program_id).0
+        };
+        let (admin_pda, _) = Pubkey::find_program_address(&[b"admin"], &program_id);
+        let (state_pda, _) = Pubkey::find_program_address(&[b"state"], &program_id);
+        let pool_authority_pda = pda(b"authority");
+
+        let mut admin = TestAccountData::new_account_with_key_and_owner(admin_pda, program_id);
+        admin.init_data(
+            &AdminAccount {
+                antitoken_multisig: multisig,
+                ..test_admin()
+            },
+            AdminAccount::LEN,
+        );
+        let mut state = TestAccountData::new_account_with_key_and_owner(state_pda, program_id);
+        state.init_data(
+            &StateAccount {
+                version: STATE_VERSION,
+                index: 1,
+                authority: multisig,
+                archived: 0,
+            },
+            StateAccount::LEN,
+        );
+        let mut prediction =
+            TestAccountData::new_account_with_key_and_owner(pda(b"prediction"), program_id);
+        prediction.init_data(
+            &PredictionAccount {
+                version: PREDICTION_VERSION,
+                ..create_settled_test_prediction(creator, 2)
+            },
+            PredictionAccount::LEN,
+        );
+        let pool_at = |seed: &[u8], owner: Pubkey, mint: Pubkey| {
+            let mut account = TestAccountData::new_token(pda(seed));
+            account.init_token_account(owner, mint).unwrap();
+            account
+        };
+        let mut authority = TestAccountData::new_authority_account(multisig);
+        let mut stranger = TestAccountData::new_authority_account(Pubkey::new_unique());
+        let mut creator_account = TestAccountData::new_authority_account(creator);
+        let mut pool_authority = TestAccountData::new_authority_account(pool_authority_pda);
+        let mut prediction_anti = pool_at(b"anti_token", pool_authority_pda, ANTI_MINT_ADDRESS);
+        let mut prediction_pro = pool_at(b"pro_token", pool_authority_pda, PRO_MINT_ADDRESS);
+        let mut stray_anti = pool_at(b"anti_token", multisig, ANTI_MINT_ADDRESS);
+        let mut treasury_anti = TestAccountData::new_token_account(multisig, ANTI_MINT_ADDRESS, 0);
+        let mut treasury_pro = TestAccountData::new_token_account(multisig, PRO_MINT_ADDRESS, 0);
+        let mut foreign_anti = TestAccountData::new_token_account(creator, ANTI_MINT_ADDRESS, 0);
+        let mut anti_mint = TestAccountData::new_mint(ANTI_MINT_ADDRESS);
+        let mut pro_mint = TestAccountData::new_mint(PRO_MINT_ADDRESS);
+        let mut token_program = TestAccountData::new_token_program();
+
+        let authority_info = authority.to_account_info(true);
+        let creator_info = creator_account.to_account_info(false);
+        let prediction_anti_info = prediction_anti.to_account_info(false);
+        let treasury_anti_info = treasury_anti.to_account_info(false);
+        let treasury_pro_info = treasury_pro.to_account_info(false);
+        let accounts = vec![
+            admin.to_account_info(false),
+            state.to_account_info(false),
+            prediction.to_account_info(false),
+            authority_info.clone(),
+            creator_info.clone(),
+            pool_authority.to_account_info(false),
+            prediction_anti_info.clone(),
+            prediction_pro.to_account_info(false),
+            treasury_anti_info.clone(),
+            treasury_pro_info.clone(),
+            anti_mint.to_account_info(false),
+            pro_mint.to_account_info(false),
+            token_program.to_account_info(false),
+        ];
+        let stranger_info = stranger.to_account_info(true);
+        let stray_anti_info = stray_anti.to_account_info(false);
+        let foreign_anti_info = foreign_anti.to_account_info(false);
+
+        // Only the multisig closes, rent goes to the creator and leftovers to the treasury
+        let cases: Vec<(Vec<AccountInfo>, Option<Error>)> = [
+            (3, &authority_info, None),
+            (3, &stranger_info, Some(PredictError::Unauthorised.into())),
+            (4, &authority_info, Some(PredictError::Unauthorised.into())),
+            (
+                6,
+                &stray_anti_info,
+                Some(PredictError::InvalidTokenAccount.into()),
+            ),
+            (
+                8,
+                &foreign_anti_info,
+                Some(PredictError::InvalidTokenAccount.into()),
+            ),
+            (
+                8,
+                &treasury_pro_info,
+                Some(PredictError::InvalidTokenAccount.into()),
+            ),
+        ]
+        .into_iter()
+        .map(|(slot, account, expected)| {
+            let mut swapped = accounts.clone();
+            swapped[slot] = account.clone();
+            (swapped, expected)
+        })
+        .collect();
+        for (swapped, expected) in &cases {
+            let result =
+                try_accounts::<ClosePrediction, ClosePredictionBumps>(swapped, &0u64.to_le_bytes());
+            assert_eq!(result.err().as_ref(), expected.as_ref());
+        }
+    }
+}
@@ -8,60 +8,86 @@
 //! Contact: dev@antitoken.pro
 
 // instructions/create.rs
+use crate::state::{Fees, ScalarRange};
 use crate::utils::*;
 use crate::CreatePrediction;
 use anchor_lang::prelude::*;
 use anchor_lang::system_program;
-use anchor_spl::token;
-use anchor_spl::token::spl_token::instruction::AuthorityType;
-use anchor_spl::token::SetAuthority;
 
+#[allow(clippy::too_many_arguments)]
 pub fn create(
     ctx: Context<CreatePrediction>,
     title: String,
     description: String,
     start_time: String,
     end_time: String,
+    resolution_time: Option<String>,
+    outcomes: Vec<String>,
+    scalar: Option<ScalarRange>,
+    resolver: Pubkey,
+    creator_fee_bps: u16,
     etc: Option<Vec<u8>>,
-    unix_timestamp: Option<i64>, // CRITICAL: Remove line in production!
 ) -> Result<()> {
+    let admin = &ctx.accounts.admin;
+
     // Ensure payment is sufficient
     require!(
-        ctx.accounts.authority.lamports() >= CREATION_FEE,
+        ctx.accounts.authority.lamports() >= admin.creation_fee,
         PredictError::InsufficientPayment
     );
 
     // Validate title and description lengths
     require!(
-        title.len() <= MAX_TITLE_LENGTH as usize,
+        title.len() as u64 <= admin.max_title_length,
         PredictError::TitleTooLong
     );
     require!(
-        description.len() <= MAX_DESCRIPTION_LENGTH as usize,
+        description.len() as u64 <= admin.max_description_length,
         PredictError::DescriptionTooLong
     );
 
+    // Validate outcome labels, or the range of a scalar prediction
+    match &scalar {
+        Some(range) => {
+            require!(outcomes.is_empty(), PredictError::InvalidOutcomes);
+            validate_scalar_range(range)?;
+        }
+        None => validate_outcomes(&outcomes)?,
+    }
+
+    // Ensure a resolver is assigned
+    require!(resolver != Pubkey::default(), PredictError::InvalidResolver);
+
+    // Cap the creator's cut of the payouts
+    require!(
+        creator_fee_bps <= MAX_CREATOR_FEE_BPS,
+        PredictError::InvalidFee
+    );
+
     // Ensure the title is unique
     require!(
         !state_has_title(&ctx.accounts.state, &title),
         PredictError::TitleExists
     );
 
-    // Parse and validate time ranges
+    // Parse ISO input into unix times and validate the ranges
     let start = parse_iso_timestamp(&start_time)?;
     let end = parse_iso_timestamp(&end_time)?;
-    let now = match unix_timestamp {
-        Some(ts) => ts,
-        None => Clock::get()?.unix_timestamp,
-    }; // CRITICAL: Remove block in production!
-
-    // CRITICAL: Add line in production!let now = Clock::get()?.unix_timestamp;
+    let resolution = resolution_time
+        .as_deref()
+        .map(parse_iso_timestamp)
+        .transpose()?;
+    let now = current_time()?;
 
     require!(end > start, PredictError::InvalidTimeRange);
     require!(start > now, PredictError::StartTimeInPast);
+    require!(
+        resolution.map_or(true, |resolution| resolution >= end),
+        PredictError::InvalidTimeRange
+    );
 
     // Transfer payment to state account
-    let payment_amount = CREATION_FEE;
+    let payment_amount = admin.creation_fee;
     system_program::transfer(
         CpiContext::new(
             ctx.accounts.system_program.to_account_info(),
@@ -73,66 +99,51 @@ pub fn create(
         payment_amount,
     )?;
 
-    // Set the token account authority to ANTITOKEN_MULTISIG using token instruction
-    let cpi_accounts = SetAuthority {
-        account_or_mint: ctx.accounts.prediction_anti_token.to_account_info(),
-        current_authority: ctx.accounts.authority.to_account_info(),
-    };
-
-    token::set_authority(
-        CpiContext::new(ctx.accounts.token_program.to_account_info(), cpi_accounts),
-        AuthorityType::AccountOwner,
-        Some(ANTITOKEN_MULTISIG),
-    )?;
-
-    let cpi_accounts = SetAuthority {
-        account_or_mint: ctx.accounts.prediction_pro_token.to_account_info(),
-        current_authority: ctx.accounts.authority.to_account_info(),
-    };
-
-    token::set_authority(
-        CpiContext::new(ctx.accounts.token_program.to_account_info(), cpi_accounts),
-        AuthorityType::AccountOwner,
-        Some(ANTITOKEN_MULTISIG),
-    )?;
-
-    // Get account infos for manual serialisation
-    let state_info = &ctx.accounts.state.to_account_info();
-    let prediction_info = &ctx.accounts.prediction.to_account_info();
-    let mut data_state = state_info.try_borrow_mut_data()?;
-    let mut data_prediction = prediction_info.try_borrow_mut_data()?;
-
     // Set prediction data
+    ctx.accounts.prediction.version = PREDICTION_VERSION;
     ctx.accounts.prediction.index = ctx.accounts.state.index;
     ctx.accounts.prediction.title = title.clone();
     ctx.accounts.prediction.description = description;
-    ctx.accounts.prediction.start_time = start_time.clone();
-    ctx.accounts.prediction.end_time = end_time.clone();
+    ctx.accounts.prediction.start_time = start;
+    ctx.accounts.prediction.end_time = end;
+    ctx.accounts.prediction.resolution_time = resolution;
     ctx.accounts.prediction.etc = etc;
+    ctx.accounts.prediction.outcomes = outcomes.clone();
+    ctx.accounts.prediction.scalar = scalar.clone();
+    ctx.accounts.prediction.creator = ctx.accounts.authority.key();
+    ctx.accounts.prediction.resolver = resolver;
+    ctx.accounts.prediction.anti_mint = ctx.accounts.anti_mint.key();
+    ctx.accounts.prediction.pro_mint = ctx.accounts.pro_mint.key();
+    ctx.accounts.prediction.fees = Fees::new(admin.protocol_fee_bps, creator_fee_bps);
     ctx.accounts.prediction.anti = 0;
     ctx.accounts.prediction.pro = 0;
-    ctx.accounts.prediction.deposits = vec![];
+    ctx.accounts.prediction.deposits = 0;
     ctx.accounts.prediction.equalised = false;
+    ctx.accounts.prediction.voided = false;
     ctx.accounts.prediction.equalisation = None;
 
-    // Manual serialisation
-    let serialised_prediction = ctx.accounts.prediction.try_to_vec()?;
-    data_prediction[8..8 + serialised_prediction.len()].copy_from_slice(&serialised_prediction);
+    // Serialise prediction
+    write_account(
+        &ctx.accounts.prediction.to_account_info(),
+        &*ctx.accounts.prediction,
+    )?;
 
     // Increment prediction index
     ctx.accounts.state.index += 1;
 
-    // Manual serialisation for state
-    let serialised_state = ctx.accounts.state.try_to_vec()?;
-    data_state[8..8 + serialised_state.len()].copy_from_slice(&serialised_state);
+    // Serialise state
+    write_account(&ctx.accounts.state.to_account_info(), &*ctx.accounts.state)?;
 
     // Emit event
     emit!(CreationEvent {
         index: ctx.accounts.prediction.index,
         address: ctx.accounts.authority.key(),
         title,
-        start_time,
-        end_time,
+        start_time: start,
+        end_time: end,
+        resolution_time: resolution,
+        outcomes,
+        scalar,
         timestamp: now,
     });
 
@@ -142,185 +153,21 @@ pub fn create(
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::utils::PROGRAM_ID;
+    use crate::state::AdminAccount;
+    use crate::test_utils::*;
     use crate::CreatePredictionBumps;
-    use crate::{PredictionAccount, StateAccount};
+    use crate::{Dispute, Equalisation, PredictionAccount, StateAccount};
     use anchor_lang::system_program;
-    use anchor_lang::Discriminator;
-    use anchor_spl::token::{
-        spl_token, spl_token::state::Account as SplTokenAccount, Mint, TokenAccount,
-    };
-    use solana_program::program_pack::Pack;
-    use solana_sdk::program_option::COption;
-    use std::cell::RefCell;
-    use std::str::FromStr;
-
-    // Fixed test IDs - these should be consistent across tests
-    fn program_id() -> Pubkey {
-        Pubkey::from_str(&PROGRAM_ID.to_string()).unwrap()
-    }
-
-    struct TestAccountData {
-        key: Pubkey,
-        lamports: u64,
-        data: Vec<u8>,
-        owner: Pubkey,
-        executable: bool,
-        rent_epoch: u64,
-    }
-
-    impl TestAccountData {
-        fn new_account_with_key_and_owner<T: AccountSerialize + AccountDeserialize + Clone>(
-            key: Pubkey,
-            owner: Pubkey,
-        ) -> Self {
-            Self {
-                key,
-                lamports: 1_000_000,
-                data: vec![0; 8 + PredictionAccount::LEN],
-                owner,
-                executable: true,
-                rent_epoch: 0,
-            }
-        }
-
-        fn new_mint_address(mint: Pubkey) -> Self {
-            Self {
-                key: mint,
-                lamports: 1_000_000,
-                data: vec![0; Mint::LEN],
-                owner: spl_token::ID,
-                executable: false,
-                rent_epoch: 0,
-            }
-        }
-
-        fn new_system_account() -> Self {
-            Self {
-                key: system_program::ID,
-                lamports: 1_000_000,
-                data: vec![],
-                owner: system_program::ID,
-                executable: true,
-                rent_epoch: 0,
-            }
-        }
+    use anchor_spl::token_interface::{Mint, TokenInterface};
 
-        fn new_token_account(key: Pubkey) -> Self {
-            Self {
-                key,
-                lamports: 1_000_000,
-                data: vec![0; 165],
-                owner: spl_token::ID,
-                executable: false,
-                rent_epoch: 0,
-            }
-        }
-
-        fn new_token_program() -> Self {
-            Self {
-                key: spl_token::ID,
-                lamports: 1_000_000,
-                data: vec![],
-                owner: Pubkey::default(),
-                executable: true,
-                rent_epoch: 0,
-            }
-        }
-
-        fn new_authority_account(pubkey: Pubkey) -> Self {
-            Self {
-                key: pubkey,
-                lamports: 200_000_000,
-                data: vec![],
-                owner: system_program::ID,
-                executable: true,
-                rent_epoch: 0,
-            }
-        }
-
-        fn new_vault_with_key() -> Self {
-            Self {
-                key: ANTITOKEN_MULTISIG,
-                lamports: 10_000_000,
-                data: vec![],
-                owner: system_program::ID,
-                executable: false,
-                rent_epoch: 0,
-            }
-        }
-
-        fn to_account_info<'a>(&'a mut self, is_signer: bool) -> AccountInfo<'a> {
-            AccountInfo::new(
-                &self.key,
-                is_signer,
-                true,
-                &mut self.lamports,
-                &mut self.data,
-                &self.owner,
-                self.executable,
-                self.rent_epoch,
-            )
-        }
-
-        fn init_state_data(&mut self, state: &StateAccount) -> Result<()> {
-            self.data = vec![0; 8 + StateAccount::LEN];
-            let data = self.data.as_mut_slice();
-
-            let disc = StateAccount::discriminator();
-            data[..8].copy_from_slice(&disc);
-
-            let account_data = state.try_to_vec()?;
-            data[8..8 + account_data.len()].copy_from_slice(&account_data);
-
-            Ok(())
-        }
-
-        fn init_prediction_data(&mut self, prediction: &PredictionAccount) -> Result<()> {
-            self.data = vec![0; 8 + PredictionAccount::LEN];
-            let data = self.data.as_mut_slice();
-
-            let disc = PredictionAccount::discriminator();
-            data[..8].copy_from_slice(&disc);
-
-            let account_data = prediction.try_to_vec()?;
-            data[8..8 + account_data.len()].copy_from_slice(&account_data);
-
-            Ok(())
-        }
-
-        fn init_token_account(&mut self, owner: Pubkey, mint: Pubkey) -> Result<()> {
-            self.data = vec![0; TokenAccount::LEN]; // Ensure correct buffer size
-            let data = self.data.as_mut_slice();
-
-            let close_authority: COption<Pubkey> = COption::None;
-
-            // Initialise a new SPL Token Account manually
-            let token_account = SplTokenAccount {
-                mint,
-                owner,
-                amount: 0,
-                delegate: None.into(),
-                state: spl_token::state::AccountState::Initialized,
-                is_native: None.into(),
-                delegated_amount: 0,
-                close_authority,
-            };
-
-            token_account.pack_into_slice(data);
-
-            Ok(())
-        }
-
-        fn init_rent_account() -> Self {
-            Self {
-                key: anchor_lang::solana_program::sysvar::rent::ID,
-                lamports: 1_000_000,
-                data: vec![0; 32], // Minimal rent sysvar data
-                owner: system_program::ID,
-                executable: false,
-                rent_epoch: 0,
-            }
+    fn new_vault_with_key() -> TestAccountData {
+        TestAccountData {
+            key: ANTITOKEN_MULTISIG,
+            lamports: 10_000_000,
+            data: vec![],
+            owner: system_program::ID,
+            executable: false,
+            rent_epoch: 0,
         }
     }
 
@@ -333,28 +180,28 @@ mod tests {
         let mut token_program = TestAccountData::new_token_program();
 
         // Create mints
-        let mut anti_mint = TestAccountData::new_mint_address(ANTI_MINT_ADDRESS);
-        let mut pro_mint = TestAccountData::new_mint_address(PRO_MINT_ADDRESS);
-
-        // Test double for Clock
-        thread_local! {
-            static MOCK_UNIX_TIMESTAMP: RefCell<i64> = RefCell::new(1736899200); // 2025-01-15T00:00:00Z
-        }
+        let mut anti_mint = TestAccountData::new_mint(ANTI_MINT_ADDRESS);
+        let mut pro_mint = TestAccountData::new_mint(PRO_MINT_ADDRESS);
 
         // Initialise state account
         let manager: Pubkey = Pubkey::new_unique();
-        let mut state =
-            TestAccountData::new_account_with_key_and_owner::<StateAccount>(manager, program_id);
+        let mut state = TestAccountData::new_account_with_key_and_owner(manager, program_id);
         state
             .init_state_data(&StateAccount {
+                version: STATE_VERSION,
                 index: 0,
                 authority: manager,
+                archived: 0,
             })
             .unwrap();
 
         // Derive PDAs and bumps
         let (_state_pda, state_bump) = Pubkey::find_program_address(&[b"state"], &program_id);
 
+        let (admin_pda, admin_bump) = Pubkey::find_program_address(&[b"admin"], &program_id);
+        let mut admin = TestAccountData::new_account_with_key_and_owner(admin_pda, program_id);
+        admin.init_admin_data(&test_admin()).unwrap();
+
         let (prediction_pda, prediction_bump) = Pubkey::find_program_address(
             &[b"prediction", state.data[8..16].try_into().unwrap()],
             &program_id,
@@ -370,40 +217,47 @@ mod tests {
             &program_id,
         );
 
-        let mut prediction = TestAccountData::new_account_with_key_and_owner::<PredictionAccount>(
-            prediction_pda,
-            program_id,
+        let (pool_authority_pda, pool_authority_bump) = Pubkey::find_program_address(
+            &[b"authority", state.data[8..16].try_into().unwrap()],
+            &program_id,
         );
+
+        let mut prediction =
+            TestAccountData::new_account_with_key_and_owner(prediction_pda, program_id);
         prediction
             .init_prediction_data(&PredictionAccount::default())
             .unwrap();
 
         // Initialise creator account
         let mut creator = TestAccountData::new_authority_account(Pubkey::new_unique());
+        creator.lamports = 200_000_000;
+        let mut pool_authority = TestAccountData::new_authority_account(pool_authority_pda);
 
         // Create token accounts
-        let mut prediction_anti_token = TestAccountData::new_token_account(anti_token_pda);
-        let mut prediction_pro_token = TestAccountData::new_token_account(pro_token_pda);
+        let mut prediction_anti_token = TestAccountData::new_token(anti_token_pda);
+        let mut prediction_pro_token = TestAccountData::new_token(pro_token_pda);
 
         // Rent for accounts
-        let mut rent_account = TestAccountData::init_rent_account();
+        let mut rent_account = TestAccountData::new_rent_account();
 
         // Initialise token accounts
         prediction_anti_token
-            .init_token_account(creator.key, anti_mint.key)
+            .init_token_account(pool_authority.key, anti_mint.key)
             .unwrap();
         prediction_pro_token
-            .init_token_account(creator.key, pro_mint.key)
+            .init_token_account(pool_authority.key, pro_mint.key)
             .unwrap();
 
         // Initialise other accounts
         let mut system_program = TestAccountData::new_system_account();
-        let mut vault = TestAccountData::new_vault_with_key();
+        let mut vault = new_vault_with_key();
 
         // Prepare account infos
+        let admin_info = admin.to_account_info(false);
         let state_info = state.to_account_info(false);
         let prediction_info = prediction.to_account_info(false);
         let authority_info = creator.to_account_info(true);
+        let pool_authority_info = pool_authority.to_account_info(false);
         let system_info = system_program.to_account_info(false);
         let anti_mint_info = anti_mint.to_account_info(false);
         let pro_mint_info = pro_mint.to_account_info(false);
@@ -415,29 +269,34 @@ mod tests {
 
         // Set up CreatePrediction context
         let mut accounts = CreatePrediction {
+            admin: Account::try_from(&admin_info).unwrap(),
             state: Account::try_from(&state_info).unwrap(),
             prediction: Account::try_from(&prediction_info).unwrap(),
             authority: Signer::try_from(&authority_info).unwrap(),
-            prediction_anti_token: Account::try_from(&prediction_anti_token_info).unwrap(),
-            prediction_pro_token: Account::try_from(&prediction_pro_token_info).unwrap(),
-            anti_mint: anti_mint_info.clone(),
-            pro_mint: pro_mint_info.clone(),
+            pool_authority: pool_authority_info,
+            prediction_anti_token: InterfaceAccount::try_from(&prediction_anti_token_info).unwrap(),
+            prediction_pro_token: InterfaceAccount::try_from(&prediction_pro_token_info).unwrap(),
+            anti_mint: InterfaceAccount::<Mint>::try_from(&anti_mint_info).unwrap(),
+            pro_mint: InterfaceAccount::<Mint>::try_from(&pro_mint_info).unwrap(),
             vault: vault_info,
-            token_program: Program::try_from(&token_program_info).unwrap(),
+            token_program: Interface::<TokenInterface>::try_from(&token_program_info).unwrap(),
             system_program: Program::try_from(&system_info).unwrap(),
             rent: Sysvar::<Rent>::from_account_info(&rent_account_info)?,
         };
 
         // Include the CreatePredictionBumps with the bump for the prediction account
         let bumps = CreatePredictionBumps {
+            admin: admin_bump,
             state: state_bump,
             prediction: prediction_bump,
+            pool_authority: pool_authority_bump,
             prediction_anti_token: anti_token_bump,
             prediction_pro_token: pro_token_bump,
         };
         /* Common Setup Ends Here */
 
         // Call the create function
+        set_clock_override(Some(1736899200));
         let result = create(
             Context::new(&program_id, &mut accounts, &[], bumps),
             "Test Prediction".to_string(),
@@ -445,14 +304,16 @@ mod tests {
             "2025-02-01T00:00:00Z".to_string(),
             "2025-02-02T00:00:00Z".to_string(),
             None,
-            Some(1736899200),
+            vec!["Anti".to_string(), "Pro".to_string()],
+            None,
+            ANTITOKEN_MULTISIG,
+            0,
+            None,
         );
 
         // If the test fails, print the error
-        if result.is_err() {
-            println!("Error: {:?}", result.unwrap_err());
-        } else {
-            assert!(result.is_ok());
+        if let Err(error) = &result {
+            println!("Error: {:?}", error);
         }
 
         // Verify prediction data
@@ -463,13 +324,42 @@ mod tests {
         assert_eq!(prediction_account.index, 0);
         assert_eq!(prediction_account.title, "Test Prediction");
         assert_eq!(prediction_account.description, "Test Description");
-        assert_eq!(prediction_account.start_time, "2025-02-01T00:00:00Z");
-        assert_eq!(prediction_account.end_time, "2025-02-02T00:00:00Z");
+        assert_eq!(prediction_account.start_time, 1738368000);
+        assert_eq!(prediction_account.end_time, 1738454400);
+        assert_eq!(prediction_account.resolution_time, None);
+        assert_eq!(prediction_account.outcomes, vec!["Anti", "Pro"]);
+        assert_eq!(prediction_account.resolver, ANTITOKEN_MULTISIG);
+        assert_eq!(prediction_account.anti_mint, ANTI_MINT_ADDRESS);
+        assert_eq!(prediction_account.pro_mint, PRO_MINT_ADDRESS);
+        assert_eq!(prediction_account.fees.protocol_bps, PROTOCOL_FEE_BPS);
+        assert_eq!(prediction_account.fees.creator_bps, 0);
+        assert!(!prediction_account.fees.carved);
         assert_eq!(prediction_account.anti, 0);
         assert_eq!(prediction_account.pro, 0);
-        assert!(prediction_account.deposits.is_empty());
+        assert_eq!(prediction_account.deposits, 0);
         assert!(!prediction_account.equalised);
         assert!(prediction_account.equalisation.is_none());
+
+        // Creation allocates what the strings need, and settling fits within the cap
+        let space = PredictionAccount::space(
+            "Test Prediction",
+            "Test Description",
+            &None,
+            &["Anti".to_string(), "Pro".to_string()],
+            &None,
+        );
+        assert!(space < 8 + PredictionAccount::LEN);
+        assert!(8 + prediction_account.try_to_vec()?.len() <= space);
+        let mut settled = prediction_account;
+        settled.equalisation = Some(Equalisation::new(vec![60_000, 40_000], 0));
+        settled.dispute = Some(Dispute {
+            disputer: Pubkey::default(),
+            bond: DISPUTE_BOND,
+            timestamp: 0,
+            round: 0,
+        });
+        assert!(8 + settled.try_to_vec()?.len() <= settled.max_space());
+
         // Verify state update
         let state_account: StateAccount =
             StateAccount::try_deserialize(&mut state_info.try_borrow_data().unwrap().as_ref())
@@ -487,28 +377,28 @@ mod tests {
         let mut token_program = TestAccountData::new_token_program();
 
         // Create mints
-        let mut anti_mint = TestAccountData::new_mint_address(ANTI_MINT_ADDRESS);
-        let mut pro_mint = TestAccountData::new_mint_address(PRO_MINT_ADDRESS);
-
-        // Test double for Clock
-        thread_local! {
-            static MOCK_UNIX_TIMESTAMP: RefCell<i64> = RefCell::new(1736899200); // 2025-01-15T00:00:00Z
-        }
+        let mut anti_mint = TestAccountData::new_mint(ANTI_MINT_ADDRESS);
+        let mut pro_mint = TestAccountData::new_mint(PRO_MINT_ADDRESS);
 
         // Initialise state account
         let manager: Pubkey = Pubkey::new_unique();
-        let mut state =
-            TestAccountData::new_account_with_key_and_owner::<StateAccount>(manager, program_id);
+        let mut state = TestAccountData::new_account_with_key_and_owner(manager, program_id);
         state
             .init_state_data(&StateAccount {
+                version: STATE_VERSION,
                 index: 0,
                 authority: manager,
+                archived: 0,
             })
             .unwrap();
 
         // Derive PDAs and bumps
         let (_state_pda, state_bump) = Pubkey::find_program_address(&[b"state"], &program_id);
 
+        let (admin_pda, admin_bump) = Pubkey::find_program_address(&[b"admin"], &program_id);
+        let mut admin = TestAccountData::new_account_with_key_and_owner(admin_pda, program_id);
+        admin.init_admin_data(&test_admin()).unwrap();
+
         let (prediction_pda, prediction_bump) = Pubkey::find_program_address(
             &[b"prediction", state.data[8..16].try_into().unwrap()],
             &program_id,
@@ -524,10 +414,13 @@ mod tests {
             &program_id,
         );
 
-        let mut prediction = TestAccountData::new_account_with_key_and_owner::<PredictionAccount>(
-            prediction_pda,
-            program_id,
+        let (pool_authority_pda, pool_authority_bump) = Pubkey::find_program_address(
+            &[b"authority", state.data[8..16].try_into().unwrap()],
+            &program_id,
         );
+
+        let mut prediction =
+            TestAccountData::new_account_with_key_and_owner(prediction_pda, program_id);
         prediction
             .init_prediction_data(&PredictionAccount::default())
             .unwrap();
@@ -543,11 +436,11 @@ mod tests {
         };
 
         // Create token accounts
-        let mut prediction_anti_token = TestAccountData::new_token_account(anti_token_pda);
-        let mut prediction_pro_token = TestAccountData::new_token_account(pro_token_pda);
+        let mut prediction_anti_token = TestAccountData::new_token(anti_token_pda);
+        let mut prediction_pro_token = TestAccountData::new_token(pro_token_pda);
 
         // Rent for accounts
-        let mut rent_account = TestAccountData::init_rent_account();
+        let mut rent_account = TestAccountData::new_rent_account();
 
         // Initialise token accounts
         prediction_anti_token
@@ -559,12 +452,16 @@ mod tests {
 
         // Initialise other accounts
         let mut system_program = TestAccountData::new_system_account();
-        let mut vault = TestAccountData::new_vault_with_key();
+        let mut vault = new_vault_with_key();
+
+        let mut pool_authority = TestAccountData::new_authority_account(pool_authority_pda);
 
         // Prepare account infos
+        let admin_info = admin.to_account_info(false);
         let state_info = state.to_account_info(false);
         let prediction_info = prediction.to_account_info(false);
         let authority_info = creator.to_account_info(true);
+        let pool_authority_info = pool_authority.to_account_info(false);
         let system_info = system_program.to_account_info(false);
         let anti_mint_info = anti_mint.to_account_info(false);
         let pro_mint_info = pro_mint.to_account_info(false);
@@ -576,23 +473,27 @@ mod tests {
 
         // Set up CreatePrediction context
         let mut accounts = CreatePrediction {
+            admin: Account::try_from(&admin_info).unwrap(),
             state: Account::try_from(&state_info).unwrap(),
             prediction: Account::try_from(&prediction_info).unwrap(),
             authority: Signer::try_from(&authority_info).unwrap(),
-            prediction_anti_token: Account::try_from(&prediction_anti_token_info).unwrap(),
-            prediction_pro_token: Account::try_from(&prediction_pro_token_info).unwrap(),
-            anti_mint: anti_mint_info.clone(),
-            pro_mint: pro_mint_info.clone(),
+            pool_authority: pool_authority_info,
+            prediction_anti_token: InterfaceAccount::try_from(&prediction_anti_token_info).unwrap(),
+            prediction_pro_token: InterfaceAccount::try_from(&prediction_pro_token_info).unwrap(),
+            anti_mint: InterfaceAccount::<Mint>::try_from(&anti_mint_info).unwrap(),
+            pro_mint: InterfaceAccount::<Mint>::try_from(&pro_mint_info).unwrap(),
             vault: vault_info,
-            token_program: Program::try_from(&token_program_info).unwrap(),
+            token_program: Interface::<TokenInterface>::try_from(&token_program_info).unwrap(),
             system_program: Program::try_from(&system_info).unwrap(),
             rent: Sysvar::<Rent>::from_account_info(&rent_account_info)?,
         };
 
         // Include the CreatePredictionBumps with the bump for the prediction account
         let bumps = CreatePredictionBumps {
+            admin: admin_bump,
             state: state_bump,
             prediction: prediction_bump,
+            pool_authority: pool_authority_bump,
             prediction_anti_token: anti_token_bump,
             prediction_pro_token: pro_token_bump,
         };
@@ -600,6 +501,7 @@ mod tests {
 
         // Test insufficient payment
         {
+            set_clock_override(Some(1736899200));
             let result = create(
                 Context::new(&program_id, &mut accounts, &[], bumps),
                 "Test Prediction".to_string(),
@@ -607,7 +509,11 @@ mod tests {
                 "2025-02-01T00:00:00Z".to_string(),
                 "2025-02-02T00:00:00Z".to_string(),
                 None,
-                Some(1736899200),
+                vec!["Anti".to_string(), "Pro".to_string()],
+                None,
+                ANTITOKEN_MULTISIG,
+                0,
+                None,
             );
             assert_eq!(
                 result.unwrap_err(),
@@ -626,28 +532,28 @@ mod tests {
         let mut token_program = TestAccountData::new_token_program();
 
         // Create mints
-        let mut anti_mint = TestAccountData::new_mint_address(ANTI_MINT_ADDRESS);
-        let mut pro_mint = TestAccountData::new_mint_address(PRO_MINT_ADDRESS);
-
-        // Test double for Clock
-        thread_local! {
-            static MOCK_UNIX_TIMESTAMP: RefCell<i64> = RefCell::new(1736899200); // 2025-01-15T00:00:00Z
-        }
+        let mut anti_mint = TestAccountData::new_mint(ANTI_MINT_ADDRESS);
+        let mut pro_mint = TestAccountData::new_mint(PRO_MINT_ADDRESS);
 
         // Initialise state account
         let manager: Pubkey = Pubkey::new_unique();
-        let mut state =
-            TestAccountData::new_account_with_key_and_owner::<StateAccount>(manager, program_id);
+        let mut state = TestAccountData::new_account_with_key_and_owner(manager, program_id);
         state
             .init_state_data(&StateAccount {
+                version: STATE_VERSION,
                 index: 0,
                 authority: manager,
+                archived: 0,
             })
             .unwrap();
 
         // Derive PDAs and bumps
         let (_state_pda, state_bump) = Pubkey::find_program_address(&[b"state"], &program_id);
 
+        let (admin_pda, admin_bump) = Pubkey::find_program_address(&[b"admin"], &program_id);
+        let mut admin = TestAccountData::new_account_with_key_and_owner(admin_pda, program_id);
+        admin.init_admin_data(&test_admin()).unwrap();
+
         let (prediction_pda, prediction_bump) = Pubkey::find_program_address(
             &[b"prediction", state.data[8..16].try_into().unwrap()],
             &program_id,
@@ -663,10 +569,13 @@ mod tests {
             &program_id,
         );
 
-        let mut prediction = TestAccountData::new_account_with_key_and_owner::<PredictionAccount>(
-            prediction_pda,
-            program_id,
+        let (pool_authority_pda, pool_authority_bump) = Pubkey::find_program_address(
+            &[b"authority", state.data[8..16].try_into().unwrap()],
+            &program_id,
         );
+
+        let mut prediction =
+            TestAccountData::new_account_with_key_and_owner(prediction_pda, program_id);
         prediction
             .init_prediction_data(&PredictionAccount::default())
             .unwrap();
@@ -682,11 +591,11 @@ mod tests {
         };
 
         // Create token accounts
-        let mut prediction_anti_token = TestAccountData::new_token_account(anti_token_pda);
-        let mut prediction_pro_token = TestAccountData::new_token_account(pro_token_pda);
+        let mut prediction_anti_token = TestAccountData::new_token(anti_token_pda);
+        let mut prediction_pro_token = TestAccountData::new_token(pro_token_pda);
 
         // Rent for accounts
-        let mut rent_account = TestAccountData::init_rent_account();
+        let mut rent_account = TestAccountData::new_rent_account();
 
         // Initialise token accounts
         prediction_anti_token
@@ -698,12 +607,26 @@ mod tests {
 
         // Initialise other accounts
         let mut system_program = TestAccountData::new_system_account();
-        let mut vault = TestAccountData::new_vault_with_key();
+        let mut vault = new_vault_with_key();
+
+        let mut pool_authority = TestAccountData::new_authority_account(pool_authority_pda);
 
         // Prepare account infos
+        let admin_info = admin.to_account_info(false);
+        let mut short_admin =
+            TestAccountData::new_account_with_key_and_owner(admin_pda, program_id);
+        short_admin
+            .init_admin_data(&AdminAccount {
+                version: ADMIN_VERSION,
+                max_title_length: 8,
+                ..test_admin()
+            })
+            .unwrap();
+        let short_admin_info = short_admin.to_account_info(false);
         let state_info = state.to_account_info(false);
         let prediction_info = prediction.to_account_info(false);
         let authority_info = creator.to_account_info(true);
+        let pool_authority_info = pool_authority.to_account_info(false);
         let system_info = system_program.to_account_info(false);
         let anti_mint_info = anti_mint.to_account_info(false);
         let pro_mint_info = pro_mint.to_account_info(false);
@@ -715,15 +638,17 @@ mod tests {
 
         // Set up CreatePrediction context
         let mut accounts = CreatePrediction {
+            admin: Account::try_from(&admin_info).unwrap(),
             state: Account::try_from(&state_info).unwrap(),
             prediction: Account::try_from(&prediction_info).unwrap(),
             authority: Signer::try_from(&authority_info).unwrap(),
-            prediction_anti_token: Account::try_from(&prediction_anti_token_info).unwrap(),
-            prediction_pro_token: Account::try_from(&prediction_pro_token_info).unwrap(),
-            anti_mint: anti_mint_info.clone(),
-            pro_mint: pro_mint_info.clone(),
+            pool_authority: pool_authority_info,
+            prediction_anti_token: InterfaceAccount::try_from(&prediction_anti_token_info).unwrap(),
+            prediction_pro_token: InterfaceAccount::try_from(&prediction_pro_token_info).unwrap(),
+            anti_mint: InterfaceAccount::<Mint>::try_from(&anti_mint_info).unwrap(),
+            pro_mint: InterfaceAccount::<Mint>::try_from(&pro_mint_info).unwrap(),
             vault: vault_info,
-            token_program: Program::try_from(&token_program_info).unwrap(),
+            token_program: Interface::<TokenInterface>::try_from(&token_program_info).unwrap(),
             system_program: Program::try_from(&system_info).unwrap(),
             rent: Sysvar::<Rent>::from_account_info(&rent_account_info)?,
         };
@@ -733,12 +658,15 @@ mod tests {
         {
             // Include the CreatePredictionBumps with the bump for the prediction account
             let bumps = CreatePredictionBumps {
+                admin: admin_bump,
                 state: state_bump,
                 prediction: prediction_bump,
+                pool_authority: pool_authority_bump,
                 prediction_anti_token: anti_token_bump,
                 prediction_pro_token: pro_token_bump,
             };
             let long_title = "a".repeat((MAX_TITLE_LENGTH + 1) as usize);
+            set_clock_override(Some(1736899200));
             let result = create(
                 Context::new(&program_id, &mut accounts, &[], bumps),
                 long_title,
@@ -746,7 +674,11 @@ mod tests {
                 "2025-02-01T00:00:00Z".to_string(),
                 "2025-02-02T00:00:00Z".to_string(),
                 None,
-                Some(1736899200),
+                vec!["Anti".to_string(), "Pro".to_string()],
+                None,
+                ANTITOKEN_MULTISIG,
+                0,
+                None,
             );
             assert_eq!(result.unwrap_err(), Error::from(PredictError::TitleTooLong));
         }
@@ -755,12 +687,15 @@ mod tests {
         {
             // Include the CreatePredictionBumps with the bump for the prediction account
             let bumps = CreatePredictionBumps {
+                admin: admin_bump,
                 state: state_bump,
                 prediction: prediction_bump,
+                pool_authority: pool_authority_bump,
                 prediction_anti_token: anti_token_bump,
                 prediction_pro_token: pro_token_bump,
             };
             let long_description = "a".repeat((MAX_DESCRIPTION_LENGTH + 1) as usize);
+            set_clock_override(Some(1736899200));
             let result = create(
                 Context::new(&program_id, &mut accounts, &[], bumps),
                 "Test Prediction".to_string(),
@@ -768,7 +703,11 @@ mod tests {
                 "2025-02-01T00:00:00Z".to_string(),
                 "2025-02-02T00:00:00Z".to_string(),
                 None,
-                Some(1736899200),
+                vec!["Anti".to_string(), "Pro".to_string()],
+                None,
+                ANTITOKEN_MULTISIG,
+                0,
+                None,
             );
             assert_eq!(
                 result.unwrap_err(),
@@ -776,6 +715,144 @@ mod tests {
             );
         }
 
+        // Test title limit is read from the admin account
+        {
+            accounts.admin = Account::try_from(&short_admin_info).unwrap();
+            let bumps = CreatePredictionBumps {
+                admin: admin_bump,
+                state: state_bump,
+                prediction: prediction_bump,
+                pool_authority: pool_authority_bump,
+                prediction_anti_token: anti_token_bump,
+                prediction_pro_token: pro_token_bump,
+            };
+            set_clock_override(Some(1736899200));
+            let result = create(
+                Context::new(&program_id, &mut accounts, &[], bumps),
+                "Test Prediction".to_string(),
+                "Test Description".to_string(),
+                "2025-02-01T00:00:00Z".to_string(),
+                "2025-02-02T00:00:00Z".to_string(),
+                None,
+                vec!["Anti".to_string(), "Pro".to_string()],
+                None,
+                ANTITOKEN_MULTISIG,
+                0,
+                None,
+            );
+            assert_eq!(result.unwrap_err(), Error::from(PredictError::TitleTooLong));
+        }
+
+        // Test missing resolver
+        {
+            accounts.admin = Account::try_from(&admin_info).unwrap();
+            let bumps = CreatePredictionBumps {
+                admin: admin_bump,
+                state: state_bump,
+                prediction: prediction_bump,
+                pool_authority: pool_authority_bump,
+                prediction_anti_token: anti_token_bump,
+                prediction_pro_token: pro_token_bump,
+            };
+            set_clock_override(Some(1736899200));
+            let result = create(
+                Context::new(&program_id, &mut accounts, &[], bumps),
+                "Test Prediction".to_string(),
+                "Test Description".to_string(),
+                "2025-02-01T00:00:00Z".to_string(),
+                "2025-02-02T00:00:00Z".to_string(),
+                None,
+                vec!["Anti".to_string(), "Pro".to_string()],
+                None,
+                Pubkey::default(),
+                0,
+                None,
+            );
+            assert_eq!(
+                result.unwrap_err(),
+                Error::from(PredictError::InvalidResolver)
+            );
+        }
+
+        // Test invalid outcome labels
+        for outcomes in [
+            vec!["Yes".to_string()],
+            vec!["Yes".to_string(), String::new()],
+            vec!["Yes".to_string(), "x".repeat(MAX_OUTCOME_LENGTH + 1)],
+            (0..=MAX_OUTCOMES).map(|i| i.to_string()).collect(),
+        ] {
+            let bumps = CreatePredictionBumps {
+                admin: admin_bump,
+                state: state_bump,
+                prediction: prediction_bump,
+                pool_authority: pool_authority_bump,
+                prediction_anti_token: anti_token_bump,
+                prediction_pro_token: pro_token_bump,
+            };
+            set_clock_override(Some(1736899200));
+            let result = create(
+                Context::new(&program_id, &mut accounts, &[], bumps),
+                "Test Prediction".to_string(),
+                "Test Description".to_string(),
+                "2025-02-01T00:00:00Z".to_string(),
+                "2025-02-02T00:00:00Z".to_string(),
+                None,
+                outcomes,
+                None,
+                ANTITOKEN_MULTISIG,
+                0,
+                None,
+            );
+            assert_eq!(
+                result.unwrap_err(),
+                Error::from(PredictError::InvalidOutcomes)
+            );
+        }
+
+        // Test invalid scalar ranges, and scalar predictions carrying labels
+        let range = |lower: u64, upper: u64, unit: String| ScalarRange { lower, upper, unit };
+        for (outcomes, scalar, error) in [
+            (
+                vec![],
+                range(100, 100, "USD".to_string()),
+                PredictError::InvalidScalarRange,
+            ),
+            (
+                vec![],
+                range(0, 100, "x".repeat(MAX_UNIT_LENGTH + 1)),
+                PredictError::InvalidScalarRange,
+            ),
+            (
+                vec!["Anti".to_string(), "Pro".to_string()],
+                range(0, 100, "USD".to_string()),
+                PredictError::InvalidOutcomes,
+            ),
+        ] {
+            let bumps = CreatePredictionBumps {
+                admin: admin_bump,
+                state: state_bump,
+                prediction: prediction_bump,
+                pool_authority: pool_authority_bump,
+                prediction_anti_token: anti_token_bump,
+                prediction_pro_token: pro_token_bump,
+            };
+            set_clock_override(Some(1736899200));
+            let result = create(
+                Context::new(&program_id, &mut accounts, &[], bumps),
+                "Test Prediction".to_string(),
+                "Test Description".to_string(),
+                "2025-02-01T00:00:00Z".to_string(),
+                "2025-02-02T00:00:00Z".to_string(),
+                None,
+                outcomes,
+                Some(scalar),
+                ANTITOKEN_MULTISIG,
+                0,
+                None,
+            );
+            assert_eq!(result.unwrap_err(), Error::from(error));
+        }
+
         Ok(())
     }
 
@@ -788,28 +865,28 @@ mod tests {
         let mut token_program = TestAccountData::new_token_program();
 
         // Create mints
-        let mut anti_mint = TestAccountData::new_mint_address(ANTI_MINT_ADDRESS);
-        let mut pro_mint = TestAccountData::new_mint_address(PRO_MINT_ADDRESS);
-
-        // Test double for Clock
-        thread_local! {
-            static MOCK_UNIX_TIMESTAMP: RefCell<i64> = RefCell::new(1736899200); // 2025-01-15T00:00:00Z
-        }
+        let mut anti_mint = TestAccountData::new_mint(ANTI_MINT_ADDRESS);
+        let mut pro_mint = TestAccountData::new_mint(PRO_MINT_ADDRESS);
 
         // Initialise state account
         let manager: Pubkey = Pubkey::new_unique();
-        let mut state =
-            TestAccountData::new_account_with_key_and_owner::<StateAccount>(manager, program_id);
+        let mut state = TestAccountData::new_account_with_key_and_owner(manager, program_id);
         state
             .init_state_data(&StateAccount {
+                version: STATE_VERSION,
                 index: 0,
                 authority: manager,
+                archived: 0,
             })
             .unwrap();
 
         // Derive PDAs and bumps
         let (_state_pda, state_bump) = Pubkey::find_program_address(&[b"state"], &program_id);
 
+        let (admin_pda, admin_bump) = Pubkey::find_program_address(&[b"admin"], &program_id);
+        let mut admin = TestAccountData::new_account_with_key_and_owner(admin_pda, program_id);
+        admin.init_admin_data(&test_admin()).unwrap();
+
         let (prediction_pda, prediction_bump) = Pubkey::find_program_address(
             &[b"prediction", state.data[8..16].try_into().unwrap()],
             &program_id,
@@ -825,10 +902,13 @@ mod tests {
             &program_id,
         );
 
-        let mut prediction = TestAccountData::new_account_with_key_and_owner::<PredictionAccount>(
-            prediction_pda,
-            program_id,
+        let (pool_authority_pda, pool_authority_bump) = Pubkey::find_program_address(
+            &[b"authority", state.data[8..16].try_into().unwrap()],
+            &program_id,
         );
+
+        let mut prediction =
+            TestAccountData::new_account_with_key_and_owner(prediction_pda, program_id);
         prediction
             .init_prediction_data(&PredictionAccount::default())
             .unwrap();
@@ -844,11 +924,11 @@ mod tests {
         };
 
         // Create token accounts
-        let mut prediction_anti_token = TestAccountData::new_token_account(anti_token_pda);
-        let mut prediction_pro_token = TestAccountData::new_token_account(pro_token_pda);
+        let mut prediction_anti_token = TestAccountData::new_token(anti_token_pda);
+        let mut prediction_pro_token = TestAccountData::new_token(pro_token_pda);
 
         // Rent for accounts
-        let mut rent_account = TestAccountData::init_rent_account();
+        let mut rent_account = TestAccountData::new_rent_account();
 
         // Initialise token accounts
         prediction_anti_token
@@ -860,12 +940,16 @@ mod tests {
 
         // Initialise other accounts
         let mut system_program = TestAccountData::new_system_account();
-        let mut vault = TestAccountData::new_vault_with_key();
+        let mut vault = new_vault_with_key();
+
+        let mut pool_authority = TestAccountData::new_authority_account(pool_authority_pda);
 
         // Prepare account infos
+        let admin_info = admin.to_account_info(false);
         let state_info = state.to_account_info(true);
         let prediction_info = prediction.to_account_info(true);
         let authority_info = creator.to_account_info(true);
+        let pool_authority_info = pool_authority.to_account_info(false);
         let system_info = system_program.to_account_info(false);
         let anti_mint_info = anti_mint.to_account_info(false);
         let pro_mint_info = pro_mint.to_account_info(false);
@@ -877,15 +961,17 @@ mod tests {
 
         // Set up CreatePrediction context
         let mut accounts = CreatePrediction {
+            admin: Account::try_from(&admin_info).unwrap(),
             state: Account::try_from(&state_info).unwrap(),
             prediction: Account::try_from(&prediction_info).unwrap(),
             authority: Signer::try_from(&authority_info).unwrap(),
-            prediction_anti_token: Account::try_from(&prediction_anti_token_info).unwrap(),
-            prediction_pro_token: Account::try_from(&prediction_pro_token_info).unwrap(),
-            anti_mint: anti_mint_info.clone(),
-            pro_mint: pro_mint_info.clone(),
+            pool_authority: pool_authority_info,
+            prediction_anti_token: InterfaceAccount::try_from(&prediction_anti_token_info).unwrap(),
+            prediction_pro_token: InterfaceAccount::try_from(&prediction_pro_token_info).unwrap(),
+            anti_mint: InterfaceAccount::<Mint>::try_from(&anti_mint_info).unwrap(),
+            pro_mint: InterfaceAccount::<Mint>::try_from(&pro_mint_info).unwrap(),
             vault: vault_info,
-            token_program: Program::try_from(&token_program_info).unwrap(),
+            token_program: Interface::<TokenInterface>::try_from(&token_program_info).unwrap(),
             system_program: Program::try_from(&system_info).unwrap(),
             rent: Sysvar::<Rent>::from_account_info(&rent_account_info)?,
         };
@@ -895,11 +981,14 @@ mod tests {
         {
             // Include the CreatePredictionBumps with the bump for the prediction account
             let bumps = CreatePredictionBumps {
+                admin: admin_bump,
                 state: state_bump,
                 prediction: prediction_bump,
+                pool_authority: pool_authority_bump,
                 prediction_anti_token: anti_token_bump,
                 prediction_pro_token: pro_token_bump,
             };
+            set_clock_override(Some(1736899200));
             let result = create(
                 Context::new(&program_id, &mut accounts, &[], bumps),
                 "Test Prediction".to_string(),
@@ -907,7 +996,11 @@ mod tests {
                 "2025-02-02T00:00:00Z".to_string(), // End before start
                 "2025-02-01T00:00:00Z".to_string(),
                 None,
-                Some(1736899200),
+                vec!["Anti".to_string(), "Pro".to_string()],
+                None,
+                ANTITOKEN_MULTISIG,
+                0,
+                None,
             );
             assert_eq!(
                 result.unwrap_err(),
@@ -919,11 +1012,14 @@ mod tests {
         {
             // Include the CreatePredictionBumps with the bump for the prediction account
             let bumps = CreatePredictionBumps {
+                admin: admin_bump,
                 state: state_bump,
                 prediction: prediction_bump,
+                pool_authority: pool_authority_bump,
                 prediction_anti_token: anti_token_bump,
                 prediction_pro_token: pro_token_bump,
             };
+            set_clock_override(Some(1736899200));
             let result = create(
                 Context::new(&program_id, &mut accounts, &[], bumps),
                 "Test Prediction".to_string(),
@@ -931,7 +1027,11 @@ mod tests {
                 "2024-01-01T00:00:00Z".to_string(), // Past date
                 "2025-02-01T00:00:00Z".to_string(),
                 None,
-                Some(1736899200),
+                vec!["Anti".to_string(), "Pro".to_string()],
+                None,
+                ANTITOKEN_MULTISIG,
+                0,
+                None,
             );
             assert_eq!(
                 result.unwrap_err(),
@@ -939,6 +1039,63 @@ mod tests {
             );
         }
 
+        // Test resolution time before end time
+        {
+            let bumps = CreatePredictionBumps {
+                admin: admin_bump,
+                state: state_bump,
+                prediction: prediction_bump,
+                pool_authority: pool_authority_bump,
+                prediction_anti_token: anti_token_bump,
+                prediction_pro_token: pro_token_bump,
+            };
+            set_clock_override(Some(1736899200));
+            let result = create(
+                Context::new(&program_id, &mut accounts, &[], bumps),
+                "Test Prediction".to_string(),
+                "Test Description".to_string(),
+                "2025-02-01T00:00:00Z".to_string(),
+                "2025-02-02T00:00:00Z".to_string(),
+                Some("2025-02-01T12:00:00Z".to_string()), // Resolves before end
+                vec!["Anti".to_string(), "Pro".to_string()],
+                None,
+                ANTITOKEN_MULTISIG,
+                0,
+                None,
+            );
+            assert_eq!(
+                result.unwrap_err(),
+                Error::from(PredictError::InvalidTimeRange)
+            );
+        }
+
+        // Test creator fee above the cap
+        {
+            let bumps = CreatePredictionBumps {
+                admin: admin_bump,
+                state: state_bump,
+                prediction: prediction_bump,
+                pool_authority: pool_authority_bump,
+                prediction_anti_token: anti_token_bump,
+                prediction_pro_token: pro_token_bump,
+            };
+            set_clock_override(Some(1736899200));
+            let result = create(
+                Context::new(&program_id, &mut accounts, &[], bumps),
+                "Test Prediction".to_string(),
+                "Test Description".to_string(),
+                "2025-02-01T00:00:00Z".to_string(),
+                "2025-02-02T00:00:00Z".to_string(),
+                None,
+                vec!["Anti".to_string(), "Pro".to_string()],
+                None,
+                ANTITOKEN_MULTISIG,
+                MAX_CREATOR_FEE_BPS + 1,
+                None,
+            );
+            assert_eq!(result.unwrap_err(), Error::from(PredictError::InvalidFee));
+        }
+
         Ok(())
     }
 }
@@ -12,112 +12,133 @@ use crate::state::*;
 use crate::utils::*;
 use crate::DepositTokens;
 use anchor_lang::prelude::*;
-use anchor_spl::token::{self, Transfer};
+use anchor_spl::token_interface::{self, TransferChecked};
 
 pub fn deposit(
     ctx: Context<DepositTokens>,
     index: u64,
+    nonce: u64,
     anti: u64,
     pro: u64,
-    unix_timestamp: Option<i64>, // CRITICAL: Remove line in production!
+    distribution: Vec<u64>,
 ) -> Result<()> {
+    let admin = &ctx.accounts.admin;
     let prediction = &mut ctx.accounts.prediction;
 
-    // Get current time, supporting local testing override
-    let now = match unix_timestamp {
-        Some(ts) => ts,
-        None => Clock::get()?.unix_timestamp,
-    }; // CRITICAL: Remove block in production!
-
-    // CRITICAL: Add line in production!let now = Clock::get()?.unix_timestamp;
+    // Get current time
+    let now = current_time()?;
 
     // Verify prediction is active
     require!(prediction.is_active(now), PredictError::PredictionInactive);
+    require!(!prediction.voided, PredictError::PredictionVoided);
+
+    // Tokens credited to the pool, net of any transfer fee on the mints
+    let anti_received = received_amount(&ctx.accounts.anti_mint.to_account_info(), anti)?;
+    let pro_received = received_amount(&ctx.accounts.pro_mint.to_account_info(), pro)?;
 
     // Verify minimum deposit
     require!(
-        anti >= MIN_DEPOSIT_AMOUNT || pro >= MIN_DEPOSIT_AMOUNT,
+        anti_received >= admin.min_deposit_amount || pro_received >= admin.min_deposit_amount,
         PredictError::InsufficientDeposit
     );
 
-    // Check prediction token account authorities
-    require!(
-        ctx.accounts.prediction_anti_token.owner == ANTITOKEN_MULTISIG,
-        PredictError::InvalidTokenAccount
-    );
-    require!(
-        ctx.accounts.prediction_pro_token.owner == ANTITOKEN_MULTISIG,
-        PredictError::InvalidTokenAccount
-    );
+    // Calculate metrics (mean and stddev values)
+    let (mean, stddev) = collide(anti_received, pro_received, admin.float_basis)?;
+
+    // Scalar predictions derive the estimate from the $ANTI/$PRO split, and
+    // binary predictions default to the split as the distribution
+    let distribution = match &prediction.scalar {
+        Some(range) => {
+            require!(distribution.is_empty(), PredictError::InvalidDistribution);
+            vec![scalar_estimate(range, anti_received, pro_received, mean)?]
+        }
+        None => {
+            let distribution = if distribution.is_empty() && prediction.outcomes.len() == 2 {
+                vec![anti_received, pro_received]
+            } else {
+                distribution
+            };
+            validate_distribution(&distribution, prediction.outcomes.len())?;
+            distribution
+        }
+    };
 
     // Transfer $ANTI tokens if amount > 0
     if anti > 0 {
-        token::transfer(
+        token_interface::transfer_checked(
             CpiContext::new(
                 ctx.accounts.token_program.to_account_info(),
-                Transfer {
+                TransferChecked {
                     from: ctx.accounts.user_anti_token.to_account_info(),
+                    mint: ctx.accounts.anti_mint.to_account_info(),
                     to: ctx.accounts.prediction_anti_token.to_account_info(),
                     authority: ctx.accounts.authority.to_account_info(),
                 },
             ),
             anti,
+            ctx.accounts.anti_mint.decimals,
         )?;
     }
 
     // Transfer $PRO tokens if amount > 0
     if pro > 0 {
-        token::transfer(
+        token_interface::transfer_checked(
             CpiContext::new(
                 ctx.accounts.token_program.to_account_info(),
-                Transfer {
+                TransferChecked {
                     from: ctx.accounts.user_pro_token.to_account_info(),
+                    mint: ctx.accounts.pro_mint.to_account_info(),
                     to: ctx.accounts.prediction_pro_token.to_account_info(),
                     authority: ctx.accounts.authority.to_account_info(),
                 },
             ),
             pro,
+            ctx.accounts.pro_mint.decimals,
         )?;
     }
 
-    // Calculate metrics (mean and stddev values)
-    let (mean, stddev) = collide(anti, pro)?;
-
-    // Serialise and update prediction data
-    let prediction_info = prediction.to_account_info();
-    let mut data_prediction = prediction_info.try_borrow_mut_data()?;
-
     // Create deposit record
-    let deposit = Deposit {
+    let deposit_record = &mut ctx.accounts.deposit_record;
+    deposit_record.index = index;
+    deposit_record.nonce = nonce;
+    deposit_record.deposit = Deposit {
         address: ctx.accounts.authority.key(),
-        anti,
-        pro,
+        anti: anti_received,
+        pro: pro_received,
         mean,
         stddev,
         withdrawn: false,
+        distribution,
     };
+    deposit_record.bin = None;
+    deposit_record.binned = false;
+
+    // Serialise deposit record
+    write_account(&deposit_record.to_account_info(), &**deposit_record)?;
 
     // Update prediction state
-    prediction.deposits.push(deposit);
+    prediction.deposits = prediction
+        .deposits
+        .checked_add(1)
+        .ok_or(error!(PredictError::MathError))?;
     prediction.anti = prediction
         .anti
-        .checked_add(anti)
+        .checked_add(anti_received)
         .ok_or(error!(PredictError::MathError))?;
     prediction.pro = prediction
         .pro
-        .checked_add(pro)
+        .checked_add(pro_received)
         .ok_or(error!(PredictError::MathError))?;
 
     // Serialise updated prediction state
-    let serialised_prediction = prediction.try_to_vec()?;
-    data_prediction[8..8 + serialised_prediction.len()].copy_from_slice(&serialised_prediction);
+    write_account(&prediction.to_account_info(), &**prediction)?;
 
     // Emit deposit event
     emit!(DepositEvent {
         index,
         address: ctx.accounts.authority.key(),
-        anti,
-        pro,
+        anti: anti_received,
+        pro: pro_received,
         mean,
         stddev,
         timestamp: now,
@@ -129,161 +150,60 @@ pub fn deposit(
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::test_utils::*;
     use crate::DepositTokensBumps;
-    use anchor_lang::prelude::AccountInfo;
-    use anchor_lang::solana_program::system_program;
-    use anchor_lang::Discriminator;
-    use anchor_spl::token::{spl_token, Mint, Token};
-    use anchor_spl::token::{spl_token::state::Account as SplTokenAccount, TokenAccount};
-    use solana_sdk::program_option::COption;
-    use solana_sdk::program_pack::Pack;
-    use std::cell::RefCell;
-    use std::str::FromStr;
-
-    // Fixed test IDs - these should be consistent across tests
-    fn program_id() -> Pubkey {
-        Pubkey::from_str(&PROGRAM_ID.to_string()).unwrap()
-    }
-
-    struct TestAccountData {
-        key: Pubkey,
-        lamports: u64,
-        data: Vec<u8>,
-        owner: Pubkey,
-        executable: bool,
-        rent_epoch: u64,
-    }
-
-    impl TestAccountData {
-        fn new_mint(mint: Pubkey) -> Self {
-            Self {
-                key: mint,
-                lamports: 1_000_000,
-                data: vec![0; Mint::LEN],
-                owner: spl_token::ID,
-                executable: false,
-                rent_epoch: 0,
-            }
-        }
-
-        fn new_account_with_key_and_owner<T: AccountSerialize + AccountDeserialize + Clone>(
-            key: Pubkey,
-            owner: Pubkey,
-        ) -> Self {
-            Self {
-                key,
-                lamports: 1_000_000,
-                data: vec![0; 8 + PredictionAccount::LEN],
-                owner,
-                executable: true,
-                rent_epoch: 0,
-            }
-        }
-
-        fn to_account_info<'a>(&'a mut self, is_signer: bool) -> AccountInfo<'a> {
-            AccountInfo::new(
-                &self.key,
-                is_signer,
-                true,
-                &mut self.lamports,
-                &mut self.data,
-                &self.owner,
-                self.executable,
-                self.rent_epoch,
-            )
-        }
-
-        fn new_token(key: Pubkey) -> Self {
-            Self {
-                key,
-                lamports: 1_000_000,
-                data: vec![0; 165],
-                owner: spl_token::ID,
-                executable: false,
-                rent_epoch: 0,
-            }
-        }
-
-        fn into_token_account<'a>(account_info: &'a AccountInfo<'a>) -> Account<'a, TokenAccount> {
-            Account::try_from(account_info).unwrap()
-        }
-
-        fn init_state_data(&mut self, state: &StateAccount) -> Result<()> {
-            self.data = vec![0; 8 + StateAccount::LEN];
-            let data = self.data.as_mut_slice();
-
-            let disc = StateAccount::discriminator();
-            data[..8].copy_from_slice(&disc);
-
-            let account_data = state.try_to_vec()?;
-            data[8..8 + account_data.len()].copy_from_slice(&account_data);
-
-            Ok(())
-        }
-
-        fn init_prediction_data(&mut self, prediction: &PredictionAccount) -> Result<()> {
-            self.data = vec![0; 8 + PredictionAccount::LEN];
-            let data = self.data.as_mut_slice();
-
-            let disc = PredictionAccount::discriminator();
-            data[..8].copy_from_slice(&disc);
+    use anchor_lang::error::ErrorCode;
 
-            let account_data = prediction.try_to_vec()?;
-            data[8..8 + account_data.len()].copy_from_slice(&account_data);
-
-            Ok(())
-        }
-
-        fn init_token_account(&mut self, owner: Pubkey, mint: Pubkey) -> Result<()> {
-            self.data = vec![0; TokenAccount::LEN]; // Ensure correct buffer size
-            let data = self.data.as_mut_slice();
-
-            let close_authority: COption<Pubkey> = COption::None;
-
-            // Initialise a new SPL Token Account manually
-            let token_account = SplTokenAccount {
-                mint,
-                owner,
-                amount: 0,
-                delegate: None.into(),
-                state: spl_token::state::AccountState::Initialized,
-                is_native: None.into(),
-                delegated_amount: 0,
-                close_authority,
-            };
-
-            token_account.pack_into_slice(data);
+    use anchor_lang::solana_program::system_program;
 
-            Ok(())
-        }
-    }
+    use anchor_spl::token::spl_token;
+    use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface};
 
     // Struct to hold all test accounts
     struct TestAccounts {
+        pub admin: TestAccountData,
         pub prediction_data: TestAccountData,
+        pub deposit_record: TestAccountData,
         pub authority: TestAccountData,
+        pub pool_authority: TestAccountData,
         pub user_anti_token: TestAccountData,
         pub user_pro_token: TestAccountData,
         pub prediction_anti_token: TestAccountData,
         pub prediction_pro_token: TestAccountData,
         pub token_program: TestAccountData,
+        pub system_program: TestAccountData,
     }
 
     fn create_test_accounts(
+        admin_pda: Pubkey,
         prediction_pda: Pubkey,
+        deposit_record_pda: Pubkey,
         anti_token_pda: Pubkey,
         pro_token_pda: Pubkey,
+        pool_authority_pda: Pubkey,
         program_id: Pubkey,
     ) -> TestAccounts {
+        let mut admin = TestAccountData::new_account_with_key_and_owner(admin_pda, program_id);
+        admin.init_admin_data(&test_admin()).unwrap();
+
         TestAccounts {
-            prediction_data: TestAccountData::new_account_with_key_and_owner::<StateAccount>(
+            admin,
+            prediction_data: TestAccountData::new_account_with_key_and_owner(
                 prediction_pda,
                 program_id,
             ),
-            authority: TestAccountData::new_account_with_key_and_owner::<StateAccount>(
+            deposit_record: TestAccountData::new_account_with_key_and_owner(
+                deposit_record_pda,
+                program_id,
+            ),
+            authority: TestAccountData::new_account_with_key_and_owner(
                 ANTITOKEN_MULTISIG,
                 program_id,
             ),
+            pool_authority: TestAccountData::new_account_with_key_and_owner(
+                pool_authority_pda,
+                system_program::ID,
+            ),
             user_anti_token: TestAccountData::new_token(Pubkey::new_unique()),
             user_pro_token: TestAccountData::new_token(Pubkey::new_unique()),
             prediction_anti_token: TestAccountData::new_token(anti_token_pda),
@@ -298,23 +218,24 @@ mod tests {
                 executable: true,         // Mark as an executable program
                 rent_epoch: 0,
             },
+
+            system_program: TestAccountData {
+                key: system_program::ID,
+                lamports: 1_000_000,
+                data: vec![],
+                owner: system_program::ID,
+                executable: true,
+                rent_epoch: 0,
+            },
         }
     }
 
     // Reusable method to create a test prediction
     fn create_test_prediction(start_time: &str, end_time: &str) -> PredictionAccount {
         PredictionAccount {
-            index: 0,
-            title: "Test Prediction".to_string(),
-            description: "Test Description".to_string(),
-            start_time: start_time.to_string(),
-            end_time: end_time.to_string(),
-            etc: None,
-            anti: 0,
-            pro: 0,
-            deposits: vec![],
-            equalised: false,
-            equalisation: None,
+            start_time: parse_iso_timestamp(start_time).unwrap(),
+            end_time: parse_iso_timestamp(end_time).unwrap(),
+            ..test_prediction()
         }
     }
 
@@ -324,22 +245,20 @@ mod tests {
         let program_id = program_id();
 
         // Create mints
-        let anti_mint = TestAccountData::new_mint(ANTI_MINT_ADDRESS);
-        let pro_mint = TestAccountData::new_mint(PRO_MINT_ADDRESS);
-
-        // Test double for Clock
-        thread_local! {
-            static MOCK_UNIX_TIMESTAMP: RefCell<i64> = RefCell::new(1736899200); // 2025-01-15T00:00:00Z
-        }
+        let mut anti_mint = TestAccountData::new_mint(ANTI_MINT_ADDRESS);
+        let mut pro_mint = TestAccountData::new_mint(PRO_MINT_ADDRESS);
+        let anti_mint_info = anti_mint.to_account_info(false);
+        let pro_mint_info = pro_mint.to_account_info(false);
 
         // Initialise state account
         let root: Pubkey = Pubkey::new_unique();
-        let mut state =
-            TestAccountData::new_account_with_key_and_owner::<StateAccount>(root, program_id);
+        let mut state = TestAccountData::new_account_with_key_and_owner(root, program_id);
         state
             .init_state_data(&StateAccount {
+                version: STATE_VERSION,
                 index: 0,
                 authority: root,
+                archived: 0,
             })
             .unwrap();
 
@@ -359,28 +278,52 @@ mod tests {
             &program_id,
         );
 
-        let mut accounts =
-            create_test_accounts(prediction_pda, anti_token_pda, pro_token_pda, program_id);
+        let (pool_authority_pda, pool_authority_bump) = Pubkey::find_program_address(
+            &[b"authority", state.data[8..16].try_into().unwrap()],
+            &program_id,
+        );
+
+        let (deposit_record_pda, deposit_record_bump) = Pubkey::find_program_address(
+            &[
+                b"deposit",
+                state.data[8..16].try_into().unwrap(),
+                ANTITOKEN_MULTISIG.as_ref(),
+                0u64.to_le_bytes().as_ref(),
+            ],
+            &program_id,
+        );
+
+        let (admin_pda, admin_bump) = Pubkey::find_program_address(&[b"admin"], &program_id);
+
+        let mut accounts = create_test_accounts(
+            admin_pda,
+            prediction_pda,
+            deposit_record_pda,
+            anti_token_pda,
+            pro_token_pda,
+            pool_authority_pda,
+            program_id,
+        );
 
         let authority_key = Pubkey::new_unique();
 
         // Initialise user token accounts
         accounts
             .user_anti_token
-            .init_token_account(authority_key, anti_mint.key)
+            .init_token_account(authority_key, *anti_mint_info.key)
             .unwrap();
         accounts
             .user_pro_token
-            .init_token_account(authority_key, pro_mint.key)
+            .init_token_account(authority_key, *pro_mint_info.key)
             .unwrap();
         // Initialise prediction token accounts
         accounts
             .prediction_anti_token
-            .init_token_account(ANTITOKEN_MULTISIG, anti_mint.key)
+            .init_token_account(pool_authority_pda, *anti_mint_info.key)
             .unwrap();
         accounts
             .prediction_pro_token
-            .init_token_account(ANTITOKEN_MULTISIG, pro_mint.key)
+            .init_token_account(pool_authority_pda, *pro_mint_info.key)
             .unwrap();
 
         assert_eq!(
@@ -410,12 +353,15 @@ mod tests {
         assert_eq!(prediction_pro_token.amount, 0);
 
         // Get account infos
+        let admin_info = accounts.admin.to_account_info(false);
         let authority_info = accounts.authority.to_account_info(true);
+        let pool_authority_info = accounts.pool_authority.to_account_info(false);
         let user_anti_info = accounts.user_anti_token.to_account_info(false);
         let user_pro_info = accounts.user_pro_token.to_account_info(false);
         let prediction_anti_info = accounts.prediction_anti_token.to_account_info(false);
         let prediction_pro_info = accounts.prediction_pro_token.to_account_info(false);
         let token_program_info = accounts.token_program.to_account_info(false);
+        let system_program_info = accounts.system_program.to_account_info(false);
 
         // Create and initialise the prediction account
         let prediction = create_test_prediction("2025-01-01T00:00:00Z", "2025-02-01T00:00:00Z");
@@ -426,6 +372,13 @@ mod tests {
 
         let prediction_account_info = accounts.prediction_data.to_account_info(false);
 
+        // Create the deposit record account
+        accounts
+            .deposit_record
+            .init_deposit_record_data(&DepositRecord::default())
+            .unwrap();
+        let deposit_record_info = accounts.deposit_record.to_account_info(false);
+
         // Check that the buffer is correctly allocated
         assert!(
             prediction_account_info.try_borrow_data().unwrap().len() >= 8 + PredictionAccount::LEN
@@ -433,18 +386,27 @@ mod tests {
 
         // Create deposit accounts
         let mut accounts = DepositTokens {
+            admin: Account::try_from(&admin_info).unwrap(),
             prediction: Account::try_from(&prediction_account_info).unwrap(),
+            deposit_record: Account::try_from(&deposit_record_info).unwrap(),
             authority: Signer::try_from(&authority_info).unwrap(),
+            pool_authority: pool_authority_info.clone(),
             user_anti_token: TestAccountData::into_token_account(&user_anti_info),
             user_pro_token: TestAccountData::into_token_account(&user_pro_info),
             prediction_anti_token: TestAccountData::into_token_account(&prediction_anti_info),
             prediction_pro_token: TestAccountData::into_token_account(&prediction_pro_info),
-            token_program: Program::<Token>::try_from(&token_program_info).unwrap(),
+            anti_mint: InterfaceAccount::<Mint>::try_from(&anti_mint_info).unwrap(),
+            pro_mint: InterfaceAccount::<Mint>::try_from(&pro_mint_info).unwrap(),
+            token_program: Interface::<TokenInterface>::try_from(&token_program_info).unwrap(),
+            system_program: Program::try_from(&system_program_info).unwrap(),
         };
 
         // Create bumps
         let bumps = DepositTokensBumps {
+            admin: admin_bump,
             prediction: prediction_bump,
+            deposit_record: deposit_record_bump,
+            pool_authority: pool_authority_bump,
             prediction_anti_token: anti_token_bump,
             prediction_pro_token: pro_token_bump,
         };
@@ -454,13 +416,12 @@ mod tests {
         /* Common Setup Ends Here */
 
         // Test deposit
-        let result = deposit(ctx, 0, 50_000, 50_000, Some(1736899200));
+        set_clock_override(Some(1736899200));
+        let result = deposit(ctx, 0, 0, 50_000, 50_000, vec![]);
 
         // If the test fails, print the error
-        if result.is_err() {
-            println!("Error: {:?}", result.unwrap_err());
-        } else {
-            assert!(result.is_ok());
+        if let Err(error) = &result {
+            println!("Error: {:?}", error);
         }
 
         // Verify prediction state updates
@@ -470,13 +431,19 @@ mod tests {
 
         assert_eq!(updated_prediction.anti, 50_000);
         assert_eq!(updated_prediction.pro, 50_000);
-        assert_eq!(updated_prediction.deposits.len(), 1);
-
-        let deposit_record = &updated_prediction.deposits[0];
-        assert_eq!(deposit_record.address, authority_info.key());
-        assert_eq!(deposit_record.anti, 50_000);
-        assert_eq!(deposit_record.pro, 50_000);
-        assert_eq!(deposit_record.withdrawn, false);
+        assert_eq!(updated_prediction.deposits, 1);
+
+        // Verify deposit record
+        let deposit_record_borrowed = deposit_record_info.try_borrow_data().unwrap();
+        let deposit_record =
+            DepositRecord::try_deserialize(&mut &deposit_record_borrowed[..]).unwrap();
+        assert_eq!(deposit_record.index, 0);
+        assert_eq!(deposit_record.nonce, 0);
+        assert_eq!(deposit_record.deposit.address, authority_info.key());
+        assert_eq!(deposit_record.deposit.anti, 50_000);
+        assert_eq!(deposit_record.deposit.pro, 50_000);
+        assert!(!deposit_record.deposit.withdrawn);
+        assert!(!deposit_record.binned);
     }
 
     #[test]
@@ -485,22 +452,20 @@ mod tests {
         let program_id = program_id();
 
         // Create mints
-        let anti_mint = TestAccountData::new_mint(ANTI_MINT_ADDRESS);
-        let pro_mint = TestAccountData::new_mint(PRO_MINT_ADDRESS);
-
-        // Test double for Clock
-        thread_local! {
-            static MOCK_UNIX_TIMESTAMP: RefCell<i64> = RefCell::new(1736899200); // 2025-01-15T00:00:00Z
-        }
+        let mut anti_mint = TestAccountData::new_mint(ANTI_MINT_ADDRESS);
+        let mut pro_mint = TestAccountData::new_mint(PRO_MINT_ADDRESS);
+        let anti_mint_info = anti_mint.to_account_info(false);
+        let pro_mint_info = pro_mint.to_account_info(false);
 
         // Initialise state account
         let root: Pubkey = Pubkey::new_unique();
-        let mut state =
-            TestAccountData::new_account_with_key_and_owner::<StateAccount>(root, program_id);
+        let mut state = TestAccountData::new_account_with_key_and_owner(root, program_id);
         state
             .init_state_data(&StateAccount {
+                version: STATE_VERSION,
                 index: 0,
                 authority: root,
+                archived: 0,
             })
             .unwrap();
 
@@ -520,28 +485,52 @@ mod tests {
             &program_id,
         );
 
-        let mut accounts =
-            create_test_accounts(prediction_pda, anti_token_pda, pro_token_pda, program_id);
+        let (pool_authority_pda, pool_authority_bump) = Pubkey::find_program_address(
+            &[b"authority", state.data[8..16].try_into().unwrap()],
+            &program_id,
+        );
+
+        let (deposit_record_pda, deposit_record_bump) = Pubkey::find_program_address(
+            &[
+                b"deposit",
+                state.data[8..16].try_into().unwrap(),
+                ANTITOKEN_MULTISIG.as_ref(),
+                0u64.to_le_bytes().as_ref(),
+            ],
+            &program_id,
+        );
+
+        let (admin_pda, admin_bump) = Pubkey::find_program_address(&[b"admin"], &program_id);
+
+        let mut accounts = create_test_accounts(
+            admin_pda,
+            prediction_pda,
+            deposit_record_pda,
+            anti_token_pda,
+            pro_token_pda,
+            pool_authority_pda,
+            program_id,
+        );
 
         let authority_key = Pubkey::new_unique();
 
         // Initialise user token accounts
         accounts
             .user_anti_token
-            .init_token_account(authority_key, anti_mint.key)
+            .init_token_account(authority_key, *anti_mint_info.key)
             .unwrap();
         accounts
             .user_pro_token
-            .init_token_account(authority_key, pro_mint.key)
+            .init_token_account(authority_key, *pro_mint_info.key)
             .unwrap();
         // Initialise prediction token accounts
         accounts
             .prediction_anti_token
-            .init_token_account(ANTITOKEN_MULTISIG, anti_mint.key)
+            .init_token_account(pool_authority_pda, *anti_mint_info.key)
             .unwrap();
         accounts
             .prediction_pro_token
-            .init_token_account(ANTITOKEN_MULTISIG, pro_mint.key)
+            .init_token_account(pool_authority_pda, *pro_mint_info.key)
             .unwrap();
 
         assert_eq!(
@@ -571,12 +560,15 @@ mod tests {
         assert_eq!(prediction_pro_token.amount, 0);
 
         // Get account infos
+        let admin_info = accounts.admin.to_account_info(false);
         let authority_info = accounts.authority.to_account_info(true);
+        let pool_authority_info = accounts.pool_authority.to_account_info(false);
         let user_anti_info = accounts.user_anti_token.to_account_info(false);
         let user_pro_info = accounts.user_pro_token.to_account_info(false);
         let prediction_anti_info = accounts.prediction_anti_token.to_account_info(false);
         let prediction_pro_info = accounts.prediction_pro_token.to_account_info(false);
         let token_program_info = accounts.token_program.to_account_info(false);
+        let system_program_info = accounts.system_program.to_account_info(false);
 
         // Create and initialise the prediction account
         let prediction = create_test_prediction("2025-01-01T00:00:00Z", "2025-02-01T00:00:00Z");
@@ -587,28 +579,79 @@ mod tests {
 
         let prediction_account_info = accounts.prediction_data.to_account_info(false);
 
+        // Create the deposit record account
+        accounts
+            .deposit_record
+            .init_deposit_record_data(&DepositRecord::default())
+            .unwrap();
+        let deposit_record_info = accounts.deposit_record.to_account_info(false);
+
         // Check that the buffer is correctly allocated
         assert!(
             prediction_account_info.try_borrow_data().unwrap().len() >= 8 + PredictionAccount::LEN
         );
+        // Five-outcome prediction sharing the same address
+        let mut election = TestAccountData::new_account_with_key_and_owner(
+            prediction_account_info.key(),
+            program_id,
+        );
+        election
+            .init_prediction_data(&PredictionAccount {
+                version: PREDICTION_VERSION,
+                outcomes: ["A", "B", "C", "D", "E"]
+                    .iter()
+                    .map(|label| label.to_string())
+                    .collect(),
+                ..create_test_prediction("2025-01-01T00:00:00Z", "2025-02-01T00:00:00Z")
+            })
+            .unwrap();
+        let election_info = election.to_account_info(false);
+        // Scalar prediction sharing the same address
+        let mut scalar = TestAccountData::new_account_with_key_and_owner(
+            prediction_account_info.key(),
+            program_id,
+        );
+        scalar
+            .init_prediction_data(&PredictionAccount {
+                version: PREDICTION_VERSION,
+                outcomes: vec![],
+                scalar: Some(ScalarRange {
+                    lower: 0,
+                    upper: 1_000,
+                    unit: "USD".to_string(),
+                }),
+                ..create_test_prediction("2025-01-01T00:00:00Z", "2025-02-01T00:00:00Z")
+            })
+            .unwrap();
+        let scalar_info = scalar.to_account_info(false);
+
         /* Common Setup Begins Here */
 
         // Test minimum deposit validation
         {
             // Create deposit accounts
             let mut accounts = DepositTokens {
+                admin: Account::try_from(&admin_info).unwrap(),
                 prediction: Account::try_from(&prediction_account_info).unwrap(),
+                deposit_record: Account::try_from(&deposit_record_info).unwrap(),
                 authority: Signer::try_from(&authority_info).unwrap(),
+                pool_authority: pool_authority_info.clone(),
                 user_anti_token: TestAccountData::into_token_account(&user_anti_info),
                 user_pro_token: TestAccountData::into_token_account(&user_pro_info),
                 prediction_anti_token: TestAccountData::into_token_account(&prediction_anti_info),
                 prediction_pro_token: TestAccountData::into_token_account(&prediction_pro_info),
-                token_program: Program::<Token>::try_from(&token_program_info).unwrap(),
+                anti_mint: InterfaceAccount::<Mint>::try_from(&anti_mint_info).unwrap(),
+                pro_mint: InterfaceAccount::<Mint>::try_from(&pro_mint_info).unwrap(),
+                token_program: Interface::<TokenInterface>::try_from(&token_program_info).unwrap(),
+                system_program: Program::try_from(&system_program_info).unwrap(),
             };
 
             // Create bumps
             let bumps = DepositTokensBumps {
+                admin: admin_bump,
                 prediction: prediction_bump,
+                deposit_record: deposit_record_bump,
+                pool_authority: pool_authority_bump,
                 prediction_anti_token: anti_token_bump,
                 prediction_pro_token: pro_token_bump,
             };
@@ -616,7 +659,145 @@ mod tests {
             // Create context with bump for prediction PDA
             let ctx = Context::new(&program_id, &mut accounts, &[], bumps);
 
-            let result = deposit(ctx, 0, 100, 100, Some(1736899200)); // Below MIN_DEPOSIT
+            set_clock_override(Some(1736899200));
+            let result = deposit(ctx, 0, 0, 100, 100, vec![]); // Below MIN_DEPOSIT
+            match result {
+                Err(err) => assert_eq!(err, PredictError::InsufficientDeposit.into()),
+                _ => panic!("Expected insufficient deposit error"),
+            }
+        }
+
+        // Test distribution must weigh every outcome of a multi-outcome prediction
+        {
+            for distribution in [vec![], vec![60_000, 40_000], vec![0; 5]] {
+                let mut accounts = DepositTokens {
+                    admin: Account::try_from(&admin_info).unwrap(),
+                    prediction: Account::try_from(&election_info).unwrap(),
+                    deposit_record: Account::try_from(&deposit_record_info).unwrap(),
+                    authority: Signer::try_from(&authority_info).unwrap(),
+                    pool_authority: pool_authority_info.clone(),
+                    user_anti_token: TestAccountData::into_token_account(&user_anti_info),
+                    user_pro_token: TestAccountData::into_token_account(&user_pro_info),
+                    prediction_anti_token: TestAccountData::into_token_account(
+                        &prediction_anti_info,
+                    ),
+                    prediction_pro_token: TestAccountData::into_token_account(&prediction_pro_info),
+                    anti_mint: InterfaceAccount::<Mint>::try_from(&anti_mint_info).unwrap(),
+                    pro_mint: InterfaceAccount::<Mint>::try_from(&pro_mint_info).unwrap(),
+                    token_program: Interface::<TokenInterface>::try_from(&token_program_info)
+                        .unwrap(),
+                    system_program: Program::try_from(&system_program_info).unwrap(),
+                };
+
+                let bumps = DepositTokensBumps {
+                    admin: admin_bump,
+                    prediction: prediction_bump,
+                    deposit_record: deposit_record_bump,
+                    pool_authority: pool_authority_bump,
+                    prediction_anti_token: anti_token_bump,
+                    prediction_pro_token: pro_token_bump,
+                };
+
+                let ctx = Context::new(&program_id, &mut accounts, &[], bumps);
+
+                set_clock_override(Some(1736899200));
+                let result = deposit(ctx, 0, 0, 50_000, 50_000, distribution);
+                match result {
+                    Err(err) => assert_eq!(err, PredictError::InvalidDistribution.into()),
+                    _ => panic!("Expected invalid distribution error"),
+                }
+            }
+        }
+
+        // Test scalar deposits derive their estimate from the $ANTI/$PRO split
+        for (distribution, expected) in [(vec![800], None), (vec![], Some(vec![300]))] {
+            let mut accounts = DepositTokens {
+                admin: Account::try_from(&admin_info).unwrap(),
+                prediction: Account::try_from(&scalar_info).unwrap(),
+                deposit_record: Account::try_from(&deposit_record_info).unwrap(),
+                authority: Signer::try_from(&authority_info).unwrap(),
+                pool_authority: pool_authority_info.clone(),
+                user_anti_token: TestAccountData::into_token_account(&user_anti_info),
+                user_pro_token: TestAccountData::into_token_account(&user_pro_info),
+                prediction_anti_token: TestAccountData::into_token_account(&prediction_anti_info),
+                prediction_pro_token: TestAccountData::into_token_account(&prediction_pro_info),
+                anti_mint: InterfaceAccount::<Mint>::try_from(&anti_mint_info).unwrap(),
+                pro_mint: InterfaceAccount::<Mint>::try_from(&pro_mint_info).unwrap(),
+                token_program: Interface::<TokenInterface>::try_from(&token_program_info).unwrap(),
+                system_program: Program::try_from(&system_program_info).unwrap(),
+            };
+
+            let bumps = DepositTokensBumps {
+                admin: admin_bump,
+                prediction: prediction_bump,
+                deposit_record: deposit_record_bump,
+                pool_authority: pool_authority_bump,
+                prediction_anti_token: anti_token_bump,
+                prediction_pro_token: pro_token_bump,
+            };
+
+            let ctx = Context::new(&program_id, &mut accounts, &[], bumps);
+
+            set_clock_override(Some(1736899200));
+            let result = deposit(ctx, 0, 0, 70_000, 30_000, distribution);
+            match expected {
+                None => assert_eq!(
+                    result.unwrap_err(),
+                    PredictError::InvalidDistribution.into()
+                ),
+                Some(estimate) => {
+                    assert!(result.is_ok());
+                    let deposit_record_borrowed = deposit_record_info.try_borrow_data().unwrap();
+                    let deposit_record =
+                        DepositRecord::try_deserialize(&mut &deposit_record_borrowed[..]).unwrap();
+                    assert_eq!(deposit_record.deposit.distribution, estimate);
+                }
+            }
+        }
+
+        // Test minimum deposit is read from the admin account
+        {
+            let mut raised_admin =
+                TestAccountData::new_account_with_key_and_owner(admin_info.key(), program_id);
+            raised_admin
+                .init_admin_data(&AdminAccount {
+                    version: ADMIN_VERSION,
+                    min_deposit_amount: 100_000,
+                    ..test_admin()
+                })
+                .unwrap();
+            let raised_admin_info = raised_admin.to_account_info(false);
+
+            let mut accounts = DepositTokens {
+                admin: Account::try_from(&raised_admin_info).unwrap(),
+                prediction: Account::try_from(&prediction_account_info).unwrap(),
+                deposit_record: Account::try_from(&deposit_record_info).unwrap(),
+                authority: Signer::try_from(&authority_info).unwrap(),
+                pool_authority: pool_authority_info.clone(),
+                user_anti_token: TestAccountData::into_token_account(&user_anti_info),
+                user_pro_token: TestAccountData::into_token_account(&user_pro_info),
+                prediction_anti_token: TestAccountData::into_token_account(&prediction_anti_info),
+                prediction_pro_token: TestAccountData::into_token_account(&prediction_pro_info),
+                anti_mint: InterfaceAccount::<Mint>::try_from(&anti_mint_info).unwrap(),
+                pro_mint: InterfaceAccount::<Mint>::try_from(&pro_mint_info).unwrap(),
+                token_program: Interface::<TokenInterface>::try_from(&token_program_info).unwrap(),
+                system_program: Program::try_from(&system_program_info).unwrap(),
+            };
+
+            let bumps = DepositTokensBumps {
+                admin: admin_bump,
+                prediction: prediction_bump,
+                deposit_record: deposit_record_bump,
+                pool_authority: pool_authority_bump,
+                prediction_anti_token: anti_token_bump,
+                prediction_pro_token: pro_token_bump,
+            };
+
+            let ctx = Context::new(&program_id, &mut accounts, &[], bumps);
+
+            // Above the compile-time default, below the governed minimum
+            set_clock_override(Some(1736899200));
+            let result = deposit(ctx, 0, 0, 50_000, 50_000, vec![]);
             match result {
                 Err(err) => assert_eq!(err, PredictError::InsufficientDeposit.into()),
                 _ => panic!("Expected insufficient deposit error"),
@@ -633,7 +814,8 @@ mod tests {
             let invalid_anti_info = invalid_anti_token.to_account_info(false);
 
             // Try to convert the invalid account - this should return an error
-            let token_account_result = Account::<TokenAccount>::try_from(&invalid_anti_info);
+            let token_account_result =
+                InterfaceAccount::<TokenAccount>::try_from(&invalid_anti_info);
             assert!(token_account_result.is_err());
 
             if let Err(error) = token_account_result {
@@ -641,7 +823,6 @@ mod tests {
                     anchor_lang::error::Error::AnchorError(e) => {
                         let error_code: u32 = ErrorCode::AccountOwnedByWrongProgram.into();
                         assert_eq!(e.error_code_number, error_code);
-                        assert!(e.compared_values.is_some());
                     }
                     _ => panic!("Expected AccountOwnedByWrongProgram error"),
                 }
@@ -655,22 +836,20 @@ mod tests {
         let program_id = program_id();
 
         // Create mints
-        let anti_mint = TestAccountData::new_mint(ANTI_MINT_ADDRESS);
-        let pro_mint = TestAccountData::new_mint(PRO_MINT_ADDRESS);
-
-        // Test double for Clock
-        thread_local! {
-            static MOCK_UNIX_TIMESTAMP: RefCell<i64> = RefCell::new(1736899200); // 2025-01-15T00:00:00Z
-        }
+        let mut anti_mint = TestAccountData::new_mint(ANTI_MINT_ADDRESS);
+        let mut pro_mint = TestAccountData::new_mint(PRO_MINT_ADDRESS);
+        let anti_mint_info = anti_mint.to_account_info(false);
+        let pro_mint_info = pro_mint.to_account_info(false);
 
         // Initialise state account
         let root: Pubkey = Pubkey::new_unique();
-        let mut state =
-            TestAccountData::new_account_with_key_and_owner::<StateAccount>(root, program_id);
+        let mut state = TestAccountData::new_account_with_key_and_owner(root, program_id);
         state
             .init_state_data(&StateAccount {
+                version: STATE_VERSION,
                 index: 0,
                 authority: root,
+                archived: 0,
             })
             .unwrap();
 
@@ -690,28 +869,52 @@ mod tests {
             &program_id,
         );
 
-        let mut accounts =
-            create_test_accounts(prediction_pda, anti_token_pda, pro_token_pda, program_id);
+        let (pool_authority_pda, pool_authority_bump) = Pubkey::find_program_address(
+            &[b"authority", state.data[8..16].try_into().unwrap()],
+            &program_id,
+        );
+
+        let (deposit_record_pda, deposit_record_bump) = Pubkey::find_program_address(
+            &[
+                b"deposit",
+                state.data[8..16].try_into().unwrap(),
+                ANTITOKEN_MULTISIG.as_ref(),
+                0u64.to_le_bytes().as_ref(),
+            ],
+            &program_id,
+        );
+
+        let (admin_pda, admin_bump) = Pubkey::find_program_address(&[b"admin"], &program_id);
+
+        let mut accounts = create_test_accounts(
+            admin_pda,
+            prediction_pda,
+            deposit_record_pda,
+            anti_token_pda,
+            pro_token_pda,
+            pool_authority_pda,
+            program_id,
+        );
 
         let authority_key = Pubkey::new_unique();
 
         // Initialise user token accounts
         accounts
             .user_anti_token
-            .init_token_account(authority_key, anti_mint.key)
+            .init_token_account(authority_key, *anti_mint_info.key)
             .unwrap();
         accounts
             .user_pro_token
-            .init_token_account(authority_key, pro_mint.key)
+            .init_token_account(authority_key, *pro_mint_info.key)
             .unwrap();
         // Initialise prediction token accounts
         accounts
             .prediction_anti_token
-            .init_token_account(ANTITOKEN_MULTISIG, anti_mint.key)
+            .init_token_account(pool_authority_pda, *anti_mint_info.key)
             .unwrap();
         accounts
             .prediction_pro_token
-            .init_token_account(ANTITOKEN_MULTISIG, pro_mint.key)
+            .init_token_account(pool_authority_pda, *pro_mint_info.key)
             .unwrap();
 
         assert_eq!(
@@ -741,12 +944,15 @@ mod tests {
         assert_eq!(prediction_pro_token.amount, 0);
 
         // Get account infos
+        let admin_info = accounts.admin.to_account_info(false);
         let authority_info = accounts.authority.to_account_info(true);
+        let pool_authority_info = accounts.pool_authority.to_account_info(false);
         let user_anti_info = accounts.user_anti_token.to_account_info(false);
         let user_pro_info = accounts.user_pro_token.to_account_info(false);
         let prediction_anti_info = accounts.prediction_anti_token.to_account_info(false);
         let prediction_pro_info = accounts.prediction_pro_token.to_account_info(false);
         let token_program_info = accounts.token_program.to_account_info(false);
+        let system_program_info = accounts.system_program.to_account_info(false);
 
         // Create and initialise the prediction account
         let prediction = create_test_prediction("2025-01-01T00:00:00Z", "2025-02-01T00:00:00Z");
@@ -757,6 +963,13 @@ mod tests {
 
         let prediction_account_info = accounts.prediction_data.to_account_info(false);
 
+        // Create the deposit record account
+        accounts
+            .deposit_record
+            .init_deposit_record_data(&DepositRecord::default())
+            .unwrap();
+        let deposit_record_info = accounts.deposit_record.to_account_info(false);
+
         // Check that the buffer is correctly allocated
         assert!(
             prediction_account_info.try_borrow_data().unwrap().len() >= 8 + PredictionAccount::LEN
@@ -764,18 +977,27 @@ mod tests {
 
         // Create deposit accounts
         let mut accounts = DepositTokens {
+            admin: Account::try_from(&admin_info).unwrap(),
             prediction: Account::try_from(&prediction_account_info).unwrap(),
+            deposit_record: Account::try_from(&deposit_record_info).unwrap(),
             authority: Signer::try_from(&authority_info).unwrap(),
+            pool_authority: pool_authority_info.clone(),
             user_anti_token: TestAccountData::into_token_account(&user_anti_info),
             user_pro_token: TestAccountData::into_token_account(&user_pro_info),
             prediction_anti_token: TestAccountData::into_token_account(&prediction_anti_info),
             prediction_pro_token: TestAccountData::into_token_account(&prediction_pro_info),
-            token_program: Program::<Token>::try_from(&token_program_info).unwrap(),
+            anti_mint: InterfaceAccount::<Mint>::try_from(&anti_mint_info).unwrap(),
+            pro_mint: InterfaceAccount::<Mint>::try_from(&pro_mint_info).unwrap(),
+            token_program: Interface::<TokenInterface>::try_from(&token_program_info).unwrap(),
+            system_program: Program::try_from(&system_program_info).unwrap(),
         };
 
         // Create bumps
         let bumps = DepositTokensBumps {
+            admin: admin_bump,
             prediction: prediction_bump,
+            deposit_record: deposit_record_bump,
+            pool_authority: pool_authority_bump,
             prediction_anti_token: anti_token_bump,
             prediction_pro_token: pro_token_bump,
         };
@@ -787,7 +1009,8 @@ mod tests {
         let anti = 70_000;
         let pro = 30_000;
 
-        let result = deposit(ctx, 0, anti, pro, Some(1736899200));
+        set_clock_override(Some(1736899200));
+        let result = deposit(ctx, 0, 0, anti, pro, vec![]);
         assert!(result.is_ok());
 
         let prediction_info_borrowed = prediction_account_info.try_borrow_data().unwrap();
@@ -797,14 +1020,175 @@ mod tests {
         assert_eq!(updated_prediction.anti, anti);
         assert_eq!(updated_prediction.pro, pro);
 
-        let deposit = &updated_prediction.deposits[0];
-        let (expected_u, expected_s) = collide(anti, pro).unwrap();
+        let deposit_record_borrowed = deposit_record_info.try_borrow_data().unwrap();
+        let deposit_record =
+            DepositRecord::try_deserialize(&mut &deposit_record_borrowed[..]).unwrap();
+        let deposit = &deposit_record.deposit;
+        let (expected_u, expected_s) = collide(anti, pro, FLOAT_BASIS).unwrap();
 
         assert_eq!(deposit.mean, expected_u);
         assert_eq!(deposit.stddev, expected_s);
         assert_eq!(deposit.anti, anti);
         assert_eq!(deposit.pro, pro);
+        assert_eq!(deposit.distribution, vec![anti, pro]); // Binary default
         assert!(!deposit.withdrawn);
         assert_eq!(deposit.address, authority_info.key());
     }
+
+    #[test]
+    fn test_deposit_constraints() {
+        let program_id = program_id();
+        let pda = |seed: &[u8]| {
+            Pubkey::find_program_address(&[seed, 0u64.to_le_bytes().as_ref()], &program_id).0
+        };
+        let record_pda = |nonce: u64| {
+            Pubkey::find_program_address(
+                &[
+                    b"deposit",
+                    0u64.to_le_bytes().as_ref(),
+                    ANTITOKEN_MULTISIG.as_ref(),
+                    nonce.to_le_bytes().as_ref(),
+                ],
+                &program_id,
+            )
+            .0
+        };
+        let (admin_pda, _) = Pubkey::find_program_address(&[b"admin"], &program_id);
+        let mut test_accounts = create_test_accounts(
+            admin_pda,
+            pda(b"prediction"),
+            record_pda(0),
+            pda(b"anti_token"),
+            pda(b"pro_token"),
+            pda(b"authority"),
+            program_id,
+        );
+        // The record is created in place, so it must already hold its rent
+        test_accounts.deposit_record.lamports =
+            Rent::default().minimum_balance(test_accounts.deposit_record.data.len());
+        let prediction_data = PredictionAccount {
+            version: PREDICTION_VERSION,
+            ..create_test_prediction("2025-01-01T00:00:00Z", "2025-02-01T00:00:00Z")
+        };
+        test_accounts
+            .prediction_data
+            .init_prediction_data(&prediction_data)
+            .unwrap();
+        let pool_authority = test_accounts.pool_authority.key;
+        for (account, owner, mint) in [
+            (
+                &mut test_accounts.user_anti_token,
+                ANTITOKEN_MULTISIG,
+                ANTI_MINT_ADDRESS,
+            ),
+            (
+                &mut test_accounts.user_pro_token,
+                ANTITOKEN_MULTISIG,
+                PRO_MINT_ADDRESS,
+            ),
+            (
+                &mut test_accounts.prediction_anti_token,
+                pool_authority,
+                ANTI_MINT_ADDRESS,
+            ),
+            (
+                &mut test_accounts.prediction_pro_token,
+                pool_authority,
+                PRO_MINT_ADDRESS,
+            ),
+        ] {
+            account.init_token_account(owner, mint).unwrap();
+        }
+
+        // Prediction recorded against a since-rotated $ANTI mint
+        let mut rotated =
+            TestAccountData::new_account_with_key_and_owner(pda(b"prediction"), program_id);
+        rotated
+            .init_prediction_data(&PredictionAccount {
+                anti_mint: Pubkey::new_unique(),
+                ..prediction_data
+            })
+            .unwrap();
+        let mut other_record =
+            TestAccountData::new_account_with_key_and_owner(record_pda(1), program_id);
+        let mut foreign_anti =
+            TestAccountData::new_token_account(Pubkey::new_unique(), ANTI_MINT_ADDRESS, 0);
+        let mut stray_anti = TestAccountData::new_token(pda(b"anti_token"));
+        stray_anti
+            .init_token_account(ANTITOKEN_MULTISIG, ANTI_MINT_ADDRESS)
+            .unwrap();
+        let mut anti_mint = TestAccountData::new_mint(ANTI_MINT_ADDRESS);
+        let mut pro_mint = TestAccountData::new_mint(PRO_MINT_ADDRESS);
+        let mut anti_mint_2022 = TestAccountData::new_token_2022_mint(ANTI_MINT_ADDRESS);
+
+        let user_pro_info = test_accounts.user_pro_token.to_account_info(false);
+        let accounts = vec![
+            test_accounts.admin.to_account_info(false),
+            test_accounts.prediction_data.to_account_info(false),
+            test_accounts.deposit_record.to_account_info(false),
+            test_accounts.authority.to_account_info(true),
+            test_accounts.user_anti_token.to_account_info(false),
+            user_pro_info.clone(),
+            test_accounts.pool_authority.to_account_info(false),
+            test_accounts.prediction_anti_token.to_account_info(false),
+            test_accounts.prediction_pro_token.to_account_info(false),
+            anti_mint.to_account_info(false),
+            pro_mint.to_account_info(false),
+            test_accounts.token_program.to_account_info(false),
+            test_accounts.system_program.to_account_info(false),
+        ];
+        let rotated_info = rotated.to_account_info(false);
+        let other_record_info = other_record.to_account_info(false);
+        let foreign_anti_info = foreign_anti.to_account_info(false);
+        let stray_anti_info = stray_anti.to_account_info(false);
+        let anti_mint_2022_info = anti_mint_2022.to_account_info(false);
+
+        // Deposits settle into the prediction's pools, in its mints, from the
+        // depositor's own accounts, under a record at the depositor's nonce
+        let cases: Vec<(Vec<AccountInfo>, Option<Error>)> = [
+            (5, &user_pro_info, None),
+            (
+                1,
+                &rotated_info,
+                Some(PredictError::InvalidTokenAccount.into()),
+            ),
+            (
+                2,
+                &other_record_info,
+                Some(ErrorCode::ConstraintSeeds.into()),
+            ),
+            (
+                4,
+                &foreign_anti_info,
+                Some(PredictError::InvalidTokenAccount.into()),
+            ),
+            (
+                4,
+                &user_pro_info,
+                Some(PredictError::InvalidTokenAccount.into()),
+            ),
+            (
+                7,
+                &stray_anti_info,
+                Some(PredictError::InvalidTokenAccount.into()),
+            ),
+            (
+                9,
+                &anti_mint_2022_info,
+                Some(ErrorCode::ConstraintMintTokenProgram.into()),
+            ),
+        ]
+        .into_iter()
+        .map(|(slot, account, expected)| {
+            let mut swapped = accounts.clone();
+            swapped[slot] = account.clone();
+            (swapped, expected)
+        })
+        .collect();
+        let ix_data = [0u64.to_le_bytes(), 0u64.to_le_bytes()].concat();
+        for (swapped, expected) in &cases {
+            let result = try_accounts::<DepositTokens, DepositTokensBumps>(swapped, &ix_data);
+            assert_eq!(result.err().as_ref(), expected.as_ref());
+        }
+    }
 }
@@ -0,0 +1,708 @@
+//! Program Author: sshmatrix, for Antitoken
+//! Program Description: Collider's instruction set
+//! Version: 1.0.0-beta
+//! License: MIT
+//! Created: 20 Jan 2025
+//! Last Modified: 20 Jan 2025
+//! Repository: https://github.com/antitokens/solana-collider
+//! Contact: dev@antitoken.pro
+
+// instructions/dispute.rs
+use crate::instructions::equalise::{allocate, bin_records};
+use crate::state::*;
+use crate::utils::*;
+use crate::{ArbitrateEqualisation, DisputeEqualisation};
+use anchor_lang::prelude::*;
+use anchor_lang::system_program;
+
+pub fn dispute(ctx: Context<DisputeEqualisation>, index: u64) -> Result<()> {
+    // Get current time
+    let now = current_time()?;
+
+    let prediction = &ctx.accounts.prediction;
+
+    // Verify prediction has been equalised
+    require!(prediction.equalised, PredictError::NotEqualised);
+    let round = prediction
+        .equalisation
+        .as_ref()
+        .ok_or(error!(PredictError::NotEqualised))?
+        .round;
+
+    // Verify the challenge period is still open
+    require!(
+        now < prediction.challenge_end,
+        PredictError::ChallengePeriodEnded
+    );
+
+    // Only one dispute may be pending
+    require!(prediction.dispute.is_none(), PredictError::AlreadyDisputed);
+
+    // Record the dispute
+    let bond = ctx.accounts.admin.dispute_bond;
+    let prediction = &mut ctx.accounts.prediction;
+    prediction.dispute = Some(Dispute {
+        disputer: ctx.accounts.authority.key(),
+        bond,
+        timestamp: now,
+        round,
+    });
+
+    // Serialise updated prediction state, growing it at the disputer's expense
+    // before the bond arrives, so the bond never counts toward the rent
+    write_account_growing(
+        &prediction.to_account_info(),
+        &**prediction,
+        &ctx.accounts.authority.to_account_info(),
+        &ctx.accounts.system_program.to_account_info(),
+        &ctx.accounts.rent,
+        prediction.max_space(),
+    )?;
+
+    // Post the bond to the prediction account
+    system_program::transfer(
+        CpiContext::new(
+            ctx.accounts.system_program.to_account_info(),
+            system_program::Transfer {
+                from: ctx.accounts.authority.to_account_info(),
+                to: ctx.accounts.prediction.to_account_info(),
+            },
+        ),
+        bond,
+    )?;
+
+    emit!(DisputeEvent {
+        index,
+        disputer: ctx.accounts.authority.key(),
+        bond,
+        timestamp: now,
+    });
+
+    Ok(())
+}
+
+pub fn arbitrate<'a, 'b, 'c: 'info, 'info>(
+    ctx: Context<'a, 'b, 'c, 'info, ArbitrateEqualisation<'info>>,
+    index: u64,
+    truth: Vec<u64>,
+) -> Result<()> {
+    // Get current time
+    let now = current_time()?;
+
+    let admin = &ctx.accounts.admin;
+    let prediction = &mut ctx.accounts.prediction;
+
+    // Verify signer is the arbiter
+    require!(
+        ctx.accounts.authority.key() == admin.arbiter,
+        PredictError::UnauthorisedArbiter
+    );
+
+    // Verify a dispute is pending and the disputer is supplied
+    let dispute = prediction
+        .dispute
+        .clone()
+        .ok_or(error!(PredictError::NoDispute))?;
+    require!(
+        ctx.accounts.disputer.key() == dispute.disputer,
+        PredictError::Unauthorised
+    );
+
+    // Validate truth values
+    validate_truth(prediction, &truth, admin.truth_basis)?;
+
+    let mut equalisation = prediction
+        .equalisation
+        .clone()
+        .ok_or(error!(PredictError::NotEqualised))?;
+
+    if equalisation.round == dispute.round {
+        if equalisation.truth == truth {
+            // Original truth stands; bond is forfeited to the vault
+            transfer_bond(
+                &prediction.to_account_info(),
+                &ctx.accounts.vault,
+                dispute.bond,
+            )?;
+            prediction.dispute = None;
+            prediction.challenge_end = now;
+
+            // Serialise updated prediction state
+            write_account(&prediction.to_account_info(), &**prediction)?;
+
+            emit!(ArbitrationEvent {
+                index,
+                truth,
+                upheld: true,
+                timestamp: now,
+            });
+
+            return Ok(());
+        }
+
+        // Overturned; start a new round with the arbiter's truth
+        let round = equalisation
+            .round
+            .checked_add(1)
+            .ok_or(error!(PredictError::MathError))?;
+        equalisation = Equalisation::new(truth.clone(), now);
+        equalisation.round = round;
+        prediction.equalised = false;
+    } else {
+        // Resume a partial re-equalisation with the same truth
+        require!(
+            equalisation.truth == truth,
+            PredictError::InvalidTruthValues
+        );
+    }
+
+    // Re-bin the deposit records supplied in this batch
+    bin_records(
+        &mut equalisation,
+        ctx.remaining_accounts,
+        index,
+        prediction.scalar.as_ref(),
+        admin.float_basis,
+    )?;
+
+    // Recompute allocations once every deposit record has been re-binned
+    let complete = equalisation.binned == prediction.deposits;
+    if complete {
+        // Fees were carved out at equalisation and are not charged again
+        let (anti_pool, pro_pool) = prediction.pools()?;
+        allocate(
+            &mut equalisation,
+            anti_pool,
+            pro_pool,
+            admin.float_basis,
+            now,
+        )?;
+
+        // Successful dispute; bond is returned to the disputer
+        transfer_bond(
+            &prediction.to_account_info(),
+            &ctx.accounts.disputer,
+            dispute.bond,
+        )?;
+        prediction.dispute = None;
+        prediction.challenge_end = now;
+    }
+
+    // Update prediction state with re-equalisation results
+    prediction.equalised = complete;
+    prediction.equalisation = Some(equalisation);
+
+    // Serialise updated prediction state
+    write_account(&prediction.to_account_info(), &**prediction)?;
+
+    if complete {
+        emit!(ArbitrationEvent {
+            index,
+            truth,
+            upheld: false,
+            timestamp: now,
+        });
+    }
+
+    Ok(())
+}
+
+// Move a dispute bond out of the program-owned prediction account
+fn transfer_bond<'info>(
+    from: &AccountInfo<'info>,
+    to: &AccountInfo<'info>,
+    amount: u64,
+) -> Result<()> {
+    let from_lamports = from
+        .lamports()
+        .checked_sub(amount)
+        .ok_or(error!(PredictError::MathError))?;
+    let to_lamports = to
+        .lamports()
+        .checked_add(amount)
+        .ok_or(error!(PredictError::MathError))?;
+    **from.try_borrow_mut_lamports()? = from_lamports;
+    **to.try_borrow_mut_lamports()? = to_lamports;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::*;
+    use crate::{ArbitrateEqualisationBumps, DisputeEqualisationBumps};
+    use anchor_lang::Discriminator;
+
+    // Arbiter configured in the test admin account
+    fn arbiter() -> Pubkey {
+        Pubkey::new_from_array([9; 32])
+    }
+
+    fn new_admin_account(program_id: Pubkey) -> TestAccountData {
+        let (admin_pda, _) = Pubkey::find_program_address(&[b"admin"], &program_id);
+        let mut account = TestAccountData::new_account_with_key_and_owner(admin_pda, program_id);
+        account
+            .init_admin_data(&AdminAccount {
+                arbiter: arbiter(),
+                ..test_admin()
+            })
+            .unwrap();
+        account
+    }
+
+    // Reusable method to create an equalised prediction inside its challenge period
+    fn create_equalised_test_prediction(dispute: Option<Dispute>) -> PredictionAccount {
+        PredictionAccount {
+            anti: 70000,
+            pro: 30000,
+            deposits: 1,
+            equalised: true,
+            equalisation: Some(Equalisation {
+                anti: vec![70000],
+                pro: vec![30000],
+                anti_staked: vec![70000],
+                pro_staked: vec![30000],
+                binned: 1,
+                truth: vec![60000, 40000],
+                timestamp: 1736899200,
+                round: 0,
+            }),
+            challenge_end: 1736899200 + CHALLENGE_PERIOD,
+            dispute,
+            ..test_prediction()
+        }
+    }
+
+    #[test]
+    fn test_dispute_and_overturn() {
+        let program_id = program_id();
+        let now: i64 = 1736899200 + 3600; // Inside the challenge period
+        let (_, admin_bump) = Pubkey::find_program_address(&[b"admin"], &program_id);
+        let (prediction_pda, prediction_bump) = Pubkey::find_program_address(
+            &[b"prediction", 0u64.to_le_bytes().as_ref()],
+            &program_id,
+        );
+
+        // Create test accounts
+        let mut admin = new_admin_account(program_id);
+        let mut prediction =
+            TestAccountData::new_account_with_key_and_owner(prediction_pda, program_id);
+        prediction
+            .init_prediction_data(&create_equalised_test_prediction(None))
+            .unwrap();
+        let mut disputer = TestAccountData::new_authority_account(Pubkey::new_unique());
+        let mut arbiter = TestAccountData::new_authority_account(arbiter());
+        let mut vault = TestAccountData::new_authority_account(ANTITOKEN_MULTISIG);
+        let mut system_program = TestAccountData::new_system_account();
+        let mut rent = TestAccountData::new_rent_account();
+        let mut deposit_record =
+            TestAccountData::new_account_with_key_and_owner(Pubkey::new_unique(), program_id);
+        deposit_record
+            .init_deposit_record_data(&test_deposit_record(disputer.key, 0, 70000, 30000))
+            .unwrap();
+
+        // Get account infos
+        let admin_info = admin.to_account_info(false);
+        let prediction_info = prediction.to_account_info(false);
+        let disputer_info = disputer.to_account_info(true);
+        let arbiter_info = arbiter.to_account_info(true);
+        let vault_info = vault.to_account_info(false);
+        let system_program_info = system_program.to_account_info(false);
+        let rent_info = rent.to_account_info(false);
+        let deposit_record_info = deposit_record.to_account_info(false);
+        let remaining_accounts = vec![deposit_record_info.clone()];
+
+        // Dispute the equalisation
+        {
+            let mut accounts = DisputeEqualisation {
+                admin: Account::try_from(&admin_info).unwrap(),
+                prediction: Account::try_from(&prediction_info).unwrap(),
+                authority: Signer::try_from(&disputer_info).unwrap(),
+                system_program: Program::try_from(&system_program_info).unwrap(),
+                rent: Sysvar::from_account_info(&rent_info).unwrap(),
+            };
+            let bumps = DisputeEqualisationBumps {
+                admin: admin_bump,
+                prediction: prediction_bump,
+            };
+            set_clock_override(Some(now));
+            let result = dispute(Context::new(&program_id, &mut accounts, &[], bumps), 0);
+            assert!(result.is_ok());
+
+            let prediction_account: PredictionAccount = PredictionAccount::try_deserialize(
+                &mut prediction_info.try_borrow_data().unwrap().as_ref(),
+            )
+            .unwrap();
+            let pending = prediction_account.dispute.clone().unwrap();
+            assert_eq!(pending.disputer, disputer_info.key());
+            assert_eq!(pending.bond, DISPUTE_BOND);
+            assert!(!prediction_account.is_settled(now + CHALLENGE_PERIOD));
+        }
+
+        // Bond is held by the prediction account (transfer CPI is stubbed off-chain)
+        **prediction_info.try_borrow_mut_lamports().unwrap() += DISPUTE_BOND;
+        let disputer_lamports = disputer_info.lamports();
+
+        // Arbiter overturns the truth and re-bins the deposit
+        {
+            let mut accounts = ArbitrateEqualisation {
+                admin: Account::try_from(&admin_info).unwrap(),
+                prediction: Account::try_from(&prediction_info).unwrap(),
+                authority: Signer::try_from(&arbiter_info).unwrap(),
+                disputer: disputer_info.clone(),
+                vault: vault_info.clone(),
+            };
+            let bumps = ArbitrateEqualisationBumps {
+                admin: admin_bump,
+                prediction: prediction_bump,
+            };
+            let truth = vec![40000, 60000];
+            set_clock_override(Some(now + 60));
+            let result = arbitrate(
+                Context::new(&program_id, &mut accounts, &remaining_accounts, bumps),
+                0,
+                truth.clone(),
+            );
+            assert!(result.is_ok());
+
+            let prediction_account: PredictionAccount = PredictionAccount::try_deserialize(
+                &mut prediction_info.try_borrow_data().unwrap().as_ref(),
+            )
+            .unwrap();
+            let equalisation = prediction_account.equalisation.clone().unwrap();
+            assert!(prediction_account.equalised);
+            assert!(prediction_account.dispute.is_none());
+            assert_eq!(equalisation.truth, truth);
+            assert_eq!(equalisation.round, 1);
+            assert_eq!(equalisation.binned, 1);
+            assert!(prediction_account.is_settled(now + 60));
+
+            // Deposit record re-binned in the new round
+            let record: DepositRecord = DepositRecord::try_deserialize(
+                &mut deposit_record_info.try_borrow_data().unwrap().as_ref(),
+            )
+            .unwrap();
+            assert_eq!(record.round, 1);
+
+            // Payouts match the single-pass equalisation under the new truth
+            let (anti, pro) = equalise_with_truth(
+                std::slice::from_ref(&record.deposit),
+                70000,
+                30000,
+                &truth,
+                None,
+                FLOAT_BASIS,
+            )
+            .unwrap();
+            assert_eq!(payout(&equalisation, &record).unwrap(), (anti[0], pro[0]));
+        }
+
+        // Bond returned to the successful disputer
+        assert_eq!(disputer_info.lamports(), disputer_lamports + DISPUTE_BOND);
+    }
+
+    #[test]
+    fn test_dispute_upheld() {
+        let program_id = program_id();
+        let now: i64 = 1736899200 + 3600;
+        let (_, admin_bump) = Pubkey::find_program_address(&[b"admin"], &program_id);
+        let (prediction_pda, prediction_bump) = Pubkey::find_program_address(
+            &[b"prediction", 0u64.to_le_bytes().as_ref()],
+            &program_id,
+        );
+
+        let disputer_key = Pubkey::new_unique();
+        let mut admin = new_admin_account(program_id);
+        let mut prediction =
+            TestAccountData::new_account_with_key_and_owner(prediction_pda, program_id);
+        prediction
+            .init_prediction_data(&create_equalised_test_prediction(Some(Dispute {
+                disputer: disputer_key,
+                bond: DISPUTE_BOND,
+                timestamp: now,
+                round: 0,
+            })))
+            .unwrap();
+        prediction.lamports += DISPUTE_BOND;
+        let mut disputer = TestAccountData::new_authority_account(disputer_key);
+        let mut arbiter = TestAccountData::new_authority_account(arbiter());
+        let mut vault = TestAccountData::new_authority_account(ANTITOKEN_MULTISIG);
+
+        let admin_info = admin.to_account_info(false);
+        let prediction_info = prediction.to_account_info(false);
+        let disputer_info = disputer.to_account_info(false);
+        let arbiter_info = arbiter.to_account_info(true);
+        let vault_info = vault.to_account_info(false);
+        let vault_lamports = vault_info.lamports();
+
+        let mut accounts = ArbitrateEqualisation {
+            admin: Account::try_from(&admin_info).unwrap(),
+            prediction: Account::try_from(&prediction_info).unwrap(),
+            authority: Signer::try_from(&arbiter_info).unwrap(),
+            disputer: disputer_info.clone(),
+            vault: vault_info.clone(),
+        };
+        let bumps = ArbitrateEqualisationBumps {
+            admin: admin_bump,
+            prediction: prediction_bump,
+        };
+
+        // Arbiter confirms the original truth
+        set_clock_override(Some(now));
+        let result = arbitrate(
+            Context::new(&program_id, &mut accounts, &[], bumps),
+            0,
+            vec![60000, 40000],
+        );
+        assert!(result.is_ok());
+
+        let prediction_account: PredictionAccount = PredictionAccount::try_deserialize(
+            &mut prediction_info.try_borrow_data().unwrap().as_ref(),
+        )
+        .unwrap();
+        assert!(prediction_account.equalised);
+        assert!(prediction_account.dispute.is_none());
+        assert!(prediction_account.is_settled(now));
+        assert_eq!(prediction_account.equalisation.unwrap().round, 0);
+
+        // Bond forfeited to the vault
+        assert_eq!(vault_info.lamports(), vault_lamports + DISPUTE_BOND);
+    }
+
+    #[test]
+    fn test_dispute_bond_leaves_rent() {
+        let program_id = program_id();
+        let now: i64 = 1736899200 + 3600;
+        let (_, admin_bump) = Pubkey::find_program_address(&[b"admin"], &program_id);
+        let (prediction_pda, prediction_bump) = Pubkey::find_program_address(
+            &[b"prediction", 0u64.to_le_bytes().as_ref()],
+            &program_id,
+        );
+
+        // Equalised prediction sized to its data, holding exactly its rent-exempt minimum
+        let equalised = create_equalised_test_prediction(None);
+        let space = 8 + equalised.try_to_vec().unwrap().len();
+        let rent = Rent::default();
+        let mut admin = new_admin_account(program_id);
+        let mut prediction = ReallocAccountData::new_legacy(
+            prediction_pda,
+            program_id,
+            PredictionAccount::discriminator(),
+            &equalised.try_to_vec().unwrap(),
+            space,
+        );
+        prediction.lamports = rent.minimum_balance(space);
+        let mut disputer = TestAccountData::new_authority_account(Pubkey::new_unique());
+        let mut arbiter = TestAccountData::new_authority_account(arbiter());
+        let mut vault = TestAccountData::new_authority_account(ANTITOKEN_MULTISIG);
+        let mut system_program = TestAccountData::new_system_account();
+        let mut rent_sysvar = TestAccountData::new_rent_account();
+
+        let admin_info = admin.to_account_info(false);
+        let prediction_info = prediction.to_account_info();
+        let disputer_info = disputer.to_account_info(true);
+        let arbiter_info = arbiter.to_account_info(true);
+        let vault_info = vault.to_account_info(false);
+        let system_program_info = system_program.to_account_info(false);
+        let rent_info = rent_sysvar.to_account_info(false);
+
+        let mut accounts = DisputeEqualisation {
+            admin: Account::try_from(&admin_info).unwrap(),
+            prediction: Account::try_from(&prediction_info).unwrap(),
+            authority: Signer::try_from(&disputer_info).unwrap(),
+            system_program: Program::try_from(&system_program_info).unwrap(),
+            rent: Sysvar::from_account_info(&rent_info).unwrap(),
+        };
+        let bumps = DisputeEqualisationBumps {
+            admin: admin_bump,
+            prediction: prediction_bump,
+        };
+        set_clock_override(Some(now));
+        dispute(Context::new(&program_id, &mut accounts, &[], bumps), 0).unwrap();
+        assert!(prediction_info.data_len() > space);
+
+        // Transfers are stubbed off-chain, so apply them in the order issued:
+        // the growth is priced on the lamports held before the bond arrives
+        let shortfall = rent
+            .minimum_balance(prediction_info.data_len())
+            .saturating_sub(prediction_info.lamports());
+        assert!(shortfall > 0);
+        **prediction_info.try_borrow_mut_lamports().unwrap() += shortfall + DISPUTE_BOND;
+
+        // Paying out the whole bond leaves the prediction rent exempt
+        let mut accounts = ArbitrateEqualisation {
+            admin: Account::try_from(&admin_info).unwrap(),
+            prediction: Account::try_from(&prediction_info).unwrap(),
+            authority: Signer::try_from(&arbiter_info).unwrap(),
+            disputer: disputer_info.clone(),
+            vault: vault_info.clone(),
+        };
+        let bumps = ArbitrateEqualisationBumps {
+            admin: admin_bump,
+            prediction: prediction_bump,
+        };
+        arbitrate(
+            Context::new(&program_id, &mut accounts, &[], bumps),
+            0,
+            vec![60000, 40000],
+        )
+        .unwrap();
+        assert!(rent.is_exempt(prediction_info.lamports(), prediction_info.data_len()));
+        assert_eq!(
+            prediction_info.lamports(),
+            rent.minimum_balance(prediction_info.data_len())
+        );
+    }
+
+    #[test]
+    fn test_dispute_validation_failures() {
+        let program_id = program_id();
+        let now: i64 = 1736899200 + 3600;
+        let (_, admin_bump) = Pubkey::find_program_address(&[b"admin"], &program_id);
+        let (prediction_pda, prediction_bump) = Pubkey::find_program_address(
+            &[b"prediction", 0u64.to_le_bytes().as_ref()],
+            &program_id,
+        );
+
+        let mut admin = new_admin_account(program_id);
+        let mut prediction =
+            TestAccountData::new_account_with_key_and_owner(prediction_pda, program_id);
+        let mut disputer = TestAccountData::new_authority_account(Pubkey::new_unique());
+        let mut attacker = TestAccountData::new_authority_account(Pubkey::new_unique());
+        let mut vault = TestAccountData::new_authority_account(ANTITOKEN_MULTISIG);
+        let mut system_program = TestAccountData::new_system_account();
+        let mut rent = TestAccountData::new_rent_account();
+
+        // Test dispute after the challenge period (should fail)
+        {
+            prediction
+                .init_prediction_data(&create_equalised_test_prediction(None))
+                .unwrap();
+            let admin_info = admin.to_account_info(false);
+            let prediction_info = prediction.to_account_info(false);
+            let disputer_info = disputer.to_account_info(true);
+            let system_program_info = system_program.to_account_info(false);
+            let rent_info = rent.to_account_info(false);
+
+            let mut accounts = DisputeEqualisation {
+                admin: Account::try_from(&admin_info).unwrap(),
+                prediction: Account::try_from(&prediction_info).unwrap(),
+                authority: Signer::try_from(&disputer_info).unwrap(),
+                system_program: Program::try_from(&system_program_info).unwrap(),
+                rent: Sysvar::from_account_info(&rent_info).unwrap(),
+            };
+            let bumps = DisputeEqualisationBumps {
+                admin: admin_bump,
+                prediction: prediction_bump,
+            };
+            set_clock_override(Some(1736899200 + CHALLENGE_PERIOD));
+            let result = dispute(Context::new(&program_id, &mut accounts, &[], bumps), 0);
+            match result {
+                Err(err) => assert_eq!(err, PredictError::ChallengePeriodEnded.into()),
+                _ => panic!("Expected challenge period ended error"),
+            }
+        }
+
+        // Test second dispute (should fail)
+        {
+            prediction
+                .init_prediction_data(&create_equalised_test_prediction(Some(Dispute {
+                    disputer: disputer.key,
+                    bond: DISPUTE_BOND,
+                    timestamp: now,
+                    round: 0,
+                })))
+                .unwrap();
+            let admin_info = admin.to_account_info(false);
+            let prediction_info = prediction.to_account_info(false);
+            let attacker_info = attacker.to_account_info(true);
+            let system_program_info = system_program.to_account_info(false);
+            let rent_info = rent.to_account_info(false);
+
+            let mut accounts = DisputeEqualisation {
+                admin: Account::try_from(&admin_info).unwrap(),
+                prediction: Account::try_from(&prediction_info).unwrap(),
+                authority: Signer::try_from(&attacker_info).unwrap(),
+                system_program: Program::try_from(&system_program_info).unwrap(),
+                rent: Sysvar::from_account_info(&rent_info).unwrap(),
+            };
+            let bumps = DisputeEqualisationBumps {
+                admin: admin_bump,
+                prediction: prediction_bump,
+            };
+            set_clock_override(Some(now));
+            let result = dispute(Context::new(&program_id, &mut accounts, &[], bumps), 0);
+            match result {
+                Err(err) => assert_eq!(err, PredictError::AlreadyDisputed.into()),
+                _ => panic!("Expected already disputed error"),
+            }
+        }
+
+        // Test arbitration by a signer other than the arbiter (should fail)
+        {
+            let admin_info = admin.to_account_info(false);
+            let prediction_info = prediction.to_account_info(false);
+            let attacker_info = attacker.to_account_info(true);
+            let disputer_info = disputer.to_account_info(false);
+            let vault_info = vault.to_account_info(false);
+
+            let mut accounts = ArbitrateEqualisation {
+                admin: Account::try_from(&admin_info).unwrap(),
+                prediction: Account::try_from(&prediction_info).unwrap(),
+                authority: Signer::try_from(&attacker_info).unwrap(),
+                disputer: disputer_info,
+                vault: vault_info,
+            };
+            let bumps = ArbitrateEqualisationBumps {
+                admin: admin_bump,
+                prediction: prediction_bump,
+            };
+            set_clock_override(Some(now));
+            let result = arbitrate(
+                Context::new(&program_id, &mut accounts, &[], bumps),
+                0,
+                vec![40000, 60000],
+            );
+            match result {
+                Err(err) => assert_eq!(err, PredictError::UnauthorisedArbiter.into()),
+                _ => panic!("Expected unauthorised arbiter error"),
+            }
+        }
+
+        // Test arbitration without a pending dispute (should fail)
+        {
+            prediction
+                .init_prediction_data(&create_equalised_test_prediction(None))
+                .unwrap();
+            let mut arbiter = TestAccountData::new_authority_account(arbiter());
+            let admin_info = admin.to_account_info(false);
+            let prediction_info = prediction.to_account_info(false);
+            let arbiter_info = arbiter.to_account_info(true);
+            let disputer_info = disputer.to_account_info(false);
+            let vault_info = vault.to_account_info(false);
+
+            let mut accounts = ArbitrateEqualisation {
+                admin: Account::try_from(&admin_info).unwrap(),
+                prediction: Account::try_from(&prediction_info).unwrap(),
+                authority: Signer::try_from(&arbiter_info).unwrap(),
+                disputer: disputer_info,
+                vault: vault_info,
+            };
+            let bumps = ArbitrateEqualisationBumps {
+                admin: admin_bump,
+                prediction: prediction_bump,
+            };
+            set_clock_override(Some(now));
+            let result = arbitrate(
+                Context::new(&program_id, &mut accounts, &[], bumps),
+                0,
+                vec![40000, 60000],
+            );
+            match result {
+                Err(err) => assert_eq!(err, PredictError::NoDispute.into()),
+                _ => panic!("Expected no dispute error"),
+            }
+        }
+    }
+}
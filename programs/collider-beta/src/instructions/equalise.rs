@@ -12,67 +12,256 @@ use crate::state::*;
 use crate::utils::*;
 use crate::EqualiseTokens;
 use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{self, Mint, TokenAccount, TransferChecked};
 
-pub fn equalise(
-    ctx: Context<EqualiseTokens>,
+pub fn equalise<'a, 'b, 'c: 'info, 'info>(
+    ctx: Context<'a, 'b, 'c, 'info, EqualiseTokens<'info>>,
     index: u64,
     truth: Vec<u64>,
-    unix_timestamp: Option<i64>, // CRITICAL: Remove line in production!
 ) -> Result<()> {
+    let admin = &ctx.accounts.admin;
     let prediction = &mut ctx.accounts.prediction;
 
     // Verify prediction has ended
-    // Get current time, supporting local testing override
-    let now = match unix_timestamp {
-        Some(ts) => ts,
-        None => Clock::get()?.unix_timestamp,
-    }; // CRITICAL: Remove block in production!
+    // Get current time
+    let now = current_time()?;
 
-    // CRITICAL: Add line in production!let now = Clock::get()?.unix_timestamp;
-
-    let end_time = parse_iso_timestamp(&prediction.end_time)?;
-    require!(now >= end_time, PredictError::PredictionActive);
-
-    // Validate truth values
     require!(
-        truth.len() == 2 && truth.iter().all(|v| *v <= TRUTH_BASIS),
-        PredictError::InvalidTruthValues
+        now >= prediction.resolves_at(),
+        PredictError::PredictionActive
     );
 
+    // Validate truth values
+    validate_truth(prediction, &truth, admin.truth_basis)?;
+
     // Check if prediction not already equalised
     require!(!prediction.equalised, PredictError::AlreadyEqualised);
+    require!(!prediction.voided, PredictError::PredictionVoided);
+
+    // Fees are paid to the prediction's creator when it charges a creator fee
+    if prediction.fees.creator_bps > 0 {
+        require!(
+            ctx.accounts.creator_anti_token.is_some() && ctx.accounts.creator_pro_token.is_some(),
+            PredictError::MissingCreatorAccount
+        );
+    }
+
+    // Start equalisation, or resume a partial one with the same truth
+    let mut equalisation = match prediction.equalisation.clone() {
+        Some(equalisation) => {
+            require!(
+                equalisation.truth == truth,
+                PredictError::InvalidTruthValues
+            );
+            equalisation
+        }
+        None => Equalisation::new(truth.clone(), now),
+    };
 
-    // Calculate distributions and returns
-    let (anti, pro) = equalise_with_truth(
-        &prediction.deposits,
-        prediction.anti,
-        prediction.pro,
-        &truth,
+    // Bin the deposit records supplied in this batch
+    bin_records(
+        &mut equalisation,
+        ctx.remaining_accounts,
+        index,
+        prediction.scalar.as_ref(),
+        admin.float_basis,
     )?;
 
+    // Carve out the fees and allocate the rest of the pools once every
+    // deposit record has been binned
+    let complete = equalisation.binned == prediction.deposits;
+    let carving = complete && !prediction.fees.carved;
+    if carving {
+        let (anti, pro) = (prediction.anti, prediction.pro);
+        prediction.fees.carve(anti, pro)?;
+    }
+    if complete {
+        let (anti_pool, pro_pool) = prediction.pools()?;
+        allocate(
+            &mut equalisation,
+            anti_pool,
+            pro_pool,
+            admin.float_basis,
+            now,
+        )?;
+        prediction.challenge_end = now
+            .checked_add(admin.challenge_period)
+            .ok_or(error!(PredictError::MathError))?;
+    }
+
     // Update prediction state with equalisation results
-    prediction.equalised = true;
-    prediction.equalisation = Some(Equalisation {
-        anti,
-        pro,
-        truth: truth.clone(),
-        timestamp: now,
-    });
-
-    // Get account info and serialise
-    let prediction_info = prediction.to_account_info();
-    let mut data = prediction_info.try_borrow_mut_data()?;
-    let serialised_prediction = prediction.try_to_vec()?;
-    data[8..8 + serialised_prediction.len()].copy_from_slice(&serialised_prediction);
+    prediction.equalised = complete;
+    prediction.equalisation = Some(equalisation);
+
+    // Serialise, with the resolver topping up any room the deposits left short
+    write_account_growing(
+        &prediction.to_account_info(),
+        &**prediction,
+        &ctx.accounts.authority.to_account_info(),
+        &ctx.accounts.system_program.to_account_info(),
+        &ctx.accounts.rent,
+        prediction.max_space(),
+    )?;
+
+    // Pay the fees out of the pools, signed by the prediction's PDA authority
+    if carving {
+        let index_bytes = index.to_le_bytes();
+        let pool_bump = [ctx.bumps.pool_authority];
+        let pool_seeds: &[&[u8]] = &[b"authority", &index_bytes, &pool_bump];
+        let signer_seeds: &[&[&[u8]]] = &[pool_seeds];
+        let fees = &ctx.accounts.prediction.fees;
+        let accounts = &ctx.accounts;
+
+        transfer_fee(
+            accounts,
+            &accounts.prediction_anti_token,
+            &accounts.protocol_anti_token,
+            &accounts.anti_mint,
+            signer_seeds,
+            fees.anti_protocol,
+        )?;
+        transfer_fee(
+            accounts,
+            &accounts.prediction_pro_token,
+            &accounts.protocol_pro_token,
+            &accounts.pro_mint,
+            signer_seeds,
+            fees.pro_protocol,
+        )?;
+        if let (Some(creator_anti_token), Some(creator_pro_token)) =
+            (&accounts.creator_anti_token, &accounts.creator_pro_token)
+        {
+            transfer_fee(
+                accounts,
+                &accounts.prediction_anti_token,
+                creator_anti_token,
+                &accounts.anti_mint,
+                signer_seeds,
+                fees.anti_creator,
+            )?;
+            transfer_fee(
+                accounts,
+                &accounts.prediction_pro_token,
+                creator_pro_token,
+                &accounts.pro_mint,
+                signer_seeds,
+                fees.pro_creator,
+            )?;
+        }
+
+        emit!(FeeEvent {
+            index,
+            creator: accounts.prediction.creator,
+            anti_protocol: fees.anti_protocol,
+            pro_protocol: fees.pro_protocol,
+            anti_creator: fees.anti_creator,
+            pro_creator: fees.pro_creator,
+            timestamp: now,
+        });
+    }
 
     // Emit equalisation event
-    emit!(EqualisationEvent {
-        index,
-        truth,
-        anti: prediction.anti,
-        pro: prediction.pro,
-        timestamp: now,
-    });
+    let prediction = &ctx.accounts.prediction;
+    if complete {
+        emit!(EqualisationEvent {
+            index,
+            truth,
+            anti: prediction.anti,
+            pro: prediction.pro,
+            timestamp: now,
+        });
+    }
+
+    Ok(())
+}
+
+// Move a fee from a pool into its fee account
+fn transfer_fee<'info>(
+    accounts: &EqualiseTokens<'info>,
+    from: &InterfaceAccount<'info, TokenAccount>,
+    to: &InterfaceAccount<'info, TokenAccount>,
+    mint: &InterfaceAccount<'info, Mint>,
+    signer_seeds: &[&[&[u8]]],
+    amount: u64,
+) -> Result<()> {
+    if amount == 0 {
+        return Ok(());
+    }
+    token_interface::transfer_checked(
+        CpiContext::new_with_signer(
+            accounts.token_program.to_account_info(),
+            TransferChecked {
+                from: from.to_account_info(),
+                mint: mint.to_account_info(),
+                to: to.to_account_info(),
+                authority: accounts.pool_authority.to_account_info(),
+            },
+            signer_seeds,
+        ),
+        amount,
+        mint.decimals,
+    )
+}
+
+// Bin a batch of deposit records into the current equalisation round
+pub fn bin_records<'info>(
+    equalisation: &mut Equalisation,
+    deposit_records: &'info [AccountInfo<'info>],
+    index: u64,
+    scalar: Option<&ScalarRange>,
+    float_basis: u64,
+) -> Result<()> {
+    for deposit_record_info in deposit_records.iter() {
+        let mut deposit_record: Account<'info, DepositRecord> =
+            Account::try_from(deposit_record_info)?;
+        require!(
+            deposit_record.index == index,
+            PredictError::InvalidDepositRecord
+        );
+        if deposit_record.binned && deposit_record.round == equalisation.round {
+            continue;
+        }
+
+        let bin = bin_deposit(
+            &deposit_record.deposit,
+            &equalisation.truth,
+            scalar,
+            float_basis,
+        )?;
+        if let Some(bin_index) = bin {
+            // Stake binned ahead of this record fixes its slice of the bin
+            deposit_record.anti_offset = equalisation.anti_staked[bin_index];
+            deposit_record.pro_offset = equalisation.pro_staked[bin_index];
+            equalisation.anti_staked[bin_index] = equalisation.anti_staked[bin_index]
+                .checked_add(deposit_record.deposit.anti)
+                .ok_or(error!(PredictError::MathError))?;
+            equalisation.pro_staked[bin_index] = equalisation.pro_staked[bin_index]
+                .checked_add(deposit_record.deposit.pro)
+                .ok_or(error!(PredictError::MathError))?;
+        }
+        equalisation.binned += 1;
+
+        // Serialise updated deposit record
+        deposit_record.bin = bin.map(|bin_index| bin_index as u8);
+        deposit_record.binned = true;
+        deposit_record.round = equalisation.round;
+        write_account(deposit_record_info, &*deposit_record)?;
+    }
+
+    Ok(())
+}
+
+// Allocate each pool across the bins holding stake of that token
+pub fn allocate(
+    equalisation: &mut Equalisation,
+    anti_pool: u64,
+    pro_pool: u64,
+    float_basis: u64,
+    now: i64,
+) -> Result<()> {
+    equalisation.anti = allocations(&equalisation.anti_staked, anti_pool, float_basis)?;
+    equalisation.pro = allocations(&equalisation.pro_staked, pro_pool, float_basis)?;
+    equalisation.timestamp = now;
 
     Ok(())
 }
@@ -80,249 +269,193 @@ pub fn equalise(
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::utils::PROGRAM_ID;
+    use crate::test_utils::*;
     use crate::EqualiseTokensBumps;
-    use anchor_lang::{system_program, Discriminator};
-    use anchor_spl::token::{spl_token, Mint, Token};
-    use anchor_spl::token::{spl_token::state::Account as SplTokenAccount, TokenAccount};
-    use solana_sdk::program_option::COption;
-    use solana_sdk::program_pack::Pack;
-    use std::cell::RefCell;
-    use std::str::FromStr;
-
-    // Fixed test IDs - these should be consistent across tests
-    fn program_id() -> Pubkey {
-        Pubkey::from_str(&PROGRAM_ID.to_string()).unwrap()
+    use anchor_lang::Discriminator;
+    use anchor_spl::token_interface::TokenInterface;
+
+    // Resolver assigned to the test predictions
+    fn resolver() -> Pubkey {
+        Pubkey::new_from_array([7; 32])
     }
 
-    struct TestAccountData {
-        key: Pubkey,
-        lamports: u64,
-        data: Vec<u8>,
-        owner: Pubkey,
-        executable: bool,
-        rent_epoch: u64,
+    fn new_admin_account(admin: &AdminAccount, program_id: Pubkey) -> TestAccountData {
+        let (admin_pda, _) = Pubkey::find_program_address(&[b"admin"], &program_id);
+        let mut account = TestAccountData::new_account_with_key_and_owner(admin_pda, program_id);
+        account.init_admin_data(admin).unwrap();
+        account
     }
 
-    impl TestAccountData {
-        fn new_account_with_key_and_owner<T: AccountSerialize + AccountDeserialize + Clone>(
-            key: Pubkey,
-            owner: Pubkey,
-        ) -> Self {
-            Self {
-                key,
-                lamports: 1_000_000,
-                data: vec![0; 8 + PredictionAccount::LEN],
-                owner,
-                executable: true,
-                rent_epoch: 0,
-            }
+    // Reusable method to create a test deposit record
+    fn create_test_deposit_record(authority: Pubkey, index: u64, nonce: u64) -> DepositRecord {
+        DepositRecord {
+            index,
+            bin: None,
+            binned: false,
+            ..test_deposit_record(authority, nonce, 70000, 30000)
         }
+    }
 
-        fn to_account_info<'a>(&'a mut self, is_signer: bool) -> AccountInfo<'a> {
-            AccountInfo::new(
-                &self.key,
-                is_signer,
-                true,
-                &mut self.lamports,
-                &mut self.data,
-                &self.owner,
-                self.executable,
-                self.rent_epoch,
-            )
+    // Reusable method to create a test prediction
+    fn create_test_prediction() -> PredictionAccount {
+        PredictionAccount {
+            resolver: resolver(),
+            anti: 70000,
+            pro: 30000,
+            deposits: 1,
+            ..test_prediction()
         }
+    }
 
-        fn new_authority_account(pubkey: Pubkey) -> Self {
-            Self {
-                key: pubkey,
-                lamports: 1_000_000,
-                data: vec![],
-                owner: system_program::ID,
-                executable: true,
-                rent_epoch: 0,
-            }
+    // Reusable method to create an active test prediction
+    fn create_active_test_prediction() -> PredictionAccount {
+        PredictionAccount {
+            start_time: 1738368000,
+            end_time: 1740787200, // Still active
+            resolver: resolver(),
+            anti: 70000,
+            pro: 30000,
+            deposits: 1,
+            ..test_prediction()
         }
+    }
 
-        fn new_token() -> Self {
-            Self {
-                key: Pubkey::new_unique(),
-                lamports: 1_000_000,
-                data: vec![0; 165],
-                owner: spl_token::ID,
-                executable: false,
-                rent_epoch: 0,
-            }
+    // Reusable method to create an equalised test prediction
+    fn create_equalised_test_prediction() -> PredictionAccount {
+        PredictionAccount {
+            resolver: resolver(),
+            anti: 70000,
+            pro: 30000,
+            deposits: 1,
+            equalised: true,
+            equalisation: Some(Equalisation {
+                anti: vec![70000],
+                pro: vec![30000],
+                anti_staked: vec![70000],
+                pro_staked: vec![30000],
+                binned: 1,
+                truth: vec![60000, 40000],
+                timestamp: 0,
+                round: 0,
+            }),
+            ..test_prediction()
         }
+    }
 
-        fn new_token_program() -> Self {
-            Self {
-                key: spl_token::ID,
-                lamports: 1_000_000,
-                data: vec![],
-                owner: Pubkey::default(),
-                executable: true,
-                rent_epoch: 0,
-            }
-        }
+    // Pools, fee accounts and mints of prediction 0
+    struct TestTokenAccounts {
+        pool_authority: TestAccountData,
+        prediction_anti: TestAccountData,
+        prediction_pro: TestAccountData,
+        protocol_anti: TestAccountData,
+        protocol_pro: TestAccountData,
+        creator_anti: TestAccountData,
+        creator_pro: TestAccountData,
+        anti_mint: TestAccountData,
+        pro_mint: TestAccountData,
+        token_program: TestAccountData,
+        system_program: TestAccountData,
+        rent: TestAccountData,
+    }
 
-        fn into_token_account<'a>(account_info: &'a AccountInfo<'a>) -> Account<'a, TokenAccount> {
-            Account::try_from(account_info).unwrap()
-        }
+    struct TestTokenInfos<'a> {
+        pool_authority: AccountInfo<'a>,
+        prediction_anti: AccountInfo<'a>,
+        prediction_pro: AccountInfo<'a>,
+        protocol_anti: AccountInfo<'a>,
+        protocol_pro: AccountInfo<'a>,
+        creator_anti: AccountInfo<'a>,
+        creator_pro: AccountInfo<'a>,
+        anti_mint: AccountInfo<'a>,
+        pro_mint: AccountInfo<'a>,
+        token_program: AccountInfo<'a>,
+        system_program: AccountInfo<'a>,
+        rent: AccountInfo<'a>,
+    }
 
-        fn init_token_account(&mut self, owner: Pubkey, mint: Pubkey) -> Result<()> {
-            self.data = vec![0; TokenAccount::LEN]; // Ensure correct buffer size
-            let data = self.data.as_mut_slice();
-
-            let close_authority: COption<Pubkey> = COption::None;
-
-            // Initialise a new SPL Token Account manually
-            let token_account = SplTokenAccount {
-                mint,
-                owner,
-                amount: 0,
-                delegate: None.into(),
-                state: spl_token::state::AccountState::Initialized,
-                is_native: None.into(),
-                delegated_amount: 0,
-                close_authority,
+    impl TestTokenAccounts {
+        // Fee accounts are held by the multisig and the fixtures' default creator
+        fn new(program_id: Pubkey) -> Self {
+            let (pool_authority_pda, _) = Pubkey::find_program_address(
+                &[b"authority", 0u64.to_le_bytes().as_ref()],
+                &program_id,
+            );
+            let token = |owner: Pubkey, mint: Pubkey| {
+                let mut account = TestAccountData::new_token(Pubkey::new_unique());
+                account.init_token_account(owner, mint).unwrap();
+                account
             };
-
-            token_account.pack_into_slice(data);
-
-            Ok(())
-        }
-
-        fn new_mint(mint: Pubkey) -> Self {
             Self {
-                key: mint,
-                lamports: 1_000_000,
-                data: vec![0; Mint::LEN],
-                owner: spl_token::ID,
-                executable: false,
-                rent_epoch: 0,
-            }
-        }
-
-        // Reusable method to create a test prediction
-        fn create_test_prediction(authority: Pubkey) -> PredictionAccount {
-            PredictionAccount {
-                index: 0,
-                title: "Test Prediction".to_string(),
-                description: "Test Description".to_string(),
-                start_time: "2025-01-01T00:00:00Z".to_string(),
-                end_time: "2025-01-02T00:00:00Z".to_string(), // Already ended
-                etc: None,
-                anti: 70000,
-                pro: 30000,
-                deposits: vec![Deposit {
-                    address: authority,
-                    anti: 70000,
-                    pro: 30000,
-                    mean: 40000,
-                    stddev: 100000,
-                    withdrawn: false,
-                }],
-                equalised: false,
-                equalisation: None,
+                pool_authority: TestAccountData::new_authority_account(pool_authority_pda),
+                prediction_anti: token(pool_authority_pda, ANTI_MINT_ADDRESS),
+                prediction_pro: token(pool_authority_pda, PRO_MINT_ADDRESS),
+                protocol_anti: token(ANTITOKEN_MULTISIG, ANTI_MINT_ADDRESS),
+                protocol_pro: token(ANTITOKEN_MULTISIG, PRO_MINT_ADDRESS),
+                creator_anti: token(Pubkey::default(), ANTI_MINT_ADDRESS),
+                creator_pro: token(Pubkey::default(), PRO_MINT_ADDRESS),
+                anti_mint: TestAccountData::new_mint(ANTI_MINT_ADDRESS),
+                pro_mint: TestAccountData::new_mint(PRO_MINT_ADDRESS),
+                token_program: TestAccountData::new_token_program(),
+                system_program: TestAccountData::new_system_account(),
+                rent: TestAccountData::new_rent_account(),
             }
         }
 
-        // Reusable method to create an active test prediction
-        fn create_active_test_prediction(authority: Pubkey) -> PredictionAccount {
-            PredictionAccount {
-                index: 0,
-                title: "Test Prediction".to_string(),
-                description: "Test Description".to_string(),
-                start_time: "2025-02-01T00:00:00Z".to_string(),
-                end_time: "2025-03-01T00:00:00Z".to_string(), // Still active
-                etc: None,
-                anti: 70000,
-                pro: 30000,
-                deposits: vec![Deposit {
-                    address: authority,
-                    anti: 70000,
-                    pro: 30000,
-                    mean: 40000,
-                    stddev: 100000,
-                    withdrawn: false,
-                }],
-                equalised: false,
-                equalisation: None,
+        #[allow(clippy::wrong_self_convention)]
+        fn to_account_infos(&mut self) -> TestTokenInfos<'_> {
+            TestTokenInfos {
+                pool_authority: self.pool_authority.to_account_info(false),
+                prediction_anti: self.prediction_anti.to_account_info(false),
+                prediction_pro: self.prediction_pro.to_account_info(false),
+                protocol_anti: self.protocol_anti.to_account_info(false),
+                protocol_pro: self.protocol_pro.to_account_info(false),
+                creator_anti: self.creator_anti.to_account_info(false),
+                creator_pro: self.creator_pro.to_account_info(false),
+                anti_mint: self.anti_mint.to_account_info(false),
+                pro_mint: self.pro_mint.to_account_info(false),
+                token_program: self.token_program.to_account_info(false),
+                system_program: self.system_program.to_account_info(false),
+                rent: self.rent.to_account_info(false),
             }
         }
+    }
 
-        // Reusable method to create an equalised test prediction
-        fn create_equalised_test_prediction(authority: Pubkey) -> PredictionAccount {
-            PredictionAccount {
-                index: 0,
-                title: "Test Prediction".to_string(),
-                description: "Test Description".to_string(),
-                start_time: "2025-01-01T00:00:00Z".to_string(),
-                end_time: "2025-01-02T00:00:00Z".to_string(), // Already ended
-                etc: None,
-                anti: 70000,
-                pro: 30000,
-                deposits: vec![Deposit {
-                    address: authority,
-                    anti: 70000,
-                    pro: 30000,
-                    mean: 40000,
-                    stddev: 100000,
-                    withdrawn: false,
-                }],
-                equalised: true,
-                equalisation: Some(Equalisation {
-                    truth: vec![60000, 40000],
-                    anti: vec![70000],
-                    pro: vec![30000],
-                    timestamp: 0,
-                }),
-            }
+    // Bumps of the PDAs passed to equalise for prediction 0
+    fn bumps(program_id: Pubkey) -> EqualiseTokensBumps {
+        let index = 0u64.to_le_bytes();
+        let bump =
+            |seed: &[u8]| Pubkey::find_program_address(&[seed, index.as_ref()], &program_id).1;
+        EqualiseTokensBumps {
+            admin: Pubkey::find_program_address(&[b"admin"], &program_id).1,
+            prediction: bump(b"prediction"),
+            pool_authority: bump(b"authority"),
+            prediction_anti_token: bump(b"anti_token"),
+            prediction_pro_token: bump(b"pro_token"),
         }
     }
 
     #[test]
     fn test_equalise_success() {
         let program_id = program_id();
-
-        // Test double for Clock
-        thread_local! {
-            static MOCK_UNIX_TIMESTAMP: RefCell<i64> = RefCell::new(1736899200); // 2025-01-15T00:00:00Z
-        }
+        let mut admin = new_admin_account(&test_admin(), program_id);
 
         // Create test accounts
-        let mut prediction = TestAccountData::new_account_with_key_and_owner::<PredictionAccount>(
-            Pubkey::new_unique(),
-            program_id,
-        );
-        let mut authority = TestAccountData::new_authority_account(Pubkey::new_unique());
-
-        // Initialise token accounts
-        let mint_key = Pubkey::new_unique();
-        let user_authority_key = Pubkey::new_unique();
-
-        let mut user_anti = TestAccountData::new_token();
-        let mut user_pro = TestAccountData::new_token();
-        let mut prediction_anti = TestAccountData::new_token();
-        let mut prediction_pro = TestAccountData::new_token();
-
-        user_anti
-            .init_token_account(user_authority_key, mint_key)
-            .unwrap();
-        user_pro
-            .init_token_account(user_authority_key, mint_key)
-            .unwrap();
-        prediction_anti
-            .init_token_account(Pubkey::new_unique(), mint_key)
+        let mut prediction =
+            TestAccountData::new_account_with_key_and_owner(Pubkey::new_unique(), program_id);
+        let mut authority = TestAccountData::new_authority_account(resolver());
+
+        // Initialise pool, fee and mint accounts
+        let mut tokens = TestTokenAccounts::new(program_id);
+
+        // Create deposit record
+        let mut deposit_record =
+            TestAccountData::new_account_with_key_and_owner(Pubkey::new_unique(), program_id);
+        deposit_record
+            .init_deposit_record_data(&create_test_deposit_record(authority.key, 0, 0))
             .unwrap();
-        prediction_pro
-            .init_token_account(Pubkey::new_unique(), mint_key)
-            .unwrap();
-
-        let mut token_program = TestAccountData::new_token_program();
 
         // Create prediction with deposits
-        let prediction_data = TestAccountData::create_test_prediction(authority.key);
+        let prediction_data = create_test_prediction();
 
         // Write discriminator
         prediction.data[..8].copy_from_slice(&PredictionAccount::discriminator());
@@ -332,35 +465,50 @@ mod tests {
         prediction.data[8..8 + serialised_prediction.len()].copy_from_slice(&serialised_prediction);
 
         // Get account infos
+        let admin_info = admin.to_account_info(false);
         let prediction_info = prediction.to_account_info(false);
         let authority_info = authority.to_account_info(true);
-        let user_anti_info = user_anti.to_account_info(false);
-        let user_pro_info = user_pro.to_account_info(false);
-        let prediction_anti_info = prediction_anti.to_account_info(false);
-        let prediction_pro_info = prediction_pro.to_account_info(false);
-        let token_program_info = token_program.to_account_info(false);
+        let token_infos = tokens.to_account_infos();
+        let deposit_record_info = deposit_record.to_account_info(false);
+        let remaining_accounts = vec![deposit_record_info.clone()];
 
         let mut accounts = EqualiseTokens {
+            admin: Account::try_from(&admin_info).unwrap(),
             prediction: Account::try_from(&prediction_info).unwrap(),
             authority: Signer::try_from(&authority_info).unwrap(),
-            user_anti_token: TestAccountData::into_token_account(&user_anti_info),
-            user_pro_token: TestAccountData::into_token_account(&user_pro_info),
-            prediction_anti_token: TestAccountData::into_token_account(&prediction_anti_info),
-            prediction_pro_token: TestAccountData::into_token_account(&prediction_pro_info),
-            token_program: Program::<Token>::try_from(&token_program_info).unwrap(),
+            pool_authority: token_infos.pool_authority.clone(),
+            prediction_anti_token: TestAccountData::into_token_account(
+                &token_infos.prediction_anti,
+            ),
+            prediction_pro_token: TestAccountData::into_token_account(&token_infos.prediction_pro),
+            protocol_anti_token: TestAccountData::into_token_account(&token_infos.protocol_anti),
+            protocol_pro_token: TestAccountData::into_token_account(&token_infos.protocol_pro),
+            // No creator fee is charged, so no creator accounts are needed
+            creator_anti_token: None,
+            creator_pro_token: None,
+            anti_mint: InterfaceAccount::<Mint>::try_from(&token_infos.anti_mint).unwrap(),
+            pro_mint: InterfaceAccount::<Mint>::try_from(&token_infos.pro_mint).unwrap(),
+            token_program: Interface::<TokenInterface>::try_from(&token_infos.token_program)
+                .unwrap(),
+            system_program: Program::try_from(&token_infos.system_program).unwrap(),
+            rent: Sysvar::from_account_info(&token_infos.rent).unwrap(),
         };
 
-        let context = Context::new(&program_id, &mut accounts, &[], EqualiseTokensBumps {});
+        let context = Context::new(
+            &program_id,
+            &mut accounts,
+            &remaining_accounts,
+            bumps(program_id),
+        );
 
         // Test equalisation
         let truth = vec![6000, 4000]; // 60-40 split
-        let result = equalise(context, 0, truth.clone(), Some(1736899200));
+        set_clock_override(Some(1736899200));
+        let result = equalise(context, 0, truth.clone());
 
         // If the test fails, print the error
-        if result.is_err() {
-            println!("Error: {:?}", result.unwrap_err());
-        } else {
-            assert!(result.is_ok());
+        if let Err(error) = &result {
+            println!("Error: {:?}", error);
         }
 
         // Verify prediction state after equalisation
@@ -374,52 +522,258 @@ mod tests {
 
         let results = prediction_account.equalisation.unwrap();
         assert_eq!(results.truth, truth);
+        assert_eq!(results.binned, 1);
         assert!(!results.anti.is_empty());
         assert!(!results.pro.is_empty());
+
+        // Verify deposit record was binned
+        let deposit_record_account: DepositRecord = DepositRecord::try_deserialize(
+            &mut deposit_record_info.try_borrow_data().unwrap().as_ref(),
+        )
+        .unwrap();
+        assert!(deposit_record_account.binned);
+        assert!(deposit_record_account.bin.is_some());
     }
 
     #[test]
-    fn test_equalise_validation_failures() {
+    fn test_equalise_carves_fees() {
         let program_id = program_id();
+        let mut admin = new_admin_account(&test_admin(), program_id);
+        let mut prediction =
+            TestAccountData::new_account_with_key_and_owner(Pubkey::new_unique(), program_id);
+        let mut authority = TestAccountData::new_authority_account(resolver());
+        let mut tokens = TestTokenAccounts::new(program_id);
+        let mut deposit_record =
+            TestAccountData::new_account_with_key_and_owner(Pubkey::new_unique(), program_id);
+        deposit_record
+            .init_deposit_record_data(&create_test_deposit_record(authority.key, 0, 0))
+            .unwrap();
 
-        // Create mints
-        let anti_mint = TestAccountData::new_mint(ANTI_MINT_ADDRESS);
-        let pro_mint = TestAccountData::new_mint(PRO_MINT_ADDRESS);
+        // Prediction charging the protocol fee and a 2% creator fee
+        let mut prediction_data = create_test_prediction();
+        prediction_data.fees = Fees::new(PROTOCOL_FEE_BPS, 200);
+        prediction.data[..8].copy_from_slice(&PredictionAccount::discriminator());
+        let serialised_prediction = prediction_data.try_to_vec().unwrap();
+        prediction.data[8..8 + serialised_prediction.len()].copy_from_slice(&serialised_prediction);
 
-        // Test double for Clock
-        thread_local! {
-            static MOCK_UNIX_TIMESTAMP: RefCell<i64> = RefCell::new(1736899200); // 2025-01-15T00:00:00Z
-        }
+        let admin_info = admin.to_account_info(false);
+        let prediction_info = prediction.to_account_info(false);
+        let authority_info = authority.to_account_info(true);
+        let token_infos = tokens.to_account_infos();
+        let deposit_record_info = deposit_record.to_account_info(false);
+        let remaining_accounts = vec![deposit_record_info.clone()];
 
-        // Create test accounts
-        let mut prediction = TestAccountData::new_account_with_key_and_owner::<PredictionAccount>(
-            Pubkey::new_unique(),
-            program_id,
+        let mut accounts = EqualiseTokens {
+            admin: Account::try_from(&admin_info).unwrap(),
+            prediction: Account::try_from(&prediction_info).unwrap(),
+            authority: Signer::try_from(&authority_info).unwrap(),
+            pool_authority: token_infos.pool_authority.clone(),
+            prediction_anti_token: TestAccountData::into_token_account(
+                &token_infos.prediction_anti,
+            ),
+            prediction_pro_token: TestAccountData::into_token_account(&token_infos.prediction_pro),
+            protocol_anti_token: TestAccountData::into_token_account(&token_infos.protocol_anti),
+            protocol_pro_token: TestAccountData::into_token_account(&token_infos.protocol_pro),
+            creator_anti_token: Some(TestAccountData::into_token_account(
+                &token_infos.creator_anti,
+            )),
+            creator_pro_token: Some(TestAccountData::into_token_account(
+                &token_infos.creator_pro,
+            )),
+            anti_mint: InterfaceAccount::<Mint>::try_from(&token_infos.anti_mint).unwrap(),
+            pro_mint: InterfaceAccount::<Mint>::try_from(&token_infos.pro_mint).unwrap(),
+            token_program: Interface::<TokenInterface>::try_from(&token_infos.token_program)
+                .unwrap(),
+            system_program: Program::try_from(&token_infos.system_program).unwrap(),
+            rent: Sysvar::from_account_info(&token_infos.rent).unwrap(),
+        };
+
+        // Creator fee accounts must be supplied when the creator charges a fee
+        let creator_pro_token = accounts.creator_pro_token.take();
+        set_clock_override(Some(1736899200));
+        let ctx = Context::new(
+            &program_id,
+            &mut accounts,
+            &remaining_accounts,
+            bumps(program_id),
         );
-        let mut authority = TestAccountData::new_authority_account(Pubkey::new_unique());
+        let result = equalise(ctx, 0, vec![6000, 4000]);
+        assert_eq!(
+            result.unwrap_err(),
+            Error::from(PredictError::MissingCreatorAccount)
+        );
+        accounts.creator_pro_token = creator_pro_token;
 
-        // Initialise token accounts
-        let user = Pubkey::new_unique();
-        let mut user_anti = TestAccountData::new_token();
-        let mut user_pro = TestAccountData::new_token();
-        let mut prediction_anti = TestAccountData::new_token();
-        let mut prediction_pro = TestAccountData::new_token();
-
-        user_anti.init_token_account(user, anti_mint.key).unwrap();
-        user_pro.init_token_account(user, pro_mint.key).unwrap();
-        prediction_anti
-            .init_token_account(Pubkey::new_unique(), anti_mint.key)
+        let ctx = Context::new(
+            &program_id,
+            &mut accounts,
+            &remaining_accounts,
+            bumps(program_id),
+        );
+        equalise(ctx, 0, vec![6000, 4000]).unwrap();
+
+        // Fees come out of the pools before allocation
+        let prediction_account: PredictionAccount = PredictionAccount::try_deserialize(
+            &mut prediction_info.try_borrow_data().unwrap().as_ref(),
+        )
+        .unwrap();
+        let fees = &prediction_account.fees;
+        assert!(fees.carved);
+        assert_eq!((fees.anti_protocol, fees.pro_protocol), (700, 300));
+        assert_eq!((fees.anti_creator, fees.pro_creator), (1400, 600));
+        assert_eq!(prediction_account.pools().unwrap(), (67900, 29100));
+
+        let equalisation = prediction_account.equalisation.unwrap();
+        assert_eq!(equalisation.anti.iter().sum::<u64>(), 67900);
+        assert_eq!(equalisation.pro.iter().sum::<u64>(), 29100);
+    }
+
+    #[test]
+    fn test_equalise_in_batches() {
+        let program_id = program_id();
+        let mut admin = new_admin_account(&test_admin(), program_id);
+
+        // Create test accounts
+        let mut prediction =
+            TestAccountData::new_account_with_key_and_owner(Pubkey::new_unique(), program_id);
+        let mut authority = TestAccountData::new_authority_account(resolver());
+        let mut tokens = TestTokenAccounts::new(program_id);
+
+        // Create two deposit records
+        let mut first_record =
+            TestAccountData::new_account_with_key_and_owner(Pubkey::new_unique(), program_id);
+        first_record
+            .init_deposit_record_data(&create_test_deposit_record(authority.key, 0, 0))
             .unwrap();
-        prediction_pro
-            .init_token_account(Pubkey::new_unique(), pro_mint.key)
+        let mut second_record =
+            TestAccountData::new_account_with_key_and_owner(Pubkey::new_unique(), program_id);
+        second_record
+            .init_deposit_record_data(&create_test_deposit_record(authority.key, 0, 1))
             .unwrap();
 
-        let mut token_program = TestAccountData::new_token_program();
+        // Prediction holding both deposits
+        let mut prediction_data = create_test_prediction();
+        prediction_data.anti = 140000;
+        prediction_data.pro = 60000;
+        prediction_data.deposits = 2;
+        prediction.data[..8].copy_from_slice(&PredictionAccount::discriminator());
+        let serialised_prediction = prediction_data.try_to_vec().unwrap();
+        prediction.data[8..8 + serialised_prediction.len()].copy_from_slice(&serialised_prediction);
+
+        // Get account infos
+        let admin_info = admin.to_account_info(false);
+        let prediction_info = prediction.to_account_info(false);
+        let authority_info = authority.to_account_info(true);
+        let token_infos = tokens.to_account_infos();
+        let first_record_info = first_record.to_account_info(false);
+        let second_record_info = second_record.to_account_info(false);
+
+        let truth = vec![6000, 4000];
+        let batches = [
+            vec![first_record_info.clone()],
+            vec![second_record_info.clone()],
+        ];
+        for (batch, remaining_accounts) in batches.iter().enumerate() {
+            let mut accounts = EqualiseTokens {
+                admin: Account::try_from(&admin_info).unwrap(),
+                prediction: Account::try_from(&prediction_info).unwrap(),
+                authority: Signer::try_from(&authority_info).unwrap(),
+                pool_authority: token_infos.pool_authority.clone(),
+                prediction_anti_token: TestAccountData::into_token_account(
+                    &token_infos.prediction_anti,
+                ),
+                prediction_pro_token: TestAccountData::into_token_account(
+                    &token_infos.prediction_pro,
+                ),
+                protocol_anti_token: TestAccountData::into_token_account(
+                    &token_infos.protocol_anti,
+                ),
+                protocol_pro_token: TestAccountData::into_token_account(&token_infos.protocol_pro),
+                creator_anti_token: Some(TestAccountData::into_token_account(
+                    &token_infos.creator_anti,
+                )),
+                creator_pro_token: Some(TestAccountData::into_token_account(
+                    &token_infos.creator_pro,
+                )),
+                anti_mint: InterfaceAccount::<Mint>::try_from(&token_infos.anti_mint).unwrap(),
+                pro_mint: InterfaceAccount::<Mint>::try_from(&token_infos.pro_mint).unwrap(),
+                token_program: Interface::<TokenInterface>::try_from(&token_infos.token_program)
+                    .unwrap(),
+                system_program: Program::try_from(&token_infos.system_program).unwrap(),
+                rent: Sysvar::from_account_info(&token_infos.rent).unwrap(),
+            };
+            let ctx = Context::new(
+                &program_id,
+                &mut accounts,
+                remaining_accounts,
+                bumps(program_id),
+            );
+            set_clock_override(Some(1736899200));
+            equalise(ctx, 0, truth.clone()).unwrap();
+
+            let prediction_account: PredictionAccount = PredictionAccount::try_deserialize(
+                &mut prediction_info.try_borrow_data().unwrap().as_ref(),
+            )
+            .unwrap();
+            let equalisation = prediction_account.equalisation.unwrap();
+            assert_eq!(equalisation.binned, batch as u64 + 1);
+
+            // Only the final batch completes equalisation
+            assert_eq!(prediction_account.equalised, batch == 1);
+        }
+
+        // Both deposits share a bin and split its allocation equally
+        let prediction_account: PredictionAccount = PredictionAccount::try_deserialize(
+            &mut prediction_info.try_borrow_data().unwrap().as_ref(),
+        )
+        .unwrap();
+        let equalisation = prediction_account.equalisation.unwrap();
+        let first: DepositRecord = DepositRecord::try_deserialize(
+            &mut first_record_info.try_borrow_data().unwrap().as_ref(),
+        )
+        .unwrap();
+        let second: DepositRecord = DepositRecord::try_deserialize(
+            &mut second_record_info.try_borrow_data().unwrap().as_ref(),
+        )
+        .unwrap();
+        assert_eq!(
+            payout(&equalisation, &first).unwrap(),
+            payout(&equalisation, &second).unwrap()
+        );
+
+        // Matches the single-pass equalisation
+        let (anti, pro) = equalise_with_truth(
+            &[first.deposit.clone(), second.deposit.clone()],
+            140000,
+            60000,
+            &truth,
+            None,
+            FLOAT_BASIS,
+        )
+        .unwrap();
+        assert_eq!(payout(&equalisation, &first).unwrap(), (anti[0], pro[0]));
+        assert_eq!(payout(&equalisation, &second).unwrap(), (anti[1], pro[1]));
+    }
+
+    #[test]
+    fn test_equalise_validation_failures() {
+        let program_id = program_id();
+        let mut admin = new_admin_account(&test_admin(), program_id);
+
+        // Create test accounts
+        let mut prediction =
+            TestAccountData::new_account_with_key_and_owner(Pubkey::new_unique(), program_id);
+        let mut authority = TestAccountData::new_authority_account(resolver());
+
+        // Initialise pool, fee and mint accounts
+        let user = Pubkey::new_unique();
+
+        let mut tokens = TestTokenAccounts::new(program_id);
 
         // Test active prediction (should fail)
         {
             // Create prediction with deposits
-            let prediction_data = TestAccountData::create_active_test_prediction(authority.key);
+            let prediction_data = create_active_test_prediction();
 
             // Write discriminator
             prediction.data[..8].copy_from_slice(&PredictionAccount::discriminator());
@@ -430,27 +784,44 @@ mod tests {
                 .copy_from_slice(&serialised_prediction);
 
             // Get account infos
+            let admin_info = admin.to_account_info(false);
             let prediction_info = prediction.to_account_info(false);
             let authority_info = authority.to_account_info(true);
-            let user_anti_info = user_anti.to_account_info(false);
-            let user_pro_info = user_pro.to_account_info(false);
-            let prediction_anti_info = prediction_anti.to_account_info(false);
-            let prediction_pro_info = prediction_pro.to_account_info(false);
-            let token_program_info = token_program.to_account_info(false);
+            let token_infos = tokens.to_account_infos();
 
             let mut accounts = EqualiseTokens {
+                admin: Account::try_from(&admin_info).unwrap(),
                 prediction: Account::try_from(&prediction_info).unwrap(),
                 authority: Signer::try_from(&authority_info).unwrap(),
-                user_anti_token: TestAccountData::into_token_account(&user_anti_info),
-                user_pro_token: TestAccountData::into_token_account(&user_pro_info),
-                prediction_anti_token: TestAccountData::into_token_account(&prediction_anti_info),
-                prediction_pro_token: TestAccountData::into_token_account(&prediction_pro_info),
-                token_program: Program::<Token>::try_from(&token_program_info).unwrap(),
+                pool_authority: token_infos.pool_authority.clone(),
+                prediction_anti_token: TestAccountData::into_token_account(
+                    &token_infos.prediction_anti,
+                ),
+                prediction_pro_token: TestAccountData::into_token_account(
+                    &token_infos.prediction_pro,
+                ),
+                protocol_anti_token: TestAccountData::into_token_account(
+                    &token_infos.protocol_anti,
+                ),
+                protocol_pro_token: TestAccountData::into_token_account(&token_infos.protocol_pro),
+                creator_anti_token: Some(TestAccountData::into_token_account(
+                    &token_infos.creator_anti,
+                )),
+                creator_pro_token: Some(TestAccountData::into_token_account(
+                    &token_infos.creator_pro,
+                )),
+                anti_mint: InterfaceAccount::<Mint>::try_from(&token_infos.anti_mint).unwrap(),
+                pro_mint: InterfaceAccount::<Mint>::try_from(&token_infos.pro_mint).unwrap(),
+                token_program: Interface::<TokenInterface>::try_from(&token_infos.token_program)
+                    .unwrap(),
+                system_program: Program::try_from(&token_infos.system_program).unwrap(),
+                rent: Sysvar::from_account_info(&token_infos.rent).unwrap(),
             };
 
-            let ctx = Context::new(&program_id, &mut accounts, &[], EqualiseTokensBumps {});
+            let ctx = Context::new(&program_id, &mut accounts, &[], bumps(program_id));
             let truth = vec![6000, 4000];
-            let result = equalise(ctx, 0, truth, Some(1736899200));
+            set_clock_override(Some(1736899200));
+            let result = equalise(ctx, 0, truth);
             match result {
                 Err(err) => assert_eq!(err, PredictError::PredictionActive.into()),
                 _ => panic!("Expected prediction active error"),
@@ -460,7 +831,7 @@ mod tests {
         // Test invalid truth values
         {
             // Create prediction with deposits
-            let prediction_data = TestAccountData::create_test_prediction(authority.key);
+            let prediction_data = create_test_prediction();
 
             // Write discriminator
             prediction.data[..8].copy_from_slice(&PredictionAccount::discriminator());
@@ -471,27 +842,112 @@ mod tests {
                 .copy_from_slice(&serialised_prediction);
 
             // Get account infos
+            let admin_info = admin.to_account_info(false);
             let prediction_info = prediction.to_account_info(false);
             let authority_info = authority.to_account_info(true);
-            let user_anti_info = user_anti.to_account_info(false);
-            let user_pro_info = user_pro.to_account_info(false);
-            let prediction_anti_info = prediction_anti.to_account_info(false);
-            let prediction_pro_info = prediction_pro.to_account_info(false);
-            let token_program_info = token_program.to_account_info(false);
+            let token_infos = tokens.to_account_infos();
 
             let mut accounts = EqualiseTokens {
+                admin: Account::try_from(&admin_info).unwrap(),
                 prediction: Account::try_from(&prediction_info).unwrap(),
                 authority: Signer::try_from(&authority_info).unwrap(),
-                user_anti_token: TestAccountData::into_token_account(&user_anti_info),
-                user_pro_token: TestAccountData::into_token_account(&user_pro_info),
-                prediction_anti_token: TestAccountData::into_token_account(&prediction_anti_info),
-                prediction_pro_token: TestAccountData::into_token_account(&prediction_pro_info),
-                token_program: Program::<Token>::try_from(&token_program_info).unwrap(),
+                pool_authority: token_infos.pool_authority.clone(),
+                prediction_anti_token: TestAccountData::into_token_account(
+                    &token_infos.prediction_anti,
+                ),
+                prediction_pro_token: TestAccountData::into_token_account(
+                    &token_infos.prediction_pro,
+                ),
+                protocol_anti_token: TestAccountData::into_token_account(
+                    &token_infos.protocol_anti,
+                ),
+                protocol_pro_token: TestAccountData::into_token_account(&token_infos.protocol_pro),
+                creator_anti_token: Some(TestAccountData::into_token_account(
+                    &token_infos.creator_anti,
+                )),
+                creator_pro_token: Some(TestAccountData::into_token_account(
+                    &token_infos.creator_pro,
+                )),
+                anti_mint: InterfaceAccount::<Mint>::try_from(&token_infos.anti_mint).unwrap(),
+                pro_mint: InterfaceAccount::<Mint>::try_from(&token_infos.pro_mint).unwrap(),
+                token_program: Interface::<TokenInterface>::try_from(&token_infos.token_program)
+                    .unwrap(),
+                system_program: Program::try_from(&token_infos.system_program).unwrap(),
+                rent: Sysvar::from_account_info(&token_infos.rent).unwrap(),
             };
 
-            let ctx = Context::new(&program_id, &mut accounts, &[], EqualiseTokensBumps {});
+            let ctx = Context::new(&program_id, &mut accounts, &[], bumps(program_id));
             let invalid_truth = vec![50_000_000, 5_000_000_000];
-            let result = equalise(ctx, 0, invalid_truth, Some(1736899200));
+            set_clock_override(Some(1736899200));
+            let result = equalise(ctx, 0, invalid_truth);
+            match result {
+                Err(err) => assert_eq!(err, PredictError::InvalidTruthValues.into()),
+                _ => panic!("Expected invalid truth values error"),
+            }
+        }
+
+        // Test truth basis is read from the admin account
+        {
+            // Create prediction with deposits
+            let prediction_data = create_test_prediction();
+
+            // Write discriminator
+            prediction.data[..8].copy_from_slice(&PredictionAccount::discriminator());
+
+            // Serialise initial prediction data
+            let serialised_prediction = prediction_data.try_to_vec().unwrap();
+            prediction.data[8..8 + serialised_prediction.len()]
+                .copy_from_slice(&serialised_prediction);
+
+            // Admin with a lowered truth basis
+            let mut lowered_admin = new_admin_account(
+                &AdminAccount {
+                    version: ADMIN_VERSION,
+                    truth_basis: 5000,
+                    ..test_admin()
+                },
+                program_id,
+            );
+
+            // Get account infos
+            let admin_info = lowered_admin.to_account_info(false);
+            let prediction_info = prediction.to_account_info(false);
+            let authority_info = authority.to_account_info(true);
+            let token_infos = tokens.to_account_infos();
+
+            let mut accounts = EqualiseTokens {
+                admin: Account::try_from(&admin_info).unwrap(),
+                prediction: Account::try_from(&prediction_info).unwrap(),
+                authority: Signer::try_from(&authority_info).unwrap(),
+                pool_authority: token_infos.pool_authority.clone(),
+                prediction_anti_token: TestAccountData::into_token_account(
+                    &token_infos.prediction_anti,
+                ),
+                prediction_pro_token: TestAccountData::into_token_account(
+                    &token_infos.prediction_pro,
+                ),
+                protocol_anti_token: TestAccountData::into_token_account(
+                    &token_infos.protocol_anti,
+                ),
+                protocol_pro_token: TestAccountData::into_token_account(&token_infos.protocol_pro),
+                creator_anti_token: Some(TestAccountData::into_token_account(
+                    &token_infos.creator_anti,
+                )),
+                creator_pro_token: Some(TestAccountData::into_token_account(
+                    &token_infos.creator_pro,
+                )),
+                anti_mint: InterfaceAccount::<Mint>::try_from(&token_infos.anti_mint).unwrap(),
+                pro_mint: InterfaceAccount::<Mint>::try_from(&token_infos.pro_mint).unwrap(),
+                token_program: Interface::<TokenInterface>::try_from(&token_infos.token_program)
+                    .unwrap(),
+                system_program: Program::try_from(&token_infos.system_program).unwrap(),
+                rent: Sysvar::from_account_info(&token_infos.rent).unwrap(),
+            };
+
+            let ctx = Context::new(&program_id, &mut accounts, &[], bumps(program_id));
+            let truth = vec![6000, 4000]; // Within TRUTH_BASIS, above governed basis
+            set_clock_override(Some(1736899200));
+            let result = equalise(ctx, 0, truth);
             match result {
                 Err(err) => assert_eq!(err, PredictError::InvalidTruthValues.into()),
                 _ => panic!("Expected invalid truth values error"),
@@ -501,7 +957,7 @@ mod tests {
         // Test already equalised prediction
         {
             // Create prediction with deposits
-            let prediction_data = TestAccountData::create_equalised_test_prediction(authority.key);
+            let prediction_data = create_equalised_test_prediction();
 
             // Write discriminator
             prediction.data[..8].copy_from_slice(&PredictionAccount::discriminator());
@@ -512,31 +968,239 @@ mod tests {
                 .copy_from_slice(&serialised_prediction);
 
             // Get account infos
+            let admin_info = admin.to_account_info(false);
             let prediction_info = prediction.to_account_info(false);
             let authority_info = authority.to_account_info(true);
-            let user_anti_info = user_anti.to_account_info(false);
-            let user_pro_info = user_pro.to_account_info(false);
-            let prediction_anti_info = prediction_anti.to_account_info(false);
-            let prediction_pro_info = prediction_pro.to_account_info(false);
-            let token_program_info = token_program.to_account_info(false);
+            let token_infos = tokens.to_account_infos();
 
             let mut accounts = EqualiseTokens {
+                admin: Account::try_from(&admin_info).unwrap(),
                 prediction: Account::try_from(&prediction_info).unwrap(),
                 authority: Signer::try_from(&authority_info).unwrap(),
-                user_anti_token: TestAccountData::into_token_account(&user_anti_info),
-                user_pro_token: TestAccountData::into_token_account(&user_pro_info),
-                prediction_anti_token: TestAccountData::into_token_account(&prediction_anti_info),
-                prediction_pro_token: TestAccountData::into_token_account(&prediction_pro_info),
-                token_program: Program::<Token>::try_from(&token_program_info).unwrap(),
+                pool_authority: token_infos.pool_authority.clone(),
+                prediction_anti_token: TestAccountData::into_token_account(
+                    &token_infos.prediction_anti,
+                ),
+                prediction_pro_token: TestAccountData::into_token_account(
+                    &token_infos.prediction_pro,
+                ),
+                protocol_anti_token: TestAccountData::into_token_account(
+                    &token_infos.protocol_anti,
+                ),
+                protocol_pro_token: TestAccountData::into_token_account(&token_infos.protocol_pro),
+                creator_anti_token: Some(TestAccountData::into_token_account(
+                    &token_infos.creator_anti,
+                )),
+                creator_pro_token: Some(TestAccountData::into_token_account(
+                    &token_infos.creator_pro,
+                )),
+                anti_mint: InterfaceAccount::<Mint>::try_from(&token_infos.anti_mint).unwrap(),
+                pro_mint: InterfaceAccount::<Mint>::try_from(&token_infos.pro_mint).unwrap(),
+                token_program: Interface::<TokenInterface>::try_from(&token_infos.token_program)
+                    .unwrap(),
+                system_program: Program::try_from(&token_infos.system_program).unwrap(),
+                rent: Sysvar::from_account_info(&token_infos.rent).unwrap(),
             };
 
-            let ctx = Context::new(&program_id, &mut accounts, &[], EqualiseTokensBumps {});
+            let ctx = Context::new(&program_id, &mut accounts, &[], bumps(program_id));
             let truth = vec![6000, 4000];
-            let result = equalise(ctx, 0, truth, Some(1736899200));
+            set_clock_override(Some(1736899200));
+            let result = equalise(ctx, 0, truth);
             match result {
                 Err(err) => assert_eq!(err, PredictError::AlreadyEqualised.into()),
                 _ => panic!("Expected already equalised error"),
             }
         }
+
+        // Test deposit record from another prediction
+        {
+            // Create prediction with deposits
+            let prediction_data = create_test_prediction();
+
+            // Write discriminator
+            prediction.data[..8].copy_from_slice(&PredictionAccount::discriminator());
+
+            // Serialise initial prediction data
+            let serialised_prediction = prediction_data.try_to_vec().unwrap();
+            prediction.data[8..8 + serialised_prediction.len()]
+                .copy_from_slice(&serialised_prediction);
+
+            // Deposit record belonging to prediction 1
+            let mut foreign_record =
+                TestAccountData::new_account_with_key_and_owner(Pubkey::new_unique(), program_id);
+            foreign_record
+                .init_deposit_record_data(&create_test_deposit_record(user, 1, 0))
+                .unwrap();
+
+            // Get account infos
+            let admin_info = admin.to_account_info(false);
+            let prediction_info = prediction.to_account_info(false);
+            let authority_info = authority.to_account_info(true);
+            let token_infos = tokens.to_account_infos();
+            let remaining_accounts = vec![foreign_record.to_account_info(false)];
+
+            let mut accounts = EqualiseTokens {
+                admin: Account::try_from(&admin_info).unwrap(),
+                prediction: Account::try_from(&prediction_info).unwrap(),
+                authority: Signer::try_from(&authority_info).unwrap(),
+                pool_authority: token_infos.pool_authority.clone(),
+                prediction_anti_token: TestAccountData::into_token_account(
+                    &token_infos.prediction_anti,
+                ),
+                prediction_pro_token: TestAccountData::into_token_account(
+                    &token_infos.prediction_pro,
+                ),
+                protocol_anti_token: TestAccountData::into_token_account(
+                    &token_infos.protocol_anti,
+                ),
+                protocol_pro_token: TestAccountData::into_token_account(&token_infos.protocol_pro),
+                creator_anti_token: Some(TestAccountData::into_token_account(
+                    &token_infos.creator_anti,
+                )),
+                creator_pro_token: Some(TestAccountData::into_token_account(
+                    &token_infos.creator_pro,
+                )),
+                anti_mint: InterfaceAccount::<Mint>::try_from(&token_infos.anti_mint).unwrap(),
+                pro_mint: InterfaceAccount::<Mint>::try_from(&token_infos.pro_mint).unwrap(),
+                token_program: Interface::<TokenInterface>::try_from(&token_infos.token_program)
+                    .unwrap(),
+                system_program: Program::try_from(&token_infos.system_program).unwrap(),
+                rent: Sysvar::from_account_info(&token_infos.rent).unwrap(),
+            };
+
+            let ctx = Context::new(
+                &program_id,
+                &mut accounts,
+                &remaining_accounts,
+                bumps(program_id),
+            );
+            let truth = vec![6000, 4000];
+            set_clock_override(Some(1736899200));
+            let result = equalise(ctx, 0, truth);
+            match result {
+                Err(err) => assert_eq!(err, PredictError::InvalidDepositRecord.into()),
+                _ => panic!("Expected invalid deposit record error"),
+            }
+        }
+    }
+
+    #[test]
+    fn test_equalise_constraints() {
+        let program_id = program_id();
+        let seeds = |prefix: &[u8]| {
+            Pubkey::find_program_address(&[prefix, 0u64.to_le_bytes().as_ref()], &program_id).0
+        };
+        let pool_authority_pda = seeds(b"authority");
+        let mut admin = new_admin_account(&test_admin(), program_id);
+        let mut prediction =
+            TestAccountData::new_account_with_key_and_owner(seeds(b"prediction"), program_id);
+        prediction.init_data(&create_test_prediction(), PredictionAccount::LEN);
+        let token_at = |key: Pubkey, owner: Pubkey, mint: Pubkey| {
+            let mut account = TestAccountData::new_token(key);
+            account.init_token_account(owner, mint).unwrap();
+            account
+        };
+        let creator = create_test_prediction().creator;
+        let mut authority = TestAccountData::new_authority_account(resolver());
+        let mut attacker = TestAccountData::new_authority_account(Pubkey::new_unique());
+        let mut pool_authority = TestAccountData::new_authority_account(pool_authority_pda);
+        let mut prediction_anti =
+            token_at(seeds(b"anti_token"), pool_authority_pda, ANTI_MINT_ADDRESS);
+        let mut prediction_pro =
+            token_at(seeds(b"pro_token"), pool_authority_pda, PRO_MINT_ADDRESS);
+        let mut stray_anti = token_at(seeds(b"anti_token"), ANTITOKEN_MULTISIG, ANTI_MINT_ADDRESS);
+        let mut mismatched_anti =
+            token_at(seeds(b"anti_token"), pool_authority_pda, PRO_MINT_ADDRESS);
+        let mut protocol_anti =
+            TestAccountData::new_token_account(ANTITOKEN_MULTISIG, ANTI_MINT_ADDRESS, 0);
+        let mut protocol_pro =
+            TestAccountData::new_token_account(ANTITOKEN_MULTISIG, PRO_MINT_ADDRESS, 0);
+        let mut creator_anti = TestAccountData::new_token_account(creator, ANTI_MINT_ADDRESS, 0);
+        let mut creator_pro = TestAccountData::new_token_account(creator, PRO_MINT_ADDRESS, 0);
+        let mut foreign_anti =
+            TestAccountData::new_token_account(Pubkey::new_unique(), ANTI_MINT_ADDRESS, 0);
+        let mut anti_mint = TestAccountData::new_mint(ANTI_MINT_ADDRESS);
+        let mut pro_mint = TestAccountData::new_mint(PRO_MINT_ADDRESS);
+        let mut token_program = TestAccountData::new_token_program();
+        let mut system_program = TestAccountData::new_system_account();
+        let mut rent = TestAccountData::new_rent_account();
+
+        let authority_info = authority.to_account_info(true);
+        let protocol_pro_info = protocol_pro.to_account_info(false);
+        let creator_pro_info = creator_pro.to_account_info(false);
+        let accounts = vec![
+            admin.to_account_info(false),
+            prediction.to_account_info(false),
+            authority_info.clone(),
+            pool_authority.to_account_info(false),
+            prediction_anti.to_account_info(false),
+            prediction_pro.to_account_info(false),
+            protocol_anti.to_account_info(false),
+            protocol_pro_info.clone(),
+            creator_anti.to_account_info(false),
+            creator_pro_info.clone(),
+            anti_mint.to_account_info(false),
+            pro_mint.to_account_info(false),
+            token_program.to_account_info(false),
+            system_program.to_account_info(false),
+            rent.to_account_info(false),
+        ];
+        let attacker_info = attacker.to_account_info(true);
+        let stray_anti_info = stray_anti.to_account_info(false);
+        let mismatched_anti_info = mismatched_anti.to_account_info(false);
+        let foreign_anti_info = foreign_anti.to_account_info(false);
+
+        // Only the resolver equalises, from the prediction's pools, paying fees
+        // to the multisig and the creator in the prediction's mints
+        let cases: Vec<(Vec<AccountInfo>, Option<Error>)> = [
+            (2, &authority_info, None),
+            (
+                2,
+                &attacker_info,
+                Some(PredictError::UnauthorisedResolver.into()),
+            ),
+            (
+                4,
+                &stray_anti_info,
+                Some(PredictError::InvalidTokenAccount.into()),
+            ),
+            (
+                4,
+                &mismatched_anti_info,
+                Some(PredictError::InvalidTokenAccount.into()),
+            ),
+            (
+                6,
+                &foreign_anti_info,
+                Some(PredictError::InvalidTokenAccount.into()),
+            ),
+            (
+                6,
+                &protocol_pro_info,
+                Some(PredictError::InvalidTokenAccount.into()),
+            ),
+            (
+                8,
+                &foreign_anti_info,
+                Some(PredictError::InvalidTokenAccount.into()),
+            ),
+            (
+                8,
+                &creator_pro_info,
+                Some(PredictError::InvalidTokenAccount.into()),
+            ),
+        ]
+        .into_iter()
+        .map(|(slot, account, expected)| {
+            let mut swapped = accounts.clone();
+            swapped[slot] = account.clone();
+            (swapped, expected)
+        })
+        .collect();
+        for (swapped, expected) in &cases {
+            let result =
+                try_accounts::<EqualiseTokens, EqualiseTokensBumps>(swapped, &0u64.to_le_bytes());
+            assert_eq!(result.err().as_ref(), expected.as_ref());
+        }
     }
 }
@@ -8,6 +8,7 @@
 //! Contact: dev@antitoken.pro
 
 // instructions/initialise.rs
+use crate::utils::STATE_VERSION;
 use crate::Initialise;
 use crate::PredictError;
 use anchor_lang::prelude::*;
@@ -19,8 +20,10 @@ pub fn initialise(ctx: Context<Initialise>) -> Result<()> {
     require!(state.index == 0, PredictError::AlreadyInitialised);
 
     // Directly set values without redundant references
+    state.version = STATE_VERSION;
     state.index = 0;
     state.authority = ctx.accounts.authority.key();
+    state.archived = 0;
 
     Ok(())
 }
@@ -28,93 +31,21 @@ pub fn initialise(ctx: Context<Initialise>) -> Result<()> {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::utils::PROGRAM_ID;
+    use crate::test_utils::*;
     use crate::InitialiseBumps;
     use crate::PredictError;
     use crate::PredictionAccount;
     use crate::StateAccount;
-    use anchor_lang::prelude::AccountInfo;
-    use anchor_lang::solana_program::system_program;
-    use anchor_lang::Discriminator;
     use solana_sdk::signature::{Keypair, Signer as _};
-    use std::str::FromStr;
 
-    // Fixed test IDs - these should be consistent across tests
-    fn program_id() -> Pubkey {
-        Pubkey::from_str(&PROGRAM_ID.to_string()).unwrap()
-    }
-
-    struct TestAccountData {
-        key: Pubkey,
-        lamports: u64,
-        data: Vec<u8>,
-        owner: Pubkey,
-        executable: bool,
-        rent_epoch: u64,
-    }
-
-    impl TestAccountData {
-        fn new_owned_state<T: AccountSerialize + AccountDeserialize + Clone>(
-            owner: Pubkey,
-            key: Pubkey,
-        ) -> Self {
-            Self {
-                key,
-                lamports: 1_000_000,
-                data: vec![0; 8 + PredictionAccount::LEN],
-                owner,
-                executable: false,
-                rent_epoch: 0,
-            }
-        }
-
-        fn new_system_account() -> Self {
-            Self {
-                key: system_program::ID,
-                lamports: 1_000_000,
-                data: vec![],
-                owner: system_program::ID,
-                executable: true,
-                rent_epoch: 0,
-            }
-        }
-
-        fn new_authority_account(pubkey: Pubkey) -> Self {
-            Self {
-                key: pubkey,
-                lamports: 1_000_000,
-                data: vec![],
-                owner: system_program::ID,
-                executable: true,
-                rent_epoch: 0,
-            }
-        }
-
-        fn to_account_info<'a>(&'a mut self, is_signer: bool) -> AccountInfo<'a> {
-            AccountInfo::new(
-                &self.key,
-                is_signer,
-                true,
-                &mut self.lamports,
-                &mut self.data,
-                &self.owner,
-                self.executable,
-                self.rent_epoch,
-            )
-        }
-
-        fn init_state_data(&mut self, state: &StateAccount) -> Result<()> {
-            let data = self.data.as_mut_slice();
-
-            // Write discriminator
-            let disc = StateAccount::discriminator();
-            data[..8].copy_from_slice(&disc);
-
-            // Write account data
-            let account_data = state.try_to_vec()?;
-            data[8..8 + account_data.len()].copy_from_slice(&account_data);
-
-            Ok(())
+    fn new_owned_state(owner: Pubkey, key: Pubkey) -> TestAccountData {
+        TestAccountData {
+            key,
+            lamports: 1_000_000,
+            data: vec![0; 8 + PredictionAccount::LEN],
+            owner,
+            executable: false,
+            rent_epoch: 0,
         }
     }
 
@@ -125,14 +56,16 @@ mod tests {
 
         // Create test accounts
         let (state_pda, state_bump) = Pubkey::find_program_address(&[b"state"], &program_id);
-        let mut state = TestAccountData::new_owned_state::<StateAccount>(program_id, state_pda);
+        let mut state = new_owned_state(program_id, state_pda);
         let mut authority = TestAccountData::new_authority_account(authority.pubkey());
         let mut system = TestAccountData::new_system_account();
 
         // Initialise state account
         let state_data = StateAccount {
+            version: STATE_VERSION,
             index: 0,
             authority: authority.key,
+            archived: 0,
         };
         state.init_state_data(&state_data).unwrap();
 
@@ -163,7 +96,7 @@ mod tests {
         let authority = Keypair::new();
 
         let (state_pda, state_bump) = Pubkey::find_program_address(&[b"state"], &program_id);
-        let mut state = TestAccountData::new_owned_state::<StateAccount>(program_id, state_pda);
+        let mut state = new_owned_state(program_id, state_pda);
         let mut authority = TestAccountData::new_authority_account(authority.pubkey());
         let mut system = TestAccountData::new_system_account();
 
@@ -171,8 +104,10 @@ mod tests {
 
         // Initialise state account
         let state_data = StateAccount {
+            version: STATE_VERSION,
             index: 0,
             authority: authority_key,
+            archived: 0,
         };
         state.init_state_data(&state_data).unwrap();
 
@@ -199,8 +134,10 @@ mod tests {
 
         // Update state
         let updated_state = StateAccount {
+            version: STATE_VERSION,
             index: 1,
             authority: authority_key,
+            archived: 0,
         };
         state.init_state_data(&updated_state).unwrap();
 
@@ -236,7 +173,7 @@ mod tests {
         let authority = Keypair::new();
 
         let (state_pda, state_bump) = Pubkey::find_program_address(&[b"state"], &program_id);
-        let mut state = TestAccountData::new_owned_state::<StateAccount>(program_id, state_pda);
+        let mut state = new_owned_state(program_id, state_pda);
         let mut authority = TestAccountData::new_authority_account(authority.pubkey());
         let mut system = TestAccountData::new_system_account();
 
@@ -244,8 +181,10 @@ mod tests {
         let different_authority = Pubkey::new_unique();
 
         let state_data = StateAccount {
+            version: STATE_VERSION,
             index: 0,
             authority: different_authority,
+            archived: 0,
         };
         state.init_state_data(&state_data).unwrap();
 
@@ -284,13 +223,15 @@ mod tests {
         let authority = Keypair::new();
 
         let (state_pda, state_bump) = Pubkey::find_program_address(&[b"state"], &program_id);
-        let mut state = TestAccountData::new_owned_state::<StateAccount>(program_id, state_pda);
+        let mut state = new_owned_state(program_id, state_pda);
         let mut authority = TestAccountData::new_authority_account(authority.pubkey());
         let mut system = TestAccountData::new_system_account();
 
         let state_data = StateAccount {
+            version: STATE_VERSION,
             index: 0,
             authority: authority.key,
+            archived: 0,
         };
 
         // Initialise state account data before running the test
@@ -0,0 +1,689 @@
+//! Program Author: sshmatrix, for Antitoken
+//! Program Description: Collider's instruction set
+//! Version: 1.0.0-beta
+//! License: MIT
+//! Created: 20 Jan 2025
+//! Last Modified: 20 Jan 2025
+//! Repository: https://github.com/antitokens/solana-collider
+//! Contact: dev@antitoken.pro
+
+// instructions/migrate.rs
+use crate::state::*;
+use crate::utils::*;
+use crate::{MigrateAccount, MigratePrediction};
+use anchor_lang::error::ErrorCode;
+use anchor_lang::prelude::*;
+use anchor_lang::system_program;
+use anchor_lang::Discriminator;
+
+pub fn migrate_prediction<'a, 'b, 'c: 'info, 'info>(
+    ctx: Context<'a, 'b, 'c, 'info, MigratePrediction<'info>>,
+    index: u64,
+    offset: u64,
+    batch: u64,
+) -> Result<()> {
+    // Get current time
+    let now = current_time()?;
+
+    // Verify signer is the multisig
+    require!(
+        ctx.accounts.authority.key() == ctx.accounts.admin.antitoken_multisig,
+        PredictError::Unauthorised
+    );
+
+    // Verify the account is a prediction of this program
+    let prediction_info = &ctx.accounts.prediction;
+    require!(
+        prediction_info.owner == ctx.program_id,
+        PredictError::PredictionNotFound
+    );
+    let legacy = {
+        let data = prediction_info.try_borrow_data()?;
+        require!(
+            data.len() > 8 && data[..8] == PredictionAccount::discriminator(),
+            PredictError::PredictionNotFound
+        );
+
+        // Migrated accounts are rejected, whether read by their version or by
+        // native times failing to decode as ISO strings
+        require!(
+            decode_current::<PredictionAccount>(&data).is_none(),
+            PredictError::AlreadyMigrated
+        );
+        LegacyPredictionAccount::deserialize(&mut &data[8..])
+            .map_err(|_| error!(PredictError::AlreadyMigrated))?
+    };
+    let admin = &ctx.accounts.admin;
+    let (prediction, records) = legacy.migrate(
+        admin.antitoken_multisig,
+        admin.anti_mint_address,
+        admin.pro_mint_address,
+    )?;
+    require!(prediction.index == index, PredictError::PredictionNotFound);
+
+    // Records are written in order, a batch at a time, while the prediction keeps
+    // its legacy layout; only the batch reaching the last deposit rewrites it
+    let end = offset
+        .checked_add(batch)
+        .ok_or(error!(PredictError::MathError))?;
+    require!(
+        batch <= MAX_MIGRATE_BATCH && end <= records.len() as u64,
+        PredictError::InvalidMigrationBatch
+    );
+    let batch_records = &records[offset as usize..end as usize];
+
+    // The legacy layout holds no progress, so each batch after the first quotes
+    // the last record already migrated ahead of its own records
+    let remaining_accounts: &[AccountInfo<'info>] = ctx.remaining_accounts;
+    let previous = (offset > 0) as usize;
+    require!(
+        remaining_accounts.len() == previous + batch_records.len(),
+        PredictError::InvalidDepositRecord
+    );
+    let index_bytes = index.to_le_bytes();
+    let record_address = |record: &DepositRecord| {
+        Pubkey::find_program_address(
+            &[
+                b"deposit",
+                &index_bytes,
+                record.deposit.address.as_ref(),
+                &record.nonce.to_le_bytes(),
+            ],
+            ctx.program_id,
+        )
+    };
+    if offset > 0 {
+        let previous_info = &remaining_accounts[0];
+        let (previous_key, _) = record_address(&records[offset as usize - 1]);
+        require!(
+            previous_info.key() == previous_key,
+            PredictError::InvalidDepositRecord
+        );
+        let previous_record: Account<'info, DepositRecord> = Account::try_from(previous_info)
+            .map_err(|_| error!(PredictError::InvalidMigrationBatch))?;
+        require!(
+            previous_record.index == index,
+            PredictError::InvalidMigrationBatch
+        );
+    }
+    let record_infos = &remaining_accounts[previous..];
+    let mut bumps = Vec::with_capacity(batch_records.len());
+    for (record_info, record) in record_infos.iter().zip(batch_records.iter()) {
+        let (record_key, record_bump) = record_address(record);
+        require!(
+            record_info.key() == record_key,
+            PredictError::InvalidDepositRecord
+        );
+        bumps.push(record_bump);
+    }
+
+    for ((record_info, record), bump) in record_infos.iter().zip(batch_records.iter()).zip(bumps) {
+        // Create the record unless the program already allocated it, and never
+        // overwrite one that holds data
+        if record_info.owner != ctx.program_id {
+            let space = 8 + DepositRecord::LEN;
+            let nonce_bytes = record.nonce.to_le_bytes();
+            let bump = [bump];
+            let record_seeds: &[&[u8]] = &[
+                b"deposit",
+                &index_bytes,
+                record.deposit.address.as_ref(),
+                &nonce_bytes,
+                &bump,
+            ];
+            system_program::create_account(
+                CpiContext::new_with_signer(
+                    ctx.accounts.system_program.to_account_info(),
+                    system_program::CreateAccount {
+                        from: ctx.accounts.authority.to_account_info(),
+                        to: record_info.clone(),
+                    },
+                    &[record_seeds],
+                ),
+                ctx.accounts.rent.minimum_balance(space),
+                space as u64,
+                ctx.program_id,
+            )?;
+        }
+        require!(
+            record_info.try_borrow_data()?.iter().all(|byte| *byte == 0),
+            PredictError::AlreadyMigrated
+        );
+        record_info.try_borrow_mut_data()?[..8].copy_from_slice(&DepositRecord::discriminator());
+        write_account(record_info, record)?;
+    }
+
+    if end < records.len() as u64 {
+        emit!(PredictionUpdateEvent {
+            index,
+            field_updated: "deposits".to_string(),
+            timestamp: now,
+        });
+        return Ok(());
+    }
+
+    // Rewrite the account in the native layout, clearing the legacy tail
+    prediction_info.try_borrow_mut_data()?[8..].fill(0);
+    let max_len = prediction.max_space();
+    write_account_growing(
+        prediction_info,
+        &prediction,
+        &ctx.accounts.authority.to_account_info(),
+        &ctx.accounts.system_program.to_account_info(),
+        &ctx.accounts.rent,
+        max_len,
+    )?;
+
+    emit!(PredictionUpdateEvent {
+        index,
+        field_updated: "start_time,end_time,deposits".to_string(),
+        timestamp: now,
+    });
+
+    Ok(())
+}
+
+pub fn migrate_account(ctx: Context<MigrateAccount>) -> Result<()> {
+    // Get current time
+    let now = current_time()?;
+
+    // Verify signer is the multisig, read from either admin layout
+    require!(
+        ctx.accounts.authority.key() == admin_multisig(&ctx.accounts.admin)?,
+        PredictError::Unauthorised
+    );
+
+    // Verify the account belongs to this program
+    let account_info = ctx.accounts.account.to_account_info();
+    require!(
+        account_info.owner == ctx.program_id,
+        ErrorCode::AccountOwnedByWrongProgram
+    );
+
+    // Upgrade the account to its current layout, keyed on the discriminator
+    let discriminator = {
+        let data = account_info.try_borrow_data()?;
+        require!(data.len() > 8, ErrorCode::AccountDiscriminatorNotFound);
+        data[..8].to_vec()
+    };
+    let accounts = &ctx.accounts;
+    let version = if discriminator == AdminAccount::discriminator() {
+        upgrade::<AdminAccount>(accounts, AdminAccount::LEN)?
+    } else if discriminator == StateAccount::discriminator() {
+        upgrade::<StateAccount>(accounts, StateAccount::LEN)?
+    } else if discriminator == PredictionAccount::discriminator() {
+        upgrade::<PredictionAccount>(accounts, PredictionAccount::LEN)?
+    } else {
+        return err!(ErrorCode::AccountDiscriminatorMismatch);
+    };
+
+    emit!(AdminEvent {
+        action: "migrate_account".to_string(),
+        args: vec![
+            KeyValue {
+                key: "account".to_string(),
+                value: account_info.key().to_string(),
+            },
+            KeyValue {
+                key: "version".to_string(),
+                value: version.to_string(),
+            },
+        ],
+        timestamp: now,
+    });
+
+    Ok(())
+}
+
+// Multisig held by the admin account, which may itself await migration
+fn admin_multisig(info: &AccountInfo) -> Result<Pubkey> {
+    let data = info.try_borrow_data()?;
+    require!(
+        data.len() > 8 && data[..8] == AdminAccount::discriminator(),
+        ErrorCode::AccountDiscriminatorMismatch
+    );
+    let admin = match decode_current::<AdminAccount>(&data) {
+        Some(admin) => admin,
+        None => AdminAccount::upgrade(&data)?,
+    };
+    Ok(admin.antitoken_multisig)
+}
+
+// Grow an account written in an earlier layout and rewrite it in the current
+// one; the extra rent is paid by the multisig
+fn upgrade<T: Versioned>(accounts: &MigrateAccount, len: usize) -> Result<u8> {
+    let account_info = &accounts.account;
+    let account = T::upgrade(&account_info.try_borrow_data()?)?;
+
+    // Top up rent for the larger layout
+    let shortfall = accounts
+        .rent
+        .minimum_balance(8 + len)
+        .saturating_sub(account_info.lamports());
+    if shortfall > 0 {
+        system_program::transfer(
+            CpiContext::new(
+                accounts.system_program.to_account_info(),
+                system_program::Transfer {
+                    from: accounts.authority.to_account_info(),
+                    to: account_info.clone(),
+                },
+            ),
+            shortfall,
+        )?;
+    }
+
+    // Grow the account and rewrite it, clearing the old tail
+    account_info.realloc(8 + len, true)?;
+    account_info.try_borrow_mut_data()?[8..].fill(0);
+    write_account(account_info, &account)?;
+    Ok(T::VERSION)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::*;
+    use crate::{MigrateAccountBumps, MigratePredictionBumps};
+
+    // Reusable method to create an equalised prediction in the baseline layout
+    fn create_legacy_test_prediction(depositors: [Pubkey; 2]) -> LegacyPredictionAccount {
+        let deposit = |address: Pubkey, anti: u64, pro: u64, withdrawn: bool| LegacyDeposit {
+            address,
+            anti,
+            pro,
+            mean: 40000,
+            stddev: 100000,
+            withdrawn,
+        };
+        LegacyPredictionAccount {
+            index: 0,
+            title: "Test Prediction".to_string(),
+            description: "Test Description".to_string(),
+            start_time: "2025-01-01T00:00:00Z".to_string(),
+            end_time: "2025-01-02T00:00:00Z".to_string(),
+            etc: None,
+            anti: 10000,
+            pro: 8000,
+            deposits: vec![
+                deposit(depositors[0], 6000, 5000, true),
+                deposit(depositors[1], 4000, 3000, false),
+            ],
+            equalised: true,
+            equalisation: Some(LegacyEqualisation {
+                anti: vec![7000, 2999],
+                pro: vec![3000, 4999],
+                truth: vec![6000, 4000],
+                timestamp: 1735862400,
+            }),
+        }
+    }
+
+    fn create_legacy_admin(multisig: Pubkey) -> AdminAccountV0 {
+        AdminAccountV0 {
+            initialised: true,
+            creation_fee: CREATION_FEE,
+            max_title_length: MAX_TITLE_LENGTH,
+            max_description_length: MAX_DESCRIPTION_LENGTH,
+            truth_basis: TRUTH_BASIS,
+            float_basis: FLOAT_BASIS,
+            min_deposit_amount: MIN_DEPOSIT_AMOUNT,
+            antitoken_multisig: multisig,
+            anti_mint_address: ANTI_MINT_ADDRESS,
+            pro_mint_address: PRO_MINT_ADDRESS,
+        }
+    }
+
+    // Migrate the given account, or the admin itself when none is given
+    fn run_migrate_account(
+        program_id: Pubkey,
+        admin: &mut ReallocAccountData,
+        account: Option<&mut ReallocAccountData>,
+        signer: Pubkey,
+    ) -> Result<()> {
+        let (_, admin_bump) = Pubkey::find_program_address(&[b"admin"], &program_id);
+        let mut authority = TestAccountData::new_authority_account(signer);
+        let mut system = TestAccountData::new_system_account();
+        let mut rent = TestAccountData::new_rent_account();
+
+        let admin_info = admin.to_account_info();
+        let account_info = match account {
+            Some(account) => account.to_account_info(),
+            None => admin_info.clone(),
+        };
+        let authority_info = authority.to_account_info(true);
+        let system_info = system.to_account_info(false);
+        let rent_info = rent.to_account_info(false);
+
+        let mut accounts = MigrateAccount {
+            admin: admin_info,
+            account: account_info,
+            authority: Signer::try_from(&authority_info).unwrap(),
+            system_program: Program::try_from(&system_info).unwrap(),
+            rent: Sysvar::from_account_info(&rent_info).unwrap(),
+        };
+        let bumps = MigrateAccountBumps { admin: admin_bump };
+
+        set_clock_override(Some(1736899200));
+        migrate_account(Context::new(&program_id, &mut accounts, &[], bumps))
+    }
+
+    #[test]
+    fn test_migrate_account() {
+        let program_id = program_id();
+        let multisig = Pubkey::new_unique();
+        let (admin_pda, _) = Pubkey::find_program_address(&[b"admin"], &program_id);
+        let (state_pda, _) = Pubkey::find_program_address(&[b"state"], &program_id);
+        let (prediction_pda, _) = Pubkey::find_program_address(
+            &[
+                b"prediction",
+                (PREDICTION_VERSION as u64).to_le_bytes().as_ref(),
+            ],
+            &program_id,
+        );
+
+        // Accounts as the baseline program allocated them. The state and
+        // prediction open with an index whose low byte equals the current
+        // version, so that byte alone cannot mark them
+        let mut admin = ReallocAccountData::new_legacy(
+            admin_pda,
+            program_id,
+            AdminAccount::discriminator(),
+            &create_legacy_admin(multisig).try_to_vec().unwrap(),
+            8 + AdminAccountV0::LEN,
+        );
+        let mut state = ReallocAccountData::new_legacy(
+            state_pda,
+            program_id,
+            StateAccount::discriminator(),
+            &StateAccountV0 {
+                index: STATE_VERSION as u64,
+                authority: multisig,
+            }
+            .try_to_vec()
+            .unwrap(),
+            8 + StateAccountV0::LEN,
+        );
+        let legacy_prediction = LegacyPredictionAccount {
+            index: PREDICTION_VERSION as u64,
+            ..create_legacy_test_prediction([Pubkey::new_unique(); 2])
+        };
+        let legacy_data = legacy_prediction.try_to_vec().unwrap();
+        let mut prediction = ReallocAccountData::new_legacy(
+            prediction_pda,
+            program_id,
+            PredictionAccount::discriminator(),
+            &legacy_data,
+            8 + legacy_data.len(),
+        );
+
+        // Baseline accounts are rejected by the loader
+        assert_eq!(
+            load::<StateAccount>(&state.to_account_info()).err(),
+            Some(PredictError::UnsupportedVersion.into())
+        );
+        assert_eq!(
+            load::<PredictionAccount>(&prediction.to_account_info()).err(),
+            Some(PredictError::UnsupportedVersion.into())
+        );
+
+        // Only the multisig of the baseline admin may migrate
+        let result = run_migrate_account(
+            program_id,
+            &mut admin,
+            Some(&mut state),
+            Pubkey::new_unique(),
+        );
+        assert_eq!(result.unwrap_err(), PredictError::Unauthorised.into());
+
+        // The state migrates while the admin is still in the baseline layout
+        run_migrate_account(program_id, &mut admin, Some(&mut state), multisig).unwrap();
+        let state_info = state.to_account_info();
+        assert_eq!(state_info.data_len(), 8 + StateAccount::LEN);
+        let migrated = load::<StateAccount>(&state_info).unwrap();
+        assert_eq!(migrated.version, STATE_VERSION);
+        assert_eq!(
+            (migrated.index, migrated.archived),
+            (STATE_VERSION as u64, 0)
+        );
+        assert_eq!(migrated.authority, multisig);
+
+        // Baseline predictions migrate with their deposit records instead
+        let result = run_migrate_account(program_id, &mut admin, Some(&mut prediction), multisig);
+        assert_eq!(result.unwrap_err(), PredictError::UnsupportedVersion.into());
+
+        // The admin migrates itself, gaining defaults for the parameters it lacked
+        run_migrate_account(program_id, &mut admin, None, multisig).unwrap();
+        let admin_info = admin.to_account_info();
+        assert_eq!(admin_info.data_len(), 8 + AdminAccount::LEN);
+        let migrated = load::<AdminAccount>(&admin_info).unwrap();
+        assert_eq!(migrated.version, ADMIN_VERSION);
+        assert_eq!(migrated.antitoken_multisig, multisig);
+        assert_eq!(migrated.creation_fee, CREATION_FEE);
+        assert_eq!(migrated.challenge_period, CHALLENGE_PERIOD);
+        assert_eq!(migrated.dispute_bond, DISPUTE_BOND);
+        assert_eq!(migrated.protocol_fee_bps, PROTOCOL_FEE_BPS);
+        assert_eq!((migrated.arbiter, migrated.treasury), (multisig, multisig));
+        assert!(migrated.pending_multisig.is_none() && migrated.queue.is_empty());
+        assert!(migrated.pending_treasury.is_none());
+
+        // Repeat migrations are rejected
+        let result = run_migrate_account(program_id, &mut admin, Some(&mut state), multisig);
+        assert_eq!(result.unwrap_err(), PredictError::AlreadyMigrated.into());
+        let result = run_migrate_account(program_id, &mut admin, None, multisig);
+        assert_eq!(result.unwrap_err(), PredictError::AlreadyMigrated.into());
+
+        // Unknown versions are rejected by both the loader and the migration
+        let mut current = ReallocAccountData::new_legacy(
+            prediction_pda,
+            program_id,
+            PredictionAccount::discriminator(),
+            &test_prediction().try_to_vec().unwrap(),
+            8 + PredictionAccount::LEN,
+        );
+        let result = run_migrate_account(program_id, &mut admin, Some(&mut current), multisig);
+        assert_eq!(result.unwrap_err(), PredictError::AlreadyMigrated.into());
+        current.buffer[16] = PREDICTION_VERSION + 1;
+        assert_eq!(
+            load::<PredictionAccount>(&current.to_account_info()).err(),
+            Some(PredictError::UnsupportedVersion.into())
+        );
+        let result = run_migrate_account(program_id, &mut admin, Some(&mut current), multisig);
+        assert_eq!(result.unwrap_err(), PredictError::UnsupportedVersion.into());
+    }
+
+    #[test]
+    fn test_migrate_prediction() {
+        let program_id = program_id();
+        let multisig = Pubkey::new_unique();
+        let depositors = [Pubkey::new_unique(), Pubkey::new_unique()];
+
+        // Create test accounts
+        let (admin_pda, admin_bump) = Pubkey::find_program_address(&[b"admin"], &program_id);
+        let (prediction_pda, prediction_bump) = Pubkey::find_program_address(
+            &[
+                b"prediction",
+                (PREDICTION_VERSION as u64).to_le_bytes().as_ref(),
+            ],
+            &program_id,
+        );
+
+        // The unversioned state and prediction start with an index whose low
+        // byte equals the current version, so only their length marks them
+        let mut admin = TestAccountData::new_account_with_key_and_owner(admin_pda, program_id);
+        admin
+            .init_admin_data(&AdminAccount {
+                antitoken_multisig: multisig,
+                ..test_admin()
+            })
+            .unwrap();
+        let mut prediction =
+            TestAccountData::new_account_with_key_and_owner(prediction_pda, program_id);
+        let account_data = create_legacy_test_prediction(depositors)
+            .try_to_vec()
+            .unwrap();
+        prediction.data[..8].copy_from_slice(&PredictionAccount::discriminator());
+        prediction.data[8..8 + account_data.len()].copy_from_slice(&account_data);
+
+        // Record accounts at the deposit PDAs, allocated but not yet written
+        let mut records: Vec<TestAccountData> = depositors
+            .iter()
+            .enumerate()
+            .map(|(nonce, depositor)| {
+                let (record_pda, _) = Pubkey::find_program_address(
+                    &[
+                        b"deposit",
+                        0u64.to_le_bytes().as_ref(),
+                        depositor.as_ref(),
+                        (nonce as u64).to_le_bytes().as_ref(),
+                    ],
+                    &program_id,
+                );
+                let mut record =
+                    TestAccountData::new_account_with_key_and_owner(record_pda, program_id);
+                record.data = vec![0; 8 + DepositRecord::LEN];
+                record
+            })
+            .collect();
+        let mut stray =
+            TestAccountData::new_account_with_key_and_owner(Pubkey::new_unique(), program_id);
+        let mut system = TestAccountData::new_system_account();
+        let mut rent = TestAccountData::new_rent_account();
+
+        let admin_info = admin.to_account_info(false);
+        let prediction_info = prediction.to_account_info(false);
+        let record_infos: Vec<AccountInfo> = records
+            .iter_mut()
+            .map(|record| record.to_account_info(false))
+            .collect();
+        let stray_info = stray.to_account_info(false);
+        let system_info = system.to_account_info(false);
+        let rent_info = rent.to_account_info(false);
+        let first = vec![record_infos[0].clone()];
+        let misplaced = vec![record_infos[0].clone(), stray_info.clone()];
+        let unquoted = vec![stray_info.clone(), record_infos[1].clone()];
+        let mut stranger = TestAccountData::new_authority_account(Pubkey::new_unique());
+        let mut authority = TestAccountData::new_authority_account(multisig);
+        let stranger_info = stranger.to_account_info(true);
+        let authority_info = authority.to_account_info(true);
+
+        // Stranger first, then batches out of bounds, out of order, short of or
+        // misplacing records, then the multisig in two batches, then a repeat
+        for (signer_info, remaining_accounts, offset, batch, expected) in [
+            (
+                &stranger_info,
+                &record_infos,
+                0,
+                2,
+                Err(PredictError::Unauthorised),
+            ),
+            (
+                &authority_info,
+                &record_infos,
+                0,
+                3,
+                Err(PredictError::InvalidMigrationBatch),
+            ),
+            (
+                &authority_info,
+                &record_infos,
+                1,
+                1,
+                Err(PredictError::InvalidMigrationBatch),
+            ),
+            (
+                &authority_info,
+                &first,
+                0,
+                2,
+                Err(PredictError::InvalidDepositRecord),
+            ),
+            (
+                &authority_info,
+                &misplaced,
+                0,
+                2,
+                Err(PredictError::InvalidDepositRecord),
+            ),
+            (&authority_info, &first, 0, 1, Ok(())),
+            (
+                &authority_info,
+                &unquoted,
+                1,
+                1,
+                Err(PredictError::InvalidDepositRecord),
+            ),
+            (&authority_info, &record_infos, 1, 1, Ok(())),
+            (
+                &authority_info,
+                &record_infos,
+                1,
+                1,
+                Err(PredictError::AlreadyMigrated),
+            ),
+        ] {
+            let mut accounts = MigratePrediction {
+                admin: Account::try_from(&admin_info).unwrap(),
+                prediction: prediction_info.clone(),
+                authority: Signer::try_from(signer_info).unwrap(),
+                system_program: Program::try_from(&system_info).unwrap(),
+                rent: Sysvar::from_account_info(&rent_info).unwrap(),
+            };
+            let bumps = MigratePredictionBumps {
+                admin: admin_bump,
+                prediction: prediction_bump,
+            };
+
+            set_clock_override(Some(1736899200));
+            let result = migrate_prediction(
+                Context::new(&program_id, &mut accounts, remaining_accounts, bumps),
+                0,
+                offset,
+                batch,
+            );
+            match expected {
+                Ok(()) => {
+                    assert!(result.is_ok());
+
+                    // The prediction keeps its legacy layout until its last record is written
+                    let data = prediction_info.try_borrow_data().unwrap();
+                    let migrated = decode_current::<PredictionAccount>(&data).is_some();
+                    assert_eq!(migrated, offset + batch == 2);
+                }
+                Err(error) => assert_eq!(result.unwrap_err(), error.into()),
+            }
+        }
+
+        // Times are native and the prediction is governed by the multisig
+        let data = prediction_info.try_borrow_data().unwrap();
+        let migrated = PredictionAccount::try_deserialize(&mut &data[..]).unwrap();
+        assert_eq!(migrated.start_time, 1735689600);
+        assert_eq!(migrated.end_time, 1735776000);
+        assert_eq!(migrated.resolves_at(), migrated.end_time);
+        assert_eq!((migrated.anti, migrated.pro), (10000, 8000));
+        assert_eq!(migrated.anti_mint, ANTI_MINT_ADDRESS);
+        assert_eq!(migrated.pro_mint, PRO_MINT_ADDRESS);
+        assert_eq!((migrated.creator, migrated.resolver), (multisig, multisig));
+        assert_eq!((migrated.deposits, migrated.withdrawn), (2, 1));
+        assert_eq!((migrated.bulk.anti, migrated.bulk.pro), (7000, 3000));
+        assert!(migrated.is_settled(1736899200));
+
+        // Each inline deposit moves to its record and keeps its recorded returns
+        for (nonce, (record_info, returns)) in record_infos
+            .iter()
+            .zip([(7000, 3000), (2999, 4999)])
+            .enumerate()
+        {
+            let record =
+                DepositRecord::try_deserialize(&mut &record_info.try_borrow_data().unwrap()[..])
+                    .unwrap();
+            assert_eq!(record.nonce, nonce as u64);
+            assert_eq!(record.deposit.address, depositors[nonce]);
+            assert_eq!(record.deposit.withdrawn, nonce == 0);
+            assert_eq!(
+                payout(migrated.equalisation.as_ref().unwrap(), &record).unwrap(),
+                returns
+            );
+        }
+    }
+}
@@ -15,6 +15,11 @@ pub mod deposit;
 pub mod equalise;
 pub mod bulk_withdraw;
 pub mod user_withdraw;
+pub mod dispute;
+pub mod sweep;
+pub mod void;
+pub mod close;
+pub mod migrate;
 
 // Re-export the instruction structs
 pub use admin::*;
@@ -24,3 +29,8 @@ pub use deposit::*;
 pub use equalise::*;
 pub use bulk_withdraw::*;
 pub use user_withdraw::*;
+pub use dispute::*;
+pub use sweep::*;
+pub use void::*;
+pub use close::*;
+pub use migrate::*;
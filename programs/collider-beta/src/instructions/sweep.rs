@@ -0,0 +1,316 @@
+//! Program Author: sshmatrix, for Antitoken
+//! Program Description: Collider's instruction set
+//! Version: 1.0.0-beta
+//! License: MIT
+//! Created: 20 Jan 2025
+//! Last Modified: 20 Jan 2025
+//! Repository: https://github.com/antitokens/solana-collider
+//! Contact: dev@antitoken.pro
+
+// instructions/sweep.rs
+use crate::utils::*;
+use crate::SweepDust;
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{self, TransferChecked};
+
+pub fn sweep_dust(ctx: Context<SweepDust>, index: u64) -> Result<()> {
+    // Get current time
+    let now = current_time()?;
+
+    // Verify prediction has settled
+    let prediction = &ctx.accounts.prediction;
+    require!(prediction.equalised, PredictError::NotEqualised);
+    require!(
+        prediction.is_settled(now),
+        PredictError::ChallengePeriodActive
+    );
+
+    // Only leftovers may be swept, so every deposit record must be withdrawn
+    require!(
+        prediction.withdrawn == prediction.deposits,
+        PredictError::WithdrawalsPending
+    );
+
+    // Leftovers owed to records closed unclaimed are reported apart from dust
+    let ((anti_unclaimed, pro_unclaimed), (anti_dust, pro_dust)) = prediction.split_leftovers(
+        ctx.accounts.prediction_anti_token.amount,
+        ctx.accounts.prediction_pro_token.amount,
+    );
+    let anti_left = anti_unclaimed + anti_dust;
+    let pro_left = pro_unclaimed + pro_dust;
+
+    // Pool accounts are held by the prediction's PDA authority, which signs
+    let index_bytes = index.to_le_bytes();
+    let pool_bump = [ctx.bumps.pool_authority];
+    let pool_seeds: &[&[u8]] = &[b"authority", &index_bytes, &pool_bump];
+    let signer_seeds: &[&[&[u8]]] = &[pool_seeds];
+
+    // Transfer leftover ANTI tokens
+    if anti_left > 0 {
+        token_interface::transfer_checked(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                TransferChecked {
+                    from: ctx.accounts.prediction_anti_token.to_account_info(),
+                    mint: ctx.accounts.anti_mint.to_account_info(),
+                    to: ctx.accounts.treasury_anti_token.to_account_info(),
+                    authority: ctx.accounts.pool_authority.to_account_info(),
+                },
+                signer_seeds,
+            ),
+            anti_left,
+            ctx.accounts.anti_mint.decimals,
+        )?;
+    }
+
+    // Transfer leftover PRO tokens
+    if pro_left > 0 {
+        token_interface::transfer_checked(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                TransferChecked {
+                    from: ctx.accounts.prediction_pro_token.to_account_info(),
+                    mint: ctx.accounts.pro_mint.to_account_info(),
+                    to: ctx.accounts.treasury_pro_token.to_account_info(),
+                    authority: ctx.accounts.pool_authority.to_account_info(),
+                },
+                signer_seeds,
+            ),
+            pro_left,
+            ctx.accounts.pro_mint.decimals,
+        )?;
+    }
+
+    emit!(SweepEvent {
+        index,
+        anti: anti_dust,
+        pro: pro_dust,
+        unclaimed_anti: anti_unclaimed,
+        unclaimed_pro: pro_unclaimed,
+        timestamp: now,
+    });
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::*;
+    use crate::test_utils::*;
+    use crate::SweepDustBumps;
+    use anchor_lang::error::ErrorCode;
+    use anchor_spl::token::spl_token;
+    use anchor_spl::token_interface::{Mint, TokenInterface};
+
+    // Reusable method to create a settled test prediction
+    fn create_settled_test_prediction(withdrawn: u64) -> PredictionAccount {
+        PredictionAccount {
+            anti: 10000,
+            pro: 8000,
+            deposits: 2,
+            withdrawn,
+            equalised: true,
+            equalisation: Some(Equalisation::new(vec![6000, 4000], 1736899200)),
+            challenge_end: 1736899200 + CHALLENGE_PERIOD,
+            ..test_prediction()
+        }
+    }
+
+    #[test]
+    fn test_sweep_dust() {
+        let program_id = program_id();
+        let now: i64 = 1736899200 + CHALLENGE_PERIOD;
+        let multisig = Pubkey::new_unique();
+
+        // Create test accounts
+        let (admin_pda, admin_bump) = Pubkey::find_program_address(&[b"admin"], &program_id);
+        let (pool_authority_pda, pool_authority_bump) =
+            Pubkey::find_program_address(&[b"authority", 0u64.to_le_bytes().as_ref()], &program_id);
+        let (prediction_pda, prediction_bump) = Pubkey::find_program_address(
+            &[b"prediction", 0u64.to_le_bytes().as_ref()],
+            &program_id,
+        );
+        let (_, anti_token_bump) = Pubkey::find_program_address(
+            &[b"anti_token", 0u64.to_le_bytes().as_ref()],
+            &program_id,
+        );
+        let (_, pro_token_bump) =
+            Pubkey::find_program_address(&[b"pro_token", 0u64.to_le_bytes().as_ref()], &program_id);
+
+        let mut admin = TestAccountData::new_account_with_key_and_owner(admin_pda, program_id);
+        admin.init_data(
+            &AdminAccount {
+                antitoken_multisig: multisig,
+                ..test_admin()
+            },
+            AdminAccount::LEN,
+        );
+        let mut pool_authority = TestAccountData::new_authority_account(pool_authority_pda);
+        let mut prediction =
+            TestAccountData::new_account_with_key_and_owner(prediction_pda, program_id);
+        let mut authority = TestAccountData::new_authority_account(multisig);
+        let mut prediction_anti =
+            TestAccountData::new_token_account(pool_authority_pda, ANTI_MINT_ADDRESS, 3);
+        let mut prediction_pro =
+            TestAccountData::new_token_account(pool_authority_pda, PRO_MINT_ADDRESS, 1);
+        let mut treasury_anti = TestAccountData::new_token_account(multisig, ANTI_MINT_ADDRESS, 0);
+        let mut treasury_pro = TestAccountData::new_token_account(multisig, PRO_MINT_ADDRESS, 0);
+        let mut anti_mint = TestAccountData::new_mint(ANTI_MINT_ADDRESS);
+        let mut pro_mint = TestAccountData::new_mint(PRO_MINT_ADDRESS);
+        let mut token_program =
+            TestAccountData::new_account_with_key_and_owner(spl_token::ID, spl_token::ID);
+
+        // Sweep while a deposit record is still owed (should fail) and once all are withdrawn
+        for (withdrawn, at, expected) in [
+            (1, now, Err(PredictError::WithdrawalsPending)),
+            (2, now - 1, Err(PredictError::ChallengePeriodActive)),
+            (2, now, Ok(())),
+        ] {
+            prediction.init_data(
+                &create_settled_test_prediction(withdrawn),
+                PredictionAccount::LEN,
+            );
+
+            let admin_info = admin.to_account_info(false);
+            let pool_authority_info = pool_authority.to_account_info(false);
+            let prediction_info = prediction.to_account_info(false);
+            let authority_info = authority.to_account_info(true);
+            let prediction_anti_info = prediction_anti.to_account_info(false);
+            let prediction_pro_info = prediction_pro.to_account_info(false);
+            let treasury_anti_info = treasury_anti.to_account_info(false);
+            let treasury_pro_info = treasury_pro.to_account_info(false);
+            let anti_mint_info = anti_mint.to_account_info(false);
+            let pro_mint_info = pro_mint.to_account_info(false);
+            let token_program_info = token_program.to_account_info(false);
+
+            let mut accounts = SweepDust {
+                admin: Account::try_from(&admin_info).unwrap(),
+                prediction: Account::try_from(&prediction_info).unwrap(),
+                authority: Signer::try_from(&authority_info).unwrap(),
+                pool_authority: pool_authority_info,
+                prediction_anti_token: InterfaceAccount::try_from(&prediction_anti_info).unwrap(),
+                prediction_pro_token: InterfaceAccount::try_from(&prediction_pro_info).unwrap(),
+                treasury_anti_token: InterfaceAccount::try_from(&treasury_anti_info).unwrap(),
+                treasury_pro_token: InterfaceAccount::try_from(&treasury_pro_info).unwrap(),
+                anti_mint: InterfaceAccount::<Mint>::try_from(&anti_mint_info).unwrap(),
+                pro_mint: InterfaceAccount::<Mint>::try_from(&pro_mint_info).unwrap(),
+                token_program: Interface::<TokenInterface>::try_from(&token_program_info).unwrap(),
+            };
+            let bumps = SweepDustBumps {
+                admin: admin_bump,
+                prediction: prediction_bump,
+                pool_authority: pool_authority_bump,
+                prediction_anti_token: anti_token_bump,
+                prediction_pro_token: pro_token_bump,
+            };
+
+            set_clock_override(Some(at));
+            let result = sweep_dust(Context::new(&program_id, &mut accounts, &[], bumps), 0);
+            match expected {
+                Ok(()) => assert!(result.is_ok()),
+                Err(error) => assert_eq!(result.unwrap_err(), error.into()),
+            }
+        }
+    }
+
+    #[test]
+    fn test_sweep_dust_constraints() {
+        let program_id = program_id();
+        let multisig = Pubkey::new_unique();
+        let seeds = |prefix: &[u8]| {
+            Pubkey::find_program_address(&[prefix, 0u64.to_le_bytes().as_ref()], &program_id).0
+        };
+        let (admin_pda, _) = Pubkey::find_program_address(&[b"admin"], &program_id);
+        let pool_authority_pda = seeds(b"authority");
+
+        let mut admin = TestAccountData::new_account_with_key_and_owner(admin_pda, program_id);
+        admin.init_data(
+            &AdminAccount {
+                antitoken_multisig: multisig,
+                ..test_admin()
+            },
+            AdminAccount::LEN,
+        );
+        let mut prediction =
+            TestAccountData::new_account_with_key_and_owner(seeds(b"prediction"), program_id);
+        prediction.init_data(&create_settled_test_prediction(2), PredictionAccount::LEN);
+        let pool_at = |prefix: &[u8], owner: Pubkey, mint: Pubkey| {
+            let mut account = TestAccountData::new_token(seeds(prefix));
+            account.init_token_account(owner, mint).unwrap();
+            account
+        };
+        let mut authority = TestAccountData::new_authority_account(multisig);
+        let mut stranger = TestAccountData::new_authority_account(Pubkey::new_unique());
+        let mut pool_authority = TestAccountData::new_authority_account(pool_authority_pda);
+        let mut prediction_anti = pool_at(b"anti_token", pool_authority_pda, ANTI_MINT_ADDRESS);
+        let mut prediction_pro = pool_at(b"pro_token", pool_authority_pda, PRO_MINT_ADDRESS);
+        let mut stray_anti = pool_at(b"anti_token", multisig, ANTI_MINT_ADDRESS);
+        let mut treasury_anti = TestAccountData::new_token_account(multisig, ANTI_MINT_ADDRESS, 0);
+        let mut treasury_pro = TestAccountData::new_token_account(multisig, PRO_MINT_ADDRESS, 0);
+        let mut foreign_anti =
+            TestAccountData::new_token_account(Pubkey::new_unique(), ANTI_MINT_ADDRESS, 0);
+        let mut anti_mint = TestAccountData::new_mint(ANTI_MINT_ADDRESS);
+        let mut pro_mint = TestAccountData::new_mint(PRO_MINT_ADDRESS);
+        let mut pro_mint_2022 = TestAccountData::new_token_2022_mint(PRO_MINT_ADDRESS);
+        let mut token_program = TestAccountData::new_token_program();
+
+        let authority_info = authority.to_account_info(true);
+        let treasury_pro_info = treasury_pro.to_account_info(false);
+        let accounts = vec![
+            admin.to_account_info(false),
+            prediction.to_account_info(false),
+            authority_info.clone(),
+            pool_authority.to_account_info(false),
+            prediction_anti.to_account_info(false),
+            prediction_pro.to_account_info(false),
+            treasury_anti.to_account_info(false),
+            treasury_pro_info.clone(),
+            anti_mint.to_account_info(false),
+            pro_mint.to_account_info(false),
+            token_program.to_account_info(false),
+        ];
+        let stranger_info = stranger.to_account_info(true);
+        let stray_anti_info = stray_anti.to_account_info(false);
+        let foreign_anti_info = foreign_anti.to_account_info(false);
+        let pro_mint_2022_info = pro_mint_2022.to_account_info(false);
+
+        // Only the multisig sweeps, from the pools to the treasury, and both mints
+        // must be held by the one token program driving the transfers
+        let cases: Vec<(Vec<AccountInfo>, Option<Error>)> = [
+            (2, &authority_info, None),
+            (2, &stranger_info, Some(PredictError::Unauthorised.into())),
+            (
+                4,
+                &stray_anti_info,
+                Some(PredictError::InvalidTokenAccount.into()),
+            ),
+            (
+                6,
+                &foreign_anti_info,
+                Some(PredictError::InvalidTokenAccount.into()),
+            ),
+            (
+                6,
+                &treasury_pro_info,
+                Some(PredictError::InvalidTokenAccount.into()),
+            ),
+            (
+                9,
+                &pro_mint_2022_info,
+                Some(ErrorCode::ConstraintMintTokenProgram.into()),
+            ),
+        ]
+        .into_iter()
+        .map(|(slot, account, expected)| {
+            let mut swapped = accounts.clone();
+            swapped[slot] = account.clone();
+            (swapped, expected)
+        })
+        .collect();
+        for (swapped, expected) in &cases {
+            let result = try_accounts::<SweepDust, SweepDustBumps>(swapped, &0u64.to_le_bytes());
+            assert_eq!(result.err().as_ref(), expected.as_ref());
+        }
+    }
+}
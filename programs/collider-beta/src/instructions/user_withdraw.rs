@@ -8,122 +8,114 @@
 //! Contact: dev@antitoken.pro
 
 // instructions/user_withdraw.rs
+use crate::state::*;
 use crate::utils::*;
 use crate::UserWithdrawTokens;
 use anchor_lang::prelude::*;
-use anchor_lang::system_program;
-use anchor_spl::token::{self, Transfer};
-use borsh::BorshSerialize;
+use anchor_spl::token_interface::{self, TransferChecked};
 
 pub fn user_withdraw<'a, 'b, 'c: 'info, 'info>(
     ctx: Context<'a, 'b, 'c, 'info, UserWithdrawTokens<'info>>,
     index: u64,
 ) -> Result<()> {
-    // Check token account authorities
-    let anti_token_authority = ctx.accounts.prediction_anti_token.owner;
-    let pro_token_authority = ctx.accounts.prediction_pro_token.owner;
+    // Get current time
+    let now = current_time()?;
 
-    // Both token accounts must have the same authority
+    // Get current deposit for the user
+    let user_key = ctx.accounts.authority.key();
     require!(
-        anti_token_authority == pro_token_authority,
-        PredictError::InvalidTokenAccount
+        !ctx.accounts.deposit_record.deposit.withdrawn,
+        PredictError::AlreadyWithdrawn
     );
 
-    let current_authority = anti_token_authority;
+    // Get withdrawal amounts, refunded in full if the prediction was voided
+    let (mut anti_return, mut pro_return) =
+        withdrawal(&ctx.accounts.prediction, &ctx.accounts.deposit_record, now)?;
+
+    // Mark deposit as withdrawn; the record is closed to the user on exit
+    let deposit_record = &mut ctx.accounts.deposit_record;
+    deposit_record.deposit.withdrawn = true;
+
+    // Serialise updated deposit record
+    write_account(&deposit_record.to_account_info(), &**deposit_record)?;
+    let mut records_withdrawn: u64 = 1;
+
+    // Settle the user's further deposits passed as remaining accounts in the same
+    // call, closing each record to the user
+    let remaining_accounts: &[AccountInfo<'info>] = ctx.remaining_accounts;
+    for account in remaining_accounts.iter() {
+        let deposit_record: Account<'info, DepositRecord> = Account::try_from(account)?;
+        require!(
+            deposit_record.index == index,
+            PredictError::InvalidDepositRecord
+        );
+        require!(
+            deposit_record.deposit.address == user_key,
+            PredictError::NoDeposit
+        );
 
-    // If authority is still multisig, user withdrawals aren't enabled yet
-    if current_authority == ANTITOKEN_MULTISIG {
-        return err!(PredictError::UserWithdrawalsNotEnabled);
+        // Records settled before they could be closed pay nothing but are closed too
+        if !deposit_record.deposit.withdrawn {
+            let (anti, pro) = withdrawal(&ctx.accounts.prediction, &deposit_record, now)?;
+            anti_return = anti_return
+                .checked_add(anti)
+                .ok_or(error!(PredictError::MathError))?;
+            pro_return = pro_return
+                .checked_add(pro)
+                .ok_or(error!(PredictError::MathError))?;
+            records_withdrawn += 1;
+        }
+        deposit_record.close(ctx.accounts.authority.to_account_info())?;
     }
 
-    // Authority must be either multisig or state PDA
-    let state_pda = ctx.accounts.state.key();
-    require!(current_authority == state_pda, PredictError::Unauthorised);
-
-    // Verify prediction has been equalised
-    require!(
-        &ctx.accounts.prediction.equalised,
-        PredictError::NotEqualised
-    );
-
-    let equalisation = &ctx
-        .accounts
-        .prediction
-        .equalisation
-        .clone()
-        .ok_or(error!(PredictError::NotEqualised))?;
-
-    // Get current deposit for the user
-    let user_key = ctx.accounts.authority.key();
-    let deposit_index = ctx
-        .accounts
-        .prediction
-        .deposits
-        .iter()
-        .position(|d| d.address == user_key)
-        .ok_or(error!(PredictError::NoDeposit))?;
-
-    let deposit = ctx.accounts.prediction.deposits[deposit_index].clone();
-    require!(!deposit.withdrawn, PredictError::AlreadyWithdrawn);
-
-    // Get withdrawal amounts
-    let anti_return = equalisation.anti[deposit_index];
-    let pro_return = equalisation.pro[deposit_index];
-
-    // Calculate and transfer payment (e.g., 0.001 SOL)
-    let payment_amount = 1_000_000;
-    system_program::transfer(
-        CpiContext::new(
-            ctx.accounts.system_program.to_account_info(),
-            system_program::Transfer {
-                from: ctx.accounts.authority.to_account_info(),
-                to: ctx.accounts.vault.to_account_info(),
-            },
-        ),
-        payment_amount,
-    )?;
+    // Payouts are signed by the prediction's PDA authority
+    let index_bytes = index.to_le_bytes();
+    let pool_bump = [ctx.bumps.pool_authority];
+    let pool_seeds: &[&[u8]] = &[b"authority", &index_bytes, &pool_bump];
+    let signer_seeds: &[&[&[u8]]] = &[pool_seeds];
 
     // Transfer ANTI tokens if any
     if anti_return > 0 {
-        token::transfer(
+        token_interface::transfer_checked(
             CpiContext::new_with_signer(
                 ctx.accounts.token_program.to_account_info(),
-                Transfer {
+                TransferChecked {
                     from: ctx.accounts.prediction_anti_token.to_account_info(),
+                    mint: ctx.accounts.anti_mint.to_account_info(),
                     to: ctx.accounts.user_anti_token.to_account_info(),
-                    authority: ctx.accounts.state.to_account_info(),
+                    authority: ctx.accounts.pool_authority.to_account_info(),
                 },
-                &[&[b"state", &[ctx.bumps.state]]],
+                signer_seeds,
             ),
             anti_return,
+            ctx.accounts.anti_mint.decimals,
         )?;
     }
 
     // Transfer PRO tokens if any
     if pro_return > 0 {
-        token::transfer(
+        token_interface::transfer_checked(
             CpiContext::new_with_signer(
                 ctx.accounts.token_program.to_account_info(),
-                Transfer {
+                TransferChecked {
                     from: ctx.accounts.prediction_pro_token.to_account_info(),
+                    mint: ctx.accounts.pro_mint.to_account_info(),
                     to: ctx.accounts.user_pro_token.to_account_info(),
-                    authority: ctx.accounts.state.to_account_info(),
+                    authority: ctx.accounts.pool_authority.to_account_info(),
                 },
-                &[&[b"state", &[ctx.bumps.state]]],
+                signer_seeds,
             ),
             pro_return,
+            ctx.accounts.pro_mint.decimals,
         )?;
     }
 
-    // Mark deposit as withdrawn
+    // Count the records and payouts as withdrawn on the prediction
     let prediction = &mut ctx.accounts.prediction;
-    prediction.deposits[deposit_index].withdrawn = true;
+    prediction.record_withdrawal(records_withdrawn, anti_return, pro_return)?;
 
     // Serialise updated prediction state
-    let prediction_info = prediction.to_account_info();
-    let mut prediction_data = prediction_info.try_borrow_mut_data()?;
-    let serialised_prediction = prediction.try_to_vec()?;
-    prediction_data[8..8 + serialised_prediction.len()].copy_from_slice(&serialised_prediction);
+    write_account(&prediction.to_account_info(), &**prediction)?;
 
     // Emit withdrawal event
     emit!(WithdrawEvent {
@@ -131,7 +123,7 @@ pub fn user_withdraw<'a, 'b, 'c: 'info, 'info>(
         address: user_key,
         anti: anti_return,
         pro: pro_return,
-        timestamp: Clock::get()?.unix_timestamp,
+        timestamp: now,
     });
 
     Ok(())
@@ -140,297 +132,525 @@ pub fn user_withdraw<'a, 'b, 'c: 'info, 'info>(
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::utils::PROGRAM_ID;
-    use crate::Deposit;
+    use crate::test_utils::*;
+    use crate::DepositRecord;
     use crate::Equalisation;
     use crate::PredictionAccount;
-    use crate::StateAccount;
     use crate::UserWithdrawTokensBumps;
+    use anchor_lang::error::ErrorCode;
     use anchor_lang::Discriminator;
-    use anchor_spl::token::spl_token;
-    use anchor_spl::token::Mint;
-    use anchor_spl::token::{spl_token::state::Account as SplTokenAccount, TokenAccount};
-    use solana_sdk::program_option::COption;
-    use solana_sdk::program_pack::Pack;
-    use std::str::FromStr;
-
-    // Fixed test IDs - these should be consistent across tests
-    fn program_id() -> Pubkey {
-        Pubkey::from_str(&PROGRAM_ID.to_string()).unwrap()
-    }
-
-    struct TestAccountData {
-        key: Pubkey,
-        lamports: u64,
-        data: Vec<u8>,
-        owner: Pubkey,
-        executable: bool,
-        rent_epoch: u64,
+    use anchor_spl::token_interface::{Mint, TokenInterface};
+
+    // Reusable method to create an equalised test prediction
+    fn create_equalised_test_prediction() -> PredictionAccount {
+        PredictionAccount {
+            anti: 70000,
+            pro: 30000,
+            deposits: 1,
+            equalised: true,
+            equalisation: Some(Equalisation {
+                anti: vec![70000],
+                pro: vec![30000],
+                anti_staked: vec![70000],
+                pro_staked: vec![30000],
+                binned: 1,
+                truth: vec![60000, 40000],
+                timestamp: 1736899200,
+                round: 0,
+            }),
+            challenge_end: 1736899200 + CHALLENGE_PERIOD,
+            ..test_prediction()
+        }
     }
 
-    impl TestAccountData {
-        fn new_account_with_key_and_owner<T: AccountSerialize + AccountDeserialize + Clone>(
-            key: Pubkey,
-            owner: Pubkey,
-        ) -> Self {
-            Self {
-                key,
-                lamports: 1_000_000,
-                data: vec![0; 8 + PredictionAccount::LEN],
-                owner,
-                executable: true,
-                rent_epoch: 0,
-            }
-        }
+    #[test]
+    fn test_user_withdrawal() {
+        let program_id = program_id();
+        let index: u64 = 0;
 
-        fn to_account_info<'a>(&'a mut self, is_signer: bool) -> AccountInfo<'a> {
-            AccountInfo::new(
-                &self.key,
-                is_signer,
-                true,
-                &mut self.lamports,
-                &mut self.data,
-                &self.owner,
-                self.executable,
-                self.rent_epoch,
-            )
-        }
+        // Create mints
+        let mut anti_mint = TestAccountData::new_mint(ANTI_MINT_ADDRESS);
+        let mut pro_mint = TestAccountData::new_mint(PRO_MINT_ADDRESS);
 
-        fn new_token() -> Self {
-            Self {
-                key: Pubkey::new_unique(),
-                lamports: 1_000_000,
-                data: vec![0; 165],
-                owner: spl_token::ID,
-                executable: false,
-                rent_epoch: 0,
-            }
-        }
+        // Create test accounts
+        let (admin_pda, admin_bump) = Pubkey::find_program_address(&[b"admin"], &program_id);
+        let mut admin = TestAccountData::new_account_with_key_and_owner(admin_pda, program_id);
+        let mut prediction =
+            TestAccountData::new_account_with_key_and_owner(Pubkey::new_unique(), program_id);
+        let (pool_authority_pda, pool_authority_bump) = Pubkey::find_program_address(
+            &[b"authority", index.to_le_bytes().as_ref()],
+            &program_id,
+        );
+        let mut pool_authority = TestAccountData::new_authority_account(pool_authority_pda);
+        let mut user = TestAccountData::new_authority_account(Pubkey::new_unique());
 
-        fn init_state_data(&mut self, state: &StateAccount) -> Result<()> {
-            self.data = vec![0; 8 + StateAccount::LEN];
-            let data = self.data.as_mut_slice();
+        // Initialise token accounts
+        let mut user_anti = TestAccountData::new_token(Pubkey::new_unique());
+        let mut user_pro = TestAccountData::new_token(Pubkey::new_unique());
+        let mut prediction_anti = TestAccountData::new_token(Pubkey::new_unique());
+        let mut prediction_pro = TestAccountData::new_token(Pubkey::new_unique());
 
-            let disc = StateAccount::discriminator();
-            data[..8].copy_from_slice(&disc);
+        user_anti
+            .init_token_account(user.key, anti_mint.key)
+            .unwrap();
+        user_pro.init_token_account(user.key, pro_mint.key).unwrap();
+        prediction_anti
+            .init_token_account(pool_authority.key, anti_mint.key)
+            .unwrap(); // Note: per-prediction PDA is the authority
+        prediction_pro
+            .init_token_account(pool_authority.key, pro_mint.key)
+            .unwrap(); // Note: per-prediction PDA is the authority
 
-            let account_data = state.try_to_vec()?;
-            data[8..8 + account_data.len()].copy_from_slice(&account_data);
+        let mut token_program = TestAccountData::new_token_program();
 
-            Ok(())
-        }
+        // Create admin config
+        admin.init_admin_data(&test_admin()).unwrap();
 
-        fn init_token_account(&mut self, owner: Pubkey, mint: Pubkey) -> Result<()> {
-            self.data = vec![0; TokenAccount::LEN]; // Ensure correct buffer size
-            let data = self.data.as_mut_slice();
+        // Create prediction with user's deposit and equalisation results
+        let prediction_data = create_equalised_test_prediction();
 
-            let close_authority: COption<Pubkey> = COption::None;
+        // Write discriminator and serialise prediction data
+        prediction.data[..8].copy_from_slice(&PredictionAccount::discriminator());
+        let serialised_prediction = prediction_data.try_to_vec().unwrap();
+        prediction.data[8..8 + serialised_prediction.len()].copy_from_slice(&serialised_prediction);
 
-            // Initialise a new SPL Token Account manually
-            let token_account = SplTokenAccount {
-                mint,
-                owner,
-                amount: 0,
-                delegate: None.into(),
-                state: spl_token::state::AccountState::Initialized,
-                is_native: None.into(),
-                delegated_amount: 0,
-                close_authority,
-            };
+        // Create user's deposit record
+        let mut deposit_record =
+            TestAccountData::new_account_with_key_and_owner(Pubkey::new_unique(), program_id);
+        deposit_record
+            .init_deposit_record_data(&test_deposit_record(user.key, 0, 70000, 30000))
+            .unwrap();
 
-            token_account.pack_into_slice(data);
+        // Get account infos
+        let admin_info = admin.to_account_info(false);
+        let pool_authority_info = pool_authority.to_account_info(false);
+        let prediction_info = prediction.to_account_info(false);
+        let deposit_record_info = deposit_record.to_account_info(false);
+        let user_info = user.to_account_info(true);
+        let user_anti_info = user_anti.to_account_info(false);
+        let user_pro_info = user_pro.to_account_info(false);
+        let prediction_anti_info = prediction_anti.to_account_info(false);
+        let prediction_pro_info = prediction_pro.to_account_info(false);
+        let anti_mint_info = anti_mint.to_account_info(false);
+        let pro_mint_info = pro_mint.to_account_info(false);
+        let token_program_info = token_program.to_account_info(false);
 
-            Ok(())
-        }
+        // Derive PDAs and bumps
+        let (_prediction_pda, prediction_bump) = Pubkey::find_program_address(
+            &[b"prediction", index.to_le_bytes().as_ref()],
+            &program_id,
+        );
+        let (_anti_token_pda, anti_token_bump) = Pubkey::find_program_address(
+            &[b"anti_token", prediction_data.index.to_le_bytes().as_ref()],
+            &program_id,
+        );
+        let (_pro_token_pda, pro_token_bump) = Pubkey::find_program_address(
+            &[b"pro_token", prediction_data.index.to_le_bytes().as_ref()],
+            &program_id,
+        );
 
-        fn new_token_program() -> Self {
-            Self {
-                key: spl_token::ID,
-                lamports: 1_000_000,
-                data: vec![],
-                owner: Pubkey::default(),
-                executable: true,
-                rent_epoch: 0,
-            }
-        }
+        let mut accounts = UserWithdrawTokens {
+            admin: Account::try_from(&admin_info).unwrap(),
+            prediction: Account::try_from(&prediction_info).unwrap(),
+            deposit_record: Account::try_from(&deposit_record_info).unwrap(),
+            authority: Signer::try_from(&user_info).unwrap(),
+            user_anti_token: InterfaceAccount::try_from(&user_anti_info).unwrap(),
+            user_pro_token: InterfaceAccount::try_from(&user_pro_info).unwrap(),
+            pool_authority: pool_authority_info.clone(),
+            prediction_anti_token: InterfaceAccount::try_from(&prediction_anti_info).unwrap(),
+            prediction_pro_token: InterfaceAccount::try_from(&prediction_pro_info).unwrap(),
+            anti_mint: InterfaceAccount::<Mint>::try_from(&anti_mint_info).unwrap(),
+            pro_mint: InterfaceAccount::<Mint>::try_from(&pro_mint_info).unwrap(),
+            token_program: Interface::<TokenInterface>::try_from(&token_program_info).unwrap(),
+        };
 
-        fn new_vault_with_key() -> Self {
-            Self {
-                key: ANTITOKEN_MULTISIG,
-                lamports: 10_000_000,
-                data: vec![],
-                owner: system_program::ID,
-                executable: false,
-                rent_epoch: 0,
-            }
-        }
+        let bumps = || UserWithdrawTokensBumps {
+            admin: admin_bump,
+            prediction: prediction_bump,
+            pool_authority: pool_authority_bump,
+            prediction_anti_token: anti_token_bump,
+            prediction_pro_token: pro_token_bump,
+        };
 
-        fn new_system_account() -> Self {
-            Self {
-                key: system_program::ID,
-                lamports: 1_000_000,
-                data: vec![],
-                owner: system_program::ID,
-                executable: true,
-                rent_epoch: 0,
-            }
+        // Withdrawals are blocked while the challenge period is open
+        set_clock_override(Some(1736899200 + 3600));
+        let result = user_withdraw(Context::new(&program_id, &mut accounts, &[], bumps()), 0);
+        match result {
+            Err(err) => assert_eq!(err, PredictError::ChallengePeriodActive.into()),
+            _ => panic!("Expected challenge period active error"),
         }
 
-        fn new_mint(mint: Pubkey) -> Self {
-            Self {
-                key: mint,
-                lamports: 1_000_000,
-                data: vec![0; Mint::LEN],
-                owner: spl_token::ID,
-                executable: false,
-                rent_epoch: 0,
-            }
-        }
+        set_clock_override(Some(1736899200 + CHALLENGE_PERIOD));
+        let result = user_withdraw(Context::new(&program_id, &mut accounts, &[], bumps()), 0);
+        assert!(result.is_ok());
+
+        // Payouts count towards the prediction's conservation check
+        assert_eq!(accounts.prediction.bulk.anti, 70000);
+        assert_eq!(accounts.prediction.bulk.pro, 30000);
+
+        // Voided predictions refund deposits without equalisation or a challenge period
+        accounts.deposit_record.deposit.withdrawn = false;
+        accounts.prediction.bulk = BulkProgress::default();
+        accounts.prediction.equalised = false;
+        accounts.prediction.equalisation = None;
+        accounts.prediction.voided = true;
+        set_clock_override(Some(1736899200));
+        let result = user_withdraw(Context::new(&program_id, &mut accounts, &[], bumps()), 0);
+        assert!(result.is_ok());
+        assert!(accounts.deposit_record.deposit.withdrawn);
+    }
 
-        fn new_authority_account(pubkey: Pubkey) -> Self {
-            Self {
-                key: pubkey,
-                lamports: 1_000_000,
-                data: vec![],
-                owner: system_program::ID,
-                executable: true,
-                rent_epoch: 0,
-            }
-        }
+    #[test]
+    fn test_user_withdraw_constraints() {
+        let program_id = program_id();
+        let pda = |seed: &[u8], index: u64| {
+            Pubkey::find_program_address(&[seed, index.to_le_bytes().as_ref()], &program_id).0
+        };
+        let (admin_pda, _) = Pubkey::find_program_address(&[b"admin"], &program_id);
+        let mut admin = TestAccountData::new_account_with_key_and_owner(admin_pda, program_id);
+        admin.init_admin_data(&test_admin()).unwrap();
+
+        // Two settled predictions, each with pools held by its own PDA authority
+        let prediction_at = |key: Pubkey, index: u64| {
+            let mut account = TestAccountData::new_account_with_key_and_owner(key, program_id);
+            account
+                .init_prediction_data(&PredictionAccount {
+                    version: PREDICTION_VERSION,
+                    index,
+                    ..create_equalised_test_prediction()
+                })
+                .unwrap();
+            account
+        };
+        let mut first_prediction = prediction_at(pda(b"prediction", 0), 0);
+        let mut second_prediction = prediction_at(pda(b"prediction", 1), 1);
+        let mut misindexed_prediction = prediction_at(pda(b"prediction", 0), 1);
+        let pool_at = |seed: &[u8], index: u64, owner: Pubkey, mint: Pubkey| {
+            let mut account = TestAccountData::new_token(pda(seed, index));
+            account.init_token_account(owner, mint).unwrap();
+            account
+        };
+        let mut first_authority = TestAccountData::new_authority_account(pda(b"authority", 0));
+        let mut second_authority = TestAccountData::new_authority_account(pda(b"authority", 1));
+        let mut first_anti = pool_at(b"anti_token", 0, first_authority.key, ANTI_MINT_ADDRESS);
+        let mut first_pro = pool_at(b"pro_token", 0, first_authority.key, PRO_MINT_ADDRESS);
+        let mut second_anti = pool_at(b"anti_token", 1, second_authority.key, ANTI_MINT_ADDRESS);
+        let mut legacy_anti = pool_at(b"anti_token", 0, ANTITOKEN_MULTISIG, ANTI_MINT_ADDRESS);
+
+        // User deposited into the first prediction only
+        let mut user = TestAccountData::new_authority_account(Pubkey::new_unique());
+        let record_for = |address: Pubkey, index: u64| {
+            let mut account =
+                TestAccountData::new_account_with_key_and_owner(Pubkey::new_unique(), program_id);
+            account
+                .init_deposit_record_data(&DepositRecord {
+                    index,
+                    ..test_deposit_record(address, 0, 70000, 30000)
+                })
+                .unwrap();
+            account
+        };
+        let mut deposit_record = record_for(user.key, 0);
+        let mut replayed_record = record_for(user.key, 1);
+        let mut other_record = record_for(Pubkey::new_unique(), 0);
+        let mut user_anti = TestAccountData::new_token_account(user.key, ANTI_MINT_ADDRESS, 0);
+        let mut user_pro = TestAccountData::new_token_account(user.key, PRO_MINT_ADDRESS, 0);
+        let mut anti_mint = TestAccountData::new_mint(ANTI_MINT_ADDRESS);
+        let mut pro_mint = TestAccountData::new_mint(PRO_MINT_ADDRESS);
+        let mut pro_mint_2022 = TestAccountData::new_token_2022_mint(PRO_MINT_ADDRESS);
+        let mut token_program = TestAccountData::new_token_program();
 
-        // Reusable method to create an equalised test prediction
-        fn create_equalised_test_prediction(authority: Pubkey) -> PredictionAccount {
-            PredictionAccount {
-                index: 0,
-                title: "Test Prediction".to_string(),
-                description: "Test Description".to_string(),
-                start_time: "2025-01-01T00:00:00Z".to_string(),
-                end_time: "2025-01-02T00:00:00Z".to_string(), // Already ended
-                etc: None,
-                anti: 70000,
-                pro: 30000,
-                deposits: vec![Deposit {
-                    address: authority,
-                    anti: 70000,
-                    pro: 30000,
-                    mean: 40000,
-                    stddev: 100000,
-                    withdrawn: false,
-                }],
-                equalised: true,
-                equalisation: Some(Equalisation {
-                    truth: vec![60000, 40000],
-                    anti: vec![70000],
-                    pro: vec![30000],
-                    timestamp: 0,
-                }),
-            }
+        let first_prediction_info = first_prediction.to_account_info(false);
+        let first_authority_info = first_authority.to_account_info(false);
+        let first_anti_info = first_anti.to_account_info(false);
+        let deposit_record_info = deposit_record.to_account_info(false);
+        let user_anti_info = user_anti.to_account_info(false);
+        let user_pro_info = user_pro.to_account_info(false);
+        let pro_mint_info = pro_mint.to_account_info(false);
+        let accounts = vec![
+            admin.to_account_info(false),
+            first_prediction_info.clone(),
+            deposit_record_info.clone(),
+            user.to_account_info(true),
+            user_anti_info.clone(),
+            user_pro_info.clone(),
+            first_authority_info.clone(),
+            first_anti_info.clone(),
+            first_pro.to_account_info(false),
+            anti_mint.to_account_info(false),
+            pro_mint_info.clone(),
+            token_program.to_account_info(false),
+        ];
+        let second_prediction_info = second_prediction.to_account_info(false);
+        let misindexed_prediction_info = misindexed_prediction.to_account_info(false);
+        let second_authority_info = second_authority.to_account_info(false);
+        let second_anti_info = second_anti.to_account_info(false);
+        let legacy_anti_info = legacy_anti.to_account_info(false);
+        let replayed_record_info = replayed_record.to_account_info(false);
+        let other_record_info = other_record.to_account_info(false);
+        let pro_mint_2022_info = pro_mint_2022.to_account_info(false);
+
+        // Each account swapped in for its slot, under the index given
+        let cases: Vec<(Vec<AccountInfo>, u64, Option<Error>)> = [
+            (1, &first_prediction_info, 0, None),
+            (
+                1,
+                &second_prediction_info,
+                0,
+                Some(ErrorCode::ConstraintSeeds.into()),
+            ),
+            (
+                1,
+                &misindexed_prediction_info,
+                0,
+                Some(PredictError::IndexMismatch.into()),
+            ),
+            (
+                1,
+                &first_prediction_info,
+                1,
+                Some(ErrorCode::ConstraintSeeds.into()),
+            ),
+            (
+                6,
+                &second_authority_info,
+                0,
+                Some(ErrorCode::ConstraintSeeds.into()),
+            ),
+            (
+                7,
+                &second_anti_info,
+                0,
+                Some(ErrorCode::ConstraintSeeds.into()),
+            ),
+            (
+                7,
+                &legacy_anti_info,
+                0,
+                Some(PredictError::InvalidTokenAccount.into()),
+            ),
+            (
+                2,
+                &replayed_record_info,
+                0,
+                Some(PredictError::InvalidDepositRecord.into()),
+            ),
+            (
+                2,
+                &other_record_info,
+                0,
+                Some(PredictError::NoDeposit.into()),
+            ),
+            (
+                4,
+                &user_pro_info,
+                0,
+                Some(PredictError::InvalidTokenAccount.into()),
+            ),
+            (
+                10,
+                &pro_mint_2022_info,
+                0,
+                Some(ErrorCode::ConstraintMintTokenProgram.into()),
+            ),
+        ]
+        .into_iter()
+        .map(|(slot, account, index, expected)| {
+            let mut swapped = accounts.clone();
+            swapped[slot] = account.clone();
+            (swapped, index, expected)
+        })
+        .collect();
+        for (swapped, index, expected) in &cases {
+            let result = try_accounts::<UserWithdrawTokens, UserWithdrawTokensBumps>(
+                swapped,
+                &index.to_le_bytes(),
+            );
+            assert_eq!(result.err().as_ref(), expected.as_ref());
         }
     }
 
     #[test]
-    fn test_user_withdrawal() {
+    fn test_user_withdrawal_aggregates_deposits() {
         let program_id = program_id();
         let index: u64 = 0;
 
         // Create mints
-        let anti_mint = TestAccountData::new_mint(ANTI_MINT_ADDRESS);
-        let pro_mint = TestAccountData::new_mint(PRO_MINT_ADDRESS);
+        let mut anti_mint = TestAccountData::new_mint(ANTI_MINT_ADDRESS);
+        let mut pro_mint = TestAccountData::new_mint(PRO_MINT_ADDRESS);
 
         // Create test accounts
-        let mut prediction = TestAccountData::new_account_with_key_and_owner::<PredictionAccount>(
-            Pubkey::new_unique(),
-            program_id,
-        );
-        let mut state = TestAccountData::new_account_with_key_and_owner::<StateAccount>(
-            Pubkey::new_unique(),
-            program_id,
+        let (admin_pda, admin_bump) = Pubkey::find_program_address(&[b"admin"], &program_id);
+        let mut admin = TestAccountData::new_account_with_key_and_owner(admin_pda, program_id);
+        let mut prediction =
+            TestAccountData::new_account_with_key_and_owner(Pubkey::new_unique(), program_id);
+        let (pool_authority_pda, pool_authority_bump) = Pubkey::find_program_address(
+            &[b"authority", index.to_le_bytes().as_ref()],
+            &program_id,
         );
+        let mut pool_authority = TestAccountData::new_authority_account(pool_authority_pda);
         let mut user = TestAccountData::new_authority_account(Pubkey::new_unique());
-        let mut vault = TestAccountData::new_vault_with_key();
 
         // Initialise token accounts
-        let mut user_anti = TestAccountData::new_token();
-        let mut user_pro = TestAccountData::new_token();
-        let mut prediction_anti = TestAccountData::new_token();
-        let mut prediction_pro = TestAccountData::new_token();
-
+        let mut user_anti = TestAccountData::new_token(Pubkey::new_unique());
+        let mut user_pro = TestAccountData::new_token(Pubkey::new_unique());
+        let mut prediction_anti = TestAccountData::new_token(Pubkey::new_unique());
+        let mut prediction_pro = TestAccountData::new_token(Pubkey::new_unique());
         user_anti
             .init_token_account(user.key, anti_mint.key)
             .unwrap();
         user_pro.init_token_account(user.key, pro_mint.key).unwrap();
         prediction_anti
-            .init_token_account(state.key, anti_mint.key)
-            .unwrap(); // Note: state PDA is the authority
+            .init_token_account(pool_authority.key, anti_mint.key)
+            .unwrap();
         prediction_pro
-            .init_token_account(state.key, pro_mint.key)
-            .unwrap(); // Note: state PDA is the authority
+            .init_token_account(pool_authority.key, pro_mint.key)
+            .unwrap();
 
         let mut token_program = TestAccountData::new_token_program();
-        let mut system_program = TestAccountData::new_system_account();
 
-        // Create state data
-        let state_data = StateAccount {
-            index,
-            authority: Pubkey::new_unique(),
-        };
-        state.init_state_data(&state_data).unwrap();
-
-        // Create prediction with user's deposit and equalisation results
-        let prediction_data = TestAccountData::create_equalised_test_prediction(user.key);
+        // Create admin config
+        admin.init_admin_data(&test_admin()).unwrap();
 
-        // Write discriminator and serialise prediction data
+        // Three deposits in one bin, two of them by the same user
+        let prediction_data = PredictionAccount {
+            version: PREDICTION_VERSION,
+            deposits: 3,
+            ..create_equalised_test_prediction()
+        };
         prediction.data[..8].copy_from_slice(&PredictionAccount::discriminator());
         let serialised_prediction = prediction_data.try_to_vec().unwrap();
         prediction.data[8..8 + serialised_prediction.len()].copy_from_slice(&serialised_prediction);
 
+        let mut first_record =
+            TestAccountData::new_account_with_key_and_owner(Pubkey::new_unique(), program_id);
+        let mut first_deposit_record = test_deposit_record(user.key, 0, 70000, 30000);
+        first_deposit_record.deposit.anti = 40000;
+        first_deposit_record.deposit.pro = 20000;
+        first_record
+            .init_deposit_record_data(&first_deposit_record)
+            .unwrap();
+
+        let mut second_deposit_record = test_deposit_record(user.key, 0, 70000, 30000);
+        second_deposit_record.nonce = 1;
+        second_deposit_record.deposit.anti = 20000;
+        second_deposit_record.deposit.pro = 5000;
+        second_deposit_record.anti_offset = 40000; // Binned after the first record
+        second_deposit_record.pro_offset = 20000;
+        let mut second_record = ReallocAccountData::new_deposit_record(
+            Pubkey::new_unique(),
+            program_id,
+            &second_deposit_record,
+        );
+
+        let mut foreign_deposit_record = test_deposit_record(Pubkey::new_unique(), 0, 70000, 30000);
+        foreign_deposit_record.deposit.anti = 10000;
+        foreign_deposit_record.deposit.pro = 5000;
+        foreign_deposit_record.anti_offset = 60000;
+        foreign_deposit_record.pro_offset = 25000;
+        let mut foreign_record = ReallocAccountData::new_deposit_record(
+            Pubkey::new_unique(),
+            program_id,
+            &foreign_deposit_record,
+        );
+
         // Get account infos
-        let state_info = state.to_account_info(false);
+        let admin_info = admin.to_account_info(false);
         let prediction_info = prediction.to_account_info(false);
+        let pool_authority_info = pool_authority.to_account_info(false);
+        let first_record_info = first_record.to_account_info(false);
+        let second_record_info = second_record.to_account_info();
+        let foreign_record_info = foreign_record.to_account_info();
         let user_info = user.to_account_info(true);
         let user_anti_info = user_anti.to_account_info(false);
         let user_pro_info = user_pro.to_account_info(false);
         let prediction_anti_info = prediction_anti.to_account_info(false);
         let prediction_pro_info = prediction_pro.to_account_info(false);
+        let anti_mint_info = anti_mint.to_account_info(false);
+        let pro_mint_info = pro_mint.to_account_info(false);
         let token_program_info = token_program.to_account_info(false);
-        let system_program_info = system_program.to_account_info(false);
-        let vault_info = vault.to_account_info(false);
 
         // Derive PDAs and bumps
-        let (_state_pda, state_bump) = Pubkey::find_program_address(&[b"state"], &program_id);
         let (_prediction_pda, prediction_bump) = Pubkey::find_program_address(
             &[b"prediction", index.to_le_bytes().as_ref()],
             &program_id,
         );
         let (_anti_token_pda, anti_token_bump) = Pubkey::find_program_address(
-            &[b"anti_token", prediction_data.index.to_le_bytes().as_ref()],
+            &[b"anti_token", index.to_le_bytes().as_ref()],
             &program_id,
         );
         let (_pro_token_pda, pro_token_bump) = Pubkey::find_program_address(
-            &[b"pro_token", prediction_data.index.to_le_bytes().as_ref()],
+            &[b"pro_token", index.to_le_bytes().as_ref()],
             &program_id,
         );
 
         let mut accounts = UserWithdrawTokens {
-            state: Account::try_from(&state_info).unwrap(),
+            admin: Account::try_from(&admin_info).unwrap(),
             prediction: Account::try_from(&prediction_info).unwrap(),
+            deposit_record: Account::try_from(&first_record_info).unwrap(),
             authority: Signer::try_from(&user_info).unwrap(),
-            user_anti_token: Account::try_from(&user_anti_info).unwrap(),
-            user_pro_token: Account::try_from(&user_pro_info).unwrap(),
-            prediction_anti_token: Account::try_from(&prediction_anti_info).unwrap(),
-            prediction_pro_token: Account::try_from(&prediction_pro_info).unwrap(),
-            token_program: Program::try_from(&token_program_info).unwrap(),
-            system_program: Program::try_from(&system_program_info).unwrap(),
-            vault: vault_info,
+            user_anti_token: InterfaceAccount::try_from(&user_anti_info).unwrap(),
+            user_pro_token: InterfaceAccount::try_from(&user_pro_info).unwrap(),
+            pool_authority: pool_authority_info,
+            prediction_anti_token: InterfaceAccount::try_from(&prediction_anti_info).unwrap(),
+            prediction_pro_token: InterfaceAccount::try_from(&prediction_pro_info).unwrap(),
+            anti_mint: InterfaceAccount::<Mint>::try_from(&anti_mint_info).unwrap(),
+            pro_mint: InterfaceAccount::<Mint>::try_from(&pro_mint_info).unwrap(),
+            token_program: Interface::<TokenInterface>::try_from(&token_program_info).unwrap(),
         };
 
-        let bumps = UserWithdrawTokensBumps {
-            state: state_bump,
+        let bumps = || UserWithdrawTokensBumps {
+            admin: admin_bump,
             prediction: prediction_bump,
+            pool_authority: pool_authority_bump,
             prediction_anti_token: anti_token_bump,
             prediction_pro_token: pro_token_bump,
         };
 
-        let _ = user_withdraw(Context::new(&program_id, &mut accounts, &[], bumps), 0);
+        set_clock_override(Some(1736899200 + CHALLENGE_PERIOD));
+
+        // Deposits made by another user cannot be settled by the signer
+        let remaining_accounts = vec![foreign_record_info.clone()];
+        let result = user_withdraw(
+            Context::new(&program_id, &mut accounts, &remaining_accounts, bumps()),
+            0,
+        );
+        match result {
+            Err(err) => assert_eq!(err, PredictError::NoDeposit.into()),
+            _ => panic!("Expected no deposit error"),
+        }
+        accounts.deposit_record.deposit.withdrawn = false;
+
+        // Both of the user's deposits are settled in one call, with the further
+        // record closed to the user
+        let remaining_accounts = vec![second_record_info.clone()];
+        let result = user_withdraw(
+            Context::new(&program_id, &mut accounts, &remaining_accounts, bumps()),
+            0,
+        );
+        assert!(result.is_ok());
+        assert!(accounts.deposit_record.deposit.withdrawn);
+        assert!(second_record_info.data_is_empty());
+        assert_eq!(user_info.lamports(), 2_000_000);
+        assert_eq!(accounts.prediction.withdrawn, 2);
+        assert_eq!(accounts.prediction.bulk.anti, 60000);
+
+        // Closed records cannot be settled again
+        accounts.deposit_record.deposit.withdrawn = false;
+        let result = user_withdraw(
+            Context::new(&program_id, &mut accounts, &remaining_accounts, bumps()),
+            0,
+        );
+        assert_eq!(
+            result.unwrap_err(),
+            anchor_lang::error::ErrorCode::AccountNotInitialized.into()
+        );
+
+        // The other user's record is untouched
+        let record: DepositRecord = DepositRecord::try_deserialize(
+            &mut foreign_record_info.try_borrow_data().unwrap().as_ref(),
+        )
+        .unwrap();
+        assert!(!record.deposit.withdrawn);
     }
 }
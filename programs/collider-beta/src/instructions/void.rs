@@ -0,0 +1,251 @@
+//! Program Author: sshmatrix, for Antitoken
+//! Program Description: Collider's instruction set
+//! Version: 1.0.0-beta
+//! License: MIT
+//! Created: 20 Jan 2025
+//! Last Modified: 20 Jan 2025
+//! Repository: https://github.com/antitokens/solana-collider
+//! Contact: dev@antitoken.pro
+
+// instructions/void.rs
+use crate::utils::*;
+use crate::VoidPrediction;
+use anchor_lang::prelude::*;
+
+pub fn void_prediction(ctx: Context<VoidPrediction>, index: u64) -> Result<()> {
+    // Get current time
+    let now = current_time()?;
+
+    let authority_key = ctx.accounts.authority.key();
+    let prediction = &mut ctx.accounts.prediction;
+
+    // Verify prediction is neither voided nor equalised
+    require!(!prediction.voided, PredictError::PredictionVoided);
+    require!(!prediction.equalised, PredictError::AlreadyEqualised);
+
+    // Re-equalisation after a dispute clears the flag, but fees are paid and a bond is held
+    require!(prediction.dispute.is_none(), PredictError::AlreadyDisputed);
+    require!(
+        !prediction.fees.carved && prediction.equalisation.is_none(),
+        PredictError::AlreadyEqualised
+    );
+
+    // The multisig may void at any time, the creator only before the start
+    if authority_key != ctx.accounts.admin.antitoken_multisig {
+        require!(
+            authority_key == prediction.creator,
+            PredictError::Unauthorised
+        );
+        require!(now < prediction.start_time, PredictError::Unauthorised);
+    }
+
+    // Deposits are refunded in full from the untouched pools
+    prediction.voided = true;
+
+    // Serialise updated prediction state
+    write_account(&prediction.to_account_info(), &**prediction)?;
+
+    emit!(VoidEvent {
+        index,
+        address: authority_key,
+        anti: prediction.anti,
+        pro: prediction.pro,
+        timestamp: now,
+    });
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::*;
+    use crate::test_utils::*;
+    use crate::VoidPredictionBumps;
+
+    // Reusable method to create a funded test prediction
+    fn create_test_prediction(creator: Pubkey) -> PredictionAccount {
+        PredictionAccount {
+            start_time: 1738368000,
+            end_time: 1740787200,
+            creator,
+            anti: 10000,
+            pro: 8000,
+            deposits: 2,
+            ..test_prediction()
+        }
+    }
+
+    #[test]
+    fn test_void_prediction() {
+        let program_id = program_id();
+        let before_start: i64 = 1736899200;
+        let after_start: i64 = 1738368000;
+        let multisig = Pubkey::new_unique();
+        let creator = Pubkey::new_unique();
+        let stranger = Pubkey::new_unique();
+
+        // Create test accounts
+        let (admin_pda, admin_bump) = Pubkey::find_program_address(&[b"admin"], &program_id);
+        let (prediction_pda, prediction_bump) = Pubkey::find_program_address(
+            &[b"prediction", 0u64.to_le_bytes().as_ref()],
+            &program_id,
+        );
+
+        let mut admin = TestAccountData::new_account_with_key_and_owner(admin_pda, program_id);
+        admin.init_data(
+            &AdminAccount {
+                antitoken_multisig: multisig,
+                ..test_admin()
+            },
+            AdminAccount::LEN,
+        );
+        let mut prediction =
+            TestAccountData::new_account_with_key_and_owner(prediction_pda, program_id);
+
+        // Creator before the start, then multisig at any time before equalisation
+        for (signer, at, equalised, voided, expected) in [
+            (creator, before_start, false, false, Ok(())),
+            (
+                creator,
+                after_start,
+                false,
+                false,
+                Err(PredictError::Unauthorised),
+            ),
+            (
+                stranger,
+                before_start,
+                false,
+                false,
+                Err(PredictError::Unauthorised),
+            ),
+            (multisig, after_start, false, false, Ok(())),
+            (
+                multisig,
+                after_start,
+                true,
+                false,
+                Err(PredictError::AlreadyEqualised),
+            ),
+            (
+                multisig,
+                after_start,
+                false,
+                true,
+                Err(PredictError::PredictionVoided),
+            ),
+        ] {
+            prediction.init_data(
+                &PredictionAccount {
+                    version: PREDICTION_VERSION,
+                    equalised,
+                    voided,
+                    ..create_test_prediction(creator)
+                },
+                PredictionAccount::LEN,
+            );
+            let mut authority = TestAccountData::new_authority_account(signer);
+
+            let admin_info = admin.to_account_info(false);
+            let prediction_info = prediction.to_account_info(false);
+            let authority_info = authority.to_account_info(true);
+
+            let mut accounts = VoidPrediction {
+                admin: Account::try_from(&admin_info).unwrap(),
+                prediction: Account::try_from(&prediction_info).unwrap(),
+                authority: Signer::try_from(&authority_info).unwrap(),
+            };
+            let bumps = VoidPredictionBumps {
+                admin: admin_bump,
+                prediction: prediction_bump,
+            };
+
+            set_clock_override(Some(at));
+            let result = void_prediction(Context::new(&program_id, &mut accounts, &[], bumps), 0);
+            match expected {
+                Ok(()) => {
+                    assert!(result.is_ok());
+                    let data = prediction_info.try_borrow_data().unwrap();
+                    let voided = PredictionAccount::try_deserialize(&mut &data[..]).unwrap();
+                    assert!(voided.voided);
+                    assert_eq!((voided.anti, voided.pro), (10000, 8000));
+                }
+                Err(error) => assert_eq!(result.unwrap_err(), error.into()),
+            }
+        }
+    }
+
+    #[test]
+    fn test_void_after_equalisation_started() {
+        let program_id = program_id();
+        let now: i64 = 1740787200;
+        let (admin_pda, admin_bump) = Pubkey::find_program_address(&[b"admin"], &program_id);
+        let (prediction_pda, prediction_bump) = Pubkey::find_program_address(
+            &[b"prediction", 0u64.to_le_bytes().as_ref()],
+            &program_id,
+        );
+
+        let mut admin = TestAccountData::new_account_with_key_and_owner(admin_pda, program_id);
+        admin.init_admin_data(&test_admin()).unwrap();
+        let mut prediction =
+            TestAccountData::new_account_with_key_and_owner(prediction_pda, program_id);
+        let mut carved = Fees::new(PROTOCOL_FEE_BPS, 100);
+        carved.carve(10000, 8000).unwrap();
+        let dispute = Dispute {
+            disputer: Pubkey::new_unique(),
+            bond: DISPUTE_BOND,
+            timestamp: now,
+            round: 0,
+        };
+
+        // Partly binned, disputed, and re-equalising with the fees already paid
+        for (state, expected) in [
+            (
+                PredictionAccount {
+                    equalisation: Some(Equalisation::new(vec![6000, 4000], now)),
+                    ..create_test_prediction(Pubkey::default())
+                },
+                PredictError::AlreadyEqualised,
+            ),
+            (
+                PredictionAccount {
+                    equalisation: Some(Equalisation::new(vec![6000, 4000], now)),
+                    dispute: Some(dispute.clone()),
+                    ..create_test_prediction(Pubkey::default())
+                },
+                PredictError::AlreadyDisputed,
+            ),
+            (
+                PredictionAccount {
+                    fees: carved,
+                    equalisation: Some(Equalisation::new(vec![5000, 5000], now)),
+                    ..create_test_prediction(Pubkey::default())
+                },
+                PredictError::AlreadyEqualised,
+            ),
+        ] {
+            prediction.init_prediction_data(&state).unwrap();
+            let mut authority = TestAccountData::new_authority_account(ANTITOKEN_MULTISIG);
+
+            let admin_info = admin.to_account_info(false);
+            let prediction_info = prediction.to_account_info(false);
+            let authority_info = authority.to_account_info(true);
+
+            let mut accounts = VoidPrediction {
+                admin: Account::try_from(&admin_info).unwrap(),
+                prediction: Account::try_from(&prediction_info).unwrap(),
+                authority: Signer::try_from(&authority_info).unwrap(),
+            };
+            let bumps = VoidPredictionBumps {
+                admin: admin_bump,
+                prediction: prediction_bump,
+            };
+
+            set_clock_override(Some(now));
+            let result = void_prediction(Context::new(&program_id, &mut accounts, &[], bumps), 0);
+            assert_eq!(result.unwrap_err(), expected.into());
+            assert!(!accounts.prediction.voided);
+        }
+    }
+}
@@ -9,15 +9,15 @@
 
 // lib.rs
 use crate::utils::PROGRAM_ID;
-use crate::utils::ANTI_MINT_ADDRESS;
-use crate::utils::PRO_MINT_ADDRESS;
 use anchor_lang::prelude::*;
-use anchor_spl::token::{Token, TokenAccount};
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface};
 
 
 pub mod instructions;
 pub mod state;
 pub mod utils;
+#[cfg(test)]
+mod test_utils;
 
 declare_id!(PROGRAM_ID);
 
@@ -31,15 +31,16 @@ pub mod collider_beta {
     use instructions::equalise;
     use instructions::bulk_withdraw;
     use instructions::user_withdraw;
+    use instructions::dispute;
+    use instructions::sweep;
+    use instructions::void;
+    use instructions::close;
+    use instructions::migrate;
 
     pub fn initialise_admin(ctx: Context<Admin>) -> Result<()> {
         admin::initialise_admin(ctx)
     }
     
-    pub fn update_creation_fee(ctx: Context<Update>, new_fee: u64) -> Result<()> {
-        admin::update_creation_fee(ctx, new_fee)
-    }
-    
     pub fn update_max_title_length(ctx: Context<Update>, new_length: u64) -> Result<()> {
         admin::update_max_title_length(ctx, new_length)
     }
@@ -48,46 +49,75 @@ pub mod collider_beta {
         admin::update_max_description_length(ctx, new_length)
     }
     
-    pub fn update_truth_basis(ctx: Context<Update>, new_basis: u64) -> Result<()> {
-        admin::update_truth_basis(ctx, new_basis)
+    pub fn update_anti_mint(ctx: Context<Update>, new_mint: Pubkey) -> Result<()> {
+        admin::update_anti_mint(ctx, new_mint)
     }
     
-    pub fn update_float_basis(ctx: Context<Update>, new_basis: u64) -> Result<()> {
-        admin::update_float_basis(ctx, new_basis)
+    pub fn update_pro_mint(ctx: Context<Update>, new_mint: Pubkey) -> Result<()> {
+        admin::update_pro_mint(ctx, new_mint)
     }
     
-    pub fn update_min_deposit_amount(ctx: Context<Update>, new_min_amount: u64) -> Result<()> {
-        admin::update_min_deposit_amount(ctx, new_min_amount)
+    pub fn propose_multisig(ctx: Context<Update>, new_multisig: Pubkey) -> Result<()> {
+        admin::propose_multisig(ctx, new_multisig)
     }
     
-    pub fn update_anti_mint(ctx: Context<Update>, new_mint: Pubkey) -> Result<()> {
-        admin::update_anti_mint(ctx, new_mint)
+    pub fn accept_multisig(ctx: Context<Update>) -> Result<()> {
+        admin::accept_multisig(ctx)
     }
     
-    pub fn update_pro_mint(ctx: Context<Update>, new_mint: Pubkey) -> Result<()> {
-        admin::update_pro_mint(ctx, new_mint)
+    pub fn cancel_multisig_proposal(ctx: Context<Update>) -> Result<()> {
+        admin::cancel_multisig_proposal(ctx)
+    }
+    
+    pub fn queue_update(ctx: Context<Update>, parameter: AdminParameter, value: u64) -> Result<()> {
+        admin::queue_update(ctx, parameter, value)
+    }
+    
+    pub fn execute_update(ctx: Context<Update>, parameter: AdminParameter) -> Result<()> {
+        admin::execute_update(ctx, parameter)
     }
     
-    pub fn update_multisig(ctx: Context<Update>, new_multisig: Pubkey) -> Result<()> {
-        admin::update_multisig(ctx, new_multisig)
+    pub fn cancel_update(ctx: Context<Update>, parameter: AdminParameter) -> Result<()> {
+        admin::cancel_update(ctx, parameter)
     }
 
-    pub fn set_authority(ctx: Context<SetPredictionTokenAuthority>, index: u64) -> Result<()> {
-        admin::set_token_authority(ctx, index)
+    pub fn queue_treasury(ctx: Context<Update>, new_treasury: Pubkey) -> Result<()> {
+        admin::queue_treasury(ctx, new_treasury)
+    }
+
+    pub fn execute_treasury(ctx: Context<Update>) -> Result<()> {
+        admin::execute_treasury(ctx)
+    }
+
+    pub fn cancel_treasury(ctx: Context<Update>) -> Result<()> {
+        admin::cancel_treasury(ctx)
+    }
+
+    pub fn update_arbiter(ctx: Context<Update>, new_arbiter: Pubkey) -> Result<()> {
+        admin::update_arbiter(ctx, new_arbiter)
+    }
+
+    pub fn collect_fees(ctx: Context<CollectFees>) -> Result<()> {
+        admin::collect_fees(ctx)
     }
 
     pub fn initialiser(ctx: Context<Initialise>) -> Result<()> {
         initialise::initialise(ctx)
     }
 
+    #[allow(clippy::too_many_arguments)]
     pub fn create_prediction(
         ctx: Context<CreatePrediction>,
         title: String,
         description: String,
         start_time: String,
         end_time: String,
+        resolution_time: Option<String>,
+        outcomes: Vec<String>,
+        scalar: Option<ScalarRange>,
+        resolver: Pubkey,
+        creator_fee_bps: u16,
         etc: Option<Vec<u8>>,
-        unix_timestamp: Option<i64>, // CRITICAL: Remove line in production!
     ) -> Result<()> {
         create::create(
             ctx,
@@ -95,53 +125,147 @@ pub mod collider_beta {
             description,
             start_time,
             end_time,
+            resolution_time,
+            outcomes,
+            scalar,
+            resolver,
+            creator_fee_bps,
             etc,
-            unix_timestamp, // CRITICAL: Remove line in production!
         )
     }
 
     pub fn deposit_tokens(
         ctx: Context<DepositTokens>,
         index: u64,
+        nonce: u64,
         anti: u64,
         pro: u64,
-        unix_timestamp: Option<i64>, // CRITICAL: Remove line in production!
+        distribution: Vec<u64>,
     ) -> Result<()> {
         deposit::deposit(
             ctx,
             index,
+            nonce,
             anti,
             pro,
-            unix_timestamp, // CRITICAL: Remove line in production!
+            distribution,
         )
     }
 
-    pub fn equalise_tokens(
-        ctx: Context<EqualiseTokens>,
+    pub fn equalise_tokens<'a, 'b, 'c: 'info, 'info>(
+        ctx: Context<'a, 'b, 'c, 'info, EqualiseTokens<'info>>,
         index: u64,
         truth: Vec<u64>,
-        unix_timestamp: Option<i64>, // CRITICAL: Remove line in production!
     ) -> Result<()> {
         equalise::equalise(
             ctx,
             index,
             truth,
-            unix_timestamp, // CRITICAL: Remove line in production!
+        )
+    }
+
+    pub fn dispute_equalisation(
+        ctx: Context<DisputeEqualisation>,
+        index: u64,
+    ) -> Result<()> {
+        dispute::dispute(
+            ctx,
+            index,
+        )
+    }
+
+    pub fn arbitrate_equalisation<'a, 'b, 'c: 'info, 'info>(
+        ctx: Context<'a, 'b, 'c, 'info, ArbitrateEqualisation<'info>>,
+        index: u64,
+        truth: Vec<u64>,
+    ) -> Result<()> {
+        dispute::arbitrate(
+            ctx,
+            index,
+            truth,
         )
     }
 
     pub fn bulk_withdraw_tokens<'a, 'b, 'c: 'info, 'info>(
         ctx: Context<'a, 'b, 'c, 'info, BulkWithdrawTokens<'info>>,
         index: u64,
+        offset: u64,
+        batch: u64,
     ) -> Result<()> {
-        bulk_withdraw::bulk_withdraw(ctx, index)
+        bulk_withdraw::bulk_withdraw(
+            ctx,
+            index,
+            offset,
+            batch,
+        )
     }
 
     pub fn user_withdraw_tokens<'a, 'b, 'c: 'info, 'info>(
         ctx: Context<'a, 'b, 'c, 'info, UserWithdrawTokens<'info>>,
         index: u64,
     ) -> Result<()> {
-        user_withdraw::user_withdraw(ctx, index)
+        user_withdraw::user_withdraw(
+            ctx,
+            index,
+        )
+    }
+
+    pub fn sweep_dust(
+        ctx: Context<SweepDust>,
+        index: u64,
+    ) -> Result<()> {
+        sweep::sweep_dust(
+            ctx,
+            index,
+        )
+    }
+
+    pub fn close_prediction(
+        ctx: Context<ClosePrediction>,
+        index: u64,
+    ) -> Result<()> {
+        close::close_prediction(
+            ctx,
+            index,
+        )
+    }
+
+    pub fn close_unclaimed_records<'a, 'b, 'c: 'info, 'info>(
+        ctx: Context<'a, 'b, 'c, 'info, CloseUnclaimedRecords<'info>>,
+        index: u64,
+    ) -> Result<()> {
+        close::close_unclaimed_records(
+            ctx,
+            index,
+        )
+    }
+
+    pub fn migrate_prediction<'a, 'b, 'c: 'info, 'info>(
+        ctx: Context<'a, 'b, 'c, 'info, MigratePrediction<'info>>,
+        index: u64,
+        offset: u64,
+        batch: u64,
+    ) -> Result<()> {
+        migrate::migrate_prediction(
+            ctx,
+            index,
+            offset,
+            batch,
+        )
+    }
+
+    pub fn migrate_account(ctx: Context<MigrateAccount>) -> Result<()> {
+        migrate::migrate_account(ctx)
+    }
+
+    pub fn void_prediction(
+        ctx: Context<VoidPrediction>,
+        index: u64,
+    ) -> Result<()> {
+        void::void_prediction(
+            ctx,
+            index,
+        )
     }
 }
 
@@ -150,7 +274,7 @@ pub struct Admin<'info> {
     #[account(
         init,
         payer = authority,
-        space = 8 + AdminAccount::LEN as usize,
+        space = 8 + AdminAccount::LEN,
         seeds = [b"admin"],
         bump
     )]
@@ -163,16 +287,47 @@ pub struct Admin<'info> {
 
 #[derive(Accounts)]
 pub struct Update<'info> {
-    #[account(mut, seeds = [b"admin"], bump)]
+    #[account(
+        mut,
+        seeds = [b"admin"],
+        bump,
+        constraint = admin.version == utils::ADMIN_VERSION @ PredictError::UnsupportedVersion
+    )]
     pub admin: Account<'info, AdminAccount>,
     
     #[account(signer)]
     pub authority: Signer<'info>,
 }
 
+#[derive(Accounts)]
+pub struct CollectFees<'info> {
+    #[account(
+        seeds = [b"admin"],
+        bump,
+        constraint = admin.version == utils::ADMIN_VERSION @ PredictError::UnsupportedVersion
+    )]
+    pub admin: Account<'info, AdminAccount>,
+    
+    #[account(
+        mut,
+        seeds = [b"state"],
+        bump,
+        constraint = state.version == utils::STATE_VERSION @ PredictError::UnsupportedVersion
+    )]
+    pub state: Account<'info, StateAccount>,
+    
+    #[account(constraint = authority.key() == admin.antitoken_multisig @ PredictError::Unauthorised)]
+    pub authority: Signer<'info>,
+    
+    #[account(mut, address = admin.treasury @ PredictError::InvalidTokenAccount)]
+    /// CHECK: This is the configured treasury, receives creation fees
+    pub treasury: AccountInfo<'info>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
 #[derive(Accounts)]
 pub struct Initialise<'info> {
-    #[account(init, payer = authority, space = 8 + StateAccount::LEN as usize, seeds = [b"state"], bump)]
+    #[account(init, payer = authority, space = 8 + StateAccount::LEN, seeds = [b"state"], bump)]
     pub state: Account<'info, StateAccount>,
     
     #[account(mut)]
@@ -181,21 +336,40 @@ pub struct Initialise<'info> {
 }
 
 #[derive(Accounts)]
-#[instruction(title: String, description: String, start_time: String, end_time: String)]
+#[instruction(
+    title: String,
+    description: String,
+    start_time: String,
+    end_time: String,
+    resolution_time: Option<String>,
+    outcomes: Vec<String>,
+    scalar: Option<ScalarRange>,
+    resolver: Pubkey,
+    creator_fee_bps: u16,
+    etc: Option<Vec<u8>>
+)]
 pub struct CreatePrediction<'info> {
+    #[account(
+        seeds = [b"admin"],
+        bump,
+        constraint = admin.version == utils::ADMIN_VERSION @ PredictError::UnsupportedVersion
+    )]
+    pub admin: Account<'info, AdminAccount>,
+    
     #[account(
         mut,
         seeds = [b"state"], 
         bump,
         owner = crate::ID, 
-        constraint = state.to_account_info().data_len() >= 8 + StateAccount::LEN as usize
+        constraint = state.to_account_info().data_len() >= 8 + StateAccount::LEN,
+        constraint = state.version == utils::STATE_VERSION @ PredictError::UnsupportedVersion
     )]
     pub state: Account<'info, StateAccount>,
     
     #[account(
         init,
         payer = authority,
-        space = 8 + PredictionAccount::LEN as usize,
+        space = PredictionAccount::space(&title, &description, &etc, &outcomes, &scalar),
         seeds = [b"prediction", state.index.to_le_bytes().as_ref()],
         bump
     )]
@@ -204,70 +378,84 @@ pub struct CreatePrediction<'info> {
     #[account(mut)]
     pub authority: Signer<'info>,
     
+    #[account(seeds = [b"authority", state.index.to_le_bytes().as_ref()], bump)]
+    /// CHECK: Per-prediction PDA that owns the pool token accounts
+    pub pool_authority: AccountInfo<'info>,
+    
     #[account(
         init,
         payer = authority,
         token::mint = anti_mint,
-        token::authority = authority,
+        token::authority = pool_authority,
+        token::token_program = token_program,
         seeds = [b"anti_token", state.index.to_le_bytes().as_ref()],
         bump
     )]
-    pub prediction_anti_token: Account<'info, TokenAccount>,
+    pub prediction_anti_token: InterfaceAccount<'info, TokenAccount>,
     
     #[account(
         init,
         payer = authority,
         token::mint = pro_mint,
-        token::authority = authority,
+        token::authority = pool_authority,
+        token::token_program = token_program,
         seeds = [b"pro_token", state.index.to_le_bytes().as_ref()],
         bump
     )]
-    pub prediction_pro_token: Account<'info, TokenAccount>,
+    pub prediction_pro_token: InterfaceAccount<'info, TokenAccount>,
     
-    #[account(constraint = anti_mint.key() == ANTI_MINT_ADDRESS @ PredictError::InvalidTokenAccount)]
-    /// CHECK: This is Antitoken CA
-    pub anti_mint: AccountInfo<'info>,
+    #[account(
+        constraint = anti_mint.key() == admin.anti_mint_address @ PredictError::InvalidTokenAccount,
+        mint::token_program = token_program
+    )]
+    pub anti_mint: InterfaceAccount<'info, Mint>,
     
-    #[account(constraint = pro_mint.key() == PRO_MINT_ADDRESS @ PredictError::InvalidTokenAccount)]
-    /// CHECK: This is Protoken CA
-    pub pro_mint: AccountInfo<'info>,
-    pub token_program: Program<'info, Token>,
+    #[account(
+        constraint = pro_mint.key() == admin.pro_mint_address @ PredictError::InvalidTokenAccount,
+        mint::token_program = token_program
+    )]
+    pub pro_mint: InterfaceAccount<'info, Mint>,
+    pub token_program: Interface<'info, TokenInterface>,
     pub system_program: Program<'info, System>,
     
-    #[account(mut, address = ANTITOKEN_MULTISIG @ PredictError::InvalidTokenAccount)]
+    #[account(mut, address = admin.antitoken_multisig @ PredictError::InvalidTokenAccount)]
     /// CHECK: This is Antitoken squads vault
     pub vault: AccountInfo<'info>,
     pub rent: Sysvar<'info, Rent>,
 }
 
-impl Default for PredictionAccount {
-    fn default() -> Self {
-        Self {
-            index: 0,
-            title: String::new(),
-            description: String::new(),
-            start_time: String::new(),
-            end_time: String::new(),
-            etc: None,
-            anti: 0,
-            pro: 0,
-            deposits: vec![],
-            equalised: false,
-            equalisation: None,
-        }
-    }
-}
-
 #[derive(Accounts)]
-#[instruction(index: u64)]
+#[instruction(index: u64, nonce: u64)]
 pub struct DepositTokens<'info> {
+    #[account(
+        seeds = [b"admin"],
+        bump,
+        constraint = admin.version == utils::ADMIN_VERSION @ PredictError::UnsupportedVersion
+    )]
+    pub admin: Account<'info, AdminAccount>,
+    
     #[account(
         mut,
         seeds = [b"prediction", index.to_le_bytes().as_ref()],
-        bump
+        bump,
+        constraint = prediction.version == utils::PREDICTION_VERSION @ PredictError::UnsupportedVersion
     )]
     pub prediction: Account<'info, PredictionAccount>,
     
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + DepositRecord::LEN,
+        seeds = [
+            b"deposit",
+            index.to_le_bytes().as_ref(),
+            authority.key().as_ref(),
+            nonce.to_le_bytes().as_ref()
+        ],
+        bump
+    )]
+    pub deposit_record: Account<'info, DepositRecord>,
+    
     #[account(mut)]
     pub authority: Signer<'info>,
     
@@ -276,84 +464,257 @@ pub struct DepositTokens<'info> {
         constraint = user_anti_token.owner == authority.key() @ PredictError::InvalidTokenAccount,
         constraint = user_anti_token.mint == prediction_anti_token.mint @ PredictError::InvalidTokenAccount
     )]
-    pub user_anti_token: Account<'info, TokenAccount>,
+    pub user_anti_token: InterfaceAccount<'info, TokenAccount>,
     
     #[account(
         mut,
         constraint = user_pro_token.owner == authority.key() @ PredictError::InvalidTokenAccount,
         constraint = user_pro_token.mint == prediction_pro_token.mint @ PredictError::InvalidTokenAccount
     )]
-    pub user_pro_token: Account<'info, TokenAccount>,
+    pub user_pro_token: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(seeds = [b"authority", index.to_le_bytes().as_ref()], bump)]
+    /// CHECK: Per-prediction PDA that owns the pool token accounts
+    pub pool_authority: AccountInfo<'info>,
     
     #[account(
         mut,
         seeds = [b"anti_token", prediction.index.to_le_bytes().as_ref()],
         bump,
-        constraint = prediction_anti_token.owner == ANTITOKEN_MULTISIG @ PredictError::InvalidTokenAccount
+        constraint = prediction_anti_token.owner == pool_authority.key() @ PredictError::InvalidTokenAccount,
+        constraint = prediction_anti_token.mint == prediction.anti_mint @ PredictError::InvalidTokenAccount
     )]
-    pub prediction_anti_token: Account<'info, TokenAccount>,
+    pub prediction_anti_token: InterfaceAccount<'info, TokenAccount>,
     
     #[account(
         mut,
         seeds = [b"pro_token", prediction.index.to_le_bytes().as_ref()],
         bump,
-        constraint = prediction_pro_token.owner == ANTITOKEN_MULTISIG @ PredictError::InvalidTokenAccount
+        constraint = prediction_pro_token.owner == pool_authority.key() @ PredictError::InvalidTokenAccount,
+        constraint = prediction_pro_token.mint == prediction.pro_mint @ PredictError::InvalidTokenAccount
+    )]
+    pub prediction_pro_token: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(
+        address = prediction.anti_mint @ PredictError::InvalidTokenAccount,
+        mint::token_program = token_program
     )]
-    pub prediction_pro_token: Account<'info, TokenAccount>,
-    pub token_program: Program<'info, Token>,
+    pub anti_mint: InterfaceAccount<'info, Mint>,
+    
+    #[account(
+        address = prediction.pro_mint @ PredictError::InvalidTokenAccount,
+        mint::token_program = token_program
+    )]
+    pub pro_mint: InterfaceAccount<'info, Mint>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
+#[instruction(index: u64)]
 pub struct EqualiseTokens<'info> {
-    #[account(mut)]
+    #[account(
+        seeds = [b"admin"],
+        bump,
+        constraint = admin.version == utils::ADMIN_VERSION @ PredictError::UnsupportedVersion
+    )]
+    pub admin: Account<'info, AdminAccount>,
+    
+    #[account(
+        mut,
+        seeds = [b"prediction", index.to_le_bytes().as_ref()],
+        bump,
+        constraint = prediction.version == utils::PREDICTION_VERSION @ PredictError::UnsupportedVersion
+    )]
     pub prediction: Account<'info, PredictionAccount>,
     
-    #[account(mut)]
+    #[account(
+        mut,
+        constraint = authority.key() == prediction.resolver @ PredictError::UnauthorisedResolver
+    )]
     pub authority: Signer<'info>,
     
-    #[account(mut)]
-    pub user_anti_token: Account<'info, TokenAccount>,
+    #[account(seeds = [b"authority", index.to_le_bytes().as_ref()], bump)]
+    /// CHECK: Per-prediction PDA that owns the pool token accounts
+    pub pool_authority: AccountInfo<'info>,
     
-    #[account(mut)]
-    pub user_pro_token: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        seeds = [b"anti_token", index.to_le_bytes().as_ref()],
+        bump,
+        constraint = prediction_anti_token.owner == pool_authority.key() @ PredictError::InvalidTokenAccount,
+        constraint = prediction_anti_token.mint == prediction.anti_mint @ PredictError::InvalidTokenAccount
+    )]
+    pub prediction_anti_token: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(
+        mut,
+        seeds = [b"pro_token", index.to_le_bytes().as_ref()],
+        bump,
+        constraint = prediction_pro_token.owner == pool_authority.key() @ PredictError::InvalidTokenAccount,
+        constraint = prediction_pro_token.mint == prediction.pro_mint @ PredictError::InvalidTokenAccount
+    )]
+    pub prediction_pro_token: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(
+        mut,
+        constraint = protocol_anti_token.owner == admin.antitoken_multisig @ PredictError::InvalidTokenAccount,
+        constraint = protocol_anti_token.mint == prediction.anti_mint @ PredictError::InvalidTokenAccount
+    )]
+    pub protocol_anti_token: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(
+        mut,
+        constraint = protocol_pro_token.owner == admin.antitoken_multisig @ PredictError::InvalidTokenAccount,
+        constraint = protocol_pro_token.mint == prediction.pro_mint @ PredictError::InvalidTokenAccount
+    )]
+    pub protocol_pro_token: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(
+        mut,
+        constraint = creator_anti_token.owner == prediction.creator @ PredictError::InvalidTokenAccount,
+        constraint = creator_anti_token.mint == prediction.anti_mint @ PredictError::InvalidTokenAccount
+    )]
+    pub creator_anti_token: Option<InterfaceAccount<'info, TokenAccount>>,
+    
+    #[account(
+        mut,
+        constraint = creator_pro_token.owner == prediction.creator @ PredictError::InvalidTokenAccount,
+        constraint = creator_pro_token.mint == prediction.pro_mint @ PredictError::InvalidTokenAccount
+    )]
+    pub creator_pro_token: Option<InterfaceAccount<'info, TokenAccount>>,
+    
+    #[account(
+        address = prediction.anti_mint @ PredictError::InvalidTokenAccount,
+        mint::token_program = token_program
+    )]
+    pub anti_mint: InterfaceAccount<'info, Mint>,
+    
+    #[account(
+        address = prediction.pro_mint @ PredictError::InvalidTokenAccount,
+        mint::token_program = token_program
+    )]
+    pub pro_mint: InterfaceAccount<'info, Mint>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+#[instruction(index: u64)]
+pub struct DisputeEqualisation<'info> {
+    #[account(
+        seeds = [b"admin"],
+        bump,
+        constraint = admin.version == utils::ADMIN_VERSION @ PredictError::UnsupportedVersion
+    )]
+    pub admin: Account<'info, AdminAccount>,
+    
+    #[account(
+        mut,
+        seeds = [b"prediction", index.to_le_bytes().as_ref()],
+        bump,
+        constraint = prediction.version == utils::PREDICTION_VERSION @ PredictError::UnsupportedVersion
+    )]
+    pub prediction: Account<'info, PredictionAccount>,
     
     #[account(mut)]
-    pub prediction_anti_token: Account<'info, TokenAccount>,
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+#[instruction(index: u64)]
+pub struct ArbitrateEqualisation<'info> {
+    #[account(
+        seeds = [b"admin"],
+        bump,
+        constraint = admin.version == utils::ADMIN_VERSION @ PredictError::UnsupportedVersion
+    )]
+    pub admin: Account<'info, AdminAccount>,
+    
+    #[account(
+        mut,
+        seeds = [b"prediction", index.to_le_bytes().as_ref()],
+        bump,
+        constraint = prediction.version == utils::PREDICTION_VERSION @ PredictError::UnsupportedVersion
+    )]
+    pub prediction: Account<'info, PredictionAccount>,
+    
+    #[account(
+        mut,
+        constraint = authority.key() == admin.arbiter @ PredictError::UnauthorisedArbiter
+    )]
+    pub authority: Signer<'info>,
     
     #[account(mut)]
-    pub prediction_pro_token: Account<'info, TokenAccount>,
-    pub token_program: Program<'info, Token>,
+    /// CHECK: Refunded the bond when the dispute succeeds
+    pub disputer: AccountInfo<'info>,
+    
+    #[account(mut, address = admin.antitoken_multisig @ PredictError::InvalidTokenAccount)]
+    /// CHECK: This is Antitoken squads vault, receives forfeited bonds
+    pub vault: AccountInfo<'info>,
 }
 
 #[derive(Accounts)]
 #[instruction(index: u64)]
 pub struct BulkWithdrawTokens<'info> {
+    #[account(
+        seeds = [b"admin"],
+        bump,
+        constraint = admin.version == utils::ADMIN_VERSION @ PredictError::UnsupportedVersion
+    )]
+    pub admin: Account<'info, AdminAccount>,
+    
     #[account(
         mut,
         seeds = [b"prediction", index.to_le_bytes().as_ref()],
-        bump
+        bump,
+        constraint = prediction.version == utils::PREDICTION_VERSION @ PredictError::UnsupportedVersion
     )]
     pub prediction: Account<'info, PredictionAccount>,
     
-    #[account(mut)]
+    #[account(
+        mut,
+        constraint = authority.key() == admin.antitoken_multisig @ PredictError::Unauthorised
+    )]
     pub authority: Signer<'info>,
     
+    #[account(seeds = [b"authority", index.to_le_bytes().as_ref()], bump)]
+    /// CHECK: Per-prediction PDA that owns the pool token accounts
+    pub pool_authority: AccountInfo<'info>,
+    
     #[account(
         mut,
         seeds = [b"anti_token", index.to_le_bytes().as_ref()],
         bump,
-        constraint = prediction_anti_token.owner == ANTITOKEN_MULTISIG @ PredictError::InvalidTokenAccount
+        constraint = prediction_anti_token.owner == pool_authority.key() @ PredictError::InvalidTokenAccount,
+        constraint = prediction_anti_token.mint == prediction.anti_mint @ PredictError::InvalidTokenAccount
     )]
-    pub prediction_anti_token: Account<'info, TokenAccount>,
+    pub prediction_anti_token: InterfaceAccount<'info, TokenAccount>,
     
     #[account(
         mut,
         seeds = [b"pro_token", index.to_le_bytes().as_ref()],
         bump,
-        constraint = prediction_pro_token.owner == ANTITOKEN_MULTISIG @ PredictError::InvalidTokenAccount
+        constraint = prediction_pro_token.owner == pool_authority.key() @ PredictError::InvalidTokenAccount,
+        constraint = prediction_pro_token.mint == prediction.pro_mint @ PredictError::InvalidTokenAccount
     )]
-    pub prediction_pro_token: Account<'info, TokenAccount>,
-    pub token_program: Program<'info, Token>,
+    pub prediction_pro_token: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(
+        address = prediction.anti_mint @ PredictError::InvalidTokenAccount,
+        mint::token_program = token_program
+    )]
+    pub anti_mint: InterfaceAccount<'info, Mint>,
+    
+    #[account(
+        address = prediction.pro_mint @ PredictError::InvalidTokenAccount,
+        mint::token_program = token_program
+    )]
+    pub pro_mint: InterfaceAccount<'info, Mint>,
+    pub token_program: Interface<'info, TokenInterface>,
 }
 
 
@@ -361,88 +722,333 @@ pub struct BulkWithdrawTokens<'info> {
 #[instruction(index: u64)]
 pub struct UserWithdrawTokens<'info> {
     #[account(
-        mut,
-        seeds = [b"state"],
+        seeds = [b"admin"],
         bump,
+        constraint = admin.version == utils::ADMIN_VERSION @ PredictError::UnsupportedVersion
     )]
-    pub state: Account<'info, StateAccount>,
+    pub admin: Account<'info, AdminAccount>,
     
     #[account(
         mut,
         seeds = [b"prediction", index.to_le_bytes().as_ref()],
-        bump
+        bump,
+        constraint = prediction.index == index @ PredictError::IndexMismatch,
+        constraint = prediction.version == utils::PREDICTION_VERSION @ PredictError::UnsupportedVersion
     )]
     pub prediction: Account<'info, PredictionAccount>,
     
+    #[account(
+        mut,
+        close = authority,
+        constraint = deposit_record.index == index @ PredictError::InvalidDepositRecord,
+        constraint = deposit_record.deposit.address == authority.key() @ PredictError::NoDeposit
+    )]
+    pub deposit_record: Account<'info, DepositRecord>,
+    
     #[account(mut)]
     pub authority: Signer<'info>,
     
     #[account(
         mut,
-        constraint = user_anti_token.owner == authority.key() @ PredictError::InvalidTokenAccount
+        constraint = user_anti_token.owner == authority.key() @ PredictError::InvalidTokenAccount,
+        constraint = user_anti_token.mint == prediction.anti_mint @ PredictError::InvalidTokenAccount
+    )]
+    pub user_anti_token: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(
+        mut,
+        constraint = user_pro_token.owner == authority.key() @ PredictError::InvalidTokenAccount,
+        constraint = user_pro_token.mint == prediction.pro_mint @ PredictError::InvalidTokenAccount
+    )]
+    pub user_pro_token: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(seeds = [b"authority", index.to_le_bytes().as_ref()], bump)]
+    /// CHECK: Per-prediction PDA that owns the pool token accounts
+    pub pool_authority: AccountInfo<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"anti_token", index.to_le_bytes().as_ref()],
+        bump,
+        constraint = prediction_anti_token.owner == pool_authority.key() @ PredictError::InvalidTokenAccount,
+        constraint = prediction_anti_token.mint == prediction.anti_mint @ PredictError::InvalidTokenAccount
+    )]
+    pub prediction_anti_token: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(
+        mut,
+        seeds = [b"pro_token", index.to_le_bytes().as_ref()],
+        bump,
+        constraint = prediction_pro_token.owner == pool_authority.key() @ PredictError::InvalidTokenAccount,
+        constraint = prediction_pro_token.mint == prediction.pro_mint @ PredictError::InvalidTokenAccount
+    )]
+    pub prediction_pro_token: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(
+        address = prediction.anti_mint @ PredictError::InvalidTokenAccount,
+        mint::token_program = token_program
+    )]
+    pub anti_mint: InterfaceAccount<'info, Mint>,
+    
+    #[account(
+        address = prediction.pro_mint @ PredictError::InvalidTokenAccount,
+        mint::token_program = token_program
+    )]
+    pub pro_mint: InterfaceAccount<'info, Mint>,
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+#[instruction(index: u64)]
+pub struct SweepDust<'info> {
+    #[account(
+        seeds = [b"admin"],
+        bump,
+        constraint = admin.version == utils::ADMIN_VERSION @ PredictError::UnsupportedVersion
+    )]
+    pub admin: Account<'info, AdminAccount>,
+    
+    #[account(
+        seeds = [b"prediction", index.to_le_bytes().as_ref()],
+        bump,
+        constraint = prediction.version == utils::PREDICTION_VERSION @ PredictError::UnsupportedVersion
     )]
-    pub user_anti_token: Account<'info, TokenAccount>,
+    pub prediction: Account<'info, PredictionAccount>,
     
     #[account(
         mut,
-        constraint = user_pro_token.owner == authority.key() @ PredictError::InvalidTokenAccount
+        constraint = authority.key() == admin.antitoken_multisig @ PredictError::Unauthorised
     )]
-    pub user_pro_token: Account<'info, TokenAccount>,
+    pub authority: Signer<'info>,
+    
+    #[account(seeds = [b"authority", index.to_le_bytes().as_ref()], bump)]
+    /// CHECK: Per-prediction PDA that owns the pool token accounts
+    pub pool_authority: AccountInfo<'info>,
     
     #[account(
         mut,
         seeds = [b"anti_token", index.to_le_bytes().as_ref()],
         bump,
+        constraint = prediction_anti_token.owner == pool_authority.key() @ PredictError::InvalidTokenAccount
     )]
-    pub prediction_anti_token: Account<'info, TokenAccount>,
+    pub prediction_anti_token: InterfaceAccount<'info, TokenAccount>,
     
     #[account(
         mut,
         seeds = [b"pro_token", index.to_le_bytes().as_ref()],
         bump,
+        constraint = prediction_pro_token.owner == pool_authority.key() @ PredictError::InvalidTokenAccount
     )]
-    pub prediction_pro_token: Account<'info, TokenAccount>,
-    pub token_program: Program<'info, Token>,
-    pub system_program: Program<'info, System>,
+    pub prediction_pro_token: InterfaceAccount<'info, TokenAccount>,
     
-    #[account(mut)]
-    /// CHECK: This is Antitoken squads vault 
-    pub vault: AccountInfo<'info>,
+    #[account(
+        mut,
+        constraint = treasury_anti_token.owner == admin.antitoken_multisig @ PredictError::InvalidTokenAccount,
+        constraint = treasury_anti_token.mint == prediction.anti_mint @ PredictError::InvalidTokenAccount
+    )]
+    pub treasury_anti_token: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(
+        mut,
+        constraint = treasury_pro_token.owner == admin.antitoken_multisig @ PredictError::InvalidTokenAccount,
+        constraint = treasury_pro_token.mint == prediction.pro_mint @ PredictError::InvalidTokenAccount
+    )]
+    pub treasury_pro_token: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(
+        address = prediction.anti_mint @ PredictError::InvalidTokenAccount,
+        mint::token_program = token_program
+    )]
+    pub anti_mint: InterfaceAccount<'info, Mint>,
+    
+    #[account(
+        address = prediction.pro_mint @ PredictError::InvalidTokenAccount,
+        mint::token_program = token_program
+    )]
+    pub pro_mint: InterfaceAccount<'info, Mint>,
+    pub token_program: Interface<'info, TokenInterface>,
 }
 
 #[derive(Accounts)]
 #[instruction(index: u64)]
-pub struct SetPredictionTokenAuthority<'info> {
+pub struct VoidPrediction<'info> {
+    #[account(
+        seeds = [b"admin"],
+        bump,
+        constraint = admin.version == utils::ADMIN_VERSION @ PredictError::UnsupportedVersion
+    )]
+    pub admin: Account<'info, AdminAccount>,
+    
+    #[account(
+        mut,
+        seeds = [b"prediction", index.to_le_bytes().as_ref()],
+        bump,
+        constraint = prediction.version == utils::PREDICTION_VERSION @ PredictError::UnsupportedVersion
+    )]
+    pub prediction: Account<'info, PredictionAccount>,
+    
+    #[account(
+        mut,
+        constraint = authority.key() == admin.antitoken_multisig
+            || authority.key() == prediction.creator @ PredictError::Unauthorised
+    )]
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(index: u64)]
+pub struct ClosePrediction<'info> {
+    #[account(
+        seeds = [b"admin"],
+        bump,
+        constraint = admin.version == utils::ADMIN_VERSION @ PredictError::UnsupportedVersion
+    )]
+    pub admin: Account<'info, AdminAccount>,
+    
     #[account(
+        mut,
         seeds = [b"state"],
-        bump
+        bump,
+        constraint = state.version == utils::STATE_VERSION @ PredictError::UnsupportedVersion
     )]
     pub state: Account<'info, StateAccount>,
+    
+    #[account(
+        mut,
+        seeds = [b"prediction", index.to_le_bytes().as_ref()],
+        bump,
+        close = creator,
+        constraint = prediction.version == utils::PREDICTION_VERSION @ PredictError::UnsupportedVersion
+    )]
     pub prediction: Account<'info, PredictionAccount>,
     
-    #[account(mut)]
+    #[account(
+        mut,
+        constraint = authority.key() == admin.antitoken_multisig @ PredictError::Unauthorised
+    )]
     pub authority: Signer<'info>,
     
+    #[account(mut, address = prediction.creator @ PredictError::Unauthorised)]
+    /// CHECK: Creator of the prediction, reclaims the rent
+    pub creator: AccountInfo<'info>,
+    
+    #[account(seeds = [b"authority", index.to_le_bytes().as_ref()], bump)]
+    /// CHECK: Per-prediction PDA that owns the pool token accounts
+    pub pool_authority: AccountInfo<'info>,
+    
     #[account(
         mut,
         seeds = [b"anti_token", index.to_le_bytes().as_ref()],
         bump,
-        constraint = prediction_anti_token.owner == ANTITOKEN_MULTISIG @ PredictError::Unauthorised
+        constraint = prediction_anti_token.owner == pool_authority.key() @ PredictError::InvalidTokenAccount
     )]
-    pub prediction_anti_token: Account<'info, TokenAccount>,
+    pub prediction_anti_token: InterfaceAccount<'info, TokenAccount>,
     
     #[account(
         mut,
         seeds = [b"pro_token", index.to_le_bytes().as_ref()],
         bump,
-        constraint = prediction_pro_token.owner == ANTITOKEN_MULTISIG @ PredictError::Unauthorised
+        constraint = prediction_pro_token.owner == pool_authority.key() @ PredictError::InvalidTokenAccount
+    )]
+    pub prediction_pro_token: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(
+        mut,
+        constraint = treasury_anti_token.owner == admin.antitoken_multisig @ PredictError::InvalidTokenAccount,
+        constraint = treasury_anti_token.mint == prediction.anti_mint @ PredictError::InvalidTokenAccount
+    )]
+    pub treasury_anti_token: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(
+        mut,
+        constraint = treasury_pro_token.owner == admin.antitoken_multisig @ PredictError::InvalidTokenAccount,
+        constraint = treasury_pro_token.mint == prediction.pro_mint @ PredictError::InvalidTokenAccount
+    )]
+    pub treasury_pro_token: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(
+        address = prediction.anti_mint @ PredictError::InvalidTokenAccount,
+        mint::token_program = token_program
+    )]
+    pub anti_mint: InterfaceAccount<'info, Mint>,
+    
+    #[account(
+        address = prediction.pro_mint @ PredictError::InvalidTokenAccount,
+        mint::token_program = token_program
+    )]
+    pub pro_mint: InterfaceAccount<'info, Mint>,
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+#[instruction(index: u64)]
+pub struct CloseUnclaimedRecords<'info> {
+    #[account(
+        seeds = [b"admin"],
+        bump,
+        constraint = admin.version == utils::ADMIN_VERSION @ PredictError::UnsupportedVersion
+    )]
+    pub admin: Account<'info, AdminAccount>,
+    
+    #[account(
+        mut,
+        seeds = [b"prediction", index.to_le_bytes().as_ref()],
+        bump,
+        constraint = prediction.version == utils::PREDICTION_VERSION @ PredictError::UnsupportedVersion
+    )]
+    pub prediction: Account<'info, PredictionAccount>,
+    
+    #[account(
+        constraint = authority.key() == admin.antitoken_multisig @ PredictError::Unauthorised
+    )]
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(index: u64)]
+pub struct MigratePrediction<'info> {
+    #[account(
+        seeds = [b"admin"],
+        bump,
+        constraint = admin.version == utils::ADMIN_VERSION @ PredictError::UnsupportedVersion
+    )]
+    pub admin: Account<'info, AdminAccount>,
+    
+    #[account(
+        mut,
+        seeds = [b"prediction", index.to_le_bytes().as_ref()],
+        bump,
+        owner = crate::ID
     )]
-    pub prediction_pro_token: Account<'info, TokenAccount>,
-    pub token_program: Program<'info, Token>,
+    /// CHECK: Legacy prediction layout, decoded and rewritten by the handler
+    pub prediction: AccountInfo<'info>,
+    
+    #[account(
+        mut,
+        constraint = authority.key() == admin.antitoken_multisig @ PredictError::Unauthorised
+    )]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct MigrateAccount<'info> {
+    #[account(seeds = [b"admin"], bump)]
+    /// CHECK: Admin in either layout, as it may itself await migration
+    pub admin: AccountInfo<'info>,
+    
+    #[account(mut, owner = crate::ID)]
+    /// CHECK: Unversioned account, decoded and rewritten by the handler
+    pub account: AccountInfo<'info>,
+    
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
 }
 
 // Re-export common types for convenience
 use state::AdminAccount;
-use utils::ANTITOKEN_MULTISIG;
-pub use state::{Equalisation, PredictionAccount, StateAccount, Deposit};
+pub use state::{AdminParameter, Equalisation, PredictionAccount, StateAccount, Deposit, DepositRecord, Dispute, ScalarRange};
 pub use utils::{DepositEvent, EqualisationEvent, CreationEvent, PredictError};
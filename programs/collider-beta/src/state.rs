@@ -8,81 +8,492 @@
 //! Contact: dev@antitoken.pro
 
 // state.rs
-use crate::utils::parse_iso_timestamp;
+use crate::utils::{
+    fee_amount, parse_iso_timestamp, PredictError, ADMIN_VERSION, CHALLENGE_PERIOD,
+    CLOSE_GRACE_PERIOD, DISPUTE_BOND, MAX_OUTCOMES, MAX_OUTCOME_LENGTH, MAX_PROTOCOL_FEE_BPS,
+    MAX_QUEUED_UPDATES, MAX_UNIT_LENGTH, MIN_DISPUTE_BOND, NUM_BINS, PREDICTION_VERSION,
+    PROTOCOL_FEE_BPS, STATE_VERSION,
+};
 use anchor_lang::prelude::*;
+use anchor_lang::Discriminator;
+
+// Account carrying a layout version in the byte after its discriminator
+pub trait Versioned: AccountDeserialize + AnchorSerialize + Discriminator {
+    const VERSION: u8;
+
+    // Decode account data written in an earlier layout
+    fn upgrade(data: &[u8]) -> Result<Self>;
+}
+
+// Decode account data holding the current layout of `T`. The version byte is
+// only trusted once the rest decodes, since in layouts from before versioning
+// that byte is ordinary data
+pub fn decode_current<T: Versioned>(data: &[u8]) -> Option<T> {
+    if data.len() > 8 && data[..8] == T::discriminator() && data[8] == T::VERSION {
+        T::try_deserialize(&mut &data[..]).ok()
+    } else {
+        None
+    }
+}
+
+// Error for account data whose layout cannot be upgraded
+fn not_upgradable<T: Versioned>(data: &[u8]) -> Error {
+    match decode_current::<T>(data) {
+        Some(_) => error!(PredictError::AlreadyMigrated),
+        None => error!(PredictError::UnsupportedVersion),
+    }
+}
+
+// Decode a versioned account, rejecting layouts this program does not know
+pub fn load<T: Versioned>(info: &AccountInfo) -> Result<T> {
+    let data = info.try_borrow_data()?;
+    require!(
+        data.len() > 8 && data[..8] == T::discriminator(),
+        ErrorCode::AccountDiscriminatorMismatch
+    );
+    decode_current::<T>(&data).ok_or(error!(PredictError::UnsupportedVersion))
+}
 
 #[account]
 pub struct AdminAccount {
-    pub initialised: bool,           // Initialisation flag
-    pub creation_fee: u64,           // Fee to create prediction
-    pub max_title_length: u64,       // Maximum title length
-    pub max_description_length: u64, // Maximum description length
-    pub truth_basis: u64,            // Truth limit
-    pub float_basis: u64,            // Fixed-point arithmetic basis
-    pub min_deposit_amount: u64,     // Minimum deposit
-    pub antitoken_multisig: Pubkey,  // Multisig authority
-    pub anti_mint_address: Pubkey,   // $ANTI token mint
-    pub pro_mint_address: Pubkey,    // $PRO token mint
+    pub version: u8,                              // Layout version
+    pub initialised: bool,                        // Initialisation flag
+    pub creation_fee: u64,                        // Fee to create prediction
+    pub max_title_length: u64,                    // Maximum title length
+    pub max_description_length: u64,              // Maximum description length
+    pub truth_basis: u64,                         // Truth limit
+    pub float_basis: u64,                         // Fixed-point arithmetic basis
+    pub min_deposit_amount: u64,                  // Minimum deposit
+    pub antitoken_multisig: Pubkey,               // Multisig authority
+    pub anti_mint_address: Pubkey,                // $ANTI token mint
+    pub pro_mint_address: Pubkey,                 // $PRO token mint
+    pub challenge_period: i64,                    // Dispute window after equalisation
+    pub dispute_bond: u64,                        // Bond posted to dispute
+    pub arbiter: Pubkey,                          // Authority ruling on disputes
+    pub protocol_fee_bps: u16,                    // Protocol fee on payouts
+    pub treasury: Pubkey,                         // Recipient of collected creation fees
+    pub pending_multisig: Option<Pubkey>,         // Proposed multisig awaiting acceptance
+    pub queue: Vec<QueuedUpdate>,                 // Timelocked parameter changes
+    pub pending_treasury: Option<QueuedTreasury>, // Timelocked treasury change
 }
 
 impl AdminAccount {
-    pub const LEN: usize = 1 + (8 * 6) + (32 * 3); // Account size
+    pub const LEN: usize = 1 + 1 + (8 * 6) + (32 * 3) + 8 + 8 + 32 + 2 // Account size
+        + 32 // treasury
+        + (1 + 32) // pending multisig
+        + (4 + MAX_QUEUED_UPDATES * QueuedUpdate::LEN) // timelock queue
+        + (1 + QueuedTreasury::LEN); // pending treasury
+}
+
+impl Versioned for AdminAccount {
+    const VERSION: u8 = ADMIN_VERSION;
+
+    // The baseline layout opens with its initialisation flag, which the
+    // version never equals
+    fn upgrade(data: &[u8]) -> Result<Self> {
+        match data.get(8) {
+            Some(0) | Some(1) => Ok(AdminAccountV0::deserialize(&mut &data[8..])?.into()),
+            _ => Err(not_upgradable::<Self>(data)),
+        }
+    }
+}
+
+// Economic parameters changed through the timelock queue
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq)]
+pub enum AdminParameter {
+    CreationFee,
+    MinDepositAmount,
+    FloatBasis,
+    TruthBasis,
+    ProtocolFeeBps,
+    DisputeBond,
+    ChallengePeriod,
+}
+
+impl AdminParameter {
+    pub fn name(&self) -> &'static str {
+        match self {
+            AdminParameter::CreationFee => "creation_fee",
+            AdminParameter::MinDepositAmount => "min_deposit_amount",
+            AdminParameter::FloatBasis => "float_basis",
+            AdminParameter::TruthBasis => "truth_basis",
+            AdminParameter::ProtocolFeeBps => "protocol_fee_bps",
+            AdminParameter::DisputeBond => "dispute_bond",
+            AdminParameter::ChallengePeriod => "challenge_period",
+        }
+    }
+
+    // Reject values the parameter cannot take, both when queued and when applied
+    pub fn validate(&self, value: u64) -> Result<()> {
+        match self {
+            AdminParameter::FloatBasis | AdminParameter::TruthBasis => {
+                require!(value > 0, PredictError::InvalidParameter)
+            }
+            AdminParameter::ProtocolFeeBps => {
+                require!(
+                    value <= MAX_PROTOCOL_FEE_BPS as u64,
+                    PredictError::InvalidFee
+                )
+            }
+            AdminParameter::ChallengePeriod => require!(
+                value > 0 && value <= i64::MAX as u64,
+                PredictError::InvalidParameter
+            ),
+            AdminParameter::DisputeBond => {
+                require!(value >= MIN_DISPUTE_BOND, PredictError::InvalidParameter)
+            }
+            AdminParameter::CreationFee | AdminParameter::MinDepositAmount => {}
+        }
+        Ok(())
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq)]
+pub struct QueuedUpdate {
+    pub parameter: AdminParameter,
+    pub value: u64,
+    pub eta: i64, // Earliest time the update can be executed
+}
+
+impl QueuedUpdate {
+    pub const LEN: usize = 1 + 8 + 8;
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq)]
+pub struct QueuedTreasury {
+    pub treasury: Pubkey,
+    pub eta: i64, // Earliest time the change can be executed
+}
+
+impl QueuedTreasury {
+    pub const LEN: usize = 32 + 8;
+}
+
+// Admin layout before versioning, read only to migrate existing accounts
+#[derive(AnchorSerialize, AnchorDeserialize)]
+pub struct AdminAccountV0 {
+    pub initialised: bool,
+    pub creation_fee: u64,
+    pub max_title_length: u64,
+    pub max_description_length: u64,
+    pub truth_basis: u64,
+    pub float_basis: u64,
+    pub min_deposit_amount: u64,
+    pub antitoken_multisig: Pubkey,
+    pub anti_mint_address: Pubkey,
+    pub pro_mint_address: Pubkey,
+}
+
+impl AdminAccountV0 {
+    pub const LEN: usize = 1 + (8 * 6) + (32 * 3);
+}
+
+// Parameters the baseline layout lacked take their defaults, with the
+// multisig ruling on disputes and receiving fees
+impl From<AdminAccountV0> for AdminAccount {
+    fn from(admin: AdminAccountV0) -> Self {
+        Self {
+            version: ADMIN_VERSION,
+            initialised: admin.initialised,
+            creation_fee: admin.creation_fee,
+            max_title_length: admin.max_title_length,
+            max_description_length: admin.max_description_length,
+            truth_basis: admin.truth_basis,
+            float_basis: admin.float_basis,
+            min_deposit_amount: admin.min_deposit_amount,
+            antitoken_multisig: admin.antitoken_multisig,
+            anti_mint_address: admin.anti_mint_address,
+            pro_mint_address: admin.pro_mint_address,
+            challenge_period: CHALLENGE_PERIOD,
+            dispute_bond: DISPUTE_BOND,
+            arbiter: admin.antitoken_multisig,
+            protocol_fee_bps: PROTOCOL_FEE_BPS,
+            treasury: admin.antitoken_multisig,
+            pending_multisig: None,
+            queue: Vec::new(),
+            pending_treasury: None,
+        }
+    }
 }
 
 #[account]
 pub struct StateAccount {
+    pub version: u8, // Layout version
     pub index: u64,
     pub authority: Pubkey,
+    pub archived: u64, // Number of predictions closed
 }
 
 impl StateAccount {
     pub const LEN: usize = 8  // Discriminator
+        + 1   // version
         + 8   // index
-        + 32; // authority (Pubkey)
+        + 32  // authority (Pubkey)
+        + 8; // archived
+}
+
+impl Versioned for StateAccount {
+    const VERSION: u8 = STATE_VERSION;
+
+    // The baseline layout opens with the index, so it is only read once the
+    // data fails to decode in the current layout
+    fn upgrade(data: &[u8]) -> Result<Self> {
+        if decode_current::<Self>(data).is_some() {
+            return err!(PredictError::AlreadyMigrated);
+        }
+        StateAccountV0::deserialize(&mut &data[8..])
+            .map(Into::into)
+            .map_err(|_| error!(PredictError::UnsupportedVersion))
+    }
+}
+
+// State layout before versioning, read only to migrate existing accounts
+#[derive(AnchorSerialize, AnchorDeserialize)]
+pub struct StateAccountV0 {
+    pub index: u64,
+    pub authority: Pubkey,
+}
+
+impl StateAccountV0 {
+    pub const LEN: usize = 8 + 8 + 32; // Baseline size, which also counted the discriminator
+}
+
+impl From<StateAccountV0> for StateAccount {
+    fn from(state: StateAccountV0) -> Self {
+        Self {
+            version: STATE_VERSION,
+            index: state.index,
+            authority: state.authority,
+            archived: 0,
+        }
+    }
 }
 
 #[account]
+#[derive(Default)]
 pub struct PredictionAccount {
+    pub version: u8, // Layout version
     pub index: u64,
     pub title: String,
     pub description: String,
-    pub start_time: String,
-    pub end_time: String,
+    pub start_time: i64,              // Unix time deposits open
+    pub end_time: i64,                // Unix time deposits close
+    pub resolution_time: Option<i64>, // Earliest equalisation, if after end_time
     pub etc: Option<Vec<u8>>,
+    pub outcomes: Vec<String>,       // Outcome labels, indexed as truth
+    pub scalar: Option<ScalarRange>, // Numeric range of a scalar prediction
+    pub creator: Pubkey,             // Account that created the prediction
+    pub resolver: Pubkey,            // Authority allowed to equalise
+    pub anti_mint: Pubkey,           // $ANTI mint the pool is settled in
+    pub pro_mint: Pubkey,            // $PRO mint the pool is settled in
+    pub fees: Fees,                  // Fee schedule and fees carved out of the pools
     pub anti: u64,
     pub pro: u64,
-    pub deposits: Vec<Deposit>,
+    pub deposits: u64,      // Number of deposit records
+    pub withdrawn: u64,     // Number of deposit records withdrawn
+    pub bulk: BulkProgress, // Progress of paginated bulk withdrawal
     pub equalised: bool,
+    pub voided: bool, // Cancelled, with deposits refunded in full
     pub equalisation: Option<Equalisation>,
+    pub challenge_end: i64, // End of dispute window
+    pub dispute: Option<Dispute>,
 }
 
 impl PredictionAccount {
     pub const LEN: usize = 8 + // discriminator
+        1 + // version
         8 + // index
         256 + // title max length
         1024 + // description max length
-        64 + // start_time
-        64 + // end_time
+        8 + // start_time
+        8 + // end_time
+        1 + 8 + // resolution_time
         1024 + // etc max length
+        4 + MAX_OUTCOMES * (4 + MAX_OUTCOME_LENGTH) + // outcomes
+        1 + ScalarRange::LEN + // scalar range
+        32 + // creator
+        32 + // resolver
+        32 + // $ANTI mint
+        32 + // $PRO mint
+        Fees::LEN + // fees
         8 + // $ANTI in pool
         8 + // $PRO in pool
-        1024 + // deposits vector space
+        8 + // deposit records
+        8 + // withdrawn records
+        BulkProgress::LEN + // bulk withdrawal progress
         1 + // equalised
-        1024; // equalisation results
+        1 + // voided
+        1 + Equalisation::LEN + // equalisation results
+        8 + // challenge_end
+        1 + Dispute::LEN; // pending dispute
+
+    // Bytes a prediction with these strings occupies until it is equalised
+    pub fn space(
+        title: &str,
+        description: &str,
+        etc: &Option<Vec<u8>>,
+        outcomes: &[String],
+        scalar: &Option<ScalarRange>,
+    ) -> usize {
+        8 + // discriminator
+        1 + // version
+        8 + // index
+        4 + title.len() + // title
+        4 + description.len() + // description
+        8 + // start_time
+        8 + // end_time
+        1 + 8 + // resolution_time
+        1 + etc.as_ref().map_or(0, |etc| 4 + etc.len()) + // etc
+        4 + outcomes.iter().map(|outcome| 4 + outcome.len()).sum::<usize>() + // outcomes
+        1 + scalar.as_ref().map_or(0, |range| 8 + 8 + 4 + range.unit.len()) + // scalar range
+        32 * 4 + // creator, resolver and mints
+        Fees::LEN + // fees
+        8 * 4 + // pools and record counts
+        BulkProgress::LEN + // bulk withdrawal progress
+        1 + // equalised
+        1 + // voided
+        1 + // equalisation, unset
+        8 + // challenge_end
+        1 // dispute, unset
+    }
+
+    // Bytes once equalised and disputed, capping the growth of the account
+    pub fn max_space(&self) -> usize {
+        Self::space(
+            &self.title,
+            &self.description,
+            &self.etc,
+            &self.outcomes,
+            &self.scalar,
+        ) + Equalisation::LEN
+            + Dispute::LEN
+    }
 
     pub fn is_active(&self, current_time: i64) -> bool {
-        match (
-            parse_iso_timestamp(&self.start_time),
-            parse_iso_timestamp(&self.end_time),
-        ) {
-            (Ok(start), Ok(end)) => current_time >= start && current_time <= end,
-            _ => false, // If timestamps are invalid, prediction is not active
+        current_time >= self.start_time && current_time <= self.end_time
+    }
+
+    pub fn resolves_at(&self) -> i64 {
+        self.resolution_time.unwrap_or(self.end_time)
+    }
+
+    pub fn is_settled(&self, current_time: i64) -> bool {
+        self.equalised && self.dispute.is_none() && current_time >= self.challenge_end
+    }
+
+    // Pools left to allocate once the fees are carved out
+    pub fn pools(&self) -> Result<(u64, u64)> {
+        let anti = self
+            .anti
+            .checked_sub(self.fees.anti()?)
+            .ok_or(error!(PredictError::MathError))?;
+        let pro = self
+            .pro
+            .checked_sub(self.fees.pro()?)
+            .ok_or(error!(PredictError::MathError))?;
+        Ok((anti, pro))
+    }
+
+    // Tokens owed to depositors: the pools once voided, otherwise the equalised allocations
+    pub fn allocations(&self) -> Result<(u64, u64)> {
+        if self.voided {
+            return Ok((self.anti, self.pro));
         }
+        let equalisation = self
+            .equalisation
+            .as_ref()
+            .ok_or(error!(PredictError::NotEqualised))?;
+        Ok((
+            equalisation.anti.iter().copied().sum::<u64>(),
+            equalisation.pro.iter().copied().sum::<u64>(),
+        ))
+    }
+
+    // Record records settled and their payout from either withdrawal path
+    pub fn record_withdrawal(&mut self, records: u64, anti: u64, pro: u64) -> Result<()> {
+        self.settle_records(records, anti, pro, false)
+    }
+
+    // Record records closed unclaimed after the grace period, and what they were owed
+    pub fn record_unclaimed(&mut self, records: u64, anti: u64, pro: u64) -> Result<()> {
+        self.settle_records(records, anti, pro, true)
+    }
+
+    // Settled amounts never exceed the allocations, and once the last record is
+    // settled, payouts and unclaimed amounts together must match them exactly
+    fn settle_records(&mut self, records: u64, anti: u64, pro: u64, unclaimed: bool) -> Result<()> {
+        let (anti_allocated, pro_allocated) = self.allocations()?;
+        let mut bulk = self.bulk.clone();
+        let (anti_total, pro_total) = if unclaimed {
+            (&mut bulk.unclaimed_anti, &mut bulk.unclaimed_pro)
+        } else {
+            (&mut bulk.anti, &mut bulk.pro)
+        };
+        *anti_total = anti_total
+            .checked_add(anti)
+            .ok_or(error!(PredictError::MathError))?;
+        *pro_total = pro_total
+            .checked_add(pro)
+            .ok_or(error!(PredictError::MathError))?;
+        let anti_settled = bulk
+            .anti
+            .checked_add(bulk.unclaimed_anti)
+            .ok_or(error!(PredictError::MathError))?;
+        let pro_settled = bulk
+            .pro
+            .checked_add(bulk.unclaimed_pro)
+            .ok_or(error!(PredictError::MathError))?;
+        let withdrawn = self
+            .withdrawn
+            .checked_add(records)
+            .ok_or(error!(PredictError::MathError))?;
+        require!(
+            anti_settled <= anti_allocated && pro_settled <= pro_allocated,
+            PredictError::InvalidEqualisation
+        );
+        if withdrawn >= self.deposits {
+            require!(
+                anti_settled == anti_allocated && pro_settled == pro_allocated,
+                PredictError::InvalidEqualisation
+            );
+        }
+        self.bulk = bulk;
+        self.withdrawn = withdrawn;
+        Ok(())
+    }
+
+    // Time after which records left unclaimed may be closed without payout
+    pub fn grace_end(&self) -> Result<i64> {
+        self.end_time
+            .max(self.challenge_end)
+            .checked_add(CLOSE_GRACE_PERIOD)
+            .ok_or(error!(PredictError::MathError))
+    }
+
+    // Split pool leftovers into what unclaimed records were owed and dust
+    pub fn split_leftovers(&self, anti_left: u64, pro_left: u64) -> ((u64, u64), (u64, u64)) {
+        let anti_unclaimed = anti_left.min(self.bulk.unclaimed_anti);
+        let pro_unclaimed = pro_left.min(self.bulk.unclaimed_pro);
+        (
+            (anti_unclaimed, pro_unclaimed),
+            (anti_left - anti_unclaimed, pro_left - pro_unclaimed),
+        )
     }
 }
 
+// Baseline predictions carry their deposits inline and are migrated with
+// their records by `migrate_prediction`, so no earlier layout upgrades here
+impl Versioned for PredictionAccount {
+    const VERSION: u8 = PREDICTION_VERSION;
+
+    fn upgrade(data: &[u8]) -> Result<Self> {
+        Err(not_upgradable::<Self>(data))
+    }
+}
+
+// Deposit as embedded in baseline predictions, before per-outcome distributions
 #[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
-pub struct Deposit {
+pub struct LegacyDeposit {
     pub address: Pubkey,
     pub anti: u64,
     pub pro: u64,
@@ -91,14 +502,297 @@ pub struct Deposit {
     pub withdrawn: bool,
 }
 
+// Equalisation as stored by baseline predictions, with returns per deposit
 #[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
-pub struct Equalisation {
+pub struct LegacyEqualisation {
     pub anti: Vec<u64>,
     pub pro: Vec<u64>,
     pub truth: Vec<u64>,
     pub timestamp: i64,
 }
 
+// Baseline prediction layout with ISO string times and deposits held inline,
+// read only to migrate existing accounts
+#[derive(AnchorSerialize, AnchorDeserialize)]
+pub struct LegacyPredictionAccount {
+    pub index: u64,
+    pub title: String,
+    pub description: String,
+    pub start_time: String,
+    pub end_time: String,
+    pub etc: Option<Vec<u8>>,
+    pub anti: u64,
+    pub pro: u64,
+    pub deposits: Vec<LegacyDeposit>,
+    pub equalised: bool,
+    pub equalisation: Option<LegacyEqualisation>,
+}
+
+impl LegacyPredictionAccount {
+    // Legacy accounts predate resolvers, creators and mint rotation, so they are
+    // governed by the multisig and settle in the configured mints. Each deposit
+    // moves to its own record, binned alone so it keeps its recorded returns.
+    pub fn migrate(
+        self,
+        multisig: Pubkey,
+        anti_mint: Pubkey,
+        pro_mint: Pubkey,
+    ) -> Result<(PredictionAccount, Vec<DepositRecord>)> {
+        require!(
+            self.deposits.len() <= NUM_BINS,
+            PredictError::InvalidDepositRecord
+        );
+        let equalisation = match &self.equalisation {
+            Some(legacy) => {
+                require!(
+                    legacy.anti.len() == self.deposits.len()
+                        && legacy.pro.len() == self.deposits.len(),
+                    PredictError::InvalidEqualisation
+                );
+                let mut equalisation = Equalisation::new(legacy.truth.clone(), legacy.timestamp);
+                for (bin, deposit) in self.deposits.iter().enumerate() {
+                    equalisation.anti[bin] = legacy.anti[bin];
+                    equalisation.pro[bin] = legacy.pro[bin];
+                    equalisation.anti_staked[bin] = deposit.anti;
+                    equalisation.pro_staked[bin] = deposit.pro;
+                }
+                equalisation.binned = self.deposits.len() as u64;
+                Some(equalisation)
+            }
+            None => None,
+        };
+
+        // Payouts already made count towards the conservation check
+        let mut bulk = BulkProgress::default();
+        if let Some(legacy) = &self.equalisation {
+            for (i, deposit) in self.deposits.iter().enumerate() {
+                if deposit.withdrawn {
+                    bulk.anti = bulk
+                        .anti
+                        .checked_add(legacy.anti[i])
+                        .ok_or(error!(PredictError::MathError))?;
+                    bulk.pro = bulk
+                        .pro
+                        .checked_add(legacy.pro[i])
+                        .ok_or(error!(PredictError::MathError))?;
+                }
+            }
+        }
+
+        let binned = equalisation.is_some();
+        let records = self
+            .deposits
+            .iter()
+            .enumerate()
+            .map(|(i, deposit)| DepositRecord {
+                index: self.index,
+                nonce: i as u64,
+                deposit: Deposit {
+                    address: deposit.address,
+                    anti: deposit.anti,
+                    pro: deposit.pro,
+                    mean: deposit.mean,
+                    stddev: deposit.stddev,
+                    withdrawn: deposit.withdrawn,
+                    distribution: vec![deposit.anti, deposit.pro],
+                },
+                bin: binned.then_some(i as u8),
+                binned,
+                round: 0,
+                anti_offset: 0,
+                pro_offset: 0,
+            })
+            .collect();
+
+        let prediction = PredictionAccount {
+            version: PREDICTION_VERSION,
+            index: self.index,
+            title: self.title,
+            description: self.description,
+            start_time: parse_iso_timestamp(&self.start_time)?,
+            end_time: parse_iso_timestamp(&self.end_time)?,
+            resolution_time: None,
+            etc: self.etc,
+            outcomes: vec!["Anti".to_string(), "Pro".to_string()],
+            scalar: None,
+            creator: multisig,
+            resolver: multisig,
+            anti_mint,
+            pro_mint,
+            fees: Fees::default(),
+            anti: self.anti,
+            pro: self.pro,
+            deposits: self.deposits.len() as u64,
+            withdrawn: self.deposits.iter().filter(|d| d.withdrawn).count() as u64,
+            bulk,
+            equalised: self.equalised,
+            voided: false,
+            challenge_end: self
+                .equalisation
+                .as_ref()
+                .map_or(0, |equalisation| equalisation.timestamp),
+            equalisation,
+            dispute: None,
+        };
+        Ok((prediction, records))
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq)]
+pub struct ScalarRange {
+    pub lower: u64,   // Value at the $ANTI end of the range
+    pub upper: u64,   // Value at the $PRO end of the range
+    pub unit: String, // Unit of the realised value
+}
+
+impl ScalarRange {
+    pub const LEN: usize = 8 + 8 + (4 + MAX_UNIT_LENGTH);
+}
+
+#[account]
+#[derive(Default)]
+pub struct DepositRecord {
+    pub index: u64,       // Prediction index
+    pub nonce: u64,       // Depositor-chosen nonce
+    pub deposit: Deposit, // Deposit details
+    pub bin: Option<u8>,  // Equalisation bin
+    pub binned: bool,     // Binned during equalisation
+    pub round: u8,        // Equalisation round of bin
+    pub anti_offset: u64, // $ANTI binned ahead of this record
+    pub pro_offset: u64,  // $PRO binned ahead of this record
+}
+
+impl DepositRecord {
+    pub const LEN: usize = 8 + // index
+        8 + // nonce
+        Deposit::LEN + // deposit
+        2 + // bin
+        1 + // binned
+        1 + // round
+        8 + // anti_offset
+        8; // pro_offset
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, Default)]
+pub struct Deposit {
+    pub address: Pubkey,
+    pub anti: u64,
+    pub pro: u64,
+    pub mean: u64,
+    pub stddev: u64,
+    pub withdrawn: bool,
+    pub distribution: Vec<u64>, // Weight per outcome
+}
+
+impl Deposit {
+    pub const LEN: usize = 32 + (8 * 4) + 1 + (4 + MAX_OUTCOMES * 8);
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct Equalisation {
+    pub anti: Vec<u64>,        // $ANTI allocated per bin
+    pub pro: Vec<u64>,         // $PRO allocated per bin
+    pub anti_staked: Vec<u64>, // $ANTI deposited per bin
+    pub pro_staked: Vec<u64>,  // $PRO deposited per bin
+    pub binned: u64,           // Deposit records binned so far
+    pub truth: Vec<u64>,
+    pub timestamp: i64,
+    pub round: u8, // Incremented on each arbitration
+}
+
+impl Equalisation {
+    pub const LEN: usize = 4 * (4 + NUM_BINS * 8) + // per-bin vectors
+        8 + // binned
+        (4 + MAX_OUTCOMES * 8) + // truth
+        8 + // timestamp
+        1; // round
+
+    pub fn new(truth: Vec<u64>, timestamp: i64) -> Self {
+        Self {
+            anti: vec![0; NUM_BINS],
+            pro: vec![0; NUM_BINS],
+            anti_staked: vec![0; NUM_BINS],
+            pro_staked: vec![0; NUM_BINS],
+            binned: 0,
+            truth,
+            timestamp,
+            round: 0,
+        }
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, Default)]
+pub struct Fees {
+    pub protocol_bps: u16,  // Protocol fee on payouts, fixed at creation
+    pub creator_bps: u16,   // Creator fee on payouts
+    pub carved: bool,       // Fees taken out of the pools
+    pub anti_protocol: u64, // $ANTI paid to the protocol
+    pub pro_protocol: u64,  // $PRO paid to the protocol
+    pub anti_creator: u64,  // $ANTI paid to the creator
+    pub pro_creator: u64,   // $PRO paid to the creator
+}
+
+impl Fees {
+    pub const LEN: usize = 2 + 2 + 1 + (8 * 4);
+
+    pub fn new(protocol_bps: u16, creator_bps: u16) -> Self {
+        Self {
+            protocol_bps,
+            creator_bps,
+            ..Default::default()
+        }
+    }
+
+    // Work out the fees owed on the given pools
+    pub fn carve(&mut self, anti: u64, pro: u64) -> Result<()> {
+        self.anti_protocol = fee_amount(anti, self.protocol_bps)?;
+        self.pro_protocol = fee_amount(pro, self.protocol_bps)?;
+        self.anti_creator = fee_amount(anti, self.creator_bps)?;
+        self.pro_creator = fee_amount(pro, self.creator_bps)?;
+        self.carved = true;
+        Ok(())
+    }
+
+    // Total $ANTI carved out of the pool
+    pub fn anti(&self) -> Result<u64> {
+        self.anti_protocol
+            .checked_add(self.anti_creator)
+            .ok_or(error!(PredictError::MathError))
+    }
+
+    // Total $PRO carved out of the pool
+    pub fn pro(&self) -> Result<u64> {
+        self.pro_protocol
+            .checked_add(self.pro_creator)
+            .ok_or(error!(PredictError::MathError))
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, Default)]
+pub struct BulkProgress {
+    pub cursor: u64,         // Records settled in bulk: a replay counter, not an offset
+    pub anti: u64,           // $ANTI paid out by all withdrawals
+    pub pro: u64,            // $PRO paid out by all withdrawals
+    pub unclaimed_anti: u64, // $ANTI owed to records closed unclaimed
+    pub unclaimed_pro: u64,  // $PRO owed to records closed unclaimed
+}
+
+impl BulkProgress {
+    pub const LEN: usize = 8 * 5;
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct Dispute {
+    pub disputer: Pubkey,
+    pub bond: u64,
+    pub timestamp: i64,
+    pub round: u8, // Equalisation round under dispute
+}
+
+impl Dispute {
+    pub const LEN: usize = 32 + 8 + 8 + 1;
+}
+
 #[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
 pub struct CreatePredictionBumps {
     pub state: u8,
@@ -122,13 +816,6 @@ pub struct UserWithdrawTokensBumps {
     pub prediction_pro_token: u8,
 }
 
-#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
-pub struct SetPredictionTokenAuthorityBumps {
-    pub state: u8,
-    pub prediction_anti_token: u8,
-    pub prediction_pro_token: u8,
-}
-
 #[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
 pub struct AdminBumps {
     pub admin: u8,
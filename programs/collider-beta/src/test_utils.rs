@@ -0,0 +1,389 @@
+//! Program Author: sshmatrix, for Antitoken
+//! Program Description: Collider's shared test fixtures
+//! Version: 1.0.0-beta
+//! License: MIT
+//! Created: 20 Jan 2025
+//! Last Modified: 20 Jan 2025
+//! Repository: https://github.com/antitokens/solana-collider
+//! Contact: dev@antitoken.pro
+
+// test_utils.rs
+use crate::state::*;
+use crate::utils::*;
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::entrypoint::{MAX_PERMITTED_DATA_INCREASE, SUCCESS};
+use anchor_lang::solana_program::program_stubs::{set_syscall_stubs, SyscallStubs};
+use anchor_lang::solana_program::system_program;
+use anchor_lang::Discriminator;
+use anchor_spl::token::spl_token;
+use anchor_spl::token::spl_token::state::{Account as SplTokenAccount, Mint as SplMint};
+use anchor_spl::token_interface::TokenAccount;
+use solana_sdk::account::create_account_for_test;
+use solana_sdk::program_pack::Pack;
+use std::collections::BTreeSet;
+use std::str::FromStr;
+use std::sync::Once;
+
+// Fixed test IDs - these should be consistent across tests
+pub fn program_id() -> Pubkey {
+    Pubkey::from_str(&PROGRAM_ID.to_string()).unwrap()
+}
+
+// Backing store for an AccountInfo handed to a handler
+pub struct TestAccountData {
+    pub key: Pubkey,
+    pub lamports: u64,
+    pub data: Vec<u8>,
+    pub owner: Pubkey,
+    pub executable: bool,
+    pub rent_epoch: u64,
+}
+
+impl TestAccountData {
+    pub fn new_account_with_key_and_owner(key: Pubkey, owner: Pubkey) -> Self {
+        Self {
+            key,
+            lamports: 1_000_000,
+            data: vec![0; 8 + PredictionAccount::LEN],
+            owner,
+            executable: true,
+            rent_epoch: 0,
+        }
+    }
+
+    pub fn new_authority_account(pubkey: Pubkey) -> Self {
+        Self {
+            key: pubkey,
+            lamports: 1_000_000,
+            data: vec![],
+            owner: system_program::ID,
+            executable: true,
+            rent_epoch: 0,
+        }
+    }
+
+    pub fn new_system_account() -> Self {
+        Self {
+            key: system_program::ID,
+            lamports: 1_000_000,
+            data: vec![],
+            owner: system_program::ID,
+            executable: true,
+            rent_epoch: 0,
+        }
+    }
+
+    pub fn new_rent_account() -> Self {
+        Self {
+            key: anchor_lang::solana_program::sysvar::rent::ID,
+            lamports: 1_000_000,
+            data: create_account_for_test(&Rent::default()).data,
+            owner: anchor_lang::solana_program::sysvar::ID,
+            executable: false,
+            rent_epoch: 0,
+        }
+    }
+
+    pub fn new_token_program() -> Self {
+        Self {
+            key: spl_token::ID,
+            lamports: 1_000_000,
+            data: vec![],
+            owner: Pubkey::default(),
+            executable: true,
+            rent_epoch: 0,
+        }
+    }
+
+    pub fn new_mint(mint: Pubkey) -> Self {
+        let mut data = vec![0; SplMint::LEN];
+        SplMint {
+            decimals: 9,
+            is_initialized: true,
+            ..Default::default()
+        }
+        .pack_into_slice(&mut data);
+        Self {
+            key: mint,
+            lamports: 1_000_000,
+            data,
+            owner: spl_token::ID,
+            executable: false,
+            rent_epoch: 0,
+        }
+    }
+
+    // Mint held by the Token-2022 program rather than the classic token program
+    pub fn new_token_2022_mint(mint: Pubkey) -> Self {
+        Self {
+            owner: spl_token_2022::ID,
+            ..Self::new_mint(mint)
+        }
+    }
+
+    // Uninitialised token account, to be filled by init_token_account
+    pub fn new_token(key: Pubkey) -> Self {
+        Self {
+            key,
+            lamports: 1_000_000,
+            data: vec![0; SplTokenAccount::LEN],
+            owner: spl_token::ID,
+            executable: false,
+            rent_epoch: 0,
+        }
+    }
+
+    pub fn new_token_account(owner: Pubkey, mint: Pubkey, amount: u64) -> Self {
+        let mut account = Self::new_token(Pubkey::new_unique());
+        account.init_token_account(owner, mint).unwrap();
+        account.set_token_amount(amount);
+        account
+    }
+
+    pub fn init_token_account(&mut self, owner: Pubkey, mint: Pubkey) -> Result<()> {
+        self.data = vec![0; SplTokenAccount::LEN];
+        SplTokenAccount {
+            mint,
+            owner,
+            amount: 0,
+            delegate: None.into(),
+            state: spl_token::state::AccountState::Initialized,
+            is_native: None.into(),
+            delegated_amount: 0,
+            close_authority: None.into(),
+        }
+        .pack_into_slice(&mut self.data);
+        Ok(())
+    }
+
+    pub fn set_token_amount(&mut self, amount: u64) {
+        let mut token_account = SplTokenAccount::unpack_from_slice(&self.data).unwrap();
+        token_account.amount = amount;
+        token_account.pack_into_slice(&mut self.data);
+    }
+
+    // Account data sized for the given layout, with the value written after its discriminator
+    pub fn init_data<T: AnchorSerialize + Discriminator>(&mut self, value: &T, len: usize) {
+        self.data = vec![0; 8 + len];
+        self.data[..8].copy_from_slice(&T::discriminator());
+        let account_data = value.try_to_vec().unwrap();
+        self.data[8..8 + account_data.len()].copy_from_slice(&account_data);
+    }
+
+    pub fn init_admin_data(&mut self, admin: &AdminAccount) -> Result<()> {
+        self.init_data(admin, AdminAccount::LEN);
+        Ok(())
+    }
+
+    pub fn init_state_data(&mut self, state: &StateAccount) -> Result<()> {
+        self.init_data(state, StateAccount::LEN);
+        Ok(())
+    }
+
+    pub fn init_prediction_data(&mut self, prediction: &PredictionAccount) -> Result<()> {
+        self.init_data(prediction, PredictionAccount::LEN);
+        Ok(())
+    }
+
+    pub fn init_deposit_record_data(&mut self, deposit_record: &DepositRecord) -> Result<()> {
+        self.init_data(deposit_record, DepositRecord::LEN);
+        Ok(())
+    }
+
+    #[allow(clippy::wrong_self_convention)]
+    pub fn to_account_info<'a>(&'a mut self, is_signer: bool) -> AccountInfo<'a> {
+        AccountInfo::new(
+            &self.key,
+            is_signer,
+            true,
+            &mut self.lamports,
+            &mut self.data,
+            &self.owner,
+            self.executable,
+            self.rent_epoch,
+        )
+    }
+
+    pub fn into_token_account<'a>(
+        account_info: &'a AccountInfo<'a>,
+    ) -> InterfaceAccount<'a, TokenAccount> {
+        InterfaceAccount::try_from(account_info).unwrap()
+    }
+}
+
+// Syscalls as the default stubs, except that the Rent sysvar reads as its default,
+// which `init` constraints need to size new accounts
+struct RentSyscallStubs;
+
+impl SyscallStubs for RentSyscallStubs {
+    fn sol_get_rent_sysvar(&self, var_addr: *mut u8) -> u64 {
+        unsafe { *(var_addr as *mut Rent) = Rent::default() };
+        SUCCESS
+    }
+}
+
+static SYSCALL_STUBS: Once = Once::new();
+
+// Validate accounts, in field order, as the entrypoint would before the handler runs
+pub fn try_accounts<'info, T, B>(accounts: &'info [AccountInfo<'info>], ix_data: &[u8]) -> Result<T>
+where
+    T: Accounts<'info, B>,
+    B: Default,
+{
+    SYSCALL_STUBS.call_once(|| {
+        set_syscall_stubs(Box::new(RentSyscallStubs));
+    });
+    let mut accounts = accounts;
+    T::try_accounts(
+        &program_id(),
+        &mut accounts,
+        ix_data,
+        &mut B::default(),
+        &mut BTreeSet::new(),
+    )
+}
+
+// Key preceded by the original data length, as the runtime lays it out for realloc
+#[repr(C)]
+pub struct ReallocKey {
+    pub original_data_len: u32,
+    pub key: Pubkey,
+}
+
+// Account whose data sits behind its length with headroom to grow or shrink in place
+pub struct ReallocAccountData {
+    pub key: ReallocKey,
+    pub lamports: u64,
+    pub buffer: Vec<u8>,
+    pub owner: Pubkey,
+}
+
+impl ReallocAccountData {
+    // Account of `len` bytes holding the data behind the given discriminator
+    pub fn new_legacy(
+        key: Pubkey,
+        owner: Pubkey,
+        discriminator: [u8; 8],
+        account_data: &[u8],
+        len: usize,
+    ) -> Self {
+        let mut buffer = vec![0; 8 + len + MAX_PERMITTED_DATA_INCREASE];
+        buffer[..8].copy_from_slice(&(len as u64).to_le_bytes());
+        buffer[8..16].copy_from_slice(&discriminator);
+        buffer[16..16 + account_data.len()].copy_from_slice(account_data);
+        Self {
+            key: ReallocKey {
+                original_data_len: len as u32,
+                key,
+            },
+            lamports: 1_000_000,
+            buffer,
+            owner,
+        }
+    }
+
+    pub fn new_deposit_record(key: Pubkey, owner: Pubkey, deposit_record: &DepositRecord) -> Self {
+        Self::new_legacy(
+            key,
+            owner,
+            DepositRecord::discriminator(),
+            &deposit_record.try_to_vec().unwrap(),
+            8 + DepositRecord::LEN,
+        )
+    }
+
+    // Each instruction starts from the length left by the previous one
+    #[allow(clippy::wrong_self_convention)]
+    pub fn to_account_info<'a>(&'a mut self) -> AccountInfo<'a> {
+        let len = u64::from_le_bytes(self.buffer[..8].try_into().unwrap()) as usize;
+        self.key.original_data_len = len as u32;
+        AccountInfo::new(
+            &self.key.key,
+            false,
+            true,
+            &mut self.lamports,
+            &mut self.buffer[8..8 + len],
+            &self.owner,
+            false,
+            0,
+        )
+    }
+}
+
+// Admin config with the default parameters, governed by the multisig
+pub fn test_admin() -> AdminAccount {
+    AdminAccount {
+        version: ADMIN_VERSION,
+        initialised: true,
+        creation_fee: CREATION_FEE,
+        max_title_length: MAX_TITLE_LENGTH,
+        max_description_length: MAX_DESCRIPTION_LENGTH,
+        truth_basis: TRUTH_BASIS,
+        float_basis: FLOAT_BASIS,
+        min_deposit_amount: MIN_DEPOSIT_AMOUNT,
+        antitoken_multisig: ANTITOKEN_MULTISIG,
+        anti_mint_address: ANTI_MINT_ADDRESS,
+        pro_mint_address: PRO_MINT_ADDRESS,
+        challenge_period: CHALLENGE_PERIOD,
+        dispute_bond: DISPUTE_BOND,
+        arbiter: ANTITOKEN_MULTISIG,
+        protocol_fee_bps: PROTOCOL_FEE_BPS,
+        treasury: ANTITOKEN_MULTISIG,
+        pending_multisig: None,
+        queue: Vec::new(),
+        pending_treasury: None,
+    }
+}
+
+// Binary prediction 0 that has ended, with empty pools and nothing equalised
+pub fn test_prediction() -> PredictionAccount {
+    PredictionAccount {
+        version: PREDICTION_VERSION,
+        index: 0,
+        title: "Test Prediction".to_string(),
+        description: "Test Description".to_string(),
+        start_time: 1735689600,
+        end_time: 1735776000,
+        resolution_time: None,
+        etc: None,
+        outcomes: vec!["Anti".to_string(), "Pro".to_string()],
+        scalar: None,
+        creator: Pubkey::default(),
+        resolver: ANTITOKEN_MULTISIG,
+        anti_mint: ANTI_MINT_ADDRESS,
+        pro_mint: PRO_MINT_ADDRESS,
+        fees: Fees::default(),
+        anti: 0,
+        pro: 0,
+        deposits: 0,
+        withdrawn: 0,
+        bulk: BulkProgress::default(),
+        equalised: false,
+        voided: false,
+        equalisation: None,
+        challenge_end: 0,
+        dispute: None,
+    }
+}
+
+// Deposit record of prediction 0, binned in the first bin
+pub fn test_deposit_record(authority: Pubkey, nonce: u64, anti: u64, pro: u64) -> DepositRecord {
+    DepositRecord {
+        index: 0,
+        nonce,
+        deposit: Deposit {
+            address: authority,
+            anti,
+            pro,
+            mean: 40000,
+            stddev: 100000,
+            withdrawn: false,
+            distribution: vec![anti, pro],
+        },
+        bin: Some(0),
+        binned: true,
+        round: 0,
+        anti_offset: 0,
+        pro_offset: 0,
+    }
+}
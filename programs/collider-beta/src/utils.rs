@@ -10,15 +10,40 @@
 // utils.rs
 use crate::state::*;
 use anchor_lang::prelude::*;
+use anchor_spl::token_2022::spl_token_2022::extension::transfer_fee::TransferFeeConfig;
+use anchor_spl::token_2022::spl_token_2022::extension::{
+    BaseStateWithExtensions, StateWithExtensions,
+};
+use anchor_spl::token_2022::spl_token_2022::state::Mint;
 use chrono::NaiveDateTime;
 use solana_security_txt;
 
+// Defaults seeded into AdminAccount; runtime values are read from the admin PDA
 pub const CREATION_FEE: u64 = 100_000_000; // Fee to create prediction (0.1 SOL)
 pub const MAX_TITLE_LENGTH: u64 = 256; // Maximum title length
 pub const MAX_DESCRIPTION_LENGTH: u64 = 1_024; // Maximum description length
 pub const TRUTH_BASIS: u64 = 100_000; // Truth limit = [0, 1]
 pub const FLOAT_BASIS: u64 = 10_000; // For fixed-point arithmetic up to 0.01
 pub const MIN_DEPOSIT_AMOUNT: u64 = 10_000; // 1 token minimum deposit
+pub const CHALLENGE_PERIOD: i64 = 86_400; // Dispute window (1 day)
+pub const DISPUTE_BOND: u64 = 500_000_000; // Bond to dispute (0.5 SOL)
+pub const MIN_DISPUTE_BOND: u64 = 10_000_000; // Dispute bond floor (0.01 SOL)
+pub const NUM_BINS: usize = 100; // Equalisation bins
+pub const MAX_OUTCOMES: usize = 8; // Outcomes per prediction
+pub const MAX_OUTCOME_LENGTH: usize = 32; // Maximum outcome label length
+pub const MAX_UNIT_LENGTH: usize = 16; // Maximum scalar unit length
+pub const CLOSE_GRACE_PERIOD: i64 = 7_776_000; // Unclaimed deposit records may be closed after (90 days)
+pub const MAX_WITHDRAW_BATCH: u64 = 8; // Deposit records per bulk withdrawal
+pub const MAX_MIGRATE_BATCH: u64 = 16; // Deposit records per migration batch
+pub const FEE_BASIS: u64 = 10_000; // Fees are quoted in basis points
+pub const PROTOCOL_FEE_BPS: u16 = 100; // Protocol fee on payouts (1%)
+pub const MAX_PROTOCOL_FEE_BPS: u16 = 1_000; // Protocol fee cap (10%)
+pub const MAX_CREATOR_FEE_BPS: u16 = 500; // Creator fee cap (5%)
+pub const ADMIN_VERSION: u8 = 2; // AdminAccount layout version, never a baseline initialisation flag
+pub const STATE_VERSION: u8 = 1; // StateAccount layout version
+pub const PREDICTION_VERSION: u8 = 1; // PredictionAccount layout version
+pub const TIMELOCK_DELAY: i64 = 172_800; // Delay before queued parameter changes apply (2 days)
+pub const MAX_QUEUED_UPDATES: usize = 7; // One queued change per timelocked parameter
 pub const ANTITOKEN_MULTISIG: Pubkey =
     solana_program::pubkey!("7JZwEe2zsYjMQWEw7guWibRopdrQMZ8P2Y9XZpDxME46");
 pub const ANTI_MINT_ADDRESS: Pubkey =
@@ -96,8 +121,64 @@ pub enum PredictError {
     AlreadyEqualised,
     #[msg("No deposits in prediction pool")]
     NoDeposits,
-    #[msg("User withdrawals not enabled yet")]
-    UserWithdrawalsNotEnabled,
+    #[msg("Deposit record does not belong to prediction")]
+    InvalidDepositRecord,
+    #[msg("Signer is not the prediction resolver")]
+    UnauthorisedResolver,
+    #[msg("Invalid resolver address")]
+    InvalidResolver,
+    #[msg("Challenge period is still open")]
+    ChallengePeriodActive,
+    #[msg("Challenge period has ended")]
+    ChallengePeriodEnded,
+    #[msg("Prediction already disputed")]
+    AlreadyDisputed,
+    #[msg("No dispute pending")]
+    NoDispute,
+    #[msg("Signer is not the arbiter")]
+    UnauthorisedArbiter,
+    #[msg("Deposit records remain to be withdrawn")]
+    WithdrawalsPending,
+    #[msg("Invalid prediction outcomes")]
+    InvalidOutcomes,
+    #[msg("Invalid deposit distribution")]
+    InvalidDistribution,
+    #[msg("Invalid scalar range")]
+    InvalidScalarRange,
+    #[msg("Prediction has been voided")]
+    PredictionVoided,
+    #[msg("Account already migrated")]
+    AlreadyMigrated,
+    #[msg("Bulk withdrawal offset does not match progress")]
+    InvalidWithdrawOffset,
+    #[msg("Invalid bulk withdrawal batch size")]
+    InvalidBatchSize,
+    #[msg("Recipient token account does not match the deposit")]
+    InvalidRecipient,
+    #[msg("Fee exceeds the allowed basis points")]
+    InvalidFee,
+    #[msg("Account does not belong to the prediction index")]
+    IndexMismatch,
+    #[msg("Account layout version is not supported")]
+    UnsupportedVersion,
+    #[msg("Account data exceeds its allocated size")]
+    AccountFull,
+    #[msg("No multisig handover is pending")]
+    NoPendingMultisig,
+    #[msg("An update to the parameter is already queued")]
+    UpdateAlreadyQueued,
+    #[msg("No update to the parameter is queued")]
+    UpdateNotQueued,
+    #[msg("Timelock has not elapsed")]
+    TimelockActive,
+    #[msg("Creator fee token accounts are required")]
+    MissingCreatorAccount,
+    #[msg("Invalid value for an admin parameter")]
+    InvalidParameter,
+    #[msg("Unclaimed deposit records cannot be closed before the grace period ends")]
+    GracePeriodActive,
+    #[msg("Migration batch does not follow the records already migrated")]
+    InvalidMigrationBatch,
 }
 
 // Event emitted when a new prediction is created
@@ -106,8 +187,11 @@ pub struct CreationEvent {
     pub index: u64,
     pub address: Pubkey,
     pub title: String,
-    pub start_time: String,
-    pub end_time: String,
+    pub start_time: i64,
+    pub end_time: i64,
+    pub resolution_time: Option<i64>,
+    pub outcomes: Vec<String>,
+    pub scalar: Option<ScalarRange>,
     pub timestamp: i64,
 }
 
@@ -133,6 +217,46 @@ pub struct EqualisationEvent {
     pub timestamp: i64,
 }
 
+// Event emitted when an equalisation is disputed
+#[event]
+pub struct DisputeEvent {
+    pub index: u64,
+    pub disputer: Pubkey,
+    pub bond: u64,
+    pub timestamp: i64,
+}
+
+// Event emitted when the arbiter rules on a dispute
+#[event]
+pub struct ArbitrationEvent {
+    pub index: u64,
+    pub truth: Vec<u64>,
+    pub upheld: bool,
+    pub timestamp: i64,
+}
+
+// Event emitted when fees are carved out of the pools at equalisation
+#[event]
+pub struct FeeEvent {
+    pub index: u64,
+    pub creator: Pubkey,
+    pub anti_protocol: u64,
+    pub pro_protocol: u64,
+    pub anti_creator: u64,
+    pub pro_creator: u64,
+    pub timestamp: i64,
+}
+
+// Event emitted when a prediction is voided and its deposits become refundable
+#[event]
+pub struct VoidEvent {
+    pub index: u64,
+    pub address: Pubkey,
+    pub anti: u64,
+    pub pro: u64,
+    pub timestamp: i64,
+}
+
 // Event emitted when tokens are withdrawn
 #[event]
 pub struct WithdrawEvent {
@@ -143,6 +267,39 @@ pub struct WithdrawEvent {
     pub timestamp: i64,
 }
 
+// Event emitted when leftover tokens are swept to the treasury
+#[event]
+pub struct SweepEvent {
+    pub index: u64,
+    pub anti: u64,
+    pub pro: u64,
+    pub unclaimed_anti: u64,
+    pub unclaimed_pro: u64,
+    pub timestamp: i64,
+}
+
+// Event emitted when deposit records left unclaimed are closed after the grace period
+#[event]
+pub struct UnclaimedEvent {
+    pub index: u64,
+    pub records: u64,
+    pub anti: u64,
+    pub pro: u64,
+    pub timestamp: i64,
+}
+
+// Event emitted when a prediction is closed and its rent reclaimed
+#[event]
+pub struct CloseEvent {
+    pub index: u64,
+    pub address: Pubkey,
+    pub anti: u64,
+    pub pro: u64,
+    pub unclaimed_anti: u64,
+    pub unclaimed_pro: u64,
+    pub timestamp: i64,
+}
+
 // Event for updates to prediction parameters
 #[event]
 pub struct PredictionUpdateEvent {
@@ -165,9 +322,14 @@ pub struct AdminEvent {
 }
 
 // Utility functions for calculations
-pub fn collide(anti: u64, pro: u64) -> Result<(u64, u64)> {
-    let anti_f = anti * FLOAT_BASIS;
-    let pro_f = pro * FLOAT_BASIS;
+pub fn collide(anti: u64, pro: u64, float_basis: u64) -> Result<(u64, u64)> {
+    require!(float_basis > 0, PredictError::MathError);
+    let anti_f = anti
+        .checked_mul(float_basis)
+        .ok_or(PredictError::MathError)?;
+    let pro_f = pro
+        .checked_mul(float_basis)
+        .ok_or(PredictError::MathError)?;
     let sum = anti_f.checked_add(pro_f).ok_or(PredictError::MathError)?;
     let diff = if anti_f > pro_f {
         anti_f.checked_sub(pro_f)
@@ -177,28 +339,126 @@ pub fn collide(anti: u64, pro: u64) -> Result<(u64, u64)> {
     .ok_or(PredictError::MathError)?;
 
     // Calculate mean
-    let mean = if sum < FLOAT_BASIS {
-        0
-    } else if diff > 0 && diff < FLOAT_BASIS {
-        diff
-    } else {
-        diff
-    };
+    let mean = if sum < float_basis { 0 } else { diff };
 
     // Calculate stddev
-    let stddev = if sum < FLOAT_BASIS {
-        0
-    } else if diff == sum {
+    let stddev = if sum < float_basis || diff == sum {
         0
-    } else if diff > 0 && diff < FLOAT_BASIS {
-        (sum * FLOAT_BASIS) / FLOAT_BASIS
-    } else if diff == 0 {
+    } else if diff < float_basis {
         sum
     } else {
-        (sum * FLOAT_BASIS) / diff
+        sum.checked_mul(float_basis)
+            .ok_or(PredictError::MathError)?
+            / diff
     };
 
-    Ok((mean / FLOAT_BASIS, stddev / FLOAT_BASIS))
+    Ok((mean / float_basis, stddev / float_basis))
+}
+
+// Time source for every instruction; only unit tests can substitute a fixed
+// time for the Clock sysvar, which program tests pin with `set_sysvar`
+pub fn current_time() -> Result<i64> {
+    #[cfg(test)]
+    if let Some(timestamp) = CLOCK_OVERRIDE.with(|clock| clock.get()) {
+        return Ok(timestamp);
+    }
+
+    Ok(Clock::get()?.unix_timestamp)
+}
+
+#[cfg(test)]
+thread_local! {
+    static CLOCK_OVERRIDE: std::cell::Cell<Option<i64>> = const { std::cell::Cell::new(None) };
+}
+
+// Fix the time returned by current_time on this thread, or restore the sysvar with None
+#[cfg(test)]
+pub fn set_clock_override(timestamp: Option<i64>) {
+    CLOCK_OVERRIDE.with(|clock| clock.set(timestamp));
+}
+
+// Serialise an account behind its discriminator, bounded by the allocated size
+pub fn write_account<T: AnchorSerialize>(info: &AccountInfo, account: &T) -> Result<()> {
+    let serialised = account.try_to_vec()?;
+    let mut data = info.try_borrow_mut_data()?;
+    require!(
+        8 + serialised.len() <= data.len(),
+        PredictError::AccountFull
+    );
+    data[8..8 + serialised.len()].copy_from_slice(&serialised);
+    Ok(())
+}
+
+// Serialise an account, first growing it up to `max_len` bytes if it no longer
+// fits, with the rent for the extra space paid by `payer`
+pub fn write_account_growing<'info, T: AnchorSerialize>(
+    info: &AccountInfo<'info>,
+    account: &T,
+    payer: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+    rent: &Rent,
+    max_len: usize,
+) -> Result<()> {
+    let len = 8 + account.try_to_vec()?.len();
+    if len > info.data_len() {
+        require!(len <= max_len, PredictError::AccountFull);
+        grow_account(info, len, payer, system_program, rent)?;
+    }
+    write_account(info, account)
+}
+
+// Grow an account to `len` bytes, with the rent for the extra space paid by `payer`
+pub fn grow_account<'info>(
+    info: &AccountInfo<'info>,
+    len: usize,
+    payer: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+    rent: &Rent,
+) -> Result<()> {
+    if len <= info.data_len() {
+        return Ok(());
+    }
+    let shortfall = rent.minimum_balance(len).saturating_sub(info.lamports());
+    if shortfall > 0 {
+        anchor_lang::system_program::transfer(
+            CpiContext::new(
+                system_program.clone(),
+                anchor_lang::system_program::Transfer {
+                    from: payer.clone(),
+                    to: info.clone(),
+                },
+            ),
+            shortfall,
+        )?;
+    }
+    info.realloc(len, true)?;
+    Ok(())
+}
+
+// Fee of `bps` basis points on an amount, rounded down
+pub fn fee_amount(amount: u64, bps: u16) -> Result<u64> {
+    u64::try_from(amount as u128 * bps as u128 / FEE_BASIS as u128)
+        .map_err(|_| error!(PredictError::MathError))
+}
+
+// Amount credited by a transfer of `amount`, net of any Token-2022 transfer fee
+pub fn received_amount(mint: &AccountInfo, amount: u64) -> Result<u64> {
+    let data = mint.try_borrow_data()?;
+    let mint = StateWithExtensions::<Mint>::unpack(&data)?;
+    match mint.get_extension::<TransferFeeConfig>() {
+        Ok(config) => net_of_transfer_fee(config, Clock::get()?.epoch, amount),
+        Err(_) => Ok(amount),
+    }
+}
+
+// Deduct the fee withheld by the transfer fee extension in the given epoch
+pub fn net_of_transfer_fee(config: &TransferFeeConfig, epoch: u64, amount: u64) -> Result<u64> {
+    let fee = config
+        .calculate_epoch_fee(epoch, amount)
+        .ok_or(PredictError::MathError)?;
+    amount
+        .checked_sub(fee)
+        .ok_or(error!(PredictError::MathError))
 }
 
 // Function to parse date
@@ -226,23 +486,24 @@ pub fn state_has_title(_state: &Account<StateAccount>, _title: &str) -> bool {
 
 // Helper function to validate prediction parameters
 pub fn validate_prediction_params(
+    admin: &AdminAccount,
     title: &str,
     description: &str,
     start_time: &str,
     end_time: &str,
 ) -> Result<()> {
     require!(
-        title.len() <= MAX_TITLE_LENGTH as usize,
+        title.len() as u64 <= admin.max_title_length,
         PredictError::TitleTooLong
     );
     require!(
-        description.len() <= MAX_DESCRIPTION_LENGTH as usize,
+        description.len() as u64 <= admin.max_description_length,
         PredictError::DescriptionTooLong
     );
 
     let start = parse_iso_timestamp(start_time)?;
     let end = parse_iso_timestamp(end_time)?;
-    let now = Clock::get()?.unix_timestamp;
+    let now = current_time()?;
 
     require!(end > start, PredictError::InvalidTimeRange);
     require!(start > now, PredictError::StartTimeInPast);
@@ -250,130 +511,929 @@ pub fn validate_prediction_params(
     Ok(())
 }
 
+// Helper function to validate outcome labels
+pub fn validate_outcomes(outcomes: &[String]) -> Result<()> {
+    require!(
+        (2..=MAX_OUTCOMES).contains(&outcomes.len()),
+        PredictError::InvalidOutcomes
+    );
+    require!(
+        outcomes
+            .iter()
+            .all(|label| !label.is_empty() && label.len() <= MAX_OUTCOME_LENGTH),
+        PredictError::InvalidOutcomes
+    );
+
+    Ok(())
+}
+
+// Helper function to validate a deposit's weights over the outcomes
+pub fn validate_distribution(distribution: &[u64], outcomes: usize) -> Result<()> {
+    require!(
+        distribution.len() == outcomes,
+        PredictError::InvalidDistribution
+    );
+    let total = distribution
+        .iter()
+        .try_fold(0u64, |acc, weight| acc.checked_add(*weight))
+        .ok_or(error!(PredictError::MathError))?;
+    require!(total > 0, PredictError::InvalidDistribution);
+
+    Ok(())
+}
+
+// Helper function to validate the range of a scalar prediction
+pub fn validate_scalar_range(range: &ScalarRange) -> Result<()> {
+    require!(range.lower < range.upper, PredictError::InvalidScalarRange);
+    require!(
+        range.unit.len() <= MAX_UNIT_LENGTH,
+        PredictError::InvalidScalarRange
+    );
+
+    Ok(())
+}
+
+// Helper function to validate truth values against the prediction type
+pub fn validate_truth(
+    prediction: &PredictionAccount,
+    truth: &[u64],
+    truth_basis: u64,
+) -> Result<()> {
+    let valid = match &prediction.scalar {
+        // A single realised value inside the range
+        Some(range) => truth.len() == 1 && (range.lower..=range.upper).contains(&truth[0]),
+        None => truth.len() == prediction.outcomes.len() && truth.iter().all(|v| *v <= truth_basis),
+    };
+    require!(valid, PredictError::InvalidTruthValues);
+
+    Ok(())
+}
+
+// Map a deposit onto a scalar range. The collide mean |anti - pro|, signed
+// towards $PRO and normalised by the stake, places the estimate between the
+// lower ($ANTI) and upper ($PRO) bounds
+pub fn scalar_estimate(range: &ScalarRange, anti: u64, pro: u64, mean: u64) -> Result<u64> {
+    let total = anti as u128 + pro as u128;
+    require!(total > 0 && mean as u128 <= total, PredictError::MathError);
+    let signed = if pro >= anti {
+        total + mean as u128
+    } else {
+        total - mean as u128
+    };
+    let span = (range.upper - range.lower) as u128;
+    let offset = span * signed / (2 * total);
+
+    u64::try_from(range.lower as u128 + offset).map_err(|_| error!(PredictError::MathError))
+}
+
+// Index of the favoured outcome; ties go to the later outcome, so that for two
+// outcomes [a, b] the first is favoured only when a > b
+pub fn leading_outcome(values: &[u64]) -> usize {
+    values.iter().enumerate().fold(
+        0,
+        |best, (i, value)| if *value >= values[best] { i } else { best },
+    )
+}
+
 pub fn equalise_with_truth(
     deposits: &[Deposit],
     anti_pool: u64,
     pro_pool: u64,
     truth: &[u64],
+    scalar: Option<&ScalarRange>,
+    float_basis: u64,
 ) -> Result<(Vec<u64>, Vec<u64>)> {
-    const NUM_BINS: usize = 100;
-
-    // Calculate overlaps
-    let mut overlaps = Vec::with_capacity(deposits.len());
+    // Populate bins (overlap), recording the stake binned ahead of each deposit
+    let mut bins: Vec<Option<(usize, u64, u64)>> = Vec::with_capacity(deposits.len());
+    let mut anti_staked = vec![0u64; NUM_BINS];
+    let mut pro_staked = vec![0u64; NUM_BINS];
     for deposit in deposits {
-        let baryon = deposit.mean as f64;
-        let photon = (deposit.stddev as f64) / (FLOAT_BASIS as f64);
-        let parity = if (truth[0] > truth[1]) == (deposit.anti > deposit.pro) {
+        let bin = bin_deposit(deposit, truth, scalar, float_basis)?;
+        bins.push(bin.map(|bin_index| (bin_index, anti_staked[bin_index], pro_staked[bin_index])));
+        if let Some(bin_index) = bin {
+            anti_staked[bin_index] = anti_staked[bin_index]
+                .checked_add(deposit.anti)
+                .ok_or(error!(PredictError::MathError))?;
+            pro_staked[bin_index] = pro_staked[bin_index]
+                .checked_add(deposit.pro)
+                .ok_or(error!(PredictError::MathError))?;
+        }
+    }
+
+    // Calculate bin values (scatterer) and allocations (localiser)
+    let anti_allocations = allocations(&anti_staked, anti_pool, float_basis)?;
+    let pro_allocations = allocations(&pro_staked, pro_pool, float_basis)?;
+
+    // Calculate returns
+    let mut anti_returns = vec![0u64; deposits.len()];
+    let mut pro_returns = vec![0u64; deposits.len()];
+    for (i, bin) in bins.iter().enumerate() {
+        if let Some((bin_index, anti_offset, pro_offset)) = *bin {
+            anti_returns[i] = share(
+                anti_allocations[bin_index],
+                anti_offset,
+                deposits[i].anti,
+                anti_staked[bin_index],
+            )?;
+            pro_returns[i] = share(
+                pro_allocations[bin_index],
+                pro_offset,
+                deposits[i].pro,
+                pro_staked[bin_index],
+            )?;
+        }
+    }
+
+    Ok((anti_returns, pro_returns))
+}
+
+// Allocate a pool across the bins holding stake of that token
+pub fn allocations(staked: &[u64], pool: u64, float_basis: u64) -> Result<Vec<u64>> {
+    let occupied: Vec<bool> = staked.iter().map(|stake| *stake > 0).collect();
+    let bin_values = scatter(&occupied, float_basis)?;
+    localise(&bin_values, pool)
+}
+
+// Place a deposit into a bin according to its overlap with the truth
+pub fn bin_deposit(
+    deposit: &Deposit,
+    truth: &[u64],
+    scalar: Option<&ScalarRange>,
+    float_basis: u64,
+) -> Result<Option<usize>> {
+    require!(float_basis > 0, PredictError::MathError);
+    require!(
+        deposit.distribution.len() == truth.len(),
+        PredictError::InvalidDistribution
+    );
+
+    // Scalar deposits are binned by their distance from the realised value
+    if let Some(range) = scalar {
+        let span = range
+            .upper
+            .checked_sub(range.lower)
+            .filter(|span| *span > 0)
+            .ok_or(error!(PredictError::InvalidScalarRange))?;
+        let distance = deposit.distribution[0].abs_diff(truth[0]);
+        let bin_index = (distance as u128 * NUM_BINS as u128 / span as u128) as usize;
+        return Ok(Some(bin_index.min(NUM_BINS - 1)));
+    }
+
+    // Parity: whether the deposit favours the outcome the truth favours
+    let parity = leading_outcome(truth) == leading_outcome(&deposit.distribution);
+
+    let overlap_val = overlap(deposit.mean, deposit.stddev, parity, float_basis)?;
+    let bin_index = (overlap_val as u128 * NUM_BINS as u128 / float_basis as u128) as usize;
+    Ok(Some(bin_index.min(NUM_BINS - 1)))
+}
+
+// Weight occupied bins such that lower bins receive larger shares; weights are
+// scaled by float_basis and sum to it exactly
+pub fn scatter(occupied: &[bool], float_basis: u64) -> Result<Vec<u64>> {
+    // Weight n - i for the i-th of n occupied bins
+    let mut remaining = occupied.iter().filter(|&&filled| filled).count() as u128;
+    let weights: Vec<u128> = occupied
+        .iter()
+        .map(|&filled| {
+            if filled {
+                let weight = remaining;
+                remaining -= 1;
+                weight
+            } else {
+                0
+            }
+        })
+        .collect();
+
+    apportion(&weights, float_basis)
+}
+
+// Split a pool across bins in proportion to their bin values
+pub fn localise(bin_values: &[u64], pool: u64) -> Result<Vec<u64>> {
+    let weights: Vec<u128> = bin_values.iter().map(|&value| value as u128).collect();
+    apportion(&weights, pool)
+}
+
+// Largest-remainder apportionment of total by weight; the parts sum to total
+// exactly unless every weight is zero. Ties go to the lower index
+pub fn apportion(weights: &[u128], total: u64) -> Result<Vec<u64>> {
+    let sum = weights
+        .iter()
+        .try_fold(0u128, |acc, weight| acc.checked_add(*weight))
+        .ok_or(error!(PredictError::MathError))?;
+    if sum == 0 {
+        return Ok(vec![0; weights.len()]);
+    }
+
+    let mut parts = Vec::with_capacity(weights.len());
+    let mut remainders = Vec::with_capacity(weights.len());
+    let mut assigned: u64 = 0;
+    for (i, weight) in weights.iter().enumerate() {
+        let scaled = weight
+            .checked_mul(total as u128)
+            .ok_or(error!(PredictError::MathError))?;
+        let part = u64::try_from(scaled / sum).map_err(|_| error!(PredictError::MathError))?;
+        assigned = assigned
+            .checked_add(part)
+            .ok_or(error!(PredictError::MathError))?;
+        parts.push(part);
+        remainders.push((scaled % sum, i));
+    }
+
+    // Hand out the units lost to flooring by descending remainder
+    let leftover = total
+        .checked_sub(assigned)
+        .ok_or(error!(PredictError::MathError))? as usize;
+    remainders.sort_by(|a, b| b.0.cmp(&a.0).then(a.1.cmp(&b.1)));
+    for &(_, i) in remainders.iter().take(leftover) {
+        parts[i] += 1;
+    }
+
+    Ok(parts)
+}
+
+// Share of a bin allocation for a stake binned after `offset` of the bin's
+// stake. Shares telescope, so a bin's shares sum to its allocation exactly
+pub fn share(allocation: u64, offset: u64, stake: u64, staked: u64) -> Result<u64> {
+    if staked == 0 {
+        return Ok(0);
+    }
+    let end = offset
+        .checked_add(stake)
+        .ok_or(error!(PredictError::MathError))?;
+    require!(end <= staked, PredictError::MathError);
+    let upto = |amount: u64| (allocation as u128 * amount as u128) / staked as u128;
+    u64::try_from(upto(end) - upto(offset)).map_err(|_| error!(PredictError::MathError))
+}
+
+// Returns owed to a deposit record after equalisation
+pub fn payout(equalisation: &Equalisation, record: &DepositRecord) -> Result<(u64, u64)> {
+    require!(
+        record.binned && record.round == equalisation.round,
+        PredictError::NotEqualised
+    );
+    match record.bin {
+        Some(bin) => {
+            let bin = bin as usize;
+            Ok((
+                share(
+                    equalisation.anti[bin],
+                    record.anti_offset,
+                    record.deposit.anti,
+                    equalisation.anti_staked[bin],
+                )?,
+                share(
+                    equalisation.pro[bin],
+                    record.pro_offset,
+                    record.deposit.pro,
+                    equalisation.pro_staked[bin],
+                )?,
+            ))
+        }
+        None => Ok((0, 0)),
+    }
+}
+
+// Tokens owed to a deposit record on withdrawal: the exact deposit once the
+// prediction is voided, otherwise its payout after settlement
+pub fn withdrawal(
+    prediction: &PredictionAccount,
+    record: &DepositRecord,
+    now: i64,
+) -> Result<(u64, u64)> {
+    if prediction.voided {
+        return Ok((record.deposit.anti, record.deposit.pro));
+    }
+    require!(prediction.equalised, PredictError::NotEqualised);
+    require!(
+        prediction.is_settled(now),
+        PredictError::ChallengePeriodActive
+    );
+    let equalisation = prediction
+        .equalisation
+        .as_ref()
+        .ok_or(error!(PredictError::NotEqualised))?;
+
+    payout(equalisation, record)
+}
+
+// Fixed-point scale for intermediate logarithms
+const LN_SCALE: i128 = 1_000_000_000_000_000;
+// ln(2) at LN_SCALE
+const LN_2: i128 = 693_147_180_559_945;
+
+// Natural logarithm of x / LN_SCALE, returned at LN_SCALE
+fn ln_fixed(x: i128) -> Result<i128> {
+    require!(x > 0, PredictError::MathError);
+
+    // Reduce to m * 2^k with m in [1, 2)
+    let mut m = x;
+    let mut k: i128 = 0;
+    while m >= 2 * LN_SCALE {
+        m /= 2;
+        k += 1;
+    }
+    while m < LN_SCALE {
+        m *= 2;
+        k -= 1;
+    }
+
+    // ln(m) = 2 atanh(z) = 2 (z + z^3 / 3 + z^5 / 5 + ...), z = (m - 1) / (m + 1) <= 1/3
+    let z = (m - LN_SCALE) * LN_SCALE / (m + LN_SCALE);
+    let z2 = z * z / LN_SCALE;
+    let mut term = z;
+    let mut sum: i128 = 0;
+    let mut n: i128 = 1;
+    while term != 0 {
+        sum += term / n;
+        term = term * z2 / LN_SCALE;
+        n += 2;
+    }
+
+    Ok(2 * sum + k * LN_2)
+}
+
+// Overlap of a deposit with the truth, scaled by float_basis. With
+// L = ln(2e9 - baryon) and P = max(1, (1 + ln(photon))^2), the raw overlap
+// exp(-L^2 / 2P) normalised by its inverse log reduces to 2P / L^2, so no
+// exponential is evaluated
+fn overlap(baryon: u64, stddev: u64, parity: bool, float_basis: u64) -> Result<u64> {
+    const TWO_E9: u64 = 2_000_000_000;
+
+    // Early return if baryon too large
+    if baryon >= TWO_E9 {
+        return Ok(0);
+    }
+
+    // Squared log term L^2
+    let log_term = ln_fixed((TWO_E9 - baryon) as i128 * LN_SCALE)?;
+    let log_term = log_term * log_term / LN_SCALE;
+
+    // Photon term P; stddev is already scaled by float_basis
+    let photon = stddev as i128 * LN_SCALE / float_basis as i128;
+    let photon_term = if photon <= LN_SCALE {
+        LN_SCALE
+    } else {
+        let root = LN_SCALE + ln_fixed(photon)?;
+        root * root / LN_SCALE
+    };
+
+    // Apply inverse log normalisation: 2P / L^2, clamped to [0, 1]
+    let basis = float_basis as i128;
+    let ratio = if log_term == 0 {
+        basis
+    } else {
+        (2 * photon_term)
+            .checked_mul(basis)
+            .ok_or(error!(PredictError::MathError))?
+            / log_term
+    };
+    let normalised = if parity {
+        ratio.min(basis)
+    } else {
+        basis - ratio.min(basis)
+    };
+
+    u64::try_from(normalised).map_err(|_| error!(PredictError::MathError))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anchor_spl::token_2022::spl_token_2022::extension::transfer_fee::TransferFee;
+    use solana_program::program_pack::Pack;
+
+    // Tolerance of the fixed-point overlap against the f64 version, in units of float_basis
+    const OVERLAP_TOLERANCE: u64 = 1;
+
+    // Deterministic xorshift generator for property sweeps
+    struct Rng(u64);
+
+    impl Rng {
+        fn next(&mut self) -> u64 {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            self.0
+        }
+
+        // Log-uniform sample in [0, 2^bits)
+        fn log_uniform(&mut self, bits: u32) -> u64 {
+            let width = self.next() % (bits as u64 + 1);
+            if width == 0 {
+                0
+            } else {
+                self.next() >> (64 - width)
+            }
+        }
+    }
+
+    // Reference f64 implementation of the overlap stage
+    fn overlap_f64(baryon: f64, photon: f64, parity: f64) -> f64 {
+        const TWO_E9: f64 = 2_000_000_000.0;
+
+        if baryon >= TWO_E9 {
+            return 0.0;
+        }
+
+        let log_term = -((TWO_E9 - baryon).ln().powi(2));
+        let photon_term = 2.0
+            * if photon <= 1.0 {
+                1.0
+            } else {
+                (1.0 + photon.ln()).powi(2)
+            };
+
+        let raw_overlap = parity * (log_term / photon_term).exp();
+
+        let normalised = if raw_overlap == 0.0 {
+            0.0
+        } else if raw_overlap == 1.0 {
             1.0
+        } else if raw_overlap > 0.0 {
+            1.0 / raw_overlap.ln().abs()
         } else {
-            -1.0
+            1.0 - 1.0 / raw_overlap.abs().ln().abs()
         };
 
-        let overlap_val = overlap(baryon, photon, parity)?;
-        overlaps.push(overlap_val);
+        normalised.clamp(0.0, 1.0)
     }
-    println!("{:?}", overlaps);
-    // Initialise forward distribution structures
-    let mut bins = vec![0u64; NUM_BINS];
-    let mut items_in_bins: Vec<Vec<usize>> = vec![Vec::new(); NUM_BINS];
 
-    // Populate bins
-    for (i, &overlap_val) in overlaps.iter().enumerate() {
-        if overlap_val >= 0.0 && overlap_val <= 1.0 {
-            let bin_index = (overlap_val * (NUM_BINS as f64)).floor() as usize;
-            let bin_index = bin_index.min(NUM_BINS - 1);
+    // Reference f64 implementation of the scatterer stage
+    fn scatter_f64(occupied: &[bool]) -> Vec<f64> {
+        let non_zero_indices: Vec<usize> = occupied
+            .iter()
+            .enumerate()
+            .filter(|(_, &filled)| filled)
+            .map(|(i, _)| i)
+            .collect();
 
-            bins[bin_index] += 1;
-            items_in_bins[bin_index].push(i);
+        let mut bin_values = vec![0f64; occupied.len()];
+        if !non_zero_indices.is_empty() {
+            let total_bins = (non_zero_indices.len() + 1) as f64;
+
+            for (i, &bin_index) in non_zero_indices.iter().enumerate() {
+                let reversed_index = non_zero_indices.len() - 1 - i;
+                let value = ((reversed_index + 1) as f64) / total_bins;
+                let normalised = value
+                    / non_zero_indices
+                        .iter()
+                        .enumerate()
+                        .map(|(j, _)| ((non_zero_indices.len() - j) as f64) / total_bins)
+                        .sum::<f64>();
+                bin_values[bin_index] = normalised;
+            }
         }
+
+        bin_values
     }
 
-    // Find non-zero bins
-    let non_zero_indices: Vec<usize> = bins
-        .iter()
-        .enumerate()
-        .filter(|(_, &count)| count > 0)
-        .map(|(i, _)| i)
-        .collect();
+    // Reference f64 implementation of the localiser stage
+    fn localise_f64(bin_values: &[f64], pool: u64) -> Vec<u64> {
+        bin_values
+            .iter()
+            .map(|&value| (value * pool as f64).round() as u64)
+            .collect()
+    }
 
-    // Calculate bin values (scatterer)
-    let mut bin_values = vec![0f64; NUM_BINS];
-    if !non_zero_indices.is_empty() {
-        let total_bins = (non_zero_indices.len() + 1) as f64;
-
-        for (i, &bin_index) in non_zero_indices.iter().enumerate() {
-            let reversed_index = non_zero_indices.len() - 1 - i;
-            let value = ((reversed_index + 1) as f64) / total_bins;
-            let normalised = value
-                / non_zero_indices
-                    .iter()
-                    .enumerate()
-                    .map(|(j, _)| ((non_zero_indices.len() - j) as f64) / total_bins)
-                    .sum::<f64>();
-            bin_values[bin_index] = normalised;
+    #[test]
+    fn test_ln_fixed() {
+        for x in [1u64, 2, 3, 10, 12_345, 1_999_999_999, 2_000_000_000] {
+            let fixed = ln_fixed(x as i128 * LN_SCALE).unwrap() as f64 / LN_SCALE as f64;
+            assert!((fixed - (x as f64).ln()).abs() < 1e-12, "ln({})", x);
         }
+
+        // Fractional arguments
+        let half = ln_fixed(LN_SCALE / 2).unwrap() as f64 / LN_SCALE as f64;
+        assert!((half - 0.5f64.ln()).abs() < 1e-12);
+        assert!(ln_fixed(0).is_err());
     }
 
-    // Initialise return arrays
-    let mut anti_returns = vec![0u64; deposits.len()];
-    let mut pro_returns = vec![0u64; deposits.len()];
+    #[test]
+    fn test_overlap_matches_f64() {
+        let mut rng = Rng(0x9e37_79b9_7f4a_7c15);
+        for float_basis in [FLOAT_BASIS, 1_000, 1_000_000] {
+            for _ in 0..20_000 {
+                let baryon = rng.log_uniform(31);
+                let stddev = rng.log_uniform(48);
+                let parity = rng.next().is_multiple_of(2);
 
-    // Calculate returns (localiser)
-    for (bin_idx, indices) in items_in_bins.iter().enumerate() {
-        if indices.is_empty() || bin_values[bin_idx] == 0.0 {
-            continue;
+                let fixed = overlap(baryon, stddev, parity, float_basis).unwrap();
+                let float = overlap_f64(
+                    baryon as f64,
+                    stddev as f64 / float_basis as f64,
+                    if parity { 1.0 } else { -1.0 },
+                );
+                let expected = (float * float_basis as f64).round() as u64;
+                assert!(
+                    fixed.abs_diff(expected) <= OVERLAP_TOLERANCE,
+                    "overlap({}, {}, {}) = {}, expected {}",
+                    baryon,
+                    stddev,
+                    parity,
+                    fixed,
+                    expected
+                );
+                assert!(fixed <= float_basis);
+            }
         }
+    }
 
-        let bin_anti = (bin_values[bin_idx] * anti_pool as f64).round() as u64;
-        let bin_pro = (bin_values[bin_idx] * pro_pool as f64).round() as u64;
+    #[test]
+    fn test_bins_match_f64() {
+        let mut rng = Rng(0x2545_f491_4f6c_dd1d);
+        let truth = [60_000, 40_000];
+        for _ in 0..20_000 {
+            let anti = rng.log_uniform(32);
+            let pro = rng.log_uniform(32);
+            let (mean, stddev) = collide(anti, pro, FLOAT_BASIS).unwrap();
+            let deposit = Deposit {
+                address: Pubkey::default(),
+                anti,
+                pro,
+                mean,
+                stddev,
+                withdrawn: false,
+                distribution: vec![anti, pro],
+            };
 
-        let total_anti: u64 = indices.iter().map(|&i| deposits[i].anti).sum();
-        let total_pro: u64 = indices.iter().map(|&i| deposits[i].pro).sum();
+            let fixed = bin_deposit(&deposit, &truth, None, FLOAT_BASIS)
+                .unwrap()
+                .unwrap();
+            let parity = if (truth[0] > truth[1]) == (anti > pro) {
+                1.0
+            } else {
+                -1.0
+            };
+            let float = overlap_f64(mean as f64, stddev as f64 / FLOAT_BASIS as f64, parity);
+            let expected = ((float * NUM_BINS as f64).floor() as usize).min(NUM_BINS - 1);
 
-        for &i in indices {
-            if total_anti > 0 {
-                anti_returns[i] =
-                    ((bin_anti as u128 * deposits[i].anti as u128) / total_anti as u128) as u64;
+            // Bins may only differ where the f64 overlap sits on a bin boundary
+            if fixed != expected {
+                let edge = (float * NUM_BINS as f64).round() / NUM_BINS as f64;
+                assert!(fixed.abs_diff(expected) <= 1);
+                assert!(
+                    (float - edge).abs() * FLOAT_BASIS as f64 <= OVERLAP_TOLERANCE as f64,
+                    "bin {} != {} for ({}, {})",
+                    fixed,
+                    expected,
+                    anti,
+                    pro
+                );
             }
-            if total_pro > 0 {
-                pro_returns[i] =
-                    ((bin_pro as u128 * deposits[i].pro as u128) / total_pro as u128) as u64;
+        }
+    }
+
+    #[test]
+    fn test_scatter_and_localise_match_f64() {
+        let mut rng = Rng(0xdead_beef_cafe_f00d);
+        for float_basis in [FLOAT_BASIS, 1_000_000] {
+            for _ in 0..2_000 {
+                let occupied: Vec<bool> = (0..NUM_BINS)
+                    .map(|_| rng.next().is_multiple_of(7))
+                    .collect();
+                let filled = occupied.iter().filter(|&&filled| filled).count() as u64;
+
+                let fixed = scatter(&occupied, float_basis).unwrap();
+                let float = scatter_f64(&occupied);
+                for (value, reference) in fixed.iter().zip(float.iter()) {
+                    assert!((*value as f64 - reference * float_basis as f64).abs() < 1.0);
+                }
+
+                // Weights sum to the basis exactly
+                let total: u64 = fixed.iter().sum();
+                assert_eq!(total, if filled == 0 { 0 } else { float_basis });
+
+                let pool = rng.log_uniform(50);
+                let allocations = localise(&fixed, pool).unwrap();
+                let reference = localise_f64(&float, pool);
+                for (allocation, expected) in allocations.iter().zip(reference.iter()) {
+                    assert!(allocation.abs_diff(*expected) <= pool / float_basis + 2);
+                }
+                assert_eq!(
+                    allocations.iter().sum::<u64>(),
+                    if filled == 0 { 0 } else { pool }
+                );
             }
         }
     }
 
-    Ok((anti_returns, pro_returns))
-}
+    #[test]
+    fn test_apportion_largest_remainder() {
+        // Thirds of 100 hand the spare unit to the first largest remainder
+        assert_eq!(apportion(&[1, 1, 1], 100).unwrap(), vec![34, 33, 33]);
+        assert_eq!(apportion(&[0, 2, 1], 10).unwrap(), vec![0, 7, 3]);
+        assert_eq!(apportion(&[0, 0], 10).unwrap(), vec![0, 0]);
 
-fn overlap(baryon: f64, photon: f64, parity: f64) -> Result<f64> {
-    const TWO_E9: f64 = 2_000_000_000.0;
+        // Telescoping shares split a bin allocation exactly
+        let stakes = [3u64, 3, 3];
+        let mut offset = 0;
+        let mut total = 0;
+        for stake in stakes {
+            total += share(100, offset, stake, 9).unwrap();
+            offset += stake;
+        }
+        assert_eq!(total, 100);
+        assert!(share(100, 8, 3, 9).is_err());
+    }
 
-    // Early return if baryon too large
-    if baryon >= TWO_E9 {
-        return Ok(0.0);
+    #[test]
+    fn test_returns_conserve_pools() {
+        let mut rng = Rng(0x0123_4567_89ab_cdef);
+        for _ in 0..500 {
+            let count = 1 + (rng.next() % 64) as usize;
+            let deposits: Vec<Deposit> = (0..count)
+                .map(|_| {
+                    let anti = rng.log_uniform(28);
+                    let pro = rng.log_uniform(28);
+                    let (mean, stddev) = collide(anti, pro, FLOAT_BASIS).unwrap();
+                    Deposit {
+                        address: Pubkey::new_unique(),
+                        anti,
+                        pro,
+                        mean,
+                        stddev,
+                        withdrawn: false,
+                        distribution: vec![anti, pro],
+                    }
+                })
+                .collect();
+            let anti_pool: u64 = deposits.iter().map(|deposit| deposit.anti).sum();
+            let pro_pool: u64 = deposits.iter().map(|deposit| deposit.pro).sum();
+            let first = rng.next() % (TRUTH_BASIS + 1);
+            let truth = [first, TRUTH_BASIS - first];
+
+            let (anti_returns, pro_returns) =
+                equalise_with_truth(&deposits, anti_pool, pro_pool, &truth, None, FLOAT_BASIS)
+                    .unwrap();
+
+            // Every token deposited is returned; none is stranded or minted
+            assert_eq!(anti_returns.iter().sum::<u64>(), anti_pool);
+            assert_eq!(pro_returns.iter().sum::<u64>(), pro_pool);
+
+            // Deposits with no stake of a token receive none of it
+            for (i, deposit) in deposits.iter().enumerate() {
+                if deposit.anti == 0 {
+                    assert_eq!(anti_returns[i], 0);
+                }
+                if deposit.pro == 0 {
+                    assert_eq!(pro_returns[i], 0);
+                }
+            }
+        }
     }
 
-    // Calculate raw overlap value
-    let log_term = -((TWO_E9 - baryon).ln().powi(2));
-    let photon_term = 2.0
-        * if photon <= 1.0 {
-            1.0
-        } else {
-            (1.0 + photon.ln()).powi(2)
+    #[test]
+    fn test_leading_outcome_generalises_binary_parity() {
+        let mut rng = Rng(0x5851_f42d_4c95_7f2d);
+        for _ in 0..10_000 {
+            let a = rng.next() % 16;
+            let b = rng.next() % 16;
+            assert_eq!(leading_outcome(&[a, b]) == 0, a > b);
+        }
+        assert_eq!(leading_outcome(&[1, 5, 5, 2]), 2);
+        assert_eq!(leading_outcome(&[9, 5, 5, 2]), 0);
+    }
+
+    #[test]
+    fn test_multi_outcome_equalisation() {
+        let truth = [10_000, 60_000, 10_000, 10_000, 10_000];
+        let deposit = |distribution: Vec<u64>| {
+            let (mean, stddev) = collide(60_000, 40_000, FLOAT_BASIS).unwrap();
+            Deposit {
+                address: Pubkey::new_unique(),
+                anti: 60_000,
+                pro: 40_000,
+                mean,
+                stddev,
+                withdrawn: false,
+                distribution,
+            }
         };
+        let deposits = vec![
+            deposit(vec![0, 5, 1, 1, 1]), // Favours the true outcome
+            deposit(vec![5, 0, 1, 1, 1]),
+            deposit(vec![1, 1, 1, 1, 5]),
+        ];
 
-    let raw_overlap = parity * (log_term / photon_term).exp();
+        // Stakes favouring the same outcome as the truth share a bin apart from the rest
+        let bins: Vec<usize> = deposits
+            .iter()
+            .map(|deposit| {
+                bin_deposit(deposit, &truth, None, FLOAT_BASIS)
+                    .unwrap()
+                    .unwrap()
+            })
+            .collect();
+        assert_ne!(bins[0], bins[1]);
+        assert_eq!(bins[1], bins[2]);
 
-    // Apply inverse log normalisation
-    let normalised = if raw_overlap == 0.0 {
-        0.0
-    } else if raw_overlap == 1.0 {
-        1.0
-    } else if raw_overlap > 0.0 {
-        1.0 / raw_overlap.ln().abs()
-    } else {
-        1.0 - 1.0 / raw_overlap.abs().ln().abs()
-    };
+        let (anti_returns, pro_returns) =
+            equalise_with_truth(&deposits, 180_000, 120_000, &truth, None, FLOAT_BASIS).unwrap();
+        assert_eq!(anti_returns.iter().sum::<u64>(), 180_000);
+        assert_eq!(pro_returns.iter().sum::<u64>(), 120_000);
+        assert_eq!(anti_returns[1], anti_returns[2]);
+
+        // Truth must cover each outcome of the deposit
+        assert!(bin_deposit(&deposits[0], &[50_000, 50_000], None, FLOAT_BASIS).is_err());
+    }
+
+    #[test]
+    fn test_scalar_equalisation() {
+        let range = ScalarRange {
+            lower: 0,
+            upper: 1_000,
+            unit: "USD".to_string(),
+        };
+        let deposit = |anti: u64, pro: u64| {
+            let (mean, stddev) = collide(anti, pro, FLOAT_BASIS).unwrap();
+            Deposit {
+                address: Pubkey::new_unique(),
+                anti,
+                pro,
+                mean,
+                stddev,
+                withdrawn: false,
+                distribution: vec![scalar_estimate(&range, anti, pro, mean).unwrap()],
+            }
+        };
+        let deposits = vec![
+            deposit(50_000, 50_000),
+            deposit(20_000, 80_000),
+            deposit(80_000, 20_000),
+        ];
+
+        // Estimates span the range from the $ANTI to the $PRO end
+        let estimates: Vec<u64> = deposits.iter().map(|d| d.distribution[0]).collect();
+        assert_eq!(estimates, vec![500, 800, 200]);
+        assert_eq!(scalar_estimate(&range, 100, 0, 100).unwrap(), 0);
+        assert_eq!(scalar_estimate(&range, 0, 100, 100).unwrap(), 1_000);
+
+        // Deposits are binned by distance from the realised value
+        let truth = [800];
+        let bins: Vec<usize> = deposits
+            .iter()
+            .map(|d| {
+                bin_deposit(d, &truth, Some(&range), FLOAT_BASIS)
+                    .unwrap()
+                    .unwrap()
+            })
+            .collect();
+        assert_eq!(bins, vec![30, 0, 60]);
+
+        // Closer estimates receive more than they staked, and pools are conserved
+        let (anti_returns, pro_returns) = equalise_with_truth(
+            &deposits,
+            150_000,
+            150_000,
+            &truth,
+            Some(&range),
+            FLOAT_BASIS,
+        )
+        .unwrap();
+        assert_eq!(anti_returns.iter().sum::<u64>(), 150_000);
+        assert_eq!(pro_returns.iter().sum::<u64>(), 150_000);
+        let returns: Vec<u64> = (0..3).map(|i| anti_returns[i] + pro_returns[i]).collect();
+        assert!(returns[1] > 100_000);
+        assert!(returns[2] < 100_000);
+        assert!(returns[1] > returns[0] && returns[0] > returns[2]);
+    }
+
+    #[test]
+    fn test_voided_withdrawal_refunds_deposit() {
+        let record = DepositRecord {
+            deposit: Deposit {
+                anti: 70_000,
+                pro: 30_000,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let prediction = PredictionAccount {
+            version: PREDICTION_VERSION,
+            anti: 70_000,
+            pro: 30_000,
+            deposits: 1,
+            ..Default::default()
+        };
 
-    Ok(normalised.clamp(0.0, 1.0))
+        // Unresolved predictions owe nothing until equalised
+        assert_eq!(
+            withdrawal(&prediction, &record, 0).unwrap_err(),
+            PredictError::NotEqualised.into()
+        );
+
+        // Voided predictions refund the exact deposit
+        let voided = PredictionAccount {
+            version: PREDICTION_VERSION,
+            voided: true,
+            ..prediction
+        };
+        assert_eq!(withdrawal(&voided, &record, 0).unwrap(), (70_000, 30_000));
+    }
+
+    #[test]
+    fn test_clock_override() {
+        set_clock_override(Some(1736899200));
+        assert_eq!(current_time().unwrap(), 1736899200);
+
+        // Without an override the Clock sysvar is unavailable off-chain
+        set_clock_override(None);
+        assert!(current_time().is_err());
+    }
+
+    #[test]
+    fn test_transfer_fee_deducted_from_received_amount() {
+        // 1% fee, capped at 5_000 tokens per transfer
+        let fee = TransferFee {
+            epoch: 0.into(),
+            maximum_fee: 5_000.into(),
+            transfer_fee_basis_points: 100.into(),
+        };
+        let config = TransferFeeConfig {
+            older_transfer_fee: fee,
+            newer_transfer_fee: fee,
+            ..Default::default()
+        };
+        assert_eq!(net_of_transfer_fee(&config, 0, 100_000).unwrap(), 99_000);
+        assert_eq!(net_of_transfer_fee(&config, 0, 1_000_000).unwrap(), 995_000);
+
+        // Mints without the extension credit the full amount
+        let key = Pubkey::new_unique();
+        let owner = anchor_spl::token::spl_token::ID;
+        let mut lamports = 1_000_000;
+        let mut data = vec![0; Mint::LEN];
+        Mint {
+            decimals: 9,
+            is_initialized: true,
+            ..Default::default()
+        }
+        .pack_into_slice(&mut data);
+        let mint = AccountInfo::new(
+            &key,
+            false,
+            false,
+            &mut lamports,
+            &mut data,
+            &owner,
+            false,
+            0,
+        );
+        assert_eq!(received_amount(&mint, 100_000).unwrap(), 100_000);
+    }
+
+    #[test]
+    fn test_write_account_bounded_by_allocation() {
+        let key = Pubkey::new_unique();
+        let owner = Pubkey::new_unique();
+        let state = StateAccount {
+            version: STATE_VERSION,
+            index: 7,
+            authority: owner,
+            archived: 2,
+        };
+
+        // Accounts allocated at their full size are written behind the discriminator
+        let mut lamports = 1_000_000;
+        let mut data = vec![0; 8 + StateAccount::LEN];
+        data[..8].copy_from_slice(&[1; 8]);
+        let info = AccountInfo::new(
+            &key,
+            false,
+            true,
+            &mut lamports,
+            &mut data,
+            &owner,
+            false,
+            0,
+        );
+        write_account(&info, &state).unwrap();
+        let written =
+            StateAccount::deserialize(&mut &info.try_borrow_data().unwrap()[8..]).unwrap();
+        assert_eq!((written.index, written.archived), (7, 2));
+        assert_eq!(info.try_borrow_data().unwrap()[..8], [1; 8]);
+
+        // Accounts too small for the data are left untouched
+        let mut lamports = 1_000_000;
+        let mut data = vec![0; 8 + 16];
+        let info = AccountInfo::new(
+            &key,
+            false,
+            true,
+            &mut lamports,
+            &mut data,
+            &owner,
+            false,
+            0,
+        );
+        assert_eq!(
+            write_account(&info, &state).unwrap_err(),
+            PredictError::AccountFull.into()
+        );
+        assert!(info
+            .try_borrow_data()
+            .unwrap()
+            .iter()
+            .all(|byte| *byte == 0));
+    }
 }
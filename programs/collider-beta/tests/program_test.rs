@@ -6,6 +6,7 @@ use anchor_spl::token::spl_token;
 use anchor_spl::token::{self, Mint, TokenAccount};
 use collider_beta::instruction::Initialiser;
 use collider_beta::utils::ANTITOKEN_MULTISIG;
+use collider_beta::AdminParameter;
 use solana_program_test::*;
 use solana_sdk::system_program;
 use solana_sdk::sysvar;
@@ -91,12 +92,26 @@ async fn test_full_collider_flow() {
     let (prediction_pda, _) =
         Pubkey::find_program_address(&[b"prediction", index.to_le_bytes().as_ref()], &program_id);
 
+    let (pool_authority_pda, _) =
+        Pubkey::find_program_address(&[b"authority", index.to_le_bytes().as_ref()], &program_id);
+
     let (prediction_anti_token_pda, _) =
         Pubkey::find_program_address(&[b"anti_token", index.to_le_bytes().as_ref()], &program_id);
 
     let (prediction_pro_token_pda, _) =
         Pubkey::find_program_address(&[b"pro_token", index.to_le_bytes().as_ref()], &program_id);
 
+    let nonce = 0u64;
+    let (deposit_record_pda, _) = Pubkey::find_program_address(
+        &[
+            b"deposit",
+            index.to_le_bytes().as_ref(),
+            user.pubkey().as_ref(),
+            nonce.to_le_bytes().as_ref(),
+        ],
+        &program_id,
+    );
+
     // Initialise accounts with 10 SOL each
     program_test.add_account(
         manager.pubkey(),
@@ -142,7 +157,9 @@ async fn test_full_collider_flow() {
         },
     );
 
-    let (mut banks_client, _, recent_blockhash) = program_test.start().await;
+    let mut context = program_test.start_with_context().await;
+    let mut banks_client = context.banks_client.clone();
+    let recent_blockhash = context.last_blockhash;
 
     // Get rent for various account types
     let rent = banks_client.get_rent().await.unwrap();
@@ -230,7 +247,43 @@ async fn test_full_collider_flow() {
         recent_blockhash,
     );
     banks_client.process_transaction(tx).await.unwrap();
-    user_token_accounts.push((user_anti_token.pubkey(), user_pro_token.pubkey()));
+    user_token_accounts.push((
+        deposit_record_pda,
+        user_anti_token.pubkey(),
+        user_pro_token.pubkey(),
+        user.pubkey(),
+    ));
+
+    // Create the fee token accounts of the protocol and the creator
+    let protocol_anti_token = Keypair::new();
+    let protocol_pro_token = Keypair::new();
+    let creator_anti_token = Keypair::new();
+    let creator_pro_token = Keypair::new();
+    let fee_accounts = [
+        (&protocol_anti_token, anti_mint.pubkey(), ANTITOKEN_MULTISIG),
+        (&protocol_pro_token, pro_mint.pubkey(), ANTITOKEN_MULTISIG),
+        (&creator_anti_token, anti_mint.pubkey(), creator.pubkey()),
+        (&creator_pro_token, pro_mint.pubkey(), creator.pubkey()),
+    ];
+    for (account, mint, owner) in fee_accounts.iter() {
+        let tx = Transaction::new_signed_with_payer(
+            &[
+                system_instruction::create_account(
+                    &manager.pubkey(),
+                    &account.pubkey(),
+                    token_rent,
+                    TokenAccount::LEN as u64,
+                    &token::ID,
+                ),
+                token_instruction::initialize_account(&token::ID, &account.pubkey(), mint, owner)
+                    .unwrap(),
+            ],
+            Some(&manager.pubkey()),
+            &[&manager, *account],
+            recent_blockhash,
+        );
+        banks_client.process_transaction(tx).await.unwrap();
+    }
 
     // Mint tokens to user accounts
     let tx = Transaction::new_signed_with_payer(
@@ -281,15 +334,16 @@ async fn test_full_collider_flow() {
     banks_client.process_transaction(tx).await.unwrap();
     println!("✅ Admin initialisation passing ...");
 
-    // Test updating prediction creation fee
+    // Test queueing a prediction creation fee update
     let ix = Instruction {
         program_id,
         accounts: vec![
             AccountMeta::new(admin_pda, false),
             AccountMeta::new(antitoken_multisig.pubkey(), true),
         ],
-        data: collider_beta::instruction::UpdateCreationFee {
-            new_fee: 200_000_000,
+        data: collider_beta::instruction::QueueUpdate {
+            parameter: AdminParameter::CreationFee,
+            value: 200_000_000,
         }
         .data(),
     };
@@ -301,7 +355,7 @@ async fn test_full_collider_flow() {
         recent_blockhash,
     );
     banks_client.process_transaction(tx).await.unwrap();
-    println!("✅ Prediction creation fee update passing ...");
+    println!("✅ Prediction creation fee queueing passing ...");
 
     // Test updating max title length
     let ix = Instruction {
@@ -341,14 +395,18 @@ async fn test_full_collider_flow() {
     banks_client.process_transaction(tx).await.unwrap();
     println!("✅ Max description length update passing ...");
 
-    // Test updating truth basis
+    // Test queueing a truth basis update
     let ix = Instruction {
         program_id,
         accounts: vec![
             AccountMeta::new(admin_pda, false),
             AccountMeta::new(antitoken_multisig.pubkey(), true),
         ],
-        data: collider_beta::instruction::UpdateTruthBasis { new_basis: 200_000 }.data(),
+        data: collider_beta::instruction::QueueUpdate {
+            parameter: AdminParameter::TruthBasis,
+            value: 200_000,
+        }
+        .data(),
     };
 
     let tx = Transaction::new_signed_with_payer(
@@ -358,16 +416,20 @@ async fn test_full_collider_flow() {
         recent_blockhash,
     );
     banks_client.process_transaction(tx).await.unwrap();
-    println!("✅ Truth basis update passing ...");
+    println!("✅ Truth basis queueing passing ...");
 
-    // Test updating float basis
+    // Test queueing a float basis update
     let ix = Instruction {
         program_id,
         accounts: vec![
             AccountMeta::new(admin_pda, false),
             AccountMeta::new(antitoken_multisig.pubkey(), true),
         ],
-        data: collider_beta::instruction::UpdateFloatBasis { new_basis: 20_000 }.data(),
+        data: collider_beta::instruction::QueueUpdate {
+            parameter: AdminParameter::FloatBasis,
+            value: 20_000,
+        }
+        .data(),
     };
 
     let tx = Transaction::new_signed_with_payer(
@@ -377,17 +439,18 @@ async fn test_full_collider_flow() {
         recent_blockhash,
     );
     banks_client.process_transaction(tx).await.unwrap();
-    println!("✅ Float basis update passing ...");
+    println!("✅ Float basis queueing passing ...");
 
-    // Test updating min deposit amount
+    // Test queueing a min deposit amount update
     let ix = Instruction {
         program_id,
         accounts: vec![
             AccountMeta::new(admin_pda, false),
             AccountMeta::new(antitoken_multisig.pubkey(), true),
         ],
-        data: collider_beta::instruction::UpdateMinDepositAmount {
-            new_min_amount: 20_000,
+        data: collider_beta::instruction::QueueUpdate {
+            parameter: AdminParameter::MinDepositAmount,
+            value: 20_000,
         }
         .data(),
     };
@@ -399,7 +462,7 @@ async fn test_full_collider_flow() {
         recent_blockhash,
     );
     banks_client.process_transaction(tx).await.unwrap();
-    println!("✅ Min deposit amount update passing ...");
+    println!("✅ Min deposit amount queueing passing ...");
 
     // Test updating $ANTI mint address
     let ix = Instruction {
@@ -445,7 +508,7 @@ async fn test_full_collider_flow() {
     banks_client.process_transaction(tx).await.unwrap();
     println!("✅ $PRO mint update passing ...");
 
-    // Test updating multisig authority
+    // Test proposing a new multisig authority
     let new_multisig = Keypair::new();
     let ix = Instruction {
         program_id,
@@ -453,7 +516,7 @@ async fn test_full_collider_flow() {
             AccountMeta::new(admin_pda, false),
             AccountMeta::new(antitoken_multisig.pubkey(), true),
         ],
-        data: collider_beta::instruction::UpdateMultisig {
+        data: collider_beta::instruction::ProposeMultisig {
             new_multisig: new_multisig.pubkey(),
         }
         .data(),
@@ -466,7 +529,7 @@ async fn test_full_collider_flow() {
         recent_blockhash,
     );
     banks_client.process_transaction(tx).await.unwrap();
-    println!("✅ Multisig authority update passing ...");
+    println!("✅ Multisig authority proposal passing ...");
     println!("✅ Admin actions passing ...");
 
     // Create the initialisation instruction
@@ -494,9 +557,11 @@ async fn test_full_collider_flow() {
     let create_prediction_ix = Instruction {
         program_id,
         accounts: vec![
+            AccountMeta::new_readonly(admin_pda, false),
             AccountMeta::new(state_pda, false),
             AccountMeta::new(prediction_pda, false),
             AccountMeta::new(creator.pubkey(), true),
+            AccountMeta::new_readonly(pool_authority_pda, false),
             AccountMeta::new(prediction_anti_token_pda, false),
             AccountMeta::new(prediction_pro_token_pda, false),
             AccountMeta::new(anti_mint.pubkey(), false),
@@ -511,12 +576,18 @@ async fn test_full_collider_flow() {
             description: "Test Description".to_string(),
             start_time: "2025-02-01T00:00:00Z".to_string(),
             end_time: "2025-03-01T00:00:00Z".to_string(),
+            resolution_time: None,
+            outcomes: vec!["Anti".to_string(), "Pro".to_string()],
+            scalar: None,
+            resolver: manager.pubkey(),
+            creator_fee_bps: 100,
             etc: None,
-            unix_timestamp: Some(1736899200),
         }
         .data(),
     };
 
+    set_clock(&mut context, 1736899200).await;
+
     let tx = Transaction::new_signed_with_payer(
         &[create_prediction_ix],
         Some(&creator.pubkey()),
@@ -530,23 +601,32 @@ async fn test_full_collider_flow() {
     let deposit_ix = Instruction {
         program_id,
         accounts: vec![
+            AccountMeta::new_readonly(admin_pda, false),
             AccountMeta::new(prediction_pda, false),
+            AccountMeta::new(deposit_record_pda, false),
             AccountMeta::new(user.pubkey(), true),
             AccountMeta::new(user_anti_token.pubkey(), false),
             AccountMeta::new(user_pro_token.pubkey(), false),
+            AccountMeta::new_readonly(pool_authority_pda, false),
             AccountMeta::new(prediction_anti_token_pda, false),
             AccountMeta::new(prediction_pro_token_pda, false),
+            AccountMeta::new_readonly(anti_mint.pubkey(), false),
+            AccountMeta::new_readonly(pro_mint.pubkey(), false),
             AccountMeta::new_readonly(token::ID, false),
+            AccountMeta::new_readonly(solana_program::system_program::ID, false),
         ],
         data: collider_beta::instruction::DepositTokens {
             index,
+            nonce,
             anti: 7_000_000_000,
             pro: 3_000_000_000,
-            unix_timestamp: Some(1739577600), // 2025-02-15T00:00:00Z for testing
+            distribution: vec![], // Binary default: $ANTI/$PRO split
         }
         .data(),
     };
 
+    set_clock(&mut context, 1739577600).await; // 2025-02-15T00:00:00Z for testing
+
     let tx = Transaction::new_signed_with_payer(
         &[deposit_ix],
         Some(&manager.pubkey()),
@@ -561,22 +641,32 @@ async fn test_full_collider_flow() {
     let equalise_ix = Instruction {
         program_id,
         accounts: vec![
+            AccountMeta::new_readonly(admin_pda, false),
             AccountMeta::new(prediction_pda, false),
             AccountMeta::new(manager.pubkey(), true),
-            AccountMeta::new(user_anti_token.pubkey(), false),
-            AccountMeta::new(user_pro_token.pubkey(), false),
+            AccountMeta::new_readonly(pool_authority_pda, false),
             AccountMeta::new(prediction_anti_token_pda, false),
             AccountMeta::new(prediction_pro_token_pda, false),
+            AccountMeta::new(protocol_anti_token.pubkey(), false),
+            AccountMeta::new(protocol_pro_token.pubkey(), false),
+            AccountMeta::new(creator_anti_token.pubkey(), false),
+            AccountMeta::new(creator_pro_token.pubkey(), false),
+            AccountMeta::new_readonly(anti_mint.pubkey(), false),
+            AccountMeta::new_readonly(pro_mint.pubkey(), false),
             AccountMeta::new_readonly(token::ID, false),
+            AccountMeta::new_readonly(solana_program::system_program::ID, false),
+            AccountMeta::new_readonly(solana_program::sysvar::rent::ID, false),
+            AccountMeta::new(deposit_record_pda, false),
         ],
         data: collider_beta::instruction::EqualiseTokens {
             index,
             truth: vec![6000, 4000],
-            unix_timestamp: Some(1741996800), // 2025-03-15T00:00:00Z for testing
         }
         .data(),
     };
 
+    set_clock(&mut context, 1741996800).await; // 2025-03-15T00:00:00Z for testing
+
     let tx = Transaction::new_signed_with_payer(
         &[equalise_ix],
         Some(&manager.pubkey()),
@@ -588,25 +678,39 @@ async fn test_full_collider_flow() {
 
     // Withdraw tokens
     let mut withdraw_accounts = vec![
+        AccountMeta::new_readonly(admin_pda, false),
         AccountMeta::new(prediction_pda, false),
         AccountMeta::new(antitoken_multisig.pubkey(), true), // Only the multisig can withdraw
+        AccountMeta::new_readonly(pool_authority_pda, false), // Pool authority PDA
         AccountMeta::new(prediction_anti_token_pda, false),  // Anti Token PDA
         AccountMeta::new(prediction_pro_token_pda, false),   // Pro Token PDA
+        AccountMeta::new_readonly(anti_mint.pubkey(), false),
+        AccountMeta::new_readonly(pro_mint.pubkey(), false),
         AccountMeta::new_readonly(token::ID, false),
     ];
 
-    // Include all user token accounts dynamically
-    for (anti_account, pro_account) in user_token_accounts.iter() {
+    // Include all deposit records, user token accounts and depositors dynamically;
+    // each record is closed to its depositor
+    for (deposit_record, anti_account, pro_account, depositor) in user_token_accounts.iter() {
+        withdraw_accounts.push(AccountMeta::new(*deposit_record, false));
         withdraw_accounts.push(AccountMeta::new(*anti_account, false));
         withdraw_accounts.push(AccountMeta::new(*pro_account, false));
+        withdraw_accounts.push(AccountMeta::new(*depositor, false));
     }
 
     let withdraw_ix = Instruction {
         program_id,
         accounts: withdraw_accounts,
-        data: collider_beta::instruction::BulkWithdrawTokens { index }.data(),
+        data: collider_beta::instruction::BulkWithdrawTokens {
+            index,
+            offset: 0,
+            batch: user_token_accounts.len() as u64,
+        }
+        .data(),
     };
 
+    set_clock(&mut context, 1742083200).await; // 2025-03-16T00:00:00Z, after the challenge period
+
     let tx = Transaction::new_signed_with_payer(
         &[withdraw_ix],
         Some(&antitoken_multisig.pubkey()),
@@ -618,3 +722,10 @@ async fn test_full_collider_flow() {
 
     println!("✅ Withdrawals passing ...");
 }
+
+// Pins the Clock sysvar so that time-gated instructions see a fixed timestamp
+async fn set_clock(context: &mut ProgramTestContext, unix_timestamp: i64) {
+    let mut clock: Clock = context.banks_client.get_sysvar().await.unwrap();
+    clock.unix_timestamp = unix_timestamp;
+    context.set_sysvar(&clock);
+}
@@ -99,11 +99,18 @@ describe("collider-beta", () => {
   let predictionPda2: PublicKey;
   let predictionAntiTokenPda: PublicKey;
   let predictionProTokenPda: PublicKey;
+  let poolAuthorityPda: PublicKey;
+  let depositRecordPda: PublicKey;
 
   let userAntiToken: PublicKey;
   let userProToken: PublicKey;
+  let protocolAntiToken: PublicKey;
+  let protocolProToken: PublicKey;
+  let creatorAntiToken: PublicKey;
+  let creatorProToken: PublicKey;
 
   const index = new BN(0);
+  const nonce = new BN(0);
 
   before(async () => {
     // Create test keypairs
@@ -156,6 +163,21 @@ describe("collider-beta", () => {
       program.programId
     );
 
+    [poolAuthorityPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("authority"), index.toArrayLike(Buffer, "le", 8)],
+      program.programId
+    );
+
+    [depositRecordPda] = PublicKey.findProgramAddressSync(
+      [
+        Buffer.from("deposit"),
+        index.toArrayLike(Buffer, "le", 8),
+        user.publicKey.toBuffer(),
+        nonce.toArrayLike(Buffer, "le", 8),
+      ],
+      program.programId
+    );
+
     // Initialise token mints
     await createMint(
       provider.connection,
@@ -194,6 +216,35 @@ describe("collider-beta", () => {
       user.publicKey
     );
 
+    // Create fee token accounts for the protocol and the creator
+    protocolAntiToken = await createAccount(
+      provider.connection,
+      manager,
+      antiMintKeypair.publicKey,
+      antitokenMultisigKeypair.publicKey
+    );
+
+    protocolProToken = await createAccount(
+      provider.connection,
+      manager,
+      proMintKeypair.publicKey,
+      antitokenMultisigKeypair.publicKey
+    );
+
+    creatorAntiToken = await createAccount(
+      provider.connection,
+      manager,
+      antiMintKeypair.publicKey,
+      creator.publicKey
+    );
+
+    creatorProToken = await createAccount(
+      provider.connection,
+      manager,
+      proMintKeypair.publicKey,
+      creator.publicKey
+    );
+
     // Mint tokens to user
     await mintTo(
       provider.connection,
@@ -283,12 +334,18 @@ describe("collider-beta", () => {
           startTime,
           endTime,
           null,
-          new BN(1736899200) // Fixed timestamp for testing
+          ["Anti", "Pro"],
+          null,
+          manager.publicKey,
+          100,
+          null
         )
         .accounts({
           state: statePda,
+          admin: adminPda,
           prediction: predictionPda,
           authority: creator.publicKey,
+          poolAuthority: poolAuthorityPda,
           predictionAntiToken: predictionAntiTokenPda,
           predictionProToken: predictionProTokenPda,
           antiMint: antiMintKeypair.publicKey,
@@ -306,8 +363,14 @@ describe("collider-beta", () => {
       expect(Number(prediction.index)).to.equal(0);
       expect(prediction.title).to.equal("Test Prediction");
       expect(prediction.description).to.equal("Test Description");
-      expect(prediction.startTime).to.equal(startTime);
-      expect(prediction.endTime).to.equal(endTime);
+      expect(prediction.startTime.toNumber()).to.equal(
+        Date.parse(startTime) / 1000
+      );
+      expect(prediction.endTime.toNumber()).to.equal(Date.parse(endTime) / 1000);
+      expect(prediction.resolutionTime).to.be.null;
+      expect(prediction.antiMint.toBase58()).to.equal(
+        antiMintKeypair.publicKey.toBase58()
+      );
     });
   });
 
@@ -317,15 +380,21 @@ describe("collider-beta", () => {
       const pro = new BN(3_000_000_000);
 
       await program.methods
-        .depositTokens(index, anti, pro, new BN(1739577600))
+        .depositTokens(index, nonce, anti, pro, [])
         .accounts({
+          admin: adminPda,
           prediction: predictionPda,
+          depositRecord: depositRecordPda,
           authority: user.publicKey,
           userAntiToken: userAntiToken,
           userProToken: userProToken,
+          poolAuthority: poolAuthorityPda,
           predictionAntiToken: predictionAntiTokenPda,
           predictionProToken: predictionProTokenPda,
+          antiMint: antiMintKeypair.publicKey,
+          proMint: proMintKeypair.publicKey,
           tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
         })
         .signers([user])
         .rpc();
@@ -335,23 +404,39 @@ describe("collider-beta", () => {
       );
       expect(Number(prediction.anti)).to.equal(anti.toNumber());
       expect(Number(prediction.pro)).to.equal(pro.toNumber());
-      expect(prediction.deposits).to.have.lengthOf(1);
+      expect(Number(prediction.deposits)).to.equal(1);
+
+      const record = await program.account.depositRecord.fetch(
+        depositRecordPda
+      );
+      expect(record.deposit.address.toBase58()).to.equal(
+        user.publicKey.toBase58()
+      );
     });
   });
 
   describe("Prediction Equalisation", () => {
     it("Equalises prediction with truth", async () => {
       await program.methods
-        .equaliseTokens(index, [new BN(6000), new BN(4000)], new BN(1741996800))
+        .equaliseTokens(index, [new BN(6000), new BN(4000)])
         .accounts({
+          admin: adminPda,
           prediction: predictionPda,
           authority: manager.publicKey,
-          userAntiToken: userAntiToken,
-          userProToken: userProToken,
+          poolAuthority: poolAuthorityPda,
           predictionAntiToken: predictionAntiTokenPda,
           predictionProToken: predictionProTokenPda,
+          protocolAntiToken: protocolAntiToken,
+          protocolProToken: protocolProToken,
+          creatorAntiToken: creatorAntiToken,
+          creatorProToken: creatorProToken,
+          antiMint: antiMintKeypair.publicKey,
+          proMint: proMintKeypair.publicKey,
           tokenProgram: TOKEN_PROGRAM_ID,
         })
+        .remainingAccounts([
+          { pubkey: depositRecordPda, isWritable: true, isSigner: false },
+        ])
         .signers([manager])
         .rpc();
 
@@ -376,17 +461,22 @@ describe("collider-beta", () => {
       );
 
       const remainingAccounts = [
+        { pubkey: depositRecordPda, isWritable: true, isSigner: false },
         { pubkey: userAntiToken, isWritable: true, isSigner: false },
         { pubkey: userProToken, isWritable: true, isSigner: false },
       ];
 
       await program.methods
-        .bulkWithdrawTokens(index)
+        .bulkWithdrawTokens(index, new BN(0), new BN(1))
         .accounts({
+          admin: adminPda,
           prediction: predictionPda,
           authority: antitokenMultisigKeypair.publicKey,
+          poolAuthority: poolAuthorityPda,
           predictionAntiToken: predictionAntiTokenPda,
           predictionProToken: predictionProTokenPda,
+          antiMint: antiMintKeypair.publicKey,
+          proMint: proMintKeypair.publicKey,
           tokenProgram: TOKEN_PROGRAM_ID,
         })
         .remainingAccounts(remainingAccounts)
@@ -396,7 +486,10 @@ describe("collider-beta", () => {
       const prediction = await program.account.predictionAccount.fetch(
         predictionPda
       );
-      expect(prediction.deposits[0].withdrawn).to.be.true;
+      const record = await program.account.depositRecord.fetch(
+        depositRecordPda
+      );
+      expect(record.deposit.withdrawn).to.be.true;
 
       const afterAntiBalance = await getAccount(
         provider.connection,